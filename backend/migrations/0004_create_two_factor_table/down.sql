@@ -0,0 +1,9 @@
+/* -------------------------------------
+   TRIGGERS
+------------------------------------- */
+DROP TRIGGER IF EXISTS trigger_update_user_two_factor_timestamp ON user_two_factor;
+
+/* -------------------------------------
+   TABLES
+------------------------------------- */
+DROP TABLE IF EXISTS user_two_factor;
@@ -0,0 +1,22 @@
+/* -------------------------------------
+   TABLES
+------------------------------------- */
+-- Table for storing the TOTP second factor of a user
+CREATE TABLE user_two_factor (
+    user_id UUID PRIMARY KEY,
+    secret TEXT NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT FALSE,
+    recovery_codes TEXT[] NOT NULL DEFAULT '{}',
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+/* -------------------------------------
+   TRIGGERS
+------------------------------------- */
+-- Trigger for updating the updated_at field in the user_two_factor table
+CREATE TRIGGER trigger_update_user_two_factor_timestamp
+BEFORE UPDATE ON user_two_factor
+FOR EACH ROW
+EXECUTE FUNCTION update_timestamp();
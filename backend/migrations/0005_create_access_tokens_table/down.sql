@@ -0,0 +1,9 @@
+/* -------------------------------------
+   INDEXES
+------------------------------------- */
+DROP INDEX IF EXISTS idx_access_token_user_id;
+
+/* -------------------------------------
+   TABLES
+------------------------------------- */
+DROP TABLE IF EXISTS access_tokens;
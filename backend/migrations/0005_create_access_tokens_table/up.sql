@@ -0,0 +1,21 @@
+/* -------------------------------------
+   TABLES
+------------------------------------- */
+-- Table for storing personal access tokens; only the hash of the token is stored
+CREATE TABLE access_tokens (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL,
+    name VARCHAR(40) NOT NULL,
+    token_hash TEXT UNIQUE NOT NULL,
+    scopes TEXT[] NOT NULL DEFAULT '{}',
+    expires_at TIMESTAMP NOT NULL,
+    last_used_at TIMESTAMP,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+/* -------------------------------------
+   INDEXES
+------------------------------------- */
+-- Index on user ID for listing the tokens of a user
+CREATE INDEX IF NOT EXISTS idx_access_token_user_id ON access_tokens(user_id);
@@ -0,0 +1,9 @@
+/* -------------------------------------
+   INDEXES
+------------------------------------- */
+DROP INDEX IF EXISTS idx_user_identity_user_id;
+
+/* -------------------------------------
+   TABLES
+------------------------------------- */
+DROP TABLE IF EXISTS user_identities;
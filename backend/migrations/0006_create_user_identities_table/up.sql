@@ -0,0 +1,20 @@
+/* -------------------------------------
+   TABLES
+------------------------------------- */
+-- Table for linking users to their account at an external identity provider (OIDC)
+CREATE TABLE user_identities (
+    issuer TEXT NOT NULL,
+    subject TEXT NOT NULL,
+    user_id UUID NOT NULL,
+    email VARCHAR(100),
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    last_login_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    PRIMARY KEY (issuer, subject),
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+/* -------------------------------------
+   INDEXES
+------------------------------------- */
+-- Index on user ID for finding the identities of a user
+CREATE INDEX IF NOT EXISTS idx_user_identity_user_id ON user_identities(user_id);
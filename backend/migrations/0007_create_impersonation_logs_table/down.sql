@@ -0,0 +1,10 @@
+/* -------------------------------------
+   INDEXES
+------------------------------------- */
+DROP INDEX IF EXISTS idx_impersonation_log_admin_id;
+DROP INDEX IF EXISTS idx_impersonation_log_user_id;
+
+/* -------------------------------------
+   TABLES
+------------------------------------- */
+DROP TABLE IF EXISTS impersonation_logs;
@@ -0,0 +1,22 @@
+/* -------------------------------------
+   TABLES
+------------------------------------- */
+-- Table for the audit trail of admins acting as another user; every request made while
+-- impersonating is recorded. There are no foreign keys, so the trail outlives deleted users.
+CREATE TABLE impersonation_logs (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    session_id UUID NOT NULL,
+    admin_id UUID NOT NULL,
+    user_id UUID NOT NULL,
+    method VARCHAR(10) NOT NULL,
+    path TEXT NOT NULL,
+    status SMALLINT NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+
+/* -------------------------------------
+   INDEXES
+------------------------------------- */
+-- Indexes for finding the trail of an impersonated user, or of an admin
+CREATE INDEX IF NOT EXISTS idx_impersonation_log_user_id ON impersonation_logs(user_id);
+CREATE INDEX IF NOT EXISTS idx_impersonation_log_admin_id ON impersonation_logs(admin_id);
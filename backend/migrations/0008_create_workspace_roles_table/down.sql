@@ -0,0 +1,15 @@
+/* -------------------------------------
+   INDEXES
+------------------------------------- */
+DROP INDEX IF EXISTS idx_workspace_role_workspace_id;
+
+/* -------------------------------------
+   TRIGGERS
+------------------------------------- */
+DROP TRIGGER IF EXISTS trigger_update_workspace_roles_timestamp ON workspace_roles;
+
+/* -------------------------------------
+   TABLES
+------------------------------------- */
+ALTER TABLE workspace_members DROP COLUMN IF EXISTS custom_role;
+DROP TABLE IF EXISTS workspace_roles;
@@ -0,0 +1,34 @@
+/* -------------------------------------
+   TABLES
+------------------------------------- */
+-- Table for storing the custom roles of a workspace; named sets of permissions
+CREATE TABLE workspace_roles (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    workspace UUID NOT NULL,
+    name VARCHAR(40) NOT NULL,
+    permissions TEXT[] NOT NULL DEFAULT '{}',
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    UNIQUE (workspace, name),
+    FOREIGN KEY (workspace) REFERENCES workspaces(id) ON DELETE CASCADE
+);
+
+-- A member with a custom role gets its permissions instead of those of the built-in role; when
+-- the custom role is deleted, the built-in role applies again
+ALTER TABLE workspace_members
+ADD COLUMN custom_role UUID REFERENCES workspace_roles(id) ON DELETE SET NULL;
+
+/* -------------------------------------
+   TRIGGERS
+------------------------------------- */
+-- Trigger for updating the updated_at field in the workspace_roles table
+CREATE TRIGGER trigger_update_workspace_roles_timestamp
+BEFORE UPDATE ON workspace_roles
+FOR EACH ROW
+EXECUTE FUNCTION update_timestamp();
+
+/* -------------------------------------
+   INDEXES
+------------------------------------- */
+-- Index on workspace ID for listing the roles of a workspace
+CREATE INDEX IF NOT EXISTS idx_workspace_role_workspace_id ON workspace_roles(workspace);
@@ -0,0 +1,9 @@
+/* -------------------------------------
+   INDEXES
+------------------------------------- */
+DROP INDEX IF EXISTS idx_project_member_guest;
+
+/* -------------------------------------
+   TABLES
+------------------------------------- */
+ALTER TABLE project_members DROP COLUMN IF EXISTS guest;
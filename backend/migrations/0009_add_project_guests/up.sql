@@ -0,0 +1,13 @@
+/* -------------------------------------
+   TABLES
+------------------------------------- */
+-- A guest is a member of the project without being a member of its workspace, like an external
+-- client; guests only see their own projects and the members of those projects
+ALTER TABLE project_members
+ADD COLUMN guest BOOLEAN NOT NULL DEFAULT FALSE;
+
+/* -------------------------------------
+   INDEXES
+------------------------------------- */
+-- Partial index for finding the projects in which a user is a guest
+CREATE INDEX IF NOT EXISTS idx_project_member_guest ON project_members(member) WHERE guest;
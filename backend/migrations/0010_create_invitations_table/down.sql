@@ -0,0 +1,14 @@
+/* -------------------------------------
+   INDEXES
+------------------------------------- */
+DROP INDEX IF EXISTS idx_invitation_invitee;
+
+/* -------------------------------------
+   TRIGGERS
+------------------------------------- */
+DROP TRIGGER IF EXISTS trigger_update_invitations_timestamp ON invitations;
+
+/* -------------------------------------
+   TABLES
+------------------------------------- */
+DROP TABLE IF EXISTS invitations;
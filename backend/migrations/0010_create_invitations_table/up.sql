@@ -0,0 +1,39 @@
+/* -------------------------------------
+   TABLES
+------------------------------------- */
+-- Table for storing the invitations to a workspace; only the hash of the token is stored. A user
+-- has one invitation per workspace, which is renewed when the user is invited again
+CREATE TABLE invitations (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    workspace UUID NOT NULL,
+    invitee UUID NOT NULL,
+    inviter UUID,
+    role SMALLINT NOT NULL,
+    status SMALLINT NOT NULL DEFAULT 0,
+    token_hash TEXT UNIQUE NOT NULL,
+    expires_at TIMESTAMP NOT NULL,
+    sent_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    accepted_at TIMESTAMP,
+    revoked_at TIMESTAMP,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    UNIQUE (workspace, invitee),
+    FOREIGN KEY (workspace) REFERENCES workspaces(id) ON DELETE CASCADE,
+    FOREIGN KEY (invitee) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY (inviter) REFERENCES users(id) ON DELETE SET NULL
+);
+
+/* -------------------------------------
+   TRIGGERS
+------------------------------------- */
+-- Trigger for updating the updated_at field in the invitations table
+CREATE TRIGGER trigger_update_invitations_timestamp
+BEFORE UPDATE ON invitations
+FOR EACH ROW
+EXECUTE FUNCTION update_timestamp();
+
+/* -------------------------------------
+   INDEXES
+------------------------------------- */
+-- Index on invitee for finding the invitations of a user
+CREATE INDEX IF NOT EXISTS idx_invitation_invitee ON invitations(invitee);
@@ -0,0 +1,15 @@
+/* -------------------------------------
+   INDEXES
+------------------------------------- */
+DROP INDEX IF EXISTS idx_task_assignee;
+DROP INDEX IF EXISTS idx_task_project;
+
+/* -------------------------------------
+   TRIGGERS
+------------------------------------- */
+DROP TRIGGER IF EXISTS trigger_update_tasks_timestamp ON tasks;
+
+/* -------------------------------------
+   TABLES
+------------------------------------- */
+DROP TABLE IF EXISTS tasks;
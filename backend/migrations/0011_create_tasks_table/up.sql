@@ -0,0 +1,37 @@
+/* -------------------------------------
+   TABLES
+------------------------------------- */
+-- Table for storing the tasks of a project; the assignee is a member of the project, who is
+-- unassigned when removed from the project
+CREATE TABLE tasks (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    project UUID NOT NULL,
+    title VARCHAR(200) NOT NULL,
+    description TEXT,
+    status SMALLINT NOT NULL DEFAULT 0,
+    priority SMALLINT NOT NULL DEFAULT 1,
+    assignee UUID,
+    reporter UUID,
+    due_date DATE,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    FOREIGN KEY (project) REFERENCES projects(id) ON DELETE CASCADE,
+    FOREIGN KEY (project, assignee) REFERENCES project_members(project, member) ON DELETE SET NULL (assignee),
+    FOREIGN KEY (reporter) REFERENCES users(id) ON DELETE SET NULL
+);
+
+/* -------------------------------------
+   TRIGGERS
+------------------------------------- */
+-- Trigger for updating the updated_at field in the tasks table
+CREATE TRIGGER trigger_update_tasks_timestamp
+BEFORE UPDATE ON tasks
+FOR EACH ROW
+EXECUTE FUNCTION update_timestamp();
+
+/* -------------------------------------
+   INDEXES
+------------------------------------- */
+-- Indexes on project and assignee for listing the tasks of a project or of a member
+CREATE INDEX IF NOT EXISTS idx_task_project ON tasks(project);
+CREATE INDEX IF NOT EXISTS idx_task_assignee ON tasks(assignee);
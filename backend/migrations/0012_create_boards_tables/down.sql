@@ -0,0 +1,18 @@
+/* -------------------------------------
+   INDEXES
+------------------------------------- */
+DROP INDEX IF EXISTS idx_board_project;
+
+/* -------------------------------------
+   TRIGGERS
+------------------------------------- */
+DROP TRIGGER IF EXISTS trigger_update_board_cards_timestamp ON board_cards;
+DROP TRIGGER IF EXISTS trigger_update_board_columns_timestamp ON board_columns;
+DROP TRIGGER IF EXISTS trigger_update_boards_timestamp ON boards;
+
+/* -------------------------------------
+   TABLES
+------------------------------------- */
+DROP TABLE IF EXISTS board_cards;
+DROP TABLE IF EXISTS board_columns;
+DROP TABLE IF EXISTS boards;
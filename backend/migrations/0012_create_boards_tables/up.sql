@@ -0,0 +1,70 @@
+/* -------------------------------------
+   TABLES
+------------------------------------- */
+-- Table for storing the kanban boards of a project
+CREATE TABLE boards (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    project UUID NOT NULL,
+    name VARCHAR(80) NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    FOREIGN KEY (project) REFERENCES projects(id) ON DELETE CASCADE
+);
+
+-- Table for storing the columns of a board; the rank orders the columns byte by byte, so it
+-- uses the "C" collation
+CREATE TABLE board_columns (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    board UUID NOT NULL,
+    name VARCHAR(80) NOT NULL,
+    rank TEXT COLLATE "C" NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    FOREIGN KEY (board) REFERENCES boards(id) ON DELETE CASCADE,
+    UNIQUE (board, rank)
+);
+
+-- Table for storing the cards of a column; the version is raised on every change of a card, so a
+-- move can be rejected when the card changed in the meantime
+CREATE TABLE board_cards (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    project UUID NOT NULL,
+    board_column UUID NOT NULL,
+    title VARCHAR(200) NOT NULL,
+    description TEXT,
+    assignee UUID,
+    rank TEXT COLLATE "C" NOT NULL,
+    version INTEGER NOT NULL DEFAULT 1,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    FOREIGN KEY (project) REFERENCES projects(id) ON DELETE CASCADE,
+    FOREIGN KEY (board_column) REFERENCES board_columns(id) ON DELETE CASCADE,
+    FOREIGN KEY (project, assignee) REFERENCES project_members(project, member) ON DELETE SET NULL (assignee),
+    UNIQUE (board_column, rank)
+);
+
+/* -------------------------------------
+   TRIGGERS
+------------------------------------- */
+-- Triggers for updating the updated_at field in the board tables
+CREATE TRIGGER trigger_update_boards_timestamp
+BEFORE UPDATE ON boards
+FOR EACH ROW
+EXECUTE FUNCTION update_timestamp();
+
+CREATE TRIGGER trigger_update_board_columns_timestamp
+BEFORE UPDATE ON board_columns
+FOR EACH ROW
+EXECUTE FUNCTION update_timestamp();
+
+CREATE TRIGGER trigger_update_board_cards_timestamp
+BEFORE UPDATE ON board_cards
+FOR EACH ROW
+EXECUTE FUNCTION update_timestamp();
+
+/* -------------------------------------
+   INDEXES
+------------------------------------- */
+-- Index on project for listing the boards of a project; the columns and cards are found through
+-- the indexes of their unique constraints
+CREATE INDEX IF NOT EXISTS idx_board_project ON boards(project);
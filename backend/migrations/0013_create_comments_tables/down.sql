@@ -0,0 +1,17 @@
+/* -------------------------------------
+   INDEXES
+------------------------------------- */
+DROP INDEX IF EXISTS idx_comment_mention_mentioned;
+DROP INDEX IF EXISTS idx_comment_project;
+DROP INDEX IF EXISTS idx_comment_workspace;
+
+/* -------------------------------------
+   TRIGGERS
+------------------------------------- */
+DROP TRIGGER IF EXISTS trigger_update_comments_timestamp ON comments;
+
+/* -------------------------------------
+   TABLES
+------------------------------------- */
+DROP TABLE IF EXISTS comment_mentions;
+DROP TABLE IF EXISTS comments;
@@ -0,0 +1,47 @@
+/* -------------------------------------
+   TABLES
+------------------------------------- */
+-- Table for storing the comments on a workspace, or on one of its projects; a reply has the
+-- comment it answers as parent, and a deleted comment stays in place for its replies
+CREATE TABLE comments (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    workspace UUID NOT NULL,
+    project UUID,
+    parent UUID,
+    author UUID,
+    body TEXT NOT NULL,
+    edited_at TIMESTAMP,
+    deleted_at TIMESTAMP,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    FOREIGN KEY (workspace) REFERENCES workspaces(id) ON DELETE CASCADE,
+    FOREIGN KEY (project) REFERENCES projects(id) ON DELETE CASCADE,
+    FOREIGN KEY (parent) REFERENCES comments(id) ON DELETE CASCADE,
+    FOREIGN KEY (author) REFERENCES users(id) ON DELETE SET NULL
+);
+
+-- Table for storing the users mentioned in a comment
+CREATE TABLE comment_mentions (
+    comment UUID NOT NULL,
+    mentioned UUID NOT NULL,
+    PRIMARY KEY (comment, mentioned),
+    FOREIGN KEY (comment) REFERENCES comments(id) ON DELETE CASCADE,
+    FOREIGN KEY (mentioned) REFERENCES users(id) ON DELETE CASCADE
+);
+
+/* -------------------------------------
+   TRIGGERS
+------------------------------------- */
+-- Trigger for updating the updated_at field in the comments table
+CREATE TRIGGER trigger_update_comments_timestamp
+BEFORE UPDATE ON comments
+FOR EACH ROW
+EXECUTE FUNCTION update_timestamp();
+
+/* -------------------------------------
+   INDEXES
+------------------------------------- */
+-- Indexes for loading the thread of a workspace or project, and the mentions of a user
+CREATE INDEX IF NOT EXISTS idx_comment_workspace ON comments(workspace);
+CREATE INDEX IF NOT EXISTS idx_comment_project ON comments(project);
+CREATE INDEX IF NOT EXISTS idx_comment_mention_mentioned ON comment_mentions(mentioned);
@@ -0,0 +1,40 @@
+/* -------------------------------------
+   TABLES
+------------------------------------- */
+-- Table for storing the labels of a workspace; the color is a hex color like '#1f883d'
+CREATE TABLE labels (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    workspace UUID NOT NULL,
+    name VARCHAR(40) NOT NULL,
+    color VARCHAR(7) NOT NULL CHECK (color ~ '^#[0-9a-f]{6}$'),
+    description TEXT,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    FOREIGN KEY (workspace) REFERENCES workspaces(id) ON DELETE CASCADE,
+    UNIQUE (workspace, name)
+);
+
+-- Table for storing the labels of a project; a project only refers to its labels, so renaming or
+-- deleting a label is seen on every project at once
+CREATE TABLE project_labels (
+    project UUID NOT NULL,
+    label UUID NOT NULL,
+    PRIMARY KEY (project, label),
+    FOREIGN KEY (project) REFERENCES projects(id) ON DELETE CASCADE,
+    FOREIGN KEY (label) REFERENCES labels(id) ON DELETE CASCADE
+);
+
+/* -------------------------------------
+   TRIGGERS
+------------------------------------- */
+-- Trigger for updating the updated_at field in the labels table
+CREATE TRIGGER trigger_update_labels_timestamp
+BEFORE UPDATE ON labels
+FOR EACH ROW
+EXECUTE FUNCTION update_timestamp();
+
+/* -------------------------------------
+   INDEXES
+------------------------------------- */
+-- Index on label for finding the projects with a label
+CREATE INDEX IF NOT EXISTS idx_project_label_label ON project_labels(label);
@@ -0,0 +1,20 @@
+/* -------------------------------------
+   INDEXES
+------------------------------------- */
+DROP INDEX IF EXISTS idx_deliverable_status_change_deliverable;
+DROP INDEX IF EXISTS idx_deliverable_iteration;
+DROP INDEX IF EXISTS idx_iteration_active_sprint;
+DROP INDEX IF EXISTS idx_iteration_project;
+
+/* -------------------------------------
+   TRIGGERS
+------------------------------------- */
+DROP TRIGGER IF EXISTS trigger_update_deliverables_timestamp ON deliverables;
+DROP TRIGGER IF EXISTS trigger_update_iterations_timestamp ON iterations;
+
+/* -------------------------------------
+   TABLES
+------------------------------------- */
+DROP TABLE IF EXISTS deliverable_status_changes;
+DROP TABLE IF EXISTS deliverables;
+DROP TABLE IF EXISTS iterations;
@@ -0,0 +1,73 @@
+/* -------------------------------------
+   TABLES
+------------------------------------- */
+-- Table for storing the milestones (kind 0) and sprints (kind 1) of a project; an iteration is
+-- open (0) until it is started, active (1) while it runs and closed (2) when it is over
+CREATE TABLE iterations (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    project UUID NOT NULL,
+    kind SMALLINT NOT NULL,
+    name VARCHAR(80) NOT NULL,
+    goal TEXT,
+    start_date DATE NOT NULL,
+    end_date DATE NOT NULL,
+    state SMALLINT NOT NULL DEFAULT 0,
+    closed_at TIMESTAMP,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    FOREIGN KEY (project) REFERENCES projects(id) ON DELETE CASCADE,
+    CHECK (start_date <= end_date)
+);
+
+-- Table for storing the deliverables of an iteration; a deliverable that rolled over from a
+-- closed iteration refers to the one it continues, which stays behind unfinished
+CREATE TABLE deliverables (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    iteration UUID NOT NULL,
+    title VARCHAR(200) NOT NULL,
+    status SMALLINT NOT NULL DEFAULT 0,
+    rolled_over_from UUID,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    FOREIGN KEY (iteration) REFERENCES iterations(id) ON DELETE CASCADE,
+    FOREIGN KEY (rolled_over_from) REFERENCES deliverables(id) ON DELETE SET NULL
+);
+
+-- Table for storing every status a deliverable had, starting with the one it was added with;
+-- the burndown of an iteration is computed from these
+CREATE TABLE deliverable_status_changes (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    deliverable UUID NOT NULL,
+    status SMALLINT NOT NULL,
+    changed_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    FOREIGN KEY (deliverable) REFERENCES deliverables(id) ON DELETE CASCADE
+);
+
+/* -------------------------------------
+   TRIGGERS
+------------------------------------- */
+-- Trigger for updating the updated_at field in the iterations table
+CREATE TRIGGER trigger_update_iterations_timestamp
+BEFORE UPDATE ON iterations
+FOR EACH ROW
+EXECUTE FUNCTION update_timestamp();
+
+-- Trigger for updating the updated_at field in the deliverables table
+CREATE TRIGGER trigger_update_deliverables_timestamp
+BEFORE UPDATE ON deliverables
+FOR EACH ROW
+EXECUTE FUNCTION update_timestamp();
+
+/* -------------------------------------
+   INDEXES
+------------------------------------- */
+-- Index on project for listing the iterations of a project
+CREATE INDEX IF NOT EXISTS idx_iteration_project ON iterations(project);
+
+-- A project runs one sprint at a time
+CREATE UNIQUE INDEX IF NOT EXISTS idx_iteration_active_sprint ON iterations(project)
+WHERE kind = 1 AND state = 1;
+
+-- Indexes on iteration and deliverable for listing the deliverables and their status changes
+CREATE INDEX IF NOT EXISTS idx_deliverable_iteration ON deliverables(iteration);
+CREATE INDEX IF NOT EXISTS idx_deliverable_status_change_deliverable ON deliverable_status_changes(deliverable);
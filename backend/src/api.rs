@@ -58,4 +58,8 @@ impl<T> ApiResponse<T> {
     pub fn unauthorized(message: String) -> Error<T> {
         Self::error(Status::Unauthorized, message, None)
     }
+
+    pub fn too_many_requests(message: String) -> Error<T> {
+        Self::error(Status::TooManyRequests, message, None)
+    }
 }
@@ -0,0 +1,182 @@
+//! Authentication backends, which check the credentials of a login by form.
+//!
+//! The backends are tried in the order of the `auth` table of the Rocket configuration, or of the
+//! `AUTH_BACKENDS` environment variable. The first backend to accept the credentials logs the user
+//! in. By default only the passwords stored in the database are checked:
+//!
+//! ```toml
+//! [default.auth]
+//! # "password" checks the Argon2 hash in the database, "ldap" binds to the directory
+//! backends = ["ldap", "password"]
+//! ```
+use rocket::{fairing::Fairing, http::Status, response::status::Custom, State};
+use serde::Deserialize;
+
+use crate::{
+    api::{ApiResponse, Error, Null},
+    cache::RedisMutex,
+    database::{users as database, Db},
+    forms::password::Password,
+    models::users::{User, UserStatus},
+};
+
+use super::ldap::{LdapBackend, LdapConfig};
+
+/// Checks the username and password of a login.
+#[rocket::async_trait]
+pub trait AuthBackend: Send + Sync {
+    /// Returns the active user the credentials belong to, or `None` if this backend doesn't
+    /// accept them. An error means the backend couldn't tell, e.g. because a server is down.
+    ///
+    /// A backend that updates the user from elsewhere drops what's cached of them from `redis`.
+    async fn authenticate(
+        &self,
+        db: &Db,
+        redis: &State<RedisMutex>,
+        username: &str,
+        password: &str,
+    ) -> Result<Option<User>, Error<Null>>;
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum BackendKind {
+    Password,
+    Ldap,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct AuthConfig {
+    /// The backends to try, in order
+    pub backends: Vec<BackendKind>,
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        AuthConfig {
+            backends: vec![BackendKind::Password],
+        }
+    }
+}
+
+/// The configured backends, in the order they are tried.
+pub struct AuthBackends {
+    backends: Vec<Box<dyn AuthBackend>>,
+}
+
+impl AuthBackends {
+    /// Returns the user of the first backend that accepts the credentials.
+    ///
+    /// A backend that fails doesn't stop the next ones from being tried; its error is only
+    /// returned if no other backend accepts the credentials.
+    pub async fn authenticate(
+        &self,
+        db: &Db,
+        redis: &State<RedisMutex>,
+        username: &str,
+        password: &str,
+    ) -> Result<Option<User>, Error<Null>> {
+        let mut error = None;
+
+        for backend in &self.backends {
+            match backend.authenticate(db, redis, username, password).await {
+                Ok(Some(user)) => return Ok(Some(user)),
+                Ok(None) => {}
+                Err(e) => {
+                    eprintln!("Authentication backend failed: {}", e.1.message);
+                    error = Some(e);
+                }
+            }
+        }
+
+        match error {
+            Some(e) => Err(e),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Checks the password against the Argon2 hash of the user in the database.
+pub struct PasswordBackend;
+
+#[rocket::async_trait]
+impl AuthBackend for PasswordBackend {
+    async fn authenticate(
+        &self,
+        db: &Db,
+        _redis: &State<RedisMutex>,
+        username: &str,
+        password: &str,
+    ) -> Result<Option<User>, Error<Null>> {
+        // Get the user from the database; only active users can log in
+        let user = match database::get_user_by_username(db, username).await {
+            Ok(user) if user.status == UserStatus::Active => Some(user),
+            Ok(_) => None,
+            Err(Custom(status, _)) if status == Status::NotFound => None,
+            Err(e) => return Err(e),
+        };
+
+        // Verify the password, or a dummy hash if there is no user, so the response takes as long
+        let stored_hash = match &user {
+            Some(user) => user.password.as_str(),
+            None => Password::dummy_hash(),
+        };
+
+        let verified = Password::verify_password(password, stored_hash).map_err(|e| {
+            ApiResponse::internal_server_error(format!("Password verification failed: {}", e))
+        })?;
+
+        let user = match user {
+            Some(user) if verified => user,
+            _ => return Ok(None),
+        };
+
+        // Upgrade a hash made with weaker parameters, while the password is at hand
+        if Password::needs_rehash(&user.password) {
+            match Password::generate(Some(password)) {
+                Ok(hash) => {
+                    if let Err(e) = database::update_user_password(db, user.id, hash).await {
+                        eprintln!("Couldn't save rehashed password: {}", e.1.message);
+                    }
+                }
+                Err(e) => eprintln!("Couldn't rehash password: {e}"),
+            }
+        }
+
+        Ok(Some(user))
+    }
+}
+
+pub fn auth_backends_fairing() -> impl Fairing {
+    rocket::fairing::AdHoc::try_on_ignite("Authentication backends", |rocket| async {
+        let config = match rocket.figment().find_value("auth") {
+            Ok(_) => rocket.figment().extract_inner::<AuthConfig>("auth"),
+            Err(_) => Ok(AuthConfig::default()),
+        };
+
+        let config = match config {
+            Ok(config) => config,
+            Err(e) => {
+                eprintln!("Failed to load authentication config: {e}");
+                return Err(rocket);
+            }
+        };
+
+        let mut backends: Vec<Box<dyn AuthBackend>> = Vec::new();
+        for kind in config.backends {
+            match kind {
+                BackendKind::Password => backends.push(Box::new(PasswordBackend)),
+                BackendKind::Ldap => match rocket.figment().extract_inner::<LdapConfig>("ldap") {
+                    Ok(config) => backends.push(Box::new(LdapBackend::new(config))),
+                    Err(e) => {
+                        eprintln!("The LDAP backend is selected, but not configured: {e}");
+                        return Err(rocket);
+                    }
+                },
+            }
+        }
+
+        Ok(rocket.manage(AuthBackends { backends }))
+    })
+}
@@ -0,0 +1,43 @@
+//! Audit trail of impersonations.
+//!
+//! An admin can act as another user, to see what the user sees (see
+//! [`JwtGuard::impersonate`](super::JwtGuard::impersonate)). The [`JwtGuard`](super::JwtGuard)
+//! marks every request made with an impersonation token, and the fairing records it in the
+//! `impersonation_logs` table along with the status of the response.
+use rocket::{fairing::AdHoc, Request};
+
+use crate::{
+    database::{impersonation as database, Db},
+    models::impersonation::{Impersonation, ImpersonationLog},
+};
+
+/// The impersonation of the request, if it was made while impersonating.
+pub fn get_impersonation(request: &Request<'_>) -> Option<Impersonation> {
+    *request.local_cache(|| None::<Impersonation>)
+}
+
+pub fn impersonation_audit_fairing() -> AdHoc {
+    AdHoc::on_response("Impersonation audit", |request, response| {
+        Box::pin(async move {
+            let Some(impersonation) = get_impersonation(request) else {
+                return;
+            };
+
+            let log = ImpersonationLog::new(
+                &impersonation,
+                request.method().as_str(),
+                &request.uri().to_string(),
+                response.status().code,
+            );
+
+            let Some(db) = Db::get_one(request.rocket()).await else {
+                eprintln!("Impersonation not logged; no database connection: {log:?}");
+                return;
+            };
+
+            if let Err(e) = database::insert_impersonation_log(&db, log).await {
+                eprintln!("Impersonation not logged: {}", e.1.message);
+            }
+        })
+    })
+}
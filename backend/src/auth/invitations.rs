@@ -0,0 +1,85 @@
+//! Invitations to a workspace, with which new users set their first password.
+//!
+//! How long an invitation link stays valid is read from the `invitation` table of the Rocket
+//! configuration, or from `INVITATION_TTL_HOURS`:
+//!
+//! ```toml
+//! [default.invitation]
+//! ttl_hours = 72
+//! ```
+use chrono::{Duration, NaiveDateTime, Utc};
+use rocket::{fairing::Fairing, http::Status};
+use serde::Deserialize;
+
+use crate::{
+    api::{ApiResponse, Error, Null},
+    database::{invitations as database, Db},
+    models::invitations::{Invitation, InvitationStatus},
+};
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct InvitationConfig {
+    /// How long an invitation can be accepted after it was sent
+    pub ttl_hours: i64,
+}
+
+impl Default for InvitationConfig {
+    fn default() -> Self {
+        InvitationConfig { ttl_hours: 24 }
+    }
+}
+
+impl InvitationConfig {
+    pub fn expires_at(&self, sent_at: NaiveDateTime) -> NaiveDateTime {
+        sent_at + Duration::hours(self.ttl_hours)
+    }
+}
+
+/// Returns the invitation of the token, if it can still be accepted.
+///
+/// A link that was used, revoked or not accepted in time is gone, with a message that tells why;
+/// an expired invitation is marked as such the first time its link is used.
+pub async fn find_pending_invitation(db: &Db, token: &str) -> Result<Invitation, Error<Null>> {
+    let invitation = database::get_invitation_by_hash(db, Invitation::hash(token)).await?;
+
+    match invitation.effective_status(Utc::now().naive_utc()) {
+        InvitationStatus::Pending => Ok(invitation),
+        InvitationStatus::Accepted => Err(gone("The invitation has already been accepted")),
+        InvitationStatus::Revoked => Err(gone("The invitation has been revoked")),
+        InvitationStatus::Expired => {
+            if invitation.status == InvitationStatus::Pending {
+                database::set_invitation_expired(db, invitation.id).await?;
+            }
+
+            Err(gone("The invitation has expired"))
+        }
+    }
+}
+
+fn gone(message: &str) -> Error<Null> {
+    ApiResponse::error(Status::Gone, message.to_string(), None)
+}
+
+pub fn invitation_fairing() -> impl Fairing {
+    rocket::fairing::AdHoc::try_on_ignite("Invitation config", |rocket| async {
+        let config = match rocket.figment().find_value("invitation") {
+            Ok(_) => rocket
+                .figment()
+                .extract_inner::<InvitationConfig>("invitation"),
+            Err(_) => Ok(InvitationConfig::default()),
+        };
+
+        match config {
+            Ok(config) if config.ttl_hours > 0 => Ok(rocket.manage(config)),
+            Ok(_) => {
+                eprintln!("The TTL of invitations must be at least one hour");
+                Err(rocket)
+            }
+            Err(e) => {
+                eprintln!("Failed to load invitation config: {e}");
+                Err(rocket)
+            }
+        }
+    })
+}
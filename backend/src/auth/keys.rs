@@ -0,0 +1,196 @@
+//! Signing and verification keys for JSON Web Tokens.
+//!
+//! The keys are read from the `jwt` table of the Rocket configuration. Values from the
+//! environment are merged into that table at launch (see [`crate::rocket`]), so a minimal setup
+//! only needs `JWT_SECRET`:
+//!
+//! ```toml
+//! [default.jwt.signing]
+//! kid = "2025-06"
+//! algorithm = "RS256"
+//! private_key = "/run/secrets/jwt_private.pem"
+//! public_key = "/run/secrets/jwt_public.pem"
+//!
+//! # Keys that no longer sign new tokens, but are still accepted until their tokens expire
+//! [[default.jwt.retired]]
+//! kid = "2025-01"
+//! algorithm = "HS256"
+//! secret = "..."
+//! ```
+use std::{collections::HashMap, fs};
+
+use jsonwebtoken::{
+    decode, decode_header, encode, errors::ErrorKind, Algorithm, DecodingKey, EncodingKey, Header,
+    TokenData, Validation,
+};
+use rocket::{fairing::Fairing, http::Status};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+const DEFAULT_KID: &str = "default";
+
+#[derive(Debug, Deserialize)]
+pub struct JwtConfig {
+    /// The key used to sign new tokens
+    pub signing: KeyConfig,
+    /// Keys that are only used for verification, e.g. during a key rollover
+    #[serde(default)]
+    pub retired: Vec<KeyConfig>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct KeyConfig {
+    /// Key identifier; written to the `kid` header of every token signed with this key
+    #[serde(default = "default_kid")]
+    pub kid: String,
+    #[serde(default = "default_algorithm")]
+    pub algorithm: Algorithm,
+    /// Shared secret for the HMAC algorithms (HS256, HS384, HS512)
+    pub secret: Option<String>,
+    /// Path to a PEM encoded private key for the asymmetric algorithms (RS256, EdDSA, ...)
+    pub private_key: Option<String>,
+    /// Path to a PEM encoded public key for the asymmetric algorithms (RS256, EdDSA, ...)
+    pub public_key: Option<String>,
+}
+
+fn default_kid() -> String {
+    DEFAULT_KID.to_string()
+}
+
+fn default_algorithm() -> Algorithm {
+    Algorithm::HS256
+}
+
+impl KeyConfig {
+    fn encoding_key(&self) -> Result<EncodingKey, String> {
+        match self.algorithm {
+            Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512 => {
+                Ok(EncodingKey::from_secret(self.secret()?.as_bytes()))
+            }
+            Algorithm::ES256 | Algorithm::ES384 => {
+                EncodingKey::from_ec_pem(&self.read_pem(&self.private_key, "private_key")?)
+            }
+            Algorithm::EdDSA => {
+                EncodingKey::from_ed_pem(&self.read_pem(&self.private_key, "private_key")?)
+            }
+            _ => EncodingKey::from_rsa_pem(&self.read_pem(&self.private_key, "private_key")?),
+        }
+        .map_err(|e| format!("Invalid private key for '{}': {e}", self.kid))
+    }
+
+    fn decoding_key(&self) -> Result<DecodingKey, String> {
+        match self.algorithm {
+            Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512 => {
+                Ok(DecodingKey::from_secret(self.secret()?.as_bytes()))
+            }
+            Algorithm::ES256 | Algorithm::ES384 => {
+                DecodingKey::from_ec_pem(&self.read_pem(&self.public_key, "public_key")?)
+            }
+            Algorithm::EdDSA => {
+                DecodingKey::from_ed_pem(&self.read_pem(&self.public_key, "public_key")?)
+            }
+            _ => DecodingKey::from_rsa_pem(&self.read_pem(&self.public_key, "public_key")?),
+        }
+        .map_err(|e| format!("Invalid public key for '{}': {e}", self.kid))
+    }
+
+    fn secret(&self) -> Result<&str, String> {
+        match self.secret.as_deref() {
+            Some(secret) if !secret.is_empty() => Ok(secret),
+            _ => Err(format!("Key '{}' requires a 'secret'", self.kid)),
+        }
+    }
+
+    fn read_pem(&self, path: &Option<String>, field: &str) -> Result<Vec<u8>, String> {
+        let path = path
+            .as_deref()
+            .ok_or_else(|| format!("Key '{}' requires a '{field}'", self.kid))?;
+
+        fs::read(path).map_err(|e| format!("Couldn't read '{path}': {e}"))
+    }
+}
+
+pub struct JwtKeys {
+    kid: String,
+    algorithm: Algorithm,
+    encoding_key: EncodingKey,
+    decoding_keys: HashMap<String, (Algorithm, DecodingKey)>,
+}
+
+impl JwtKeys {
+    pub fn from_config(config: &JwtConfig) -> Result<Self, String> {
+        let signing = &config.signing;
+
+        // The signing key is always accepted for verification as well
+        let mut decoding_keys = HashMap::new();
+        for key in std::iter::once(signing).chain(config.retired.iter()) {
+            if decoding_keys
+                .insert(key.kid.clone(), (key.algorithm, key.decoding_key()?))
+                .is_some()
+            {
+                return Err(format!("Duplicate key id '{}'", key.kid));
+            }
+        }
+
+        Ok(JwtKeys {
+            kid: signing.kid.clone(),
+            algorithm: signing.algorithm,
+            encoding_key: signing.encoding_key()?,
+            decoding_keys,
+        })
+    }
+
+    /// Signs the claims with the current signing key and returns the encoded token.
+    pub fn encode<T: Serialize>(&self, claims: &T) -> Result<String, String> {
+        let mut header = Header::new(self.algorithm);
+        header.kid = Some(self.kid.clone());
+
+        encode(&header, claims, &self.encoding_key)
+            .map_err(|e| format!("Failed to create token: {e}"))
+    }
+
+    /// Verifies the signature and the expiration of the token, using the key referenced by the
+    /// `kid` header.
+    pub fn decode<T: DeserializeOwned>(
+        &self,
+        token: &str,
+    ) -> Result<TokenData<T>, (Status, String)> {
+        let unauthorized = |message: String| (Status::Unauthorized, message);
+
+        // Read the (unverified) header to find out which key signed the token
+        let header =
+            decode_header(token).map_err(|e| unauthorized(format!("Invalid token: {e}")))?;
+
+        let kid = header
+            .kid
+            .ok_or_else(|| unauthorized("Token has no key id".to_string()))?;
+
+        let (algorithm, key) = self
+            .decoding_keys
+            .get(&kid)
+            .ok_or_else(|| unauthorized(format!("Unknown key id '{kid}'")))?;
+
+        // Only accept the algorithm that is configured for the key, never the one in the header
+        decode::<T>(token, key, &Validation::new(*algorithm)).map_err(|e| match e.kind() {
+            ErrorKind::ExpiredSignature => unauthorized("Token has expired".to_string()),
+            _ => unauthorized(format!("Invalid token: {e}")),
+        })
+    }
+}
+
+pub fn jwt_fairing() -> impl Fairing {
+    rocket::fairing::AdHoc::try_on_ignite("JWT keys", |rocket| async {
+        let keys = rocket
+            .figment()
+            .extract_inner::<JwtConfig>("jwt")
+            .map_err(|e| e.to_string())
+            .and_then(|config| JwtKeys::from_config(&config));
+
+        match keys {
+            Ok(keys) => Ok(rocket.manage(keys)),
+            Err(e) => {
+                eprintln!("Failed to load JWT keys: {e}");
+                Err(rocket)
+            }
+        }
+    })
+}
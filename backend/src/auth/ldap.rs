@@ -0,0 +1,448 @@
+//! Authentication against an LDAP directory or Active Directory.
+//!
+//! The user is looked up in the directory, with the service account if there is one, and the
+//! password is checked by binding as the user. Users that log in for the first time are matched
+//! on their email address, or provisioned, if the configuration allows it. On every login,
+//! including the first, the name, email address and role of the user are updated from the
+//! directory.
+//!
+//! The directory is read from the `ldap` table of the Rocket configuration, or from the `LDAP_`
+//! environment variables (e.g. `LDAP_URL`, `LDAP_BASE_DN`), and is only used when `ldap` is one
+//! of the [`backends`](super::backends).
+//!
+//! ```toml
+//! [default.ldap]
+//! url = "ldap://ldap.example.com:389"
+//! starttls = true
+//! # Looks up users; anonymously when not set
+//! bind_dn = "cn=rustle,ou=services,dc=example,dc=com"
+//! bind_password = "..."
+//! base_dn = "ou=people,dc=example,dc=com"
+//! user_filter = "(uid={username})"
+//! # Groups are read from the memberOf attribute, unless they are searched for
+//! group_base_dn = "ou=groups,dc=example,dc=com"
+//! group_filter = "(member={dn})"
+//! provision_users = true
+//! default_role = "Reviewer"
+//! # Only for a directory that owns the addresses it hands out; never links admins
+//! link_by_email = false
+//!
+//! [default.ldap.attributes]
+//! username = "uid"
+//! first_name = "givenName"
+//! last_name = "sn"
+//! email = "mail"
+//! groups = "memberOf"
+//!
+//! # The highest role of the groups of the user applies
+//! [default.ldap.role_mapping]
+//! "cn=admins,ou=groups,dc=example,dc=com" = "Admin"
+//! "cn=managers,ou=groups,dc=example,dc=com" = "Manager"
+//! ```
+use std::{collections::HashMap, time::Duration};
+
+use chrono::Utc;
+use ldap3::{ldap_escape, Ldap, LdapConnAsync, LdapConnSettings, LdapError, SearchEntry};
+use rocket::{http::Status, State};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::{
+    api::{ApiResponse, Error, Null},
+    cache::{self, RedisMutex},
+    database::{identities as database, users as users_database, Db},
+    forms::password::Password,
+    models::{
+        identities::UserIdentity,
+        users::{User, UserRole, UserStatus},
+    },
+};
+
+use super::backends::AuthBackend;
+
+/// The result code of a bind with a wrong DN or password.
+const INVALID_CREDENTIALS: u32 = 49;
+
+#[derive(Debug, Deserialize)]
+pub struct LdapConfig {
+    /// `ldap://` or `ldaps://`, with the port
+    pub url: String,
+    /// Upgrade a `ldap://` connection to TLS
+    #[serde(default)]
+    pub starttls: bool,
+    /// The service account that looks up users; anonymous when not set
+    pub bind_dn: Option<String>,
+    pub bind_password: Option<String>,
+    /// Where users are searched for
+    pub base_dn: String,
+    /// Finds the user; `{username}` is replaced by the escaped username
+    #[serde(default = "default_user_filter")]
+    pub user_filter: String,
+    #[serde(default)]
+    pub attributes: LdapAttributes,
+    /// Where groups are searched for; without it, the groups attribute of the user is read
+    pub group_base_dn: Option<String>,
+    /// Finds the groups of the user; `{dn}` is replaced by the escaped DN of the user
+    #[serde(default = "default_group_filter")]
+    pub group_filter: String,
+    /// The role of the members of a group, by the DN of the group
+    #[serde(default)]
+    pub role_mapping: HashMap<String, UserRole>,
+    /// Create a user for an account that can't be matched to an existing user
+    #[serde(default)]
+    pub provision_users: bool,
+    /// Match an account that logs in for the first time to the user with the same email address
+    #[serde(default)]
+    pub link_by_email: bool,
+    /// The role of users that are in none of the mapped groups
+    #[serde(default)]
+    pub default_role: UserRole,
+    /// How long the directory may take to answer a login
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+/// The names of the attributes of a user in the directory.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct LdapAttributes {
+    pub username: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub email: String,
+    pub groups: String,
+}
+
+impl Default for LdapAttributes {
+    fn default() -> Self {
+        LdapAttributes {
+            username: "uid".to_string(),
+            first_name: "givenName".to_string(),
+            last_name: "sn".to_string(),
+            email: "mail".to_string(),
+            groups: "memberOf".to_string(),
+        }
+    }
+}
+
+fn default_user_filter() -> String {
+    "(uid={username})".to_string()
+}
+
+fn default_group_filter() -> String {
+    "(member={dn})".to_string()
+}
+
+fn default_timeout_secs() -> u64 {
+    5
+}
+
+/// A user as found in the directory.
+#[derive(Debug)]
+pub struct DirectoryUser {
+    pub dn: String,
+    pub username: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub email: Option<String>,
+    pub groups: Vec<String>,
+}
+
+pub struct LdapBackend {
+    config: LdapConfig,
+}
+
+#[rocket::async_trait]
+impl AuthBackend for LdapBackend {
+    async fn authenticate(
+        &self,
+        db: &Db,
+        redis: &State<RedisMutex>,
+        username: &str,
+        password: &str,
+    ) -> Result<Option<User>, Error<Null>> {
+        // An empty password would be an anonymous bind, which always succeeds
+        if username.is_empty() || password.is_empty() {
+            return Ok(None);
+        }
+
+        let timeout = Duration::from_secs(self.config.timeout_secs);
+        let directory_user =
+            match rocket::tokio::time::timeout(timeout, self.verify(username, password)).await {
+                Ok(result) => result.map_err(|e| bad_gateway(format!("LDAP failed: {e}")))?,
+                Err(_) => return Err(bad_gateway("LDAP timed out".to_string())),
+            };
+
+        let Some(directory_user) = directory_user else {
+            return Ok(None);
+        };
+
+        let user = self
+            .find_or_provision_user(db, redis, &directory_user)
+            .await?;
+
+        Ok(user.filter(|user| user.status == UserStatus::Active))
+    }
+}
+
+impl LdapBackend {
+    pub fn new(config: LdapConfig) -> Self {
+        LdapBackend { config }
+    }
+
+    /// Returns the user from the directory if the password is correct.
+    async fn verify(
+        &self,
+        username: &str,
+        password: &str,
+    ) -> Result<Option<DirectoryUser>, LdapError> {
+        let settings = LdapConnSettings::new()
+            .set_conn_timeout(Duration::from_secs(self.config.timeout_secs))
+            .set_starttls(self.config.starttls);
+        let (conn, mut ldap) = LdapConnAsync::with_settings(settings, &self.config.url).await?;
+        ldap3::drive!(conn);
+
+        let result = self.lookup_and_bind(&mut ldap, username, password).await;
+        let _ = ldap.unbind().await;
+
+        result
+    }
+
+    async fn lookup_and_bind(
+        &self,
+        ldap: &mut Ldap,
+        username: &str,
+        password: &str,
+    ) -> Result<Option<DirectoryUser>, LdapError> {
+        let config = &self.config;
+
+        if let Some(bind_dn) = &config.bind_dn {
+            ldap.simple_bind(bind_dn, config.bind_password.as_deref().unwrap_or_default())
+                .await?
+                .success()?;
+        }
+
+        let attributes = &config.attributes;
+        let filter = config
+            .user_filter
+            .replace("{username}", &ldap_escape(username));
+        let (entries, _) = ldap
+            .search(
+                &config.base_dn,
+                ldap3::Scope::Subtree,
+                &filter,
+                vec![
+                    attributes.username.as_str(),
+                    attributes.first_name.as_str(),
+                    attributes.last_name.as_str(),
+                    attributes.email.as_str(),
+                    attributes.groups.as_str(),
+                ],
+            )
+            .await?
+            .success()?;
+
+        // An unknown or ambiguous username can't log in
+        let mut entries = entries.into_iter();
+        let (Some(entry), None) = (entries.next(), entries.next()) else {
+            return Ok(None);
+        };
+        let entry = SearchEntry::construct(entry);
+
+        // The password is checked by the directory itself
+        let bind = ldap.simple_bind(&entry.dn, password).await?;
+        if bind.rc == INVALID_CREDENTIALS {
+            return Ok(None);
+        }
+        bind.success()?;
+
+        let groups = match &config.group_base_dn {
+            Some(group_base_dn) => {
+                let filter = config.group_filter.replace("{dn}", &ldap_escape(&entry.dn));
+                let (groups, _) = ldap
+                    .search(group_base_dn, ldap3::Scope::Subtree, &filter, vec!["1.1"])
+                    .await?
+                    .success()?;
+
+                groups
+                    .into_iter()
+                    .map(|group| SearchEntry::construct(group).dn)
+                    .collect()
+            }
+            None => attribute_values(&entry, &attributes.groups),
+        };
+
+        Ok(Some(DirectoryUser {
+            username: first_value(&entry, &attributes.username)
+                .unwrap_or_else(|| username.to_string()),
+            first_name: first_value(&entry, &attributes.first_name).unwrap_or_default(),
+            last_name: first_value(&entry, &attributes.last_name).unwrap_or_default(),
+            email: first_value(&entry, &attributes.email),
+            groups,
+            dn: entry.dn,
+        }))
+    }
+
+    /// The highest role of the groups of the user, or `None` without a role mapping, in which
+    /// case roles are managed in the application.
+    pub fn role_of(&self, directory_user: &DirectoryUser) -> Option<UserRole> {
+        if self.config.role_mapping.is_empty() {
+            return None;
+        }
+
+        let role = self
+            .config
+            .role_mapping
+            .iter()
+            .filter(|(group, _)| {
+                directory_user
+                    .groups
+                    .iter()
+                    .any(|member_of| member_of.eq_ignore_ascii_case(group))
+            })
+            .map(|(_, role)| *role)
+            .max()
+            .unwrap_or(self.config.default_role);
+
+        Some(role)
+    }
+
+    /// Returns the user of the directory account, updated with its attributes.
+    ///
+    /// An account that isn't linked yet is linked to the user with the same email address.
+    /// Otherwise a new user is provisioned, if the configuration allows it. A user that lost a
+    /// role in the directory is logged out everywhere, so the old role doesn't outlive the groups.
+    async fn find_or_provision_user(
+        &self,
+        db: &Db,
+        redis: &State<RedisMutex>,
+        directory_user: &DirectoryUser,
+    ) -> Result<Option<User>, Error<Null>> {
+        let issuer = &self.config.url;
+        let role = self.role_of(directory_user);
+
+        if let Some(user) = database::get_user_by_identity(db, issuer, &directory_user.dn).await? {
+            return self
+                .update_user(db, redis, user, directory_user, role)
+                .await
+                .map(Some);
+        }
+
+        // The directory takes over a user it's linked to, which an admin shouldn't be by accident
+        let existing = match &directory_user.email {
+            Some(email) if self.config.link_by_email => {
+                users_database::get_user_by_email(db, email)
+                    .await
+                    .ok()
+                    .filter(|user| user.role != UserRole::Admin)
+            }
+            _ => None,
+        };
+
+        let user = match existing {
+            Some(user) => user,
+            None if self.config.provision_users => {
+                self.provision_user(db, directory_user, role).await?
+            }
+            None => return Ok(None),
+        };
+
+        let identity = UserIdentity::new(
+            issuer,
+            &directory_user.dn,
+            user.id,
+            directory_user.email.clone(),
+        );
+        database::insert_identity(db, identity).await?;
+
+        // A linked user gets the attributes of the directory right away, like on later logins
+        self.update_user(db, redis, user, directory_user, role)
+            .await
+            .map(Some)
+    }
+
+    /// Updates the name, email address and role of the user from the directory.
+    async fn update_user(
+        &self,
+        db: &Db,
+        redis: &State<RedisMutex>,
+        user: User,
+        directory_user: &DirectoryUser,
+        role: Option<UserRole>,
+    ) -> Result<User, Error<Null>> {
+        let previous_role = user.role;
+        let user = users_database::update_directory_attributes(
+            db,
+            user.id,
+            directory_user.first_name.clone(),
+            directory_user.last_name.clone(),
+            directory_user.email.clone().unwrap_or(user.email),
+            role.unwrap_or(user.role),
+        )
+        .await?;
+
+        // The new attributes apply to the next request of the user
+        if user.role < previous_role {
+            cache::sessions::remove_user_sessions(redis, user.id).await?;
+        }
+        cache::users::remove_user_cache(redis, user.id).await;
+
+        Ok(user)
+    }
+
+    /// Creates an active user for the account, with an unusable random password.
+    async fn provision_user(
+        &self,
+        db: &Db,
+        directory_user: &DirectoryUser,
+        role: Option<UserRole>,
+    ) -> Result<User, Error<Null>> {
+        let email = directory_user
+            .email
+            .clone()
+            .ok_or_else(|| ApiResponse::unauthorized("An email address is required".to_string()))?;
+
+        let password = Password::generate(None).map_err(|e| {
+            ApiResponse::internal_server_error(format!("Coudn't hash password: {e}"))
+        })?;
+        let timestamp = Utc::now().naive_utc();
+
+        let user = User {
+            id: Uuid::new_v4(),
+            username: directory_user.username.to_lowercase(),
+            first_name: directory_user.first_name.clone(),
+            last_name: directory_user.last_name.clone(),
+            email,
+            role: role.unwrap_or(self.config.default_role),
+            status: UserStatus::Active,
+            password,
+            created_at: timestamp,
+            updated_at: timestamp,
+            ..Default::default()
+        };
+
+        users_database::inject_user(db, user.clone())
+            .await
+            .map_err(ApiResponse::from_error)?;
+
+        Ok(user)
+    }
+}
+
+/// The values of an attribute; attribute names are case insensitive.
+fn attribute_values(entry: &SearchEntry, attribute: &str) -> Vec<String> {
+    entry
+        .attrs
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(attribute))
+        .map(|(_, values)| values.clone())
+        .unwrap_or_default()
+}
+
+fn first_value(entry: &SearchEntry, attribute: &str) -> Option<String> {
+    attribute_values(entry, attribute).into_iter().next()
+}
+
+fn bad_gateway(message: String) -> Error<Null> {
+    ApiResponse::error(Status::BadGateway, message, None)
+}
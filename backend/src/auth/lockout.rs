@@ -0,0 +1,78 @@
+//! Brute-force protection for the login.
+//!
+//! Failed logins are counted per username and per client IP within a window of an hour. After a
+//! few free attempts, every failure blocks the username for an exponentially growing number of
+//! seconds, until the failure limit is reached and the account is locked for a longer period.
+//! An admin can lift the lock early.
+use std::net::IpAddr;
+
+use rocket::State;
+
+use crate::{
+    api::{ApiResponse, Error, Null},
+    cache::{users as cache, RedisMutex},
+};
+
+/// Failures that don't cause any delay, to allow for typos.
+const LOGIN_FREE_ATTEMPTS: u64 = 3;
+/// Failures after which the account is locked.
+pub const LOGIN_FAILURE_LIMIT: u64 = 10;
+/// Duration of the lock in seconds.
+const LOGIN_LOCKOUT_SECONDS: u64 = 900;
+/// Failures after which all logins from the client IP are refused.
+const LOGIN_IP_FAILURE_LIMIT: u64 = 50;
+
+/// Refuses the login attempt if the username is blocked, or the client IP made too many failed
+/// attempts. The response is the same whether the username exists or not.
+pub async fn check_login_allowed(
+    redis: &State<RedisMutex>,
+    username: &str,
+    ip: Option<IpAddr>,
+) -> Result<(), Error<Null>> {
+    let ip_blocked = match ip {
+        Some(ip) => cache::get_login_failures_ip(redis, ip).await? >= LOGIN_IP_FAILURE_LIMIT,
+        None => false,
+    };
+
+    if ip_blocked || cache::is_login_blocked(redis, username).await? {
+        return Err(ApiResponse::too_many_requests(
+            "Too many failed login attempts; try again later".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Counts the failed attempt and blocks the username for the time it has to back off.
+pub async fn register_login_failure(
+    redis: &State<RedisMutex>,
+    username: &str,
+    ip: Option<IpAddr>,
+) -> Result<(), Error<Null>> {
+    if let Some(ip) = ip {
+        cache::count_login_failure_ip(redis, ip).await?;
+    }
+
+    let failures = cache::count_login_failure(redis, username).await?;
+
+    match backoff_seconds(failures) {
+        0 => Ok(()),
+        seconds => cache::block_login(redis, username, seconds).await,
+    }
+}
+
+/// Clears the failures of the username; after a successful login, or when unlocked by an admin.
+pub async fn reset_login_failures(redis: &State<RedisMutex>, username: &str) {
+    cache::remove_login_failures(redis, username).await;
+}
+
+/// 1, 2, 4, 8... seconds after the free attempts, and the full lockout from the failure limit.
+fn backoff_seconds(failures: u64) -> u64 {
+    if failures >= LOGIN_FAILURE_LIMIT {
+        LOGIN_LOCKOUT_SECONDS
+    } else if failures > LOGIN_FREE_ATTEMPTS {
+        1 << (failures - LOGIN_FREE_ATTEMPTS - 1)
+    } else {
+        0
+    }
+}
@@ -0,0 +1,455 @@
+//! Authentication and Authorization Module
+//!
+//! This module handles user authentication, token generation, and Redis-based session management.
+//! It includes JWT-based authentication and role-based access control.
+use chrono::NaiveDateTime;
+use jsonwebtoken::TokenData;
+use rocket::{
+    http::{Cookie, CookieJar, Method, SameSite, Status},
+    request::{FromRequest, Outcome},
+    Request, State,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    api::{ApiResponse, Error, Null},
+    cache::{self, RedisMutex},
+    cookies::{CSRF_COOKIE, CSRF_HEADER, REFRESH_COOKIE, TOKEN_COOKIE},
+    database::{self, Db},
+    models::{
+        access_tokens::{AccessToken, Scope, ACCESS_TOKEN_PREFIX},
+        impersonation::Impersonation,
+        sessions::{ClientInfo, Session},
+        users::{PublicUser, User, UserStatus},
+    },
+    policies::Policy,
+};
+
+use keys::JwtKeys;
+
+pub mod backends;
+pub mod impersonation;
+pub mod invitations;
+pub mod keys;
+pub mod ldap;
+pub mod lockout;
+pub mod oidc;
+pub mod passwords;
+pub mod two_factor;
+
+/// Access tokens are short-lived; the session is kept alive through the refresh token.
+const TOKEN_VALIDITY_MINS: i64 = 15;
+
+pub struct JwtGuard {
+    claims: Claims,
+    /// Only set when the request is authenticated with a personal access token
+    scopes: Option<Vec<Scope>>,
+    /// The admin acting as the user, when the request is made while impersonating
+    impersonator: Option<PublicUser>,
+}
+
+impl JwtGuard {
+    pub fn get_user(&self) -> PublicUser {
+        self.claims.user.clone()
+    }
+
+    /// The ID of the session, or of the access token the request was authenticated with.
+    pub fn get_session_id(&self) -> Uuid {
+        self.claims.sid
+    }
+
+    pub fn is_access_token(&self) -> bool {
+        self.scopes.is_some()
+    }
+
+    /// Rejects requests made with an access token, for actions that need a real login; like
+    /// managing the access tokens themselves.
+    pub fn require_session(&self) -> Result<(), Error<Null>> {
+        match self.is_access_token() {
+            true => Err(ApiResponse::unauthorized(
+                "Not allowed with an access token".to_string(),
+            )),
+            false => Ok(()),
+        }
+    }
+
+    pub fn get_impersonator(&self) -> Option<PublicUser> {
+        self.impersonator.clone()
+    }
+
+    /// Rejects requests made while impersonating a user, for actions an admin must not take in
+    /// the name of someone else; like deleting users or changing credentials.
+    pub fn deny_impersonation(&self) -> Result<(), Error<Null>> {
+        match self.impersonator {
+            Some(_) => Err(ApiResponse::error(
+                Status::Forbidden,
+                "Not allowed while impersonating a user".to_string(),
+                None,
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Starts a session for the admin to act as the user. Returns an access token, to be sent as
+    /// bearer token, which can't be refreshed and expires along with the session.
+    pub async fn impersonate(
+        user: &User,
+        admin: &PublicUser,
+        client: &ClientInfo,
+        keys: &JwtKeys,
+        redis: &State<RedisMutex>,
+    ) -> Result<(String, NaiveDateTime), String> {
+        let session = Session::impersonate(user.id, admin.id, client);
+
+        cache::sessions::add_session(redis, &session)
+            .await
+            .map_err(|e| e.1.message.clone())?;
+
+        let validity = cache::sessions::IMPERSONATION_TTL.unwrap_or_default() as i64;
+        let mut claims = Claims::new(PublicUser::from(user), session.id, validity / 60);
+        claims.impersonator = Some(admin.clone());
+
+        let expires_at = chrono::DateTime::from_timestamp(claims.exp as i64, 0)
+            .unwrap_or_default()
+            .naive_utc();
+
+        keys.encode(&claims).map(|token| (token, expires_at))
+    }
+
+    /// Starts a new session for the user and adds the access and refresh token cookies.
+    pub async fn secure(
+        user: &User,
+        client: &ClientInfo,
+        cookies: &CookieJar<'_>,
+        keys: &JwtKeys,
+        redis: &State<RedisMutex>,
+    ) -> Result<(), String> {
+        let session = Session::new(user.id, client);
+
+        // Store the session before handing out the tokens
+        cache::sessions::add_session(redis, &session)
+            .await
+            .map_err(|e| e.1.message.clone())?;
+
+        Self::issue_tokens(user, session, cookies, keys)
+    }
+
+    /// Rotates the refresh token of an existing session and issues a new access token.
+    ///
+    /// Returns `false`, without issuing anything, if the session was revoked since it was read;
+    /// it isn't brought back by the rotation.
+    pub async fn renew(
+        user: &User,
+        mut session: Session,
+        client: &ClientInfo,
+        cookies: &CookieJar<'_>,
+        keys: &JwtKeys,
+        redis: &State<RedisMutex>,
+    ) -> Result<bool, String> {
+        session.rotate();
+        session.touch(client);
+
+        // Store the new refresh secret before handing out the tokens
+        let renewed = cache::sessions::replace_session(redis, &session)
+            .await
+            .map_err(|e| e.1.message.clone())?;
+
+        if renewed {
+            Self::issue_tokens(user, session, cookies, keys)?;
+        }
+
+        Ok(renewed)
+    }
+
+    /// Removes the token cookies and the CSRF cookie.
+    pub fn clear(cookies: &CookieJar<'_>) {
+        cookies.remove_private(TOKEN_COOKIE);
+        cookies.remove_private(REFRESH_COOKIE);
+        cookies.remove(Cookie::build(CSRF_COOKIE).path("/"));
+    }
+
+    fn issue_tokens(
+        user: &User,
+        session: Session,
+        cookies: &CookieJar<'_>,
+        keys: &JwtKeys,
+    ) -> Result<(), String> {
+        let token = Self::generate_token(PublicUser::from(user), session.id, keys)?;
+
+        cookies.add_private(Self::build_cookie(TOKEN_COOKIE, token));
+        cookies.add_private(Self::build_cookie(REFRESH_COOKIE, session.refresh_token()));
+
+        // Not private and not HTTP only, as the frontend has to read it
+        cookies.add(
+            Cookie::build((CSRF_COOKIE, session.csrf_token))
+                .same_site(SameSite::Lax)
+                .secure(false) // TODO!: Set to 'true' along with the token cookies
+                .path("/")
+                .build(),
+        );
+
+        Ok(())
+    }
+
+    fn build_cookie(name: &'static str, value: String) -> Cookie<'static> {
+        Cookie::build((name, value))
+            .http_only(true) // Prevent JavaScript access (mitigates XSS)
+            .same_site(SameSite::Lax) // Lax so frontend can reach it. SameSite to prevent CSRF attacks
+            .secure(false) // TODO!: Set to 'true': Only send cookie over HTTPS
+            .path("/") // Available site-wide
+            .build()
+    }
+
+    fn generate_token(user: PublicUser, session: Uuid, keys: &JwtKeys) -> Result<String, String> {
+        keys.encode(&Claims::new(user, session, TOKEN_VALIDITY_MINS))
+    }
+}
+
+#[async_trait]
+impl<'r> FromRequest<'r> for JwtGuard {
+    type Error = String;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        // Attempt to extract the JWT token from cookies or Authorization header
+        let cookies = request.cookies();
+        let token_cookie = cookies.get_private(TOKEN_COOKIE);
+
+        // Browsers send cookies along with cross-site requests, but not headers
+        let from_cookie = token_cookie.is_some();
+
+        let token = match token_cookie {
+            Some(cookie) => cookie.value().to_string(),
+            // If no token is found in cookies, look for it in the Authorization header
+            None => match request.headers().get_one("Authorization") {
+                Some(header_value) if header_value.starts_with("Bearer ") => {
+                    header_value[7..].to_string() // Extract the token part (skip "Bearer ")
+                }
+                _ => {
+                    return Outcome::Error((Status::Unauthorized, "No token provided".to_string()))
+                }
+            },
+        };
+
+        // Personal access tokens are looked up in the database instead of being decoded
+        if token.starts_with(ACCESS_TOKEN_PREFIX) {
+            return match Self::from_access_token(request, &token).await {
+                Ok(guard) => Outcome::Success(guard),
+                Err(e) => Outcome::Error((e.0, e.1.message.clone())),
+            };
+        }
+
+        // The keys are managed by the JWT fairing
+        let Some(keys) = request.rocket().state::<JwtKeys>() else {
+            return Outcome::Error((
+                Status::InternalServerError,
+                "JWT keys not configured".to_string(),
+            ));
+        };
+
+        // Validate the token by verifying its signature and checking the expiration
+        let claims = match Claims::decode_and_validate(&token, keys) {
+            Ok(decoded) => decoded.claims,
+            Err(e) => return Outcome::Error(e),
+        };
+
+        let redis = match request.guard::<&State<RedisMutex>>().await {
+            Outcome::Success(redis) => redis,
+            _ => {
+                return Outcome::Error((
+                    Status::InternalServerError,
+                    "Session store not available".to_string(),
+                ))
+            }
+        };
+
+        // The session must still exist; it is removed on logout and revocation
+        let session = match cache::sessions::get_session(redis, claims.sid).await {
+            Ok(Some(session)) if session.user == claims.sub => session,
+            Ok(_) => return Outcome::Error((Status::Unauthorized, "Session revoked".to_string())),
+            Err(e) => return Outcome::Error((Status::InternalServerError, e.1.message.clone())),
+        };
+
+        // An impersonation is bound to its session, and ends when the admin is no longer one
+        let impersonator = match (&claims.impersonator, session.impersonator) {
+            (None, None) => None,
+            (Some(admin), Some(admin_id)) if admin.id == admin_id => {
+                request.local_cache(|| {
+                    Some(Impersonation {
+                        session_id: session.id,
+                        admin_id,
+                        user_id: session.user,
+                    })
+                });
+
+                match Self::current_user(request, redis, admin_id).await {
+                    Ok(admin) if admin.is_admin() => Some(admin),
+                    Ok(_) => {
+                        return Outcome::Error((
+                            Status::Unauthorized,
+                            "Impersonation no longer allowed".to_string(),
+                        ))
+                    }
+                    Err(e) => return Outcome::Error((e.0, e.1.message.clone())),
+                }
+            }
+            _ => return Outcome::Error((Status::Unauthorized, "Session revoked".to_string())),
+        };
+
+        // A mutating request authenticated by cookie has to prove it comes from the frontend
+        let safe_method = matches!(
+            request.method(),
+            Method::Get | Method::Head | Method::Options
+        );
+
+        if from_cookie
+            && !safe_method
+            && !session.verify_csrf_token(request.headers().get_one(CSRF_HEADER))
+        {
+            return Outcome::Error((Status::Forbidden, "Invalid CSRF token".to_string()));
+        }
+
+        // Don't trust the user in the token; the role or status may have changed since
+        let mut claims = claims;
+        claims.user = match Self::current_user(request, redis, claims.sub).await {
+            Ok(user) => user,
+            Err(e) => return Outcome::Error((e.0, e.1.message.clone())),
+        };
+
+        Outcome::Success(JwtGuard {
+            claims,
+            scopes: None,
+            impersonator,
+        })
+    }
+}
+
+impl JwtGuard {
+    /// Resolves the current state of the user from the cache, or from the database if it isn't
+    /// cached. Only active users are accepted.
+    async fn current_user(
+        request: &Request<'_>,
+        redis: &State<RedisMutex>,
+        user_id: Uuid,
+    ) -> Result<PublicUser, Error<Null>> {
+        let user = match cache::users::get_user_cache(redis, user_id).await {
+            Ok(Some(user)) => user,
+            _ => {
+                let Outcome::Success(db) = request.guard::<Db>().await else {
+                    return Err(ApiResponse::internal_server_error(
+                        "Database not available".to_string(),
+                    ));
+                };
+
+                let user = PublicUser::from(&database::users::get_user_by_id(&db, user_id).await?);
+                cache::users::add_user_cache(redis, &user).await;
+                user
+            }
+        };
+
+        if user.status != UserStatus::Active {
+            return Err(ApiResponse::unauthorized(format!(
+                "User '{}' is not active",
+                user.username
+            )));
+        }
+
+        Ok(user)
+    }
+
+    async fn from_access_token(request: &Request<'_>, token: &str) -> Result<Self, Error<Null>> {
+        let db = match request.guard::<Db>().await {
+            Outcome::Success(db) => db,
+            _ => {
+                return Err(ApiResponse::internal_server_error(
+                    "Database not available".to_string(),
+                ))
+            }
+        };
+
+        let access_token =
+            database::access_tokens::get_access_token_by_hash(&db, AccessToken::hash(token))
+                .await
+                .map_err(|_| ApiResponse::unauthorized("Invalid access token".to_string()))?;
+
+        let now = chrono::Utc::now().naive_utc();
+        if access_token.is_expired(now) {
+            return Err(ApiResponse::unauthorized(
+                "Access token has expired".to_string(),
+            ));
+        }
+
+        // The scopes limit what the token can do; the role of the user is checked by the routes
+        let scopes = access_token.get_scopes();
+        if let Some(route) = request.route() {
+            if let Some(required) = Scope::required_for(request.method(), route.uri.base()) {
+                Policy::access_tokens_scope(&scopes, &required)?;
+            }
+        }
+
+        // The token stops working as soon as the user is no longer active
+        let user = database::users::get_user_by_id(&db, access_token.user_id).await?;
+        if user.status != UserStatus::Active {
+            return Err(ApiResponse::unauthorized(format!(
+                "User '{}' is not active",
+                user.username
+            )));
+        }
+
+        database::access_tokens::update_last_used(&db, access_token.id, now).await?;
+
+        Ok(JwtGuard {
+            claims: Claims {
+                sub: user.id,
+                user: PublicUser::from(&user),
+                sid: access_token.id,
+                exp: access_token.expires_at.and_utc().timestamp() as usize,
+                impersonator: None,
+            },
+            scopes: Some(scopes),
+            impersonator: None,
+        })
+    }
+}
+
+/// Represents the JWT claims stored in a token.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Claims {
+    /// The subject of the token (User ID).
+    pub sub: Uuid,
+    /// The public information of the user at the moment the token was issued.
+    pub user: PublicUser,
+    /// The ID of the [`Session`] the token was issued for.
+    pub sid: Uuid,
+    /// Expiration timestamp (Unix epoch).
+    pub exp: usize,
+    /// The admin acting as the user; only in tokens issued for an impersonation.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub impersonator: Option<PublicUser>,
+}
+
+impl Claims {
+    /// Claims for the user in the session, valid for the given number of minutes.
+    fn new(user: PublicUser, session: Uuid, validity_mins: i64) -> Self {
+        let expiration = chrono::Utc::now()
+            .checked_add_signed(chrono::Duration::minutes(validity_mins))
+            .expect("valid timestamp")
+            .timestamp() as usize;
+
+        Claims {
+            sub: user.id,
+            user,
+            sid: session,
+            exp: expiration,
+            impersonator: None,
+        }
+    }
+
+    /// This function verifies the signature of the JWT and checks its expiration.
+    pub fn decode_and_validate(
+        token: &str,
+        keys: &JwtKeys,
+    ) -> Result<TokenData<Claims>, (Status, String)> {
+        keys.decode::<Claims>(token)
+    }
+}
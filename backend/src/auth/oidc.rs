@@ -0,0 +1,444 @@
+//! Single sign-on through an OpenID Connect provider, with the authorization code flow and PKCE.
+//!
+//! The provider is read from the `oidc` table of the Rocket configuration, or from the `OIDC_`
+//! environment variables (e.g. `OIDC_ISSUER`, `OIDC_CLIENT_ID`). Without it, single sign-on is
+//! disabled. The endpoints of the provider are discovered from the issuer.
+//!
+//! ```toml
+//! [default.oidc]
+//! issuer = "https://login.example.com"
+//! client_id = "rustle"
+//! client_secret = "..."
+//! redirect_uri = "https://rustle.example.com/user/login/oidc/callback"
+//! post_login_redirect = "https://rustle.example.com/dashboard"
+//! # Create users that log in for the first time, instead of refusing them
+//! provision_users = true
+//! default_role = "Reviewer"
+//! # Only needed to narrow down the algorithms the provider supports, or to allow ID tokens
+//! # signed with the client secret (HS256, HS384 or HS512)
+//! id_token_signing_algs = ["RS256"]
+//! ```
+use std::collections::HashSet;
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::Utc;
+use jsonwebtoken::{decode, decode_header, jwk::JwkSet, Algorithm, DecodingKey, Validation};
+use reqwest::Url;
+use rocket::fairing::Fairing;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use uuid::Uuid;
+
+use crate::{
+    api::{ApiResponse, Error, Null},
+    cache::create_random_token,
+    database::{identities as database, users as users_database, Db},
+    forms::password::Password,
+    models::{
+        identities::UserIdentity,
+        users::{User, UserRole, UserStatus},
+    },
+};
+
+const DISCOVERY_PATH: &str = "/.well-known/openid-configuration";
+const CODE_VERIFIER_LENGTH: usize = 64;
+const STATE_LENGTH: usize = 32;
+
+#[derive(Debug, Deserialize)]
+pub struct OidcConfig {
+    /// Identifies the provider; the endpoints are discovered from it
+    pub issuer: String,
+    pub client_id: String,
+    /// Sent to the token endpoint, and the key of ID tokens signed with HS256, HS384 or HS512
+    pub client_secret: Option<String>,
+    /// The callback route, as registered at the provider
+    pub redirect_uri: String,
+    #[serde(default = "default_scopes")]
+    pub scopes: Vec<String>,
+    /// Where the browser is sent after a successful login
+    #[serde(default = "default_post_login_redirect")]
+    pub post_login_redirect: String,
+    /// Create a user for an identity that can't be matched to an existing user
+    #[serde(default)]
+    pub provision_users: bool,
+    /// The role of provisioned users
+    #[serde(default)]
+    pub default_role: UserRole,
+    /// The algorithms ID tokens may be signed with; by default the asymmetric ones the provider
+    /// supports. The client secret is only a key if one of the HS algorithms is listed here.
+    #[serde(default)]
+    pub id_token_signing_algs: Option<Vec<Algorithm>>,
+}
+
+fn default_scopes() -> Vec<String> {
+    vec![
+        "openid".to_string(),
+        "email".to_string(),
+        "profile".to_string(),
+    ]
+}
+
+fn default_post_login_redirect() -> String {
+    "/".to_string()
+}
+
+/// The provider as managed by Rocket; `None` if single sign-on is not configured.
+pub struct OidcProvider {
+    config: Option<OidcConfig>,
+    http: reqwest::Client,
+}
+
+/// The endpoints of the provider, from its discovery document.
+#[derive(Debug, Deserialize)]
+struct ProviderMetadata {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    jwks_uri: String,
+    #[serde(default)]
+    id_token_signing_alg_values_supported: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    id_token: String,
+}
+
+/// What's kept between sending the user to the provider and the callback; stored by `state`.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct OidcLogin {
+    pub nonce: String,
+    pub code_verifier: String,
+}
+
+/// The claims of a validated ID token.
+#[derive(Debug, Deserialize)]
+pub struct IdTokenClaims {
+    pub iss: String,
+    pub sub: String,
+    pub nonce: Option<String>,
+    pub email: Option<String>,
+    pub email_verified: Option<bool>,
+    pub preferred_username: Option<String>,
+    pub given_name: Option<String>,
+    pub family_name: Option<String>,
+}
+
+impl IdTokenClaims {
+    /// The email address, only if the provider says it's verified; a provider that doesn't send
+    /// `email_verified` can't be trusted with the address.
+    pub fn verified_email(&self) -> Option<&str> {
+        match self.email_verified {
+            Some(true) => self.email.as_deref(),
+            _ => None,
+        }
+    }
+}
+
+impl OidcProvider {
+    pub fn config(&self) -> Result<&OidcConfig, Error<Null>> {
+        self.config
+            .as_ref()
+            .ok_or_else(|| ApiResponse::not_found("Single sign-on is not configured".to_string()))
+    }
+
+    /// Returns the URL of the provider to send the user to, along with the `state` and what has
+    /// to be kept for the callback.
+    pub async fn authorization_url(&self) -> Result<(String, String, OidcLogin), Error<Null>> {
+        let config = self.config()?;
+        let metadata = self.discover().await?;
+
+        let state = create_random_token(STATE_LENGTH);
+        let login = OidcLogin {
+            nonce: create_random_token(STATE_LENGTH),
+            code_verifier: create_random_token(CODE_VERIFIER_LENGTH),
+        };
+
+        let url = Url::parse_with_params(
+            &metadata.authorization_endpoint,
+            &[
+                ("response_type", "code"),
+                ("client_id", &config.client_id),
+                ("redirect_uri", &config.redirect_uri),
+                ("scope", &config.scopes.join(" ")),
+                ("state", &state),
+                ("nonce", &login.nonce),
+                ("code_challenge", &code_challenge(&login.code_verifier)),
+                ("code_challenge_method", "S256"),
+            ],
+        )
+        .map_err(|e| bad_gateway(format!("Invalid authorization endpoint: {e}")))?;
+
+        Ok((url.to_string(), state, login))
+    }
+
+    /// Exchanges the code from the callback for an ID token and returns its validated claims.
+    pub async fn exchange_code(
+        &self,
+        code: &str,
+        login: &OidcLogin,
+    ) -> Result<IdTokenClaims, Error<Null>> {
+        let config = self.config()?;
+        let metadata = self.discover().await?;
+
+        let mut params = vec![
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", &config.redirect_uri),
+            ("client_id", &config.client_id),
+            ("code_verifier", &login.code_verifier),
+        ];
+
+        if let Some(secret) = &config.client_secret {
+            params.push(("client_secret", secret));
+        }
+
+        let response = self
+            .http
+            .post(&metadata.token_endpoint)
+            .form(&params)
+            .send()
+            .await
+            .map_err(|e| bad_gateway(format!("Token request failed: {e}")))?;
+
+        if !response.status().is_success() {
+            return Err(ApiResponse::unauthorized(format!(
+                "Provider refused the code: {}",
+                response.status()
+            )));
+        }
+
+        let tokens = response
+            .json::<TokenResponse>()
+            .await
+            .map_err(|e| bad_gateway(format!("Invalid token response: {e}")))?;
+
+        let claims = self.validate_id_token(&tokens.id_token, &metadata).await?;
+
+        if claims.nonce.as_deref() != Some(login.nonce.as_str()) {
+            return Err(ApiResponse::unauthorized("Invalid nonce".to_string()));
+        }
+
+        Ok(claims)
+    }
+
+    async fn validate_id_token(
+        &self,
+        id_token: &str,
+        metadata: &ProviderMetadata,
+    ) -> Result<IdTokenClaims, Error<Null>> {
+        let config = self.config()?;
+        let invalid = |e: jsonwebtoken::errors::Error| {
+            ApiResponse::unauthorized(format!("Invalid ID token: {e}"))
+        };
+
+        let header = decode_header(id_token).map_err(invalid)?;
+
+        // The header can't pick the algorithm, or an HS one would make the client secret the key
+        if !allowed_algorithms(config, metadata).contains(&header.alg) {
+            return Err(ApiResponse::unauthorized(format!(
+                "ID token signed with {:?}, which isn't allowed",
+                header.alg
+            )));
+        }
+
+        // Symmetric signatures use the client secret, asymmetric ones a key of the provider
+        let key = match header.alg {
+            Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512 => {
+                let secret = config.client_secret.as_ref().ok_or_else(|| {
+                    ApiResponse::unauthorized("ID token signed without client secret".to_string())
+                })?;
+                DecodingKey::from_secret(secret.as_bytes())
+            }
+            _ => {
+                let jwks = self
+                    .get_json::<JwkSet>(&metadata.jwks_uri)
+                    .await
+                    .map_err(|e| bad_gateway(format!("Couldn't fetch the provider keys: {e}")))?;
+
+                let jwk = match &header.kid {
+                    Some(kid) => jwks.find(kid),
+                    None if jwks.keys.len() == 1 => jwks.keys.first(),
+                    None => None,
+                }
+                .ok_or_else(|| ApiResponse::unauthorized("Unknown signing key".to_string()))?;
+
+                DecodingKey::from_jwk(jwk).map_err(invalid)?
+            }
+        };
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_audience(&[&config.client_id]);
+        validation.set_issuer(&[&metadata.issuer]);
+
+        decode::<IdTokenClaims>(id_token, &key, &validation)
+            .map(|data| data.claims)
+            .map_err(invalid)
+    }
+
+    async fn discover(&self) -> Result<ProviderMetadata, Error<Null>> {
+        let issuer = self.config()?.issuer.trim_end_matches('/');
+
+        let metadata = self
+            .get_json::<ProviderMetadata>(&format!("{issuer}{DISCOVERY_PATH}"))
+            .await
+            .map_err(|e| bad_gateway(format!("Provider discovery failed: {e}")))?;
+
+        if metadata.issuer.trim_end_matches('/') != issuer {
+            return Err(bad_gateway(format!(
+                "Provider reports a different issuer: {}",
+                metadata.issuer
+            )));
+        }
+
+        Ok(metadata)
+    }
+
+    async fn get_json<T: serde::de::DeserializeOwned>(&self, url: &str) -> reqwest::Result<T> {
+        self.http
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<T>()
+            .await
+    }
+}
+
+/// Returns the user of the identity in the claims.
+///
+/// An identity that isn't linked yet is linked to the user with the same email address, if the
+/// provider verified it. Otherwise a new user is provisioned, if the configuration allows it.
+pub async fn find_or_provision_user(
+    db: &Db,
+    config: &OidcConfig,
+    claims: &IdTokenClaims,
+) -> Result<User, Error<Null>> {
+    if let Some(user) = database::get_user_by_identity(db, &claims.iss, &claims.sub).await? {
+        return Ok(user);
+    }
+
+    let existing = match claims.verified_email() {
+        Some(email) => users_database::get_user_by_email(db, email).await.ok(),
+        None => None,
+    };
+
+    let user = match existing {
+        Some(user) => user,
+        None if config.provision_users => provision_user(db, config, claims).await?,
+        None => {
+            return Err(ApiResponse::unauthorized(
+                "No user is linked to this identity".to_string(),
+            ))
+        }
+    };
+
+    let identity = UserIdentity::new(&claims.iss, &claims.sub, user.id, claims.email.clone());
+    database::insert_identity(db, identity).await?;
+
+    Ok(user)
+}
+
+/// Creates an active user for the claims, with the default role and an unusable random password.
+async fn provision_user(
+    db: &Db,
+    config: &OidcConfig,
+    claims: &IdTokenClaims,
+) -> Result<User, Error<Null>> {
+    let email = claims.verified_email().ok_or_else(|| {
+        ApiResponse::unauthorized("A verified email address is required".to_string())
+    })?;
+
+    // The preferred username, or the local part of the email address, made unique
+    let base_username = claims
+        .preferred_username
+        .as_deref()
+        .unwrap_or_else(|| email.split('@').next().unwrap_or_default())
+        .to_lowercase()
+        .replace(|c: char| !c.is_ascii_alphanumeric() && c != '_', "_");
+
+    let existing_usernames =
+        users_database::get_username_duplicates(db, &HashSet::from([base_username.clone()]))
+            .await?;
+
+    let username = std::iter::once(base_username.clone())
+        .chain((1..).map(|n| format!("{base_username}_{n}")))
+        .find(|username| !existing_usernames.contains(username))
+        .unwrap_or(base_username);
+
+    let password = Password::generate(None)
+        .map_err(|e| ApiResponse::internal_server_error(format!("Coudn't hash password: {e}")))?;
+    let timestamp = Utc::now().naive_utc();
+
+    let user = User {
+        id: Uuid::new_v4(),
+        username,
+        first_name: claims.given_name.clone().unwrap_or_default(),
+        last_name: claims.family_name.clone().unwrap_or_default(),
+        email: email.to_string(),
+        role: config.default_role,
+        status: UserStatus::Active,
+        password,
+        created_at: timestamp,
+        updated_at: timestamp,
+        ..Default::default()
+    };
+
+    users_database::inject_user(db, user.clone())
+        .await
+        .map_err(ApiResponse::from_error)?;
+
+    Ok(user)
+}
+
+/// The PKCE `S256` challenge of the verifier.
+fn code_challenge(code_verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()))
+}
+
+/// The configured algorithms, or else the ones the provider supports that don't use the client
+/// secret; RS256 if the provider doesn't say, as every provider has to support it.
+fn allowed_algorithms(config: &OidcConfig, metadata: &ProviderMetadata) -> Vec<Algorithm> {
+    if let Some(algorithms) = &config.id_token_signing_algs {
+        return algorithms.clone();
+    }
+
+    if metadata.id_token_signing_alg_values_supported.is_empty() {
+        return vec![Algorithm::RS256];
+    }
+
+    metadata
+        .id_token_signing_alg_values_supported
+        .iter()
+        .filter_map(|alg| alg.parse::<Algorithm>().ok())
+        .filter(|alg| !matches!(alg, Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512))
+        .collect()
+}
+
+fn bad_gateway(message: String) -> Error<Null> {
+    ApiResponse::error(rocket::http::Status::BadGateway, message, None)
+}
+
+pub fn oidc_fairing() -> impl Fairing {
+    rocket::fairing::AdHoc::try_on_ignite("OIDC provider", |rocket| async {
+        let config = match rocket.figment().find_value("oidc") {
+            Ok(_) => rocket
+                .figment()
+                .extract_inner::<OidcConfig>("oidc")
+                .map(Some),
+            Err(_) => Ok(None),
+        };
+
+        match config {
+            Ok(config) => Ok(rocket.manage(OidcProvider {
+                config,
+                http: reqwest::Client::new(),
+            })),
+            Err(e) => {
+                eprintln!("Failed to load OIDC config: {e}");
+                Err(rocket)
+            }
+        }
+    })
+}
@@ -0,0 +1,197 @@
+//! Password hashing and policy.
+//!
+//! The Argon2 parameters and the policy for new passwords are read from the `password` table of
+//! the Rocket configuration, or from `PASSWORD_` prefixed environment variables (e.g.
+//! `PASSWORD_MIN_LENGTH`). Every key is optional:
+//!
+//! ```toml
+//! [default.password]
+//! memory_kib = 19456
+//! iterations = 2
+//! parallelism = 1
+//! min_length = 8
+//! required_classes = ["Lowercase", "Uppercase", "Digit", "Symbol"]
+//! # Replaces the bundled list; one password per line
+//! common_passwords = "/etc/rustle/common_passwords.txt"
+//! ```
+//!
+//! Hashes are made where no request state is available (e.g. form validation and generated
+//! passwords), so the configuration is kept process-wide; the first Rocket instance to ignite
+//! sets it, and an instance configured otherwise fails to ignite rather than use weaker values.
+use std::{collections::HashSet, path::PathBuf, sync::OnceLock};
+
+use argon2::{Algorithm, Argon2, Params, PasswordHash, Version};
+use rocket::fairing::Fairing;
+use serde::Deserialize;
+
+/// Passwords that are rejected when no other list is configured.
+const BUNDLED_COMMON_PASSWORDS: &str = include_str!("common_passwords.txt");
+
+static PASSWORD_CONFIG: OnceLock<PasswordConfig> = OnceLock::new();
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+pub enum CharacterClass {
+    Lowercase,
+    Uppercase,
+    Digit,
+    Symbol,
+}
+
+impl CharacterClass {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            CharacterClass::Lowercase => c.is_lowercase(),
+            CharacterClass::Uppercase => c.is_uppercase(),
+            CharacterClass::Digit => c.is_ascii_digit(),
+            CharacterClass::Symbol => !c.is_alphanumeric() && !c.is_whitespace(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct PasswordConfig {
+    /// Memory cost of Argon2, in KiB
+    pub memory_kib: u32,
+    /// Time cost of Argon2, the number of passes over the memory
+    pub iterations: u32,
+    /// Degree of parallelism of Argon2
+    pub parallelism: u32,
+    /// Minimum number of characters of a new password
+    pub min_length: usize,
+    /// A new password must contain at least one character of each class
+    pub required_classes: Vec<CharacterClass>,
+    /// File with passwords that are too common to be used, instead of the bundled list
+    pub common_passwords: Option<PathBuf>,
+    /// The passwords of the list, in lowercase
+    #[serde(skip)]
+    pub common_password_set: HashSet<String>,
+}
+
+impl Default for PasswordConfig {
+    fn default() -> Self {
+        PasswordConfig {
+            memory_kib: Params::DEFAULT_M_COST,
+            iterations: Params::DEFAULT_T_COST,
+            parallelism: Params::DEFAULT_P_COST,
+            min_length: 8,
+            required_classes: Vec::new(),
+            common_passwords: None,
+            common_password_set: parse_common_passwords(BUNDLED_COMMON_PASSWORDS),
+        }
+    }
+}
+
+impl PasswordConfig {
+    /// The configuration in use, or the defaults if none has been loaded.
+    pub fn get() -> &'static PasswordConfig {
+        PASSWORD_CONFIG.get_or_init(PasswordConfig::default)
+    }
+
+    fn params(&self) -> Result<Params, argon2::Error> {
+        Params::new(self.memory_kib, self.iterations, self.parallelism, None)
+    }
+
+    /// Argon2id with the configured parameters.
+    pub fn argon2(&self) -> Result<Argon2<'static>, argon2::Error> {
+        Ok(Argon2::new(
+            Algorithm::Argon2id,
+            Version::V0x13,
+            self.params()?,
+        ))
+    }
+
+    /// Whether a stored PHC string was made with an other algorithm, or with a lower memory or
+    /// time cost than configured, and should be replaced by a new hash.
+    pub fn needs_rehash(&self, stored_hash: &str) -> bool {
+        let Ok(hash) = PasswordHash::new(stored_hash) else {
+            return false;
+        };
+
+        let Ok(params) = Params::try_from(&hash) else {
+            return true;
+        };
+
+        hash.algorithm != Algorithm::Argon2id.ident()
+            || hash.version != Some(Version::V0x13.into())
+            || params.m_cost() < self.memory_kib
+            || params.t_cost() < self.iterations
+    }
+
+    /// Checks a new password against the length, the required character classes and the list
+    /// of common passwords. Returns the reason it is refused.
+    pub fn check_policy(&self, password: &str) -> Result<(), String> {
+        if password.chars().count() < self.min_length {
+            return Err(format!(
+                "Password must be at least {} characters",
+                self.min_length
+            ));
+        }
+
+        if let Some(class) = self
+            .required_classes
+            .iter()
+            .find(|class| !password.chars().any(|c| class.matches(c)))
+        {
+            return Err(format!("Password must contain a {class:?} character"));
+        }
+
+        if self
+            .common_password_set
+            .contains(&password.trim().to_lowercase())
+        {
+            return Err("Password is too common".to_string());
+        }
+
+        Ok(())
+    }
+
+    fn load(mut self) -> Result<Self, String> {
+        self.params()
+            .map_err(|e| format!("Invalid Argon2 parameters: {e}"))?;
+
+        if let Some(path) = &self.common_passwords {
+            let list = std::fs::read_to_string(path)
+                .map_err(|e| format!("Couldn't read '{}': {e}", path.display()))?;
+            self.common_password_set = parse_common_passwords(&list);
+        }
+
+        Ok(self)
+    }
+}
+
+fn parse_common_passwords(list: &str) -> HashSet<String> {
+    list.lines()
+        .map(|line| line.trim().to_lowercase())
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+pub fn password_fairing() -> impl Fairing {
+    rocket::fairing::AdHoc::try_on_ignite("Password config", |rocket| async {
+        let config = match rocket.figment().find_value("password") {
+            Ok(_) => rocket
+                .figment()
+                .extract_inner::<PasswordConfig>("password")
+                .map_err(|e| e.to_string()),
+            Err(_) => Ok(PasswordConfig::default()),
+        }
+        .and_then(PasswordConfig::load);
+
+        match config {
+            Ok(config) => match PASSWORD_CONFIG.set(config) {
+                Ok(()) => Ok(rocket),
+                // Already set by an earlier instance, e.g. in the tests, or read before ignition
+                Err(config) if PasswordConfig::get() == &config => Ok(rocket),
+                Err(_) => {
+                    eprintln!("Failed to load password config: another one is already in use");
+                    Err(rocket)
+                }
+            },
+            Err(e) => {
+                eprintln!("Failed to load password config: {e}");
+                Err(rocket)
+            }
+        }
+    })
+}
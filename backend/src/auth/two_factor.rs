@@ -0,0 +1,124 @@
+//! Second factor (TOTP) authentication.
+//!
+//! The roles for which a second factor is mandatory are read from the `two_factor` table of the
+//! Rocket configuration, or from `TWO_FACTOR_REQUIRED_ROLES` (e.g. `[Admin, Manager]`):
+//!
+//! ```toml
+//! [default.two_factor]
+//! required_roles = ["Admin", "Manager"]
+//! ```
+use rocket::{fairing::Fairing, State};
+use serde::Deserialize;
+
+use crate::{
+    api::{ApiResponse, Error, Null},
+    cache::{
+        self,
+        users::{add_two_factor_challenge, use_two_factor_code},
+        RedisMutex,
+    },
+    database::{two_factor as database, Db},
+    models::{
+        two_factor::{TwoFactor, TwoFactorChallenge},
+        users::{User, UserRole},
+    },
+};
+
+#[derive(Debug, Default, Deserialize)]
+pub struct TwoFactorConfig {
+    /// Users with one of these roles can't log in without a second factor
+    #[serde(default)]
+    pub required_roles: Vec<UserRole>,
+}
+
+impl TwoFactorConfig {
+    pub fn is_required(&self, role: UserRole) -> bool {
+        self.required_roles.contains(&role)
+    }
+}
+
+/// Starts a challenge if the user has a second factor, or their role requires one; the login is
+/// only complete once the challenge is completed at `/login/2fa`.
+pub async fn start_second_factor_challenge(
+    db: &Db,
+    redis: &State<RedisMutex>,
+    config: &TwoFactorConfig,
+    user: &User,
+) -> Result<Option<TwoFactorChallenge>, Error<Null>> {
+    let enabled = database::get_two_factor(db, user.id)
+        .await?
+        .is_some_and(|two_factor| two_factor.enabled);
+
+    if !enabled && !config.is_required(user.role) {
+        return Ok(None);
+    }
+
+    // Create a random token with a length of 64 characters
+    let challenge = cache::create_random_token(64);
+
+    // Add the challenge to the redis cache; containing the user ID
+    add_two_factor_challenge(redis, &challenge, user.id).await?;
+
+    Ok(Some(TwoFactorChallenge {
+        challenge,
+        enrollment_required: !enabled,
+    }))
+}
+
+/// Verifies a TOTP code, or, if `allow_recovery` is set, one of the recovery codes of an enabled
+/// second factor. TOTP codes can only be used once and recovery codes are removed after use.
+pub async fn verify_second_factor(
+    db: &Db,
+    redis: &State<RedisMutex>,
+    two_factor: &TwoFactor,
+    code: &str,
+    allow_recovery: bool,
+) -> Result<bool, Error<Null>> {
+    if two_factor
+        .verify_code(code)
+        .map_err(ApiResponse::internal_server_error)?
+    {
+        return use_two_factor_code(redis, two_factor.user_id, code).await;
+    }
+
+    if !(allow_recovery && two_factor.enabled) {
+        return Ok(false);
+    }
+
+    match two_factor.find_recovery_code(code) {
+        Some(index) => {
+            let mut recovery_codes = two_factor.recovery_codes.clone();
+            recovery_codes.remove(index);
+
+            // Only one request can use the code, the other finds the codes changed
+            let updated = database::update_recovery_codes(
+                db,
+                two_factor.user_id,
+                two_factor.recovery_codes.clone(),
+                recovery_codes,
+            )
+            .await?;
+            Ok(updated == 1)
+        }
+        None => Ok(false),
+    }
+}
+
+pub fn two_factor_fairing() -> impl Fairing {
+    rocket::fairing::AdHoc::try_on_ignite("Two-factor config", |rocket| async {
+        let config = match rocket.figment().find_value("two_factor") {
+            Ok(_) => rocket
+                .figment()
+                .extract_inner::<TwoFactorConfig>("two_factor"),
+            Err(_) => Ok(TwoFactorConfig::default()),
+        };
+
+        match config {
+            Ok(config) => Ok(rocket.manage(config)),
+            Err(e) => {
+                eprintln!("Failed to load two-factor config: {e}");
+                Err(rocket)
+            }
+        }
+    })
+}
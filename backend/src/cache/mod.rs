@@ -25,6 +25,7 @@ use crate::{
 };
 
 pub mod projects;
+pub mod sessions;
 pub mod users;
 pub mod workspaces;
 
@@ -34,8 +35,11 @@ pub type RedisMutex = Arc<Mutex<RedisPool>>;
 const FALLBACK_REDIS_URL: &str = "redis://127.0.0.1:6379";
 
 // TTL values
+pub const CACHE_TTL_5_MINUTES: Option<u64> = Some(300);
+pub const CACHE_TTL_30_MINUTES: Option<u64> = Some(1800);
 pub const CACHE_TTL_ONE_HOUR: Option<u64> = Some(3600);
 pub const CACHE_TTL_24_HOURS: Option<u64> = Some(86400);
+pub const CACHE_TTL_7_DAYS: Option<u64> = Some(604800);
 
 pub struct RedisPool {
     client: Client,
@@ -65,16 +69,9 @@ impl RedisPool {
             ApiResponse::internal_server_error(format!("Cache retrieval error: {e}"))
         })?;
 
-        // If data exists, deserialize it
-        match cached_data {
-            Some(data) => {
-                let deserialized_data: T = serde_json::from_str(&data).map_err(|e| {
-                    ApiResponse::conflict(format!("Failed to deserialize: {}", e), data)
-                })?;
-                Ok(Some(deserialized_data))
-            }
-            None => Ok(None),
-        }
+        // If data exists, deserialize it; data in an outdated format is treated as missing, so
+        // the caller refreshes it
+        Ok(cached_data.and_then(|data| serde_json::from_str(&data).ok()))
     }
 
     // Method to set data to Redis cache with optional TTL
@@ -113,6 +110,56 @@ impl RedisPool {
         Ok(())
     }
 
+    // Method to overwrite data in Redis cache with optional TTL, but only while it still exists
+    pub async fn replace_in_cache<T>(
+        &self,
+        key: &str,
+        value: &T,
+        ttl: Option<u64>,
+    ) -> Result<bool, Error<Null>>
+    where
+        T: Serialize,
+    {
+        let mut con = self.get_connection().await.map_err(|e| {
+            ApiResponse::internal_server_error(format!("Couldn't optain Redis connection: {e}"))
+        })?;
+
+        let serialized = serde_json::to_string(value)
+            .map_err(|e| ApiResponse::internal_server_error(format!("Failed to serialize: {e}")))?;
+
+        // XX makes the check and the write a single command
+        let mut command = redis::cmd("SET");
+        command.arg(key).arg(serialized).arg("XX");
+        if let Some(ttl_value) = ttl {
+            command.arg("EX").arg(ttl_value);
+        }
+
+        let replaced: Option<String> = command
+            .query_async(&mut con)
+            .await
+            .map_err(|e| ApiResponse::internal_server_error(format!("Redis SET error: {e}")))?;
+
+        Ok(replaced.is_some())
+    }
+
+    // Method to get and remove data from Redis cache at once, so only one caller gets it
+    pub async fn take_from_cache<T>(&self, key: &str) -> Result<Option<T>, Error<Null>>
+    where
+        T: DeserializeOwned,
+    {
+        let mut con = self.get_connection().await.map_err(|e| {
+            ApiResponse::internal_server_error(format!("Cache connection error: {e}"))
+        })?;
+
+        let cached_data: Option<String> = redis::cmd("GETDEL")
+            .arg(key)
+            .query_async(&mut con)
+            .await
+            .map_err(|e| ApiResponse::internal_server_error(format!("Redis GETDEL error: {e}")))?;
+
+        Ok(cached_data.and_then(|data| serde_json::from_str(&data).ok()))
+    }
+
     // Method to remove data to Redis cache
     pub async fn remove_from_cache(&self, key: &str) -> Result<(), Error<String>> {
         let mut conn = self.get_connection().await.map_err(|e| {
@@ -126,6 +173,82 @@ impl RedisPool {
 
         Ok(())
     }
+
+    // Method to increment a counter; the TTL is set when the counter is created
+    pub async fn increment(&self, key: &str, ttl: Option<u64>) -> Result<u64, Error<Null>> {
+        let mut conn = self.get_connection().await.map_err(|e| {
+            ApiResponse::internal_server_error(format!("Couldn't optain Redis connection: {e}"))
+        })?;
+
+        let count: u64 = conn
+            .incr(key, 1)
+            .await
+            .map_err(|e| ApiResponse::internal_server_error(format!("Redis INCR error: {e}")))?;
+
+        if let (1, Some(ttl_value)) = (count, ttl) {
+            let ttl_usize: usize = ttl_value.try_into().map_err(|e| {
+                ApiResponse::internal_server_error(format!("TTL conversion error: {e}"))
+            })?;
+            let _: () = conn.expire(key, ttl_usize).await.map_err(|e| {
+                ApiResponse::internal_server_error(format!("Redis EXPIRE error: {e}"))
+            })?;
+        }
+
+        Ok(count)
+    }
+
+    // Method to add a member to a Redis set, (re)setting the optional TTL of the whole set
+    pub async fn add_to_set(
+        &self,
+        key: &str,
+        member: &str,
+        ttl: Option<u64>,
+    ) -> Result<(), Error<Null>> {
+        let mut conn = self.get_connection().await.map_err(|e| {
+            ApiResponse::internal_server_error(format!("Couldn't optain Redis connection: {e}"))
+        })?;
+
+        let _: () = conn
+            .sadd(key, member)
+            .await
+            .map_err(|e| ApiResponse::internal_server_error(format!("Redis SADD error: {e}")))?;
+
+        if let Some(ttl_value) = ttl {
+            let ttl_usize: usize = ttl_value.try_into().map_err(|e| {
+                ApiResponse::internal_server_error(format!("TTL conversion error: {e}"))
+            })?;
+            let _: () = conn.expire(key, ttl_usize).await.map_err(|e| {
+                ApiResponse::internal_server_error(format!("Redis EXPIRE error: {e}"))
+            })?;
+        }
+
+        Ok(())
+    }
+
+    // Method to get all members of a Redis set
+    pub async fn get_set_members(&self, key: &str) -> Result<Vec<String>, Error<Null>> {
+        let mut conn = self.get_connection().await.map_err(|e| {
+            ApiResponse::internal_server_error(format!("Couldn't optain Redis connection: {e}"))
+        })?;
+
+        conn.smembers(key)
+            .await
+            .map_err(|e| ApiResponse::internal_server_error(format!("Redis SMEMBERS error: {e}")))
+    }
+
+    // Method to remove a member from a Redis set
+    pub async fn remove_from_set(&self, key: &str, member: &str) -> Result<(), Error<Null>> {
+        let mut conn = self.get_connection().await.map_err(|e| {
+            ApiResponse::internal_server_error(format!("Couldn't optain Redis connection: {e}"))
+        })?;
+
+        let _: () = conn
+            .srem(key, member)
+            .await
+            .map_err(|e| ApiResponse::internal_server_error(format!("Redis SREM error: {e}")))?;
+
+        Ok(())
+    }
 }
 
 pub fn redis_fairing() -> impl Fairing {
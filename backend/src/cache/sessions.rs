@@ -0,0 +1,186 @@
+use rocket::State;
+use uuid::Uuid;
+
+use crate::{
+    api::{Error, Null},
+    cache::{CACHE_TTL_30_MINUTES, CACHE_TTL_7_DAYS},
+    models::sessions::Session,
+};
+
+use super::RedisMutex;
+
+pub const CACHE_SESSION: &str = "session:";
+pub const CACHE_USER_SESSIONS: &str = "user_sessions:";
+
+/// Lifetime of a session without being refreshed.
+pub const SESSION_TTL: Option<u64> = CACHE_TTL_7_DAYS;
+/// Lifetime of an impersonation, which can't be refreshed.
+pub const IMPERSONATION_TTL: Option<u64> = CACHE_TTL_30_MINUTES;
+
+pub fn cache_key_session(session_id: Uuid) -> String {
+    format!("{CACHE_SESSION}{session_id}")
+}
+
+pub fn cache_key_user_sessions(user_id: Uuid) -> String {
+    format!("{CACHE_USER_SESSIONS}{user_id}")
+}
+
+/// Stores the session (resetting its TTL) and indexes it under the user, so all sessions of a
+/// user can be revoked at once.
+pub async fn add_session(redis: &State<RedisMutex>, session: &Session) -> Result<(), Error<Null>> {
+    let redis = redis.lock().await;
+
+    redis
+        .set_to_cache(
+            &cache_key_session(session.id),
+            session,
+            session_ttl(session),
+        )
+        .await?;
+
+    // The index lives as long as the longest session; impersonations don't shorten it
+    redis
+        .add_to_set(
+            &cache_key_user_sessions(session.user),
+            &session.id.to_string(),
+            SESSION_TTL,
+        )
+        .await
+}
+
+/// Stores the changed session like [`add_session`], but only if it wasn't revoked in the
+/// meantime; returns whether it was stored.
+pub async fn replace_session(
+    redis: &State<RedisMutex>,
+    session: &Session,
+) -> Result<bool, Error<Null>> {
+    let redis = redis.lock().await;
+
+    let replaced = redis
+        .replace_in_cache(
+            &cache_key_session(session.id),
+            session,
+            session_ttl(session),
+        )
+        .await?;
+
+    if replaced {
+        redis
+            .add_to_set(
+                &cache_key_user_sessions(session.user),
+                &session.id.to_string(),
+                SESSION_TTL,
+            )
+            .await?;
+    }
+
+    Ok(replaced)
+}
+
+fn session_ttl(session: &Session) -> Option<u64> {
+    match session.impersonator {
+        Some(_) => IMPERSONATION_TTL,
+        None => SESSION_TTL,
+    }
+}
+
+pub async fn get_session(
+    redis: &State<RedisMutex>,
+    session_id: Uuid,
+) -> Result<Option<Session>, Error<Null>> {
+    redis
+        .lock()
+        .await
+        .get_from_cache(&cache_key_session(session_id))
+        .await
+}
+
+/// Returns the sessions of the user that still exist; expired ones are removed from the index.
+pub async fn get_user_sessions(
+    redis: &State<RedisMutex>,
+    user_id: Uuid,
+) -> Result<Vec<Session>, Error<Null>> {
+    let redis = redis.lock().await;
+    let user_sessions = cache_key_user_sessions(user_id);
+    let mut sessions = Vec::new();
+
+    for session_id in redis.get_set_members(&user_sessions).await? {
+        let session = match Uuid::parse_str(&session_id) {
+            Ok(id) => {
+                redis
+                    .get_from_cache::<Session>(&cache_key_session(id))
+                    .await?
+            }
+            Err(_) => None,
+        };
+
+        match session {
+            Some(session) => sessions.push(session),
+            None => redis.remove_from_set(&user_sessions, &session_id).await?,
+        }
+    }
+
+    Ok(sessions)
+}
+
+pub async fn remove_session(
+    redis: &State<RedisMutex>,
+    user_id: Uuid,
+    session_id: Uuid,
+) -> Result<(), Error<Null>> {
+    let redis = redis.lock().await;
+
+    redis
+        .remove_from_cache(&cache_key_session(session_id))
+        .await?;
+
+    redis
+        .remove_from_set(&cache_key_user_sessions(user_id), &session_id.to_string())
+        .await
+}
+
+/// Revokes every session of the user, e.g. after a password change or a suspension.
+pub async fn remove_user_sessions(
+    redis: &State<RedisMutex>,
+    user_id: Uuid,
+) -> Result<(), Error<Null>> {
+    let redis = redis.lock().await;
+    let user_sessions = cache_key_user_sessions(user_id);
+
+    for session_id in redis.get_set_members(&user_sessions).await? {
+        if let Ok(session_id) = Uuid::parse_str(&session_id) {
+            redis
+                .remove_from_cache(&cache_key_session(session_id))
+                .await?;
+        }
+    }
+
+    redis.remove_from_cache(&user_sessions).await
+}
+
+/// Revokes every session of the user except the current one, e.g. after a password change.
+pub async fn remove_other_user_sessions(
+    redis: &State<RedisMutex>,
+    user_id: Uuid,
+    current_session: Uuid,
+) -> Result<(), Error<Null>> {
+    let redis = redis.lock().await;
+    let user_sessions = cache_key_user_sessions(user_id);
+    let current_session = current_session.to_string();
+
+    for session_id in redis.get_set_members(&user_sessions).await? {
+        if session_id == current_session {
+            continue;
+        }
+
+        if let Ok(session_id) = Uuid::parse_str(&session_id) {
+            redis
+                .remove_from_cache(&cache_key_session(session_id))
+                .await?;
+        }
+
+        redis.remove_from_set(&user_sessions, &session_id).await?;
+    }
+
+    Ok(())
+}
@@ -1,20 +1,106 @@
+use std::net::IpAddr;
+
 use rocket::State;
 use uuid::Uuid;
 
 use crate::{
     api::{ApiResponse, Error, Null},
-    cache::CACHE_TTL_24_HOURS,
+    auth::oidc::OidcLogin,
+    cache::{CACHE_TTL_24_HOURS, CACHE_TTL_5_MINUTES, CACHE_TTL_ONE_HOUR},
+    models::users::{EmailChange, PublicUser},
 };
 
 use super::RedisMutex;
 
-pub const CACHE_INVITE_TOKEN: &str = "invite_token:";
+pub const CACHE_USER: &str = "user:";
+pub const CACHE_PASSWORD_RESET_TOKEN: &str = "password_reset_token:";
+pub const CACHE_PASSWORD_RESET_REQUESTS: &str = "password_reset_requests:";
+pub const CACHE_EMAIL_CHANGE_TOKEN: &str = "email_change_token:";
+pub const CACHE_TWO_FACTOR_CHALLENGE: &str = "two_factor_challenge:";
+pub const CACHE_TWO_FACTOR_ATTEMPTS: &str = "two_factor_attempts:";
+pub const CACHE_TWO_FACTOR_USED_CODE: &str = "two_factor_used_code:";
+pub const CACHE_LOGIN_FAILURES: &str = "login_failures:";
+pub const CACHE_LOGIN_FAILURES_IP: &str = "login_failures_ip:";
+pub const CACHE_LOGIN_BLOCKED: &str = "login_blocked:";
+pub const CACHE_OIDC_LOGIN: &str = "oidc_login:";
+
+/// A TOTP code stays valid for three time steps (including the skew on both sides)
+const TWO_FACTOR_CODE_TTL: Option<u64> = Some(90);
+
+pub fn cache_key_user(user_id: Uuid) -> String {
+    format!("{CACHE_USER}{user_id}")
+}
+
+pub fn cache_key_password_reset_token(token: &str) -> String {
+    format!("{CACHE_PASSWORD_RESET_TOKEN}{token}")
+}
 
-pub fn cache_key_invite_token(token: &str) -> String {
-    format!("{CACHE_INVITE_TOKEN}{token}")
+pub fn cache_key_password_reset_requests(email: &str) -> String {
+    format!("{CACHE_PASSWORD_RESET_REQUESTS}{}", email.to_lowercase())
 }
 
-pub async fn add_invite_token(
+pub fn cache_key_email_change_token(token: &str) -> String {
+    format!("{CACHE_EMAIL_CHANGE_TOKEN}{token}")
+}
+
+pub fn cache_key_two_factor_challenge(challenge: &str) -> String {
+    format!("{CACHE_TWO_FACTOR_CHALLENGE}{challenge}")
+}
+
+pub fn cache_key_two_factor_attempts(challenge: &str) -> String {
+    format!("{CACHE_TWO_FACTOR_ATTEMPTS}{challenge}")
+}
+
+pub fn cache_key_two_factor_used_code(user_id: Uuid, code: &str) -> String {
+    format!("{CACHE_TWO_FACTOR_USED_CODE}{user_id}:{code}")
+}
+
+pub fn cache_key_login_failures(username: &str) -> String {
+    format!("{CACHE_LOGIN_FAILURES}{}", username.to_lowercase())
+}
+
+pub fn cache_key_login_failures_ip(ip: IpAddr) -> String {
+    format!("{CACHE_LOGIN_FAILURES_IP}{ip}")
+}
+
+pub fn cache_key_login_blocked(username: &str) -> String {
+    format!("{CACHE_LOGIN_BLOCKED}{}", username.to_lowercase())
+}
+
+pub fn cache_key_oidc_login(state: &str) -> String {
+    format!("{CACHE_OIDC_LOGIN}{state}")
+}
+
+/// Caches the current state of the user, as seen by the [`JwtGuard`](crate::auth::JwtGuard).
+pub async fn add_user_cache(redis: &State<RedisMutex>, user: &PublicUser) {
+    let _ = redis
+        .lock()
+        .await
+        .set_to_cache(&cache_key_user(user.id), user, CACHE_TTL_ONE_HOUR)
+        .await;
+}
+
+pub async fn get_user_cache(
+    redis: &State<RedisMutex>,
+    user_id: Uuid,
+) -> Result<Option<PublicUser>, Error<Null>> {
+    redis
+        .lock()
+        .await
+        .get_from_cache(&cache_key_user(user_id))
+        .await
+}
+
+/// Must be called whenever the role, status or information of a user changes.
+pub async fn remove_user_cache(redis: &State<RedisMutex>, user_id: Uuid) {
+    let _ = redis
+        .lock()
+        .await
+        .remove_from_cache(&cache_key_user(user_id))
+        .await;
+}
+
+pub async fn add_password_reset_token(
     redis: &State<RedisMutex>,
     token: &str,
     user_id: Uuid,
@@ -22,15 +108,73 @@ pub async fn add_invite_token(
     redis
         .lock()
         .await
-        .set_to_cache(&cache_key_invite_token(token), &user_id, CACHE_TTL_24_HOURS)
+        .set_to_cache(
+            &cache_key_password_reset_token(token),
+            &user_id,
+            CACHE_TTL_ONE_HOUR,
+        )
+        .await
+}
+
+/// Takes the user ID of the token from the cache, after which the token can't be used again.
+pub async fn take_password_reset_token(
+    redis: &State<RedisMutex>,
+    token: &str,
+) -> Result<Uuid, Error<Null>> {
+    match redis
+        .lock()
+        .await
+        .take_from_cache(&cache_key_password_reset_token(token))
+        .await?
+    {
+        Some(value) => Ok(value),
+        None => Err(ApiResponse::not_found(
+            "Key not found; possibly expired".to_string(),
+        )),
+    }
+}
+
+/// Counts a password reset request for the address and returns the number of requests made
+/// within the last hour.
+pub async fn count_password_reset_request(
+    redis: &State<RedisMutex>,
+    email: &str,
+) -> Result<u64, Error<Null>> {
+    redis
+        .lock()
+        .await
+        .increment(
+            &cache_key_password_reset_requests(email),
+            CACHE_TTL_ONE_HOUR,
+        )
+        .await
+}
+
+/// Stores the requested email change until the link sent to the new address is confirmed.
+pub async fn add_email_change_token(
+    redis: &State<RedisMutex>,
+    token: &str,
+    change: &EmailChange,
+) -> Result<(), Error<Null>> {
+    redis
+        .lock()
+        .await
+        .set_to_cache(
+            &cache_key_email_change_token(token),
+            change,
+            CACHE_TTL_24_HOURS,
+        )
         .await
 }
 
-pub async fn get_invite_token(redis: &State<RedisMutex>, token: &str) -> Result<Uuid, Error<Null>> {
+pub async fn get_email_change_token(
+    redis: &State<RedisMutex>,
+    token: &str,
+) -> Result<EmailChange, Error<Null>> {
     match redis
         .lock()
         .await
-        .get_from_cache(&cache_key_invite_token(token))
+        .get_from_cache(&cache_key_email_change_token(token))
         .await?
     {
         Some(value) => Ok(value),
@@ -40,13 +184,193 @@ pub async fn get_invite_token(redis: &State<RedisMutex>, token: &str) -> Result<
     }
 }
 
-pub async fn remove_invite_token(
+pub async fn remove_email_change_token(
     redis: &State<RedisMutex>,
     token: &str,
 ) -> Result<(), Error<Null>> {
     redis
         .lock()
         .await
-        .remove_from_cache(&cache_key_invite_token(token))
+        .remove_from_cache(&cache_key_email_change_token(token))
+        .await
+}
+
+/// Stores a login challenge for a user that still has to provide a second factor.
+pub async fn add_two_factor_challenge(
+    redis: &State<RedisMutex>,
+    challenge: &str,
+    user_id: Uuid,
+) -> Result<(), Error<Null>> {
+    redis
+        .lock()
+        .await
+        .set_to_cache(
+            &cache_key_two_factor_challenge(challenge),
+            &user_id,
+            CACHE_TTL_5_MINUTES,
+        )
+        .await
+}
+
+pub async fn get_two_factor_challenge(
+    redis: &State<RedisMutex>,
+    challenge: &str,
+) -> Result<Uuid, Error<Null>> {
+    match redis
+        .lock()
+        .await
+        .get_from_cache(&cache_key_two_factor_challenge(challenge))
+        .await?
+    {
+        Some(value) => Ok(value),
+        None => Err(ApiResponse::unauthorized(
+            "Challenge not found; possibly expired".to_string(),
+        )),
+    }
+}
+
+pub async fn remove_two_factor_challenge(
+    redis: &State<RedisMutex>,
+    challenge: &str,
+) -> Result<(), Error<Null>> {
+    let redis = redis.lock().await;
+
+    redis
+        .remove_from_cache(&cache_key_two_factor_challenge(challenge))
+        .await?;
+
+    redis
+        .remove_from_cache(&cache_key_two_factor_attempts(challenge))
+        .await
+}
+
+/// Counts an attempt to complete the challenge and returns the number of attempts so far.
+pub async fn count_two_factor_attempt(
+    redis: &State<RedisMutex>,
+    challenge: &str,
+) -> Result<u64, Error<Null>> {
+    redis
+        .lock()
+        .await
+        .increment(
+            &cache_key_two_factor_attempts(challenge),
+            CACHE_TTL_5_MINUTES,
+        )
+        .await
+}
+
+/// Marks the TOTP code as used, returning `false` if it was already used before.
+pub async fn use_two_factor_code(
+    redis: &State<RedisMutex>,
+    user_id: Uuid,
+    code: &str,
+) -> Result<bool, Error<Null>> {
+    redis
+        .lock()
+        .await
+        .increment(
+            &cache_key_two_factor_used_code(user_id, code),
+            TWO_FACTOR_CODE_TTL,
+        )
+        .await
+        .map(|count| count == 1)
+}
+
+/// Counts a failed login for the username and returns the number of failures within the window.
+pub async fn count_login_failure(
+    redis: &State<RedisMutex>,
+    username: &str,
+) -> Result<u64, Error<Null>> {
+    redis
+        .lock()
+        .await
+        .increment(&cache_key_login_failures(username), CACHE_TTL_ONE_HOUR)
+        .await
+}
+
+/// Counts a failed login from the client IP and returns the number of failures within the window.
+pub async fn count_login_failure_ip(
+    redis: &State<RedisMutex>,
+    ip: IpAddr,
+) -> Result<u64, Error<Null>> {
+    redis
+        .lock()
+        .await
+        .increment(&cache_key_login_failures_ip(ip), CACHE_TTL_ONE_HOUR)
+        .await
+}
+
+pub async fn get_login_failures_ip(
+    redis: &State<RedisMutex>,
+    ip: IpAddr,
+) -> Result<u64, Error<Null>> {
+    redis
+        .lock()
+        .await
+        .get_from_cache::<u64>(&cache_key_login_failures_ip(ip))
+        .await
+        .map(Option::unwrap_or_default)
+}
+
+/// Blocks logins for the username for the given number of seconds.
+pub async fn block_login(
+    redis: &State<RedisMutex>,
+    username: &str,
+    seconds: u64,
+) -> Result<(), Error<Null>> {
+    redis
+        .lock()
+        .await
+        .set_to_cache(&cache_key_login_blocked(username), &true, Some(seconds))
         .await
 }
+
+pub async fn is_login_blocked(
+    redis: &State<RedisMutex>,
+    username: &str,
+) -> Result<bool, Error<Null>> {
+    redis
+        .lock()
+        .await
+        .get_from_cache::<bool>(&cache_key_login_blocked(username))
+        .await
+        .map(|blocked| blocked.unwrap_or_default())
+}
+
+/// Removes the failures and the block of the username, e.g. after a successful login.
+pub async fn remove_login_failures(redis: &State<RedisMutex>, username: &str) {
+    let cache = redis.lock().await;
+    let _ = cache
+        .remove_from_cache(&cache_key_login_failures(username))
+        .await;
+    let _ = cache
+        .remove_from_cache(&cache_key_login_blocked(username))
+        .await;
+}
+
+/// Stores what the callback of a single sign-on login needs, by its `state`.
+pub async fn add_oidc_login(
+    redis: &State<RedisMutex>,
+    state: &str,
+    login: &OidcLogin,
+) -> Result<(), Error<Null>> {
+    redis
+        .lock()
+        .await
+        .set_to_cache(&cache_key_oidc_login(state), login, CACHE_TTL_5_MINUTES)
+        .await
+}
+
+/// Returns and removes the login of the `state`, so it can't be used twice.
+pub async fn take_oidc_login(
+    redis: &State<RedisMutex>,
+    state: &str,
+) -> Result<OidcLogin, Error<Null>> {
+    let cache = redis.lock().await;
+    let login = cache.get_from_cache(&cache_key_oidc_login(state)).await?;
+    let _ = cache.remove_from_cache(&cache_key_oidc_login(state)).await;
+
+    login.ok_or_else(|| {
+        ApiResponse::unauthorized("Login state not found; possibly expired".to_string())
+    })
+}
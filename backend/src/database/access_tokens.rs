@@ -0,0 +1,75 @@
+use chrono::NaiveDateTime;
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
+use uuid::Uuid;
+
+use crate::{
+    api::{ApiResponse, Error, Null},
+    database::Db,
+    models::access_tokens::AccessToken,
+    schema::access_tokens,
+};
+
+pub async fn insert_access_token(db: &Db, token: AccessToken) -> Result<usize, Error<Null>> {
+    db.run(move |conn| {
+        diesel::insert_into(access_tokens::table)
+            .values(&token)
+            .execute(conn)
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
+
+pub async fn get_access_token_by_hash(
+    db: &Db,
+    token_hash: String,
+) -> Result<AccessToken, Error<Null>> {
+    db.run(move |conn| {
+        access_tokens::table
+            .filter(access_tokens::token_hash.eq(token_hash))
+            .first::<AccessToken>(conn)
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
+
+pub async fn get_access_tokens_by_user(
+    db: &Db,
+    user_id: Uuid,
+) -> Result<Vec<AccessToken>, Error<Null>> {
+    db.run(move |conn| {
+        access_tokens::table
+            .filter(access_tokens::user_id.eq(user_id))
+            .order(access_tokens::created_at.desc())
+            .load::<AccessToken>(conn)
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
+
+pub async fn update_last_used(
+    db: &Db,
+    id: Uuid,
+    timestamp: NaiveDateTime,
+) -> Result<usize, Error<Null>> {
+    db.run(move |conn| {
+        diesel::update(access_tokens::table.find(id))
+            .set(access_tokens::last_used_at.eq(timestamp))
+            .execute(conn)
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
+
+/// Deletes the token, but only if it belongs to the user.
+pub async fn delete_access_token(db: &Db, id: Uuid, user_id: Uuid) -> Result<usize, Error<Null>> {
+    db.run(move |conn| {
+        diesel::delete(
+            access_tokens::table
+                .filter(access_tokens::id.eq(id))
+                .filter(access_tokens::user_id.eq(user_id)),
+        )
+        .execute(conn)
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
@@ -0,0 +1,501 @@
+use diesel::{
+    result::DatabaseErrorKind, Connection, ExpressionMethods, OptionalExtension, PgConnection,
+    QueryDsl, QueryResult, RunQueryDsl,
+};
+use rocket::http::Status;
+use uuid::Uuid;
+
+use crate::{
+    api::{ApiResponse, Error, Null},
+    models::boards::{
+        rank_after, rank_between, Board, BoardColumn, BoardUpdate, BoardWithColumns, Card,
+        CardMove, CardUpdate, ColumnUpdate, ColumnWithCards, NewBoard, NewCard, Position,
+    },
+    schema::{board_cards, board_columns, boards},
+};
+
+use super::Db;
+
+/// Why a column or card couldn't be placed.
+enum PlacementError {
+    Database(diesel::result::Error),
+    /// The neighbours of the position are not in order
+    Order,
+    /// The card is no longer at the version of the move
+    Changed,
+}
+
+impl From<diesel::result::Error> for PlacementError {
+    fn from(error: diesel::result::Error) -> Self {
+        PlacementError::Database(error)
+    }
+}
+
+impl PlacementError {
+    fn into_response(self) -> Error<Null> {
+        match self {
+            // Another column or card took the same rank at the same time
+            PlacementError::Database(diesel::result::Error::DatabaseError(
+                DatabaseErrorKind::UniqueViolation,
+                _,
+            )) => ApiResponse::error(
+                Status::Conflict,
+                "The board changed at the same time, please try again".to_string(),
+                None,
+            ),
+            PlacementError::Database(error) => ApiResponse::from_error(error),
+            PlacementError::Order => ApiResponse::bad_request(
+                "The neighbour after which to move has to come before the one it precedes"
+                    .to_string(),
+            ),
+            PlacementError::Changed => ApiResponse::error(
+                Status::Conflict,
+                "The card has changed in the meantime".to_string(),
+                None,
+            ),
+        }
+    }
+}
+
+pub async fn get_boards(db: &Db, project: Uuid) -> Result<Vec<Board>, Error<Null>> {
+    db.run(move |conn| {
+        boards::table
+            .filter(boards::project.eq(project))
+            .order(boards::created_at.asc())
+            .load::<Board>(conn)
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
+
+/// The board, but only if it is one of the project.
+pub async fn get_board(db: &Db, project: Uuid, id: Uuid) -> Result<Board, Error<Null>> {
+    db.run(move |conn| {
+        boards::table
+            .filter(boards::id.eq(id))
+            .filter(boards::project.eq(project))
+            .first::<Board>(conn)
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
+
+/// The board of the project with its columns and their cards, all in order.
+pub async fn get_board_with_columns(
+    db: &Db,
+    project: Uuid,
+    id: Uuid,
+) -> Result<BoardWithColumns, Error<Null>> {
+    db.run(move |conn| load_board_with_columns(conn, project, id))
+        .await
+        .map_err(ApiResponse::from_error)
+}
+
+/// Creates the board together with its first columns, in the order they are given.
+pub async fn insert_board(
+    db: &Db,
+    project: Uuid,
+    board: NewBoard,
+) -> Result<BoardWithColumns, Error<Null>> {
+    db.run(move |conn| {
+        conn.transaction::<_, diesel::result::Error, _>(|conn| {
+            let id = diesel::insert_into(boards::table)
+                .values((boards::project.eq(project), boards::name.eq(&board.name)))
+                .returning(boards::id)
+                .get_result::<Uuid>(conn)?;
+
+            let mut last_rank = None;
+            for name in &board.columns {
+                let rank = rank_after(last_rank.as_deref());
+                diesel::insert_into(board_columns::table)
+                    .values((
+                        board_columns::board.eq(id),
+                        board_columns::name.eq(name),
+                        board_columns::rank.eq(&rank),
+                    ))
+                    .execute(conn)?;
+                last_rank = Some(rank);
+            }
+
+            load_board_with_columns(conn, project, id)
+        })
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
+
+pub async fn update_board(
+    db: &Db,
+    project: Uuid,
+    id: Uuid,
+    update: BoardUpdate,
+) -> Result<Board, Error<Null>> {
+    db.run(move |conn| {
+        diesel::update(
+            boards::table
+                .filter(boards::id.eq(id))
+                .filter(boards::project.eq(project)),
+        )
+        .set(update)
+        .get_result::<Board>(conn)
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
+
+/// Removes the board, with its columns and cards.
+pub async fn remove_board(db: &Db, project: Uuid, id: Uuid) -> Result<Board, Error<Null>> {
+    db.run(move |conn| {
+        diesel::delete(
+            boards::table
+                .filter(boards::id.eq(id))
+                .filter(boards::project.eq(project)),
+        )
+        .get_result::<Board>(conn)
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
+
+/// Adds a column after the last column of the board.
+pub async fn insert_column(db: &Db, board: Uuid, name: String) -> Result<BoardColumn, Error<Null>> {
+    db.run(move |conn| {
+        conn.transaction::<_, PlacementError, _>(|conn| {
+            let (last, _) = column_bounds(conn, board, None, Position::default())?;
+            let rank = rank_after(last.as_deref());
+
+            Ok(diesel::insert_into(board_columns::table)
+                .values((
+                    board_columns::board.eq(board),
+                    board_columns::name.eq(name),
+                    board_columns::rank.eq(rank),
+                ))
+                .get_result::<BoardColumn>(conn)?)
+        })
+    })
+    .await
+    .map_err(PlacementError::into_response)
+}
+
+pub async fn update_column(
+    db: &Db,
+    board: Uuid,
+    id: Uuid,
+    update: ColumnUpdate,
+) -> Result<BoardColumn, Error<Null>> {
+    db.run(move |conn| {
+        diesel::update(
+            board_columns::table
+                .filter(board_columns::id.eq(id))
+                .filter(board_columns::board.eq(board)),
+        )
+        .set(update)
+        .get_result::<BoardColumn>(conn)
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
+
+/// Moves the column to the position; only the rank of the column itself changes.
+pub async fn move_column(
+    db: &Db,
+    board: Uuid,
+    id: Uuid,
+    position: Position,
+) -> Result<BoardColumn, Error<Null>> {
+    db.run(move |conn| {
+        conn.transaction::<_, PlacementError, _>(|conn| {
+            let (lower, upper) = column_bounds(conn, board, Some(id), position)?;
+            let rank =
+                rank_between(lower.as_deref(), upper.as_deref()).ok_or(PlacementError::Order)?;
+
+            Ok(diesel::update(
+                board_columns::table
+                    .filter(board_columns::id.eq(id))
+                    .filter(board_columns::board.eq(board)),
+            )
+            .set(board_columns::rank.eq(rank))
+            .get_result::<BoardColumn>(conn)?)
+        })
+    })
+    .await
+    .map_err(PlacementError::into_response)
+}
+
+/// Removes the column with its cards.
+pub async fn remove_column(db: &Db, board: Uuid, id: Uuid) -> Result<BoardColumn, Error<Null>> {
+    db.run(move |conn| {
+        diesel::delete(
+            board_columns::table
+                .filter(board_columns::id.eq(id))
+                .filter(board_columns::board.eq(board)),
+        )
+        .get_result::<BoardColumn>(conn)
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
+
+/// Adds a card at the bottom of its column, which has to be one of the board.
+pub async fn insert_card(
+    db: &Db,
+    project: Uuid,
+    board: Uuid,
+    card: NewCard,
+) -> Result<Card, Error<Null>> {
+    db.run(move |conn| {
+        conn.transaction::<_, PlacementError, _>(|conn| {
+            find_column(conn, board, card.column)?;
+
+            let (last, _) = card_bounds(conn, card.column, None, Position::default())?;
+            let rank = rank_after(last.as_deref());
+
+            Ok(diesel::insert_into(board_cards::table)
+                .values((
+                    board_cards::project.eq(project),
+                    board_cards::board_column.eq(card.column),
+                    board_cards::title.eq(card.title),
+                    board_cards::description.eq(card.description),
+                    board_cards::assignee.eq(card.assignee),
+                    board_cards::rank.eq(rank),
+                ))
+                .get_result::<Card>(conn)?)
+        })
+    })
+    .await
+    .map_err(PlacementError::into_response)
+}
+
+/// Updates the fields of the card that are present, and raises its version.
+pub async fn update_card(
+    db: &Db,
+    board: Uuid,
+    id: Uuid,
+    update: CardUpdate,
+) -> Result<Card, Error<Null>> {
+    db.run(move |conn| {
+        let columns = board_columns::table
+            .filter(board_columns::board.eq(board))
+            .select(board_columns::id);
+
+        diesel::update(
+            board_cards::table
+                .filter(board_cards::id.eq(id))
+                .filter(board_cards::board_column.eq_any(columns)),
+        )
+        .set((update, board_cards::version.eq(board_cards::version + 1)))
+        .get_result::<Card>(conn)
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
+
+/// Moves the card to a position in a column of the board, in a single transaction.
+///
+/// The neighbours only decide the new rank of the card, so the card is the one row that changes.
+/// The update is bound to the version of the move, so a card that changed concurrently is left
+/// as it is.
+pub async fn move_card(
+    db: &Db,
+    board: Uuid,
+    id: Uuid,
+    movement: CardMove,
+) -> Result<Card, Error<Null>> {
+    db.run(move |conn| {
+        conn.transaction::<_, PlacementError, _>(|conn| {
+            find_column(conn, board, movement.column)?;
+
+            let columns = board_columns::table
+                .filter(board_columns::board.eq(board))
+                .select(board_columns::id);
+            let card = board_cards::table
+                .filter(board_cards::id.eq(id))
+                .filter(board_cards::board_column.eq_any(columns))
+                .first::<Card>(conn)?;
+            if card.version != movement.version {
+                return Err(PlacementError::Changed);
+            }
+
+            let (lower, upper) = card_bounds(conn, movement.column, Some(id), movement.position)?;
+            let rank =
+                rank_between(lower.as_deref(), upper.as_deref()).ok_or(PlacementError::Order)?;
+
+            diesel::update(
+                board_cards::table
+                    .filter(board_cards::id.eq(id))
+                    .filter(board_cards::version.eq(movement.version)),
+            )
+            .set((
+                board_cards::board_column.eq(movement.column),
+                board_cards::rank.eq(rank),
+                board_cards::version.eq(board_cards::version + 1),
+            ))
+            .get_result::<Card>(conn)
+            .optional()?
+            .ok_or(PlacementError::Changed)
+        })
+    })
+    .await
+    .map_err(PlacementError::into_response)
+}
+
+pub async fn remove_card(db: &Db, board: Uuid, id: Uuid) -> Result<Card, Error<Null>> {
+    db.run(move |conn| {
+        let columns = board_columns::table
+            .filter(board_columns::board.eq(board))
+            .select(board_columns::id);
+
+        diesel::delete(
+            board_cards::table
+                .filter(board_cards::id.eq(id))
+                .filter(board_cards::board_column.eq_any(columns)),
+        )
+        .get_result::<Card>(conn)
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
+
+fn load_board_with_columns(
+    conn: &mut PgConnection,
+    project: Uuid,
+    id: Uuid,
+) -> QueryResult<BoardWithColumns> {
+    let board = boards::table
+        .filter(boards::id.eq(id))
+        .filter(boards::project.eq(project))
+        .first::<Board>(conn)?;
+
+    let columns = board_columns::table
+        .filter(board_columns::board.eq(id))
+        .order(board_columns::rank.asc())
+        .load::<BoardColumn>(conn)?;
+
+    let cards = board_cards::table
+        .filter(board_cards::board_column.eq_any(columns.iter().map(|column| column.id)))
+        .order(board_cards::rank.asc())
+        .load::<Card>(conn)?;
+
+    let columns = columns
+        .into_iter()
+        .map(|column| ColumnWithCards {
+            cards: cards
+                .iter()
+                .filter(|card| card.board_column == column.id)
+                .cloned()
+                .collect(),
+            column,
+        })
+        .collect();
+
+    Ok(BoardWithColumns { board, columns })
+}
+
+/// Fails with [`NotFound`](diesel::result::Error::NotFound) unless the column is one of the board.
+fn find_column(conn: &mut PgConnection, board: Uuid, column: Uuid) -> QueryResult<Uuid> {
+    board_columns::table
+        .filter(board_columns::id.eq(column))
+        .filter(board_columns::board.eq(board))
+        .select(board_columns::id)
+        .first::<Uuid>(conn)
+}
+
+/// The ranks of the columns between which the column `moved` is placed, leaving out the column
+/// itself.
+fn column_bounds(
+    conn: &mut PgConnection,
+    board: Uuid,
+    moved: Option<Uuid>,
+    position: Position,
+) -> QueryResult<(Option<String>, Option<String>)> {
+    let others = || {
+        board_columns::table
+            .filter(board_columns::board.eq(board))
+            .filter(board_columns::id.ne(moved.unwrap_or_default()))
+            .select(board_columns::rank)
+    };
+    let rank_of = |conn: &mut PgConnection, neighbour: Uuid| {
+        others()
+            .filter(board_columns::id.eq(neighbour))
+            .first::<String>(conn)
+    };
+
+    Ok(match (position.after, position.before) {
+        (Some(after), Some(before)) => (Some(rank_of(conn, after)?), Some(rank_of(conn, before)?)),
+        (Some(after), None) => {
+            let lower = rank_of(conn, after)?;
+            let upper = others()
+                .filter(board_columns::rank.gt(&lower))
+                .order(board_columns::rank.asc())
+                .first::<String>(conn)
+                .optional()?;
+            (Some(lower), upper)
+        }
+        (None, Some(before)) => {
+            let upper = rank_of(conn, before)?;
+            let lower = others()
+                .filter(board_columns::rank.lt(&upper))
+                .order(board_columns::rank.desc())
+                .first::<String>(conn)
+                .optional()?;
+            (lower, Some(upper))
+        }
+        (None, None) => (
+            others()
+                .order(board_columns::rank.desc())
+                .first::<String>(conn)
+                .optional()?,
+            None,
+        ),
+    })
+}
+
+/// The ranks of the cards in the column between which the card `moved` is placed, leaving out the
+/// card itself.
+fn card_bounds(
+    conn: &mut PgConnection,
+    column: Uuid,
+    moved: Option<Uuid>,
+    position: Position,
+) -> QueryResult<(Option<String>, Option<String>)> {
+    let others = || {
+        board_cards::table
+            .filter(board_cards::board_column.eq(column))
+            .filter(board_cards::id.ne(moved.unwrap_or_default()))
+            .select(board_cards::rank)
+    };
+    let rank_of = |conn: &mut PgConnection, neighbour: Uuid| {
+        others()
+            .filter(board_cards::id.eq(neighbour))
+            .first::<String>(conn)
+    };
+
+    Ok(match (position.after, position.before) {
+        (Some(after), Some(before)) => (Some(rank_of(conn, after)?), Some(rank_of(conn, before)?)),
+        (Some(after), None) => {
+            let lower = rank_of(conn, after)?;
+            let upper = others()
+                .filter(board_cards::rank.gt(&lower))
+                .order(board_cards::rank.asc())
+                .first::<String>(conn)
+                .optional()?;
+            (Some(lower), upper)
+        }
+        (None, Some(before)) => {
+            let upper = rank_of(conn, before)?;
+            let lower = others()
+                .filter(board_cards::rank.lt(&upper))
+                .order(board_cards::rank.desc())
+                .first::<String>(conn)
+                .optional()?;
+            (lower, Some(upper))
+        }
+        (None, None) => (
+            others()
+                .order(board_cards::rank.desc())
+                .first::<String>(conn)
+                .optional()?,
+            None,
+        ),
+    })
+}
@@ -0,0 +1,174 @@
+use chrono::NaiveDateTime;
+use diesel::{
+    pg::Pg, Connection, ExpressionMethods, PgConnection, QueryDsl, QueryResult, RunQueryDsl,
+};
+use uuid::Uuid;
+
+use crate::{
+    api::{ApiResponse, Error, Null},
+    models::{
+        comments::{Comment, CommentMention, InsertableComment, PublicComment, Thread},
+        users::User,
+    },
+    schema::{comment_mentions, comments, users},
+};
+
+use super::Db;
+
+/// All comments of the thread, from the oldest to the newest.
+pub async fn get_thread(db: &Db, thread: Thread) -> Result<Vec<PublicComment>, Error<Null>> {
+    db.run(move |conn| {
+        let thread_comments = in_thread(thread)
+            .order(comments::created_at.asc())
+            .load::<Comment>(conn)?;
+
+        public_comments(conn, thread_comments)
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
+
+/// The comment, but only if it is one of the thread.
+pub async fn get_comment(db: &Db, thread: Thread, id: Uuid) -> Result<Comment, Error<Null>> {
+    db.run(move |conn| {
+        in_thread(thread)
+            .filter(comments::id.eq(id))
+            .first::<Comment>(conn)
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
+
+/// Adds the comment with its mentions; a reply has to answer a comment of the same thread.
+pub async fn insert_comment(
+    db: &Db,
+    thread: Thread,
+    comment: InsertableComment,
+    mentions: Vec<Uuid>,
+) -> Result<PublicComment, Error<Null>> {
+    db.run(move |conn| {
+        conn.transaction::<_, diesel::result::Error, _>(|conn| {
+            if let Some(parent) = comment.parent {
+                in_thread(thread)
+                    .filter(comments::id.eq(parent))
+                    .select(comments::id)
+                    .first::<Uuid>(conn)?;
+            }
+
+            let comment = diesel::insert_into(comments::table)
+                .values(&comment)
+                .get_result::<Comment>(conn)?;
+            insert_mentions(conn, comment.id, &mentions)?;
+
+            public_comment(conn, comment)
+        })
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
+
+/// Replaces the body and the mentions of a comment that isn't deleted, and marks it as edited.
+pub async fn edit_comment(
+    db: &Db,
+    id: Uuid,
+    body: String,
+    mentions: Vec<Uuid>,
+    edited_at: NaiveDateTime,
+) -> Result<PublicComment, Error<Null>> {
+    db.run(move |conn| {
+        conn.transaction::<_, diesel::result::Error, _>(|conn| {
+            let comment = diesel::update(
+                comments::table
+                    .filter(comments::id.eq(id))
+                    .filter(comments::deleted_at.is_null()),
+            )
+            .set((comments::body.eq(body), comments::edited_at.eq(edited_at)))
+            .get_result::<Comment>(conn)?;
+
+            diesel::delete(comment_mentions::table.filter(comment_mentions::comment.eq(id)))
+                .execute(conn)?;
+            insert_mentions(conn, id, &mentions)?;
+
+            public_comment(conn, comment)
+        })
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
+
+/// Marks the comment as deleted; it stays in the thread for its replies.
+pub async fn delete_comment(
+    db: &Db,
+    id: Uuid,
+    deleted_at: NaiveDateTime,
+) -> Result<Comment, Error<Null>> {
+    db.run(move |conn| {
+        diesel::update(
+            comments::table
+                .filter(comments::id.eq(id))
+                .filter(comments::deleted_at.is_null()),
+        )
+        .set(comments::deleted_at.eq(deleted_at))
+        .get_result::<Comment>(conn)
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
+
+fn in_thread(thread: Thread) -> comments::BoxedQuery<'static, Pg> {
+    let query = comments::table
+        .filter(comments::workspace.eq(thread.workspace))
+        .into_boxed();
+
+    match thread.project {
+        Some(project) => query.filter(comments::project.eq(project)),
+        None => query.filter(comments::project.is_null()),
+    }
+}
+
+fn insert_mentions(conn: &mut PgConnection, comment: Uuid, mentions: &[Uuid]) -> QueryResult<()> {
+    let mentions: Vec<CommentMention> = mentions
+        .iter()
+        .map(|&mentioned| CommentMention { comment, mentioned })
+        .collect();
+
+    diesel::insert_into(comment_mentions::table)
+        .values(&mentions)
+        .execute(conn)?;
+
+    Ok(())
+}
+
+fn public_comment(conn: &mut PgConnection, comment: Comment) -> QueryResult<PublicComment> {
+    public_comments(conn, vec![comment]).map(|mut comments| comments.remove(0))
+}
+
+/// Adds the authors and mentions to the comments.
+fn public_comments(
+    conn: &mut PgConnection,
+    comments: Vec<Comment>,
+) -> QueryResult<Vec<PublicComment>> {
+    let authors = users::table
+        .filter(users::id.eq_any(comments.iter().filter_map(|comment| comment.author)))
+        .load::<User>(conn)?;
+
+    let mentions = comment_mentions::table
+        .filter(comment_mentions::comment.eq_any(comments.iter().map(|comment| comment.id)))
+        .load::<(Uuid, Uuid)>(conn)?;
+
+    Ok(comments
+        .iter()
+        .map(|comment| {
+            let author = authors
+                .iter()
+                .find(|author| Some(author.id) == comment.author);
+            let mentioned = mentions
+                .iter()
+                .filter(|(mention, _)| *mention == comment.id)
+                .map(|(_, mentioned)| *mentioned)
+                .collect();
+
+            PublicComment::from(comment, author, mentioned)
+        })
+        .collect())
+}
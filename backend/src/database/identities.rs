@@ -0,0 +1,49 @@
+use chrono::Utc;
+use diesel::{ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl};
+
+use crate::{
+    api::{ApiResponse, Error, Null},
+    database::Db,
+    models::{identities::UserIdentity, users::User},
+    schema::{user_identities, users},
+};
+
+/// Returns the user linked to the identity and updates the time of its last login.
+pub async fn get_user_by_identity(
+    db: &Db,
+    issuer: &str,
+    subject: &str,
+) -> Result<Option<User>, Error<Null>> {
+    let issuer = issuer.to_string();
+    let subject = subject.to_string();
+
+    db.run(move |conn| {
+        let user = user_identities::table
+            .inner_join(users::table)
+            .filter(user_identities::issuer.eq(&issuer))
+            .filter(user_identities::subject.eq(&subject))
+            .select(users::all_columns)
+            .first::<User>(conn)
+            .optional()?;
+
+        if user.is_some() {
+            diesel::update(user_identities::table.find((&issuer, &subject)))
+                .set(user_identities::last_login_at.eq(Utc::now().naive_utc()))
+                .execute(conn)?;
+        }
+
+        Ok(user)
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
+
+pub async fn insert_identity(db: &Db, identity: UserIdentity) -> Result<usize, Error<Null>> {
+    db.run(move |conn| {
+        diesel::insert_into(user_identities::table)
+            .values(&identity)
+            .execute(conn)
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
@@ -0,0 +1,46 @@
+use diesel::{BoolExpressionMethods, ExpressionMethods, QueryDsl, RunQueryDsl};
+use uuid::Uuid;
+
+use crate::{
+    api::{ApiResponse, Error, Null},
+    database::Db,
+    models::impersonation::ImpersonationLog,
+    schema::impersonation_logs,
+};
+
+/// Number of entries returned when reading the audit trail of a user.
+const IMPERSONATION_LOG_LIMIT: i64 = 500;
+
+pub async fn insert_impersonation_log(
+    db: &Db,
+    log: ImpersonationLog,
+) -> Result<usize, Error<Null>> {
+    db.run(move |conn| {
+        diesel::insert_into(impersonation_logs::table)
+            .values(&log)
+            .execute(conn)
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
+
+/// Returns the most recent requests made while impersonating the user, or by the user as an
+/// impersonating admin.
+pub async fn get_impersonation_logs(
+    db: &Db,
+    user_id: Uuid,
+) -> Result<Vec<ImpersonationLog>, Error<Null>> {
+    db.run(move |conn| {
+        impersonation_logs::table
+            .filter(
+                impersonation_logs::user_id
+                    .eq(user_id)
+                    .or(impersonation_logs::admin_id.eq(user_id)),
+            )
+            .order(impersonation_logs::created_at.desc())
+            .limit(IMPERSONATION_LOG_LIMIT)
+            .load::<ImpersonationLog>(conn)
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
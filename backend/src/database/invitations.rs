@@ -0,0 +1,249 @@
+use chrono::NaiveDateTime;
+use diesel::{
+    upsert::excluded, Connection, ExpressionMethods, JoinOnDsl, OptionalExtension, QueryDsl,
+    RunQueryDsl,
+};
+use uuid::Uuid;
+
+use crate::{
+    api::{ApiResponse, Error, Null},
+    database::Db,
+    models::{
+        invitations::{Invitation, InvitationStatus},
+        users::{User, UserStatus},
+        workspaces::WorkspaceMember,
+    },
+    schema::{invitations, users, workspace_members},
+};
+
+pub async fn get_invitation_by_hash(
+    db: &Db,
+    token_hash: String,
+) -> Result<Invitation, Error<Null>> {
+    db.run(move |conn| {
+        invitations::table
+            .filter(invitations::token_hash.eq(token_hash))
+            .first::<Invitation>(conn)
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
+
+/// The invitation, but only if it is one of the workspace.
+pub async fn get_invitation(db: &Db, workspace: Uuid, id: Uuid) -> Result<Invitation, Error<Null>> {
+    db.run(move |conn| {
+        invitations::table
+            .filter(invitations::id.eq(id))
+            .filter(invitations::workspace.eq(workspace))
+            .first::<Invitation>(conn)
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
+
+pub async fn get_invitation_of_user(
+    db: &Db,
+    workspace: Uuid,
+    invitee: Uuid,
+) -> Result<Option<Invitation>, Error<Null>> {
+    db.run(move |conn| {
+        invitations::table
+            .filter(invitations::workspace.eq(workspace))
+            .filter(invitations::invitee.eq(invitee))
+            .first::<Invitation>(conn)
+            .optional()
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
+
+/// The invitations of the workspace with the invited users; the most recently sent first.
+pub async fn get_invitations_by_workspace(
+    db: &Db,
+    workspace: Uuid,
+    status: Option<InvitationStatus>,
+) -> Result<Vec<(Invitation, User)>, Error<Null>> {
+    db.run(move |conn| {
+        let mut query = invitations::table
+            .inner_join(users::table.on(users::id.eq(invitations::invitee)))
+            .filter(invitations::workspace.eq(workspace))
+            .order(invitations::sent_at.desc())
+            .into_boxed();
+
+        if let Some(status) = status {
+            query = query.filter(invitations::status.eq(status));
+        }
+
+        query.load::<(Invitation, User)>(conn)
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
+
+/// Marks the pending invitations of the workspace that weren't accepted in time as expired.
+pub async fn expire_invitations(
+    db: &Db,
+    workspace: Uuid,
+    now: NaiveDateTime,
+) -> Result<usize, Error<Null>> {
+    db.run(move |conn| {
+        diesel::update(
+            invitations::table
+                .filter(invitations::workspace.eq(workspace))
+                .filter(invitations::status.eq(InvitationStatus::Pending))
+                .filter(invitations::expires_at.le(now)),
+        )
+        .set(invitations::status.eq(InvitationStatus::Expired))
+        .execute(conn)
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
+
+pub async fn set_invitation_expired(db: &Db, id: Uuid) -> Result<usize, Error<Null>> {
+    db.run(move |conn| {
+        diesel::update(invitations::table.find(id))
+            .set(invitations::status.eq(InvitationStatus::Expired))
+            .execute(conn)
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
+
+/// Revokes the invitation and removes the invited user from the workspace.
+///
+/// Only a pending or expired invitation is revoked, so not one accepted in the meantime, whose
+/// user is a member of the workspace now.
+pub async fn revoke_invitation(
+    db: &Db,
+    invitation: Invitation,
+    revoked_at: NaiveDateTime,
+) -> Result<Invitation, Error<Null>> {
+    db.run(move |conn| {
+        conn.transaction::<_, diesel::result::Error, _>(|conn| {
+            let revoked = diesel::update(
+                invitations::table
+                    .filter(invitations::id.eq(invitation.id))
+                    .filter(
+                        invitations::status
+                            .eq_any([InvitationStatus::Pending, InvitationStatus::Expired]),
+                    ),
+            )
+            .set((
+                invitations::status.eq(InvitationStatus::Revoked),
+                invitations::revoked_at.eq(revoked_at),
+            ))
+            .get_result::<Invitation>(conn)?;
+
+            diesel::delete(
+                workspace_members::table
+                    .filter(workspace_members::workspace.eq(invitation.workspace))
+                    .filter(workspace_members::member.eq(invitation.invitee)),
+            )
+            .execute(conn)?;
+
+            Ok(revoked)
+        })
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
+
+/// Sets the password of the invited user, which activates the user, and accepts the invitation.
+///
+/// The invitation has to be pending still, so one revoked in the meantime isn't accepted. The
+/// other pending invitations of the user are settled along: the user is a member of those
+/// workspaces already, so the ones still valid are accepted and the others expire.
+pub async fn accept_invitation(
+    db: &Db,
+    invitation: Invitation,
+    password_hash: String,
+    accepted_at: NaiveDateTime,
+) -> Result<Invitation, Error<Null>> {
+    db.run(move |conn| {
+        conn.transaction::<_, diesel::result::Error, _>(|conn| {
+            let accepted = diesel::update(
+                invitations::table
+                    .filter(invitations::id.eq(invitation.id))
+                    .filter(invitations::status.eq(InvitationStatus::Pending)),
+            )
+            .set((
+                invitations::status.eq(InvitationStatus::Accepted),
+                invitations::accepted_at.eq(accepted_at),
+            ))
+            .get_result::<Invitation>(conn)?;
+
+            // Only an invited user accepts; this also makes a link usable once
+            let updated_users = diesel::update(
+                users::table
+                    .filter(users::id.eq(invitation.invitee))
+                    .filter(users::status.eq(UserStatus::Invited)),
+            )
+            .set((
+                users::password.eq(password_hash),
+                users::status.eq(UserStatus::Active),
+            ))
+            .execute(conn)?;
+
+            if updated_users == 0 {
+                return Err(diesel::result::Error::NotFound);
+            }
+
+            let other_pending = invitations::table
+                .filter(invitations::invitee.eq(invitation.invitee))
+                .filter(invitations::status.eq(InvitationStatus::Pending));
+
+            diesel::update(other_pending.filter(invitations::expires_at.gt(accepted_at)))
+                .set((
+                    invitations::status.eq(InvitationStatus::Accepted),
+                    invitations::accepted_at.eq(accepted_at),
+                ))
+                .execute(conn)?;
+
+            diesel::update(other_pending)
+                .set(invitations::status.eq(InvitationStatus::Expired))
+                .execute(conn)?;
+
+            Ok(accepted)
+        })
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
+
+/// Stores the invitation, or renews the invitation of the user to the workspace with the token
+/// and dates of the given one, and makes sure the user is a member of the workspace again.
+pub async fn renew_invitation(db: &Db, invitation: Invitation) -> Result<Invitation, Error<Null>> {
+    db.run(move |conn| {
+        conn.transaction::<_, diesel::result::Error, _>(|conn| {
+            // A revoked invitation removed the user from the workspace
+            diesel::insert_into(workspace_members::table)
+                .values(WorkspaceMember {
+                    workspace: invitation.workspace,
+                    member: invitation.invitee,
+                    role: invitation.role,
+                    custom_role: None,
+                })
+                .on_conflict((workspace_members::workspace, workspace_members::member))
+                .do_nothing()
+                .execute(conn)?;
+
+            diesel::insert_into(invitations::table)
+                .values(&invitation)
+                .on_conflict((invitations::workspace, invitations::invitee))
+                .do_update()
+                .set((
+                    invitations::inviter.eq(excluded(invitations::inviter)),
+                    invitations::status.eq(excluded(invitations::status)),
+                    invitations::token_hash.eq(excluded(invitations::token_hash)),
+                    invitations::expires_at.eq(excluded(invitations::expires_at)),
+                    invitations::sent_at.eq(excluded(invitations::sent_at)),
+                    invitations::accepted_at.eq(None::<NaiveDateTime>),
+                    invitations::revoked_at.eq(None::<NaiveDateTime>),
+                ))
+                .get_result::<Invitation>(conn)
+        })
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
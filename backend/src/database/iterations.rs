@@ -0,0 +1,397 @@
+use chrono::NaiveDateTime;
+use diesel::{
+    result::DatabaseErrorKind, Connection, ExpressionMethods, OptionalExtension, PgConnection,
+    QueryDsl, QueryResult, RunQueryDsl,
+};
+use rocket::http::Status;
+use uuid::Uuid;
+
+use crate::{
+    api::{ApiResponse, Error, Null},
+    models::{
+        iterations::{
+            ClosedIteration, Deliverable, DeliverableUpdate, InsertableDeliverable,
+            InsertableIteration, InsertableStatusChange, Iteration, IterationKind, IterationState,
+            IterationUpdate, IterationWithDeliverables, NewDeliverable, StatusChange,
+        },
+        tasks::TaskStatus,
+    },
+    schema::{deliverable_status_changes, deliverables, iterations},
+};
+
+use super::Db;
+
+/// Why an iteration couldn't be started or closed.
+enum TransitionError {
+    Database(diesel::result::Error),
+    /// The iteration isn't in a state it can move on from
+    State(&'static str),
+    /// Another sprint of the project is active
+    ActiveSprint,
+}
+
+impl From<diesel::result::Error> for TransitionError {
+    fn from(error: diesel::result::Error) -> Self {
+        TransitionError::Database(error)
+    }
+}
+
+impl TransitionError {
+    fn into_response(self) -> Error<Null> {
+        match self {
+            // Another sprint was started at the same time
+            TransitionError::Database(diesel::result::Error::DatabaseError(
+                DatabaseErrorKind::UniqueViolation,
+                _,
+            ))
+            | TransitionError::ActiveSprint => ApiResponse::error(
+                Status::Conflict,
+                "Another sprint of the project is active, close it first".to_string(),
+                None,
+            ),
+            TransitionError::Database(error) => ApiResponse::from_error(error),
+            TransitionError::State(message) => ApiResponse::bad_request(message.to_string()),
+        }
+    }
+}
+
+/// The iterations of the project, optionally of a kind or in a state, by start date.
+pub async fn get_iterations(
+    db: &Db,
+    project: Uuid,
+    kind: Option<IterationKind>,
+    state: Option<IterationState>,
+) -> Result<Vec<Iteration>, Error<Null>> {
+    db.run(move |conn| {
+        let mut query = iterations::table
+            .filter(iterations::project.eq(project))
+            .into_boxed();
+
+        if let Some(kind) = kind {
+            query = query.filter(iterations::kind.eq(kind));
+        }
+        if let Some(state) = state {
+            query = query.filter(iterations::state.eq(state));
+        }
+
+        query
+            .order((iterations::start_date.asc(), iterations::created_at.asc()))
+            .load::<Iteration>(conn)
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
+
+/// The iteration, but only if it is one of the project.
+pub async fn get_iteration(db: &Db, project: Uuid, id: Uuid) -> Result<Iteration, Error<Null>> {
+    db.run(move |conn| {
+        iterations::table
+            .filter(iterations::id.eq(id))
+            .filter(iterations::project.eq(project))
+            .first::<Iteration>(conn)
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
+
+pub async fn get_iteration_with_deliverables(
+    db: &Db,
+    project: Uuid,
+    id: Uuid,
+) -> Result<IterationWithDeliverables, Error<Null>> {
+    db.run(move |conn| {
+        let iteration = iterations::table
+            .filter(iterations::id.eq(id))
+            .filter(iterations::project.eq(project))
+            .first::<Iteration>(conn)?;
+
+        let deliverables = deliverables::table
+            .filter(deliverables::iteration.eq(id))
+            .order(deliverables::created_at.asc())
+            .load::<Deliverable>(conn)?;
+
+        Ok(IterationWithDeliverables {
+            iteration,
+            deliverables,
+        })
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
+
+pub async fn insert_iteration(
+    db: &Db,
+    iteration: InsertableIteration,
+) -> Result<Iteration, Error<Null>> {
+    db.run(move |conn| {
+        diesel::insert_into(iterations::table)
+            .values(&iteration)
+            .get_result::<Iteration>(conn)
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
+
+pub async fn update_iteration(
+    db: &Db,
+    project: Uuid,
+    id: Uuid,
+    update: IterationUpdate,
+) -> Result<Iteration, Error<Null>> {
+    db.run(move |conn| {
+        diesel::update(
+            iterations::table
+                .filter(iterations::id.eq(id))
+                .filter(iterations::project.eq(project)),
+        )
+        .set(update)
+        .get_result::<Iteration>(conn)
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
+
+/// Removes the iteration with its deliverables; the deliverables that rolled over from them stay.
+pub async fn remove_iteration(db: &Db, project: Uuid, id: Uuid) -> Result<Iteration, Error<Null>> {
+    db.run(move |conn| {
+        diesel::delete(
+            iterations::table
+                .filter(iterations::id.eq(id))
+                .filter(iterations::project.eq(project)),
+        )
+        .get_result::<Iteration>(conn)
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
+
+/// Makes an open iteration active; a project runs one sprint at a time.
+pub async fn start_iteration(db: &Db, project: Uuid, id: Uuid) -> Result<Iteration, Error<Null>> {
+    db.run(move |conn| {
+        conn.transaction::<_, TransitionError, _>(|conn| {
+            let iteration = lock_iteration(conn, project, id)?;
+            if iteration.state != IterationState::Open {
+                return Err(TransitionError::State(
+                    "Only an open iteration can be started",
+                ));
+            }
+
+            if iteration.kind == IterationKind::Sprint {
+                let active = iterations::table
+                    .filter(iterations::project.eq(project))
+                    .filter(iterations::kind.eq(IterationKind::Sprint))
+                    .filter(iterations::state.eq(IterationState::Active))
+                    .select(iterations::id)
+                    .first::<Uuid>(conn)
+                    .optional()?;
+                if active.is_some() {
+                    return Err(TransitionError::ActiveSprint);
+                }
+            }
+
+            Ok(
+                diesel::update(iterations::table.filter(iterations::id.eq(id)))
+                    .set(iterations::state.eq(IterationState::Active))
+                    .get_result::<Iteration>(conn)?,
+            )
+        })
+    })
+    .await
+    .map_err(TransitionError::into_response)
+}
+
+/// Closes the iteration, and rolls its unfinished deliverables over to the iteration named, or
+/// else to the first open iteration of the same kind. The unfinished deliverables stay with the
+/// closed iteration as they were, so its burndown doesn't change afterwards.
+pub async fn close_iteration(
+    db: &Db,
+    project: Uuid,
+    id: Uuid,
+    roll_over_to: Option<Uuid>,
+    closed_at: NaiveDateTime,
+) -> Result<ClosedIteration, Error<Null>> {
+    db.run(move |conn| {
+        conn.transaction::<_, TransitionError, _>(|conn| {
+            let iteration = lock_iteration(conn, project, id)?;
+            if iteration.state == IterationState::Closed {
+                return Err(TransitionError::State("The iteration is already closed"));
+            }
+
+            let next = iterations::table
+                .filter(iterations::project.eq(project))
+                .filter(iterations::kind.eq(iteration.kind))
+                .filter(iterations::id.ne(id))
+                .into_boxed();
+            let next = match roll_over_to {
+                Some(target) => Some(
+                    next.filter(iterations::id.eq(target))
+                        .filter(iterations::state.ne(IterationState::Closed))
+                        .first::<Iteration>(conn)?,
+                ),
+                None => next
+                    .filter(iterations::state.eq(IterationState::Open))
+                    .order((iterations::start_date.asc(), iterations::created_at.asc()))
+                    .first::<Iteration>(conn)
+                    .optional()?,
+            };
+
+            let iteration = diesel::update(iterations::table.filter(iterations::id.eq(id)))
+                .set((
+                    iterations::state.eq(IterationState::Closed),
+                    iterations::closed_at.eq(closed_at),
+                ))
+                .get_result::<Iteration>(conn)?;
+
+            let mut rolled_over = Vec::new();
+            if let Some(next) = next {
+                let unfinished = deliverables::table
+                    .filter(deliverables::iteration.eq(id))
+                    .filter(deliverables::status.ne(TaskStatus::Done))
+                    .filter(deliverables::status.ne(TaskStatus::Cancelled))
+                    .order(deliverables::created_at.asc())
+                    .load::<Deliverable>(conn)?;
+
+                for deliverable in unfinished {
+                    let continued = InsertableDeliverable {
+                        iteration: next.id,
+                        title: deliverable.title,
+                        status: deliverable.status,
+                        rolled_over_from: Some(deliverable.id),
+                    };
+                    rolled_over.push(add_deliverable(conn, continued, closed_at)?);
+                }
+            }
+
+            Ok(ClosedIteration {
+                iteration,
+                rolled_over,
+            })
+        })
+    })
+    .await
+    .map_err(TransitionError::into_response)
+}
+
+/// Every status change of the deliverables of the iteration.
+pub async fn get_status_changes(
+    db: &Db,
+    iteration: Uuid,
+) -> Result<Vec<StatusChange>, Error<Null>> {
+    db.run(move |conn| {
+        deliverable_status_changes::table
+            .inner_join(deliverables::table)
+            .filter(deliverables::iteration.eq(iteration))
+            .select(deliverable_status_changes::all_columns)
+            .load::<StatusChange>(conn)
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
+
+/// Adds the deliverable, recording the status it starts with.
+pub async fn insert_deliverable(
+    db: &Db,
+    iteration: Uuid,
+    deliverable: NewDeliverable,
+    changed_at: NaiveDateTime,
+) -> Result<Deliverable, Error<Null>> {
+    db.run(move |conn| {
+        conn.transaction(|conn| {
+            let deliverable = InsertableDeliverable {
+                iteration,
+                title: deliverable.title.trim().to_string(),
+                status: deliverable.status,
+                rolled_over_from: None,
+            };
+
+            add_deliverable(conn, deliverable, changed_at)
+        })
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
+
+/// Updates the deliverable, recording its new status when it changed.
+pub async fn update_deliverable(
+    db: &Db,
+    iteration: Uuid,
+    id: Uuid,
+    update: DeliverableUpdate,
+    changed_at: NaiveDateTime,
+) -> Result<Deliverable, Error<Null>> {
+    db.run(move |conn| {
+        conn.transaction::<_, diesel::result::Error, _>(|conn| {
+            let current = deliverables::table
+                .filter(deliverables::id.eq(id))
+                .filter(deliverables::iteration.eq(iteration))
+                .for_update()
+                .first::<Deliverable>(conn)?;
+
+            let deliverable = diesel::update(deliverables::table.filter(deliverables::id.eq(id)))
+                .set(&update)
+                .get_result::<Deliverable>(conn)?;
+
+            if deliverable.status != current.status {
+                record_status(conn, &deliverable, changed_at)?;
+            }
+
+            Ok(deliverable)
+        })
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
+
+pub async fn remove_deliverable(
+    db: &Db,
+    iteration: Uuid,
+    id: Uuid,
+) -> Result<Deliverable, Error<Null>> {
+    db.run(move |conn| {
+        diesel::delete(
+            deliverables::table
+                .filter(deliverables::id.eq(id))
+                .filter(deliverables::iteration.eq(iteration)),
+        )
+        .get_result::<Deliverable>(conn)
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
+
+fn lock_iteration(conn: &mut PgConnection, project: Uuid, id: Uuid) -> QueryResult<Iteration> {
+    iterations::table
+        .filter(iterations::id.eq(id))
+        .filter(iterations::project.eq(project))
+        .for_update()
+        .first::<Iteration>(conn)
+}
+
+fn add_deliverable(
+    conn: &mut PgConnection,
+    deliverable: InsertableDeliverable,
+    changed_at: NaiveDateTime,
+) -> QueryResult<Deliverable> {
+    let deliverable = diesel::insert_into(deliverables::table)
+        .values(&deliverable)
+        .get_result::<Deliverable>(conn)?;
+    record_status(conn, &deliverable, changed_at)?;
+
+    Ok(deliverable)
+}
+
+fn record_status(
+    conn: &mut PgConnection,
+    deliverable: &Deliverable,
+    changed_at: NaiveDateTime,
+) -> QueryResult<()> {
+    diesel::insert_into(deliverable_status_changes::table)
+        .values(&InsertableStatusChange {
+            deliverable: deliverable.id,
+            status: deliverable.status,
+            changed_at,
+        })
+        .execute(conn)?;
+
+    Ok(())
+}
@@ -0,0 +1,142 @@
+use diesel::{Connection, ExpressionMethods, QueryDsl, RunQueryDsl};
+use uuid::Uuid;
+
+use crate::{
+    api::{ApiResponse, Error, Null},
+    models::labels::{InsertableLabel, Label, LabelUpdate, ProjectLabel},
+    schema::{labels, project_labels},
+};
+
+use super::Db;
+
+/// The labels of the workspace, by name.
+pub async fn get_labels(db: &Db, workspace: Uuid) -> Result<Vec<Label>, Error<Null>> {
+    db.run(move |conn| {
+        labels::table
+            .filter(labels::workspace.eq(workspace))
+            .order(labels::name.asc())
+            .load::<Label>(conn)
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
+
+pub async fn get_label(db: &Db, workspace: Uuid, id: Uuid) -> Result<Label, Error<Null>> {
+    db.run(move |conn| {
+        labels::table
+            .filter(labels::id.eq(id))
+            .filter(labels::workspace.eq(workspace))
+            .first::<Label>(conn)
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
+
+pub async fn insert_label(db: &Db, label: InsertableLabel) -> Result<Label, Error<Null>> {
+    db.run(move |conn| {
+        diesel::insert_into(labels::table)
+            .values(&label)
+            .get_result::<Label>(conn)
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
+
+/// Updates the label; the projects only refer to it, so they show the change right away.
+pub async fn update_label(
+    db: &Db,
+    workspace: Uuid,
+    id: Uuid,
+    update: LabelUpdate,
+) -> Result<Label, Error<Null>> {
+    db.run(move |conn| {
+        diesel::update(
+            labels::table
+                .filter(labels::id.eq(id))
+                .filter(labels::workspace.eq(workspace)),
+        )
+        .set(update)
+        .get_result::<Label>(conn)
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
+
+/// Removes the label, and with it the label from every project tagged with it.
+pub async fn remove_label(db: &Db, workspace: Uuid, id: Uuid) -> Result<Label, Error<Null>> {
+    db.run(move |conn| {
+        diesel::delete(
+            labels::table
+                .filter(labels::id.eq(id))
+                .filter(labels::workspace.eq(workspace)),
+        )
+        .get_result::<Label>(conn)
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
+
+/// The labels of the project, by name.
+pub async fn get_project_labels(db: &Db, project: Uuid) -> Result<Vec<Label>, Error<Null>> {
+    db.run(move |conn| {
+        project_labels::table
+            .inner_join(labels::table)
+            .filter(project_labels::project.eq(project))
+            .order(labels::name.asc())
+            .select(labels::all_columns)
+            .load::<Label>(conn)
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
+
+/// Tags the project with a label of its workspace; tagging it twice changes nothing.
+pub async fn attach_label(
+    db: &Db,
+    workspace: Uuid,
+    project: Uuid,
+    label: Uuid,
+) -> Result<Label, Error<Null>> {
+    db.run(move |conn| {
+        conn.transaction::<_, diesel::result::Error, _>(|conn| {
+            let label = labels::table
+                .filter(labels::id.eq(label))
+                .filter(labels::workspace.eq(workspace))
+                .first::<Label>(conn)?;
+
+            diesel::insert_into(project_labels::table)
+                .values(&ProjectLabel {
+                    project,
+                    label: label.id,
+                })
+                .on_conflict_do_nothing()
+                .execute(conn)?;
+
+            Ok(label)
+        })
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
+
+/// Removes the label from the project; a project without the label is not found.
+pub async fn detach_label(db: &Db, project: Uuid, label: Uuid) -> Result<(), Error<Null>> {
+    let removed = db
+        .run(move |conn| {
+            diesel::delete(
+                project_labels::table
+                    .filter(project_labels::project.eq(project))
+                    .filter(project_labels::label.eq(label)),
+            )
+            .execute(conn)
+        })
+        .await
+        .map_err(ApiResponse::from_error)?;
+
+    match removed {
+        0 => Err(ApiResponse::not_found(format!(
+            "The project isn't tagged with label '{label}'"
+        ))),
+        _ => Ok(()),
+    }
+}
@@ -1,5 +1,16 @@
+pub mod access_tokens;
+pub mod boards;
+pub mod comments;
+pub mod identities;
+pub mod impersonation;
+pub mod invitations;
+pub mod iterations;
+pub mod labels;
 pub mod pagination;
 pub mod projects;
+pub mod roles;
+pub mod tasks;
+pub mod two_factor;
 pub mod users;
 pub mod workspaces;
 
@@ -1,3 +1,4 @@
 pub mod meta;
 pub mod projects;
+pub mod tasks;
 pub mod users;
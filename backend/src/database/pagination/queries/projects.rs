@@ -6,9 +6,18 @@ use uuid::Uuid;
 
 use crate::{
     database::pagination::sort::{ProjectField, SortDirection},
+    models::labels::LabelMatch,
     schema::projects::BoxedQuery as ProjectQuery,
 };
 
+/// The labels the projects are filtered by, next to the search; without labels, the projects
+/// aren't filtered by them at all.
+#[derive(Clone, Debug, Default)]
+pub struct LabelFilter {
+    pub labels: Vec<Uuid>,
+    pub matching: LabelMatch,
+}
+
 pub fn sort<'a>(
     query: ProjectQuery<'a, Pg>,
     sort_by: &Option<ProjectField>,
@@ -39,8 +48,10 @@ pub fn build<'a>(
     filter_search: &str,
     workspace: Option<Uuid>,
     user: Option<Uuid>,
+    label_filter: &LabelFilter,
 ) -> ProjectQuery<'a, diesel::pg::Pg> {
     use crate::schema::{
+        project_labels::dsl as project_labels_dsl,
         project_members::dsl as project_members_dsl,
         projects::{self, dsl as projects_dsl},
     };
@@ -62,6 +73,26 @@ pub fn build<'a>(
         ));
     }
 
+    // If labels are provided, the project needs any or all of them
+    if !label_filter.labels.is_empty() {
+        let tagged_with = |labels: Vec<Uuid>| {
+            exists(
+                project_labels_dsl::project_labels
+                    .filter(project_labels_dsl::project.eq(projects::id))
+                    .filter(project_labels_dsl::label.eq_any(labels)),
+            )
+        };
+
+        match label_filter.matching {
+            LabelMatch::Any => query = query.filter(tagged_with(label_filter.labels.clone())),
+            LabelMatch::All => {
+                for label in &label_filter.labels {
+                    query = query.filter(tagged_with(vec![*label]));
+                }
+            }
+        }
+    }
+
     // Add the search filter
     if !filter_search.is_empty() {
         // Add excape characters for unsafe characters
@@ -0,0 +1,88 @@
+use diesel::{
+    pg::Pg, BoolExpressionMethods, ExpressionMethods, PgSortExpressionMethods,
+    PgTextExpressionMethods, QueryDsl,
+};
+use uuid::Uuid;
+
+use crate::{
+    database::pagination::sort::{SortDirection, TaskField},
+    models::tasks::{TaskPriority, TaskStatus},
+    schema::tasks::BoxedQuery as TaskQuery,
+};
+
+/// The filters on the tasks of a project, next to the search.
+#[derive(Clone, Copy, Default)]
+pub struct TaskFilter {
+    pub status: Option<TaskStatus>,
+    pub priority: Option<TaskPriority>,
+    pub assignee: Option<Uuid>,
+}
+
+pub fn sort<'a>(
+    query: TaskQuery<'a, Pg>,
+    sort_by: &Option<TaskField>,
+    sort_dir: &Option<SortDirection>,
+) -> TaskQuery<'a, Pg> {
+    use crate::schema::tasks::dsl::*;
+
+    match sort_dir {
+        Some(SortDirection::Desc) => match sort_by {
+            Some(TaskField::Title) => query.order(title.desc()),
+            Some(TaskField::Status) => query.order(status.desc()),
+            Some(TaskField::Priority) => query.order(priority.desc()),
+            Some(TaskField::DueDate) => query.order(due_date.desc().nulls_last()),
+            Some(TaskField::CreatedAt) => query.order(created_at.desc()),
+            Some(TaskField::UpdatedAt) => query.order(updated_at.desc()),
+            _ => query.order(id.desc()),
+        },
+        // Ascending by default
+        _ => match sort_by {
+            Some(TaskField::Title) => query.order(title.asc()),
+            Some(TaskField::Status) => query.order(status.asc()),
+            Some(TaskField::Priority) => query.order(priority.asc()),
+            Some(TaskField::DueDate) => query.order(due_date.asc().nulls_last()),
+            Some(TaskField::CreatedAt) => query.order(created_at.asc()),
+            Some(TaskField::UpdatedAt) => query.order(updated_at.asc()),
+            _ => query.order(id.asc()),
+        },
+    }
+}
+
+pub fn build<'a>(filter_search: &str, project: Uuid, filter: TaskFilter) -> TaskQuery<'a, Pg> {
+    use crate::schema::tasks::{self, dsl as tasks_dsl};
+
+    // Only the tasks of the project
+    let mut query = tasks_dsl::tasks
+        .into_boxed::<Pg>()
+        .filter(tasks::project.eq(project));
+
+    if let Some(status) = filter.status {
+        query = query.filter(tasks::status.eq(status));
+    }
+
+    if let Some(priority) = filter.priority {
+        query = query.filter(tasks::priority.eq(priority));
+    }
+
+    if let Some(assignee) = filter.assignee {
+        query = query.filter(tasks::assignee.eq(assignee));
+    }
+
+    // Add the search filter
+    if !filter_search.is_empty() {
+        // Add excape characters for unsafe characters
+        let safe_search = format!(
+            "%{}%",
+            filter_search.replace('%', "\\%").replace('_', "\\_")
+        );
+
+        // Apply the search filter on the query
+        query = query.filter(
+            tasks::title
+                .ilike(safe_search.clone())
+                .or(tasks::description.ilike(safe_search)),
+        );
+    }
+
+    query
+}
@@ -1,12 +1,12 @@
 use diesel::{
     pg::Pg, BoolExpressionMethods, ExpressionMethods, PgConnection, PgTextExpressionMethods,
-    QueryDsl, RunQueryDsl,
+    QueryDsl, QueryResult, RunQueryDsl,
 };
 use uuid::Uuid;
 
 use crate::{
     database::pagination::sort::{SortDirection, UserField},
-    models::users::PublicUser,
+    models::users::{PublicUser, UserRole, UserStatus},
     schema::users::BoxedQuery as UserQuery,
 };
 
@@ -48,9 +48,10 @@ pub fn build<'a>(
     conn: &mut PgConnection,
     user: PublicUser,
     filter_search: &str,
-    filter_status: Option<i16>,
-    filter_role: Option<i16>,
-) -> UserQuery<'a, diesel::pg::Pg> {
+    filter_status: Option<UserStatus>,
+    filter_role: Option<UserRole>,
+) -> QueryResult<UserQuery<'a, diesel::pg::Pg>> {
+    use crate::schema::project_members::dsl as project_members;
     use crate::schema::users::dsl as users;
     use crate::schema::workspace_members::dsl as workspace_members;
 
@@ -92,16 +93,29 @@ pub fn build<'a>(
         let workspace_ids: Vec<Uuid> = workspace_members::workspace_members
             .filter(workspace_members::member.eq(user.id))
             .select(workspace_members::workspace)
-            .load(conn)
-            .expect("Failed to fetch workspaces");
+            .load(conn)?;
 
         // Find user_ids in those workspaces
-        let accessible_user_ids: Vec<Uuid> = workspace_members::workspace_members
+        let mut accessible_user_ids: Vec<Uuid> = workspace_members::workspace_members
             .filter(workspace_members::workspace.eq_any(&workspace_ids))
             .select(workspace_members::member)
             .distinct()
-            .load(conn)
-            .expect("Failed to fetch accessible users");
+            .load(conn)?;
+
+        // Guests are not members of the workspace; they are only found by the members of their
+        // projects, and find only those
+        let project_ids: Vec<Uuid> = project_members::project_members
+            .filter(project_members::member.eq(user.id))
+            .select(project_members::project)
+            .load(conn)?;
+
+        let project_user_ids: Vec<Uuid> = project_members::project_members
+            .filter(project_members::project.eq_any(&project_ids))
+            .select(project_members::member)
+            .distinct()
+            .load(conn)?;
+
+        accessible_user_ids.extend(project_user_ids);
 
         // Restrict query to those users
         query = query.filter(users::id.eq_any(accessible_user_ids));
@@ -109,5 +123,5 @@ pub fn build<'a>(
 
     // Remove self from the list
     query = query.filter(users::id.ne(user.id));
-    query
+    Ok(query)
 }
@@ -11,6 +11,7 @@ pub trait SortField {}
 
 impl SortField for UserField {}
 impl SortField for ProjectField {}
+impl SortField for TaskField {}
 
 #[derive(Clone, Deserialize, Serialize)]
 #[serde(rename_all = "snake_case")]
@@ -33,3 +34,14 @@ pub enum ProjectField {
     CreatedAt,
     UpdatedAt,
 }
+
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskField {
+    Title,
+    Status,
+    Priority,
+    DueDate,
+    CreatedAt,
+    UpdatedAt,
+}
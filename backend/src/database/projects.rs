@@ -9,14 +9,19 @@ use crate::{
         sort::ProjectField,
     },
     models::{
-        projects::{NewProject, Project, ProjectMember, ProjectUpdate, ProjectWithMembers},
+        projects::{
+            NewProject, Project, ProjectMember, ProjectRole, ProjectUpdate, ProjectWithMembers,
+        },
         users::{PublicUser, User},
         MemberInfo,
     },
     schema::{project_members, projects, users},
 };
 
-use super::{pagination::queries::projects as query_projects, Db};
+use super::{
+    pagination::queries::projects::{self as query_projects, LabelFilter},
+    Db,
+};
 
 pub async fn get_project_by_id(db: &Db, id: Uuid) -> Result<ProjectWithMembers, Error<Null>> {
     db.run(move |conn| {
@@ -28,13 +33,19 @@ pub async fn get_project_by_id(db: &Db, id: Uuid) -> Result<ProjectWithMembers,
         let members = project_members::table
             .inner_join(users::table.on(users::id.eq(project_members::member)))
             .filter(project_members::project.eq(id))
-            .select((users::all_columns, project_members::role))
-            .load::<(User, i16)>(conn)
+            .select((
+                users::all_columns,
+                project_members::role,
+                project_members::guest,
+            ))
+            .load::<(User, ProjectRole, bool)>(conn)
             .map_err(ApiResponse::from_error)?
             .into_iter()
-            .map(|(user, role)| MemberInfo {
+            .map(|(user, role, guest)| MemberInfo {
                 user: PublicUser::from(&user),
                 role,
+                custom_role: None,
+                guest,
             })
             .collect();
 
@@ -136,6 +147,32 @@ pub async fn remove_member_from_project(
     .map_err(ApiResponse::from_error)
 }
 
+/// Turns guests who became members of the workspace into regular members of its projects;
+/// returns the IDs of the affected projects.
+pub async fn remove_guest_status(
+    db: &Db,
+    workspace: Uuid,
+    members: Vec<Uuid>,
+) -> Result<Vec<Uuid>, Error<Null>> {
+    db.run(move |conn| {
+        let workspace_projects = projects::table
+            .filter(projects::workspace.eq(workspace))
+            .select(projects::id);
+
+        diesel::update(
+            project_members::table
+                .filter(project_members::guest.eq(true))
+                .filter(project_members::member.eq_any(members))
+                .filter(project_members::project.eq_any(workspace_projects)),
+        )
+        .set(project_members::guest.eq(false))
+        .returning(project_members::project)
+        .get_results::<Uuid>(conn)
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
+
 fn fetch_project_with_members(
     id: Uuid,
     conn: &mut PgConnection,
@@ -153,11 +190,13 @@ fn fetch_project_with_members(
         .load(conn)?;
 
     // Build members list
-    let members: Vec<MemberInfo> = member_results
+    let members: Vec<MemberInfo<ProjectRole>> = member_results
         .into_iter()
         .map(|(membership, user)| MemberInfo {
             user: PublicUser::from(&user),
             role: membership.role,
+            custom_role: None,
+            guest: membership.guest,
         })
         .collect();
 
@@ -169,6 +208,7 @@ pub async fn get_projects_paginated(
     db: &Db,
     workspace: Option<Uuid>,
     user: Option<Uuid>,
+    label_filter: LabelFilter,
     params: Json<PaginationRequest<ProjectField>>,
 ) -> Result<PaginatedRecords<Project>, Error<Null>> {
     // Extract the pagination request
@@ -180,7 +220,7 @@ pub async fn get_projects_paginated(
             let search = params.search.as_deref().unwrap_or_default();
 
             // Build the query as COUNT to get the total
-            let total = query_projects::build(search, workspace, user)
+            let total = query_projects::build(search, workspace, user, &label_filter)
                 .count()
                 .get_result::<i64>(conn)?;
 
@@ -188,7 +228,7 @@ pub async fn get_projects_paginated(
             let meta = PaginationMetaData::new(total, &params);
 
             // Build the query again for LOAD and apply filtering
-            let mut query = query_projects::build(search, workspace, user);
+            let mut query = query_projects::build(search, workspace, user, &label_filter);
 
             // Apply sorting to the query
             query = query_projects::sort(query, &params.sort_by, &params.sort_dir);
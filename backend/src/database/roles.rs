@@ -0,0 +1,85 @@
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
+use uuid::Uuid;
+
+use crate::{
+    api::{ApiResponse, Error, Null},
+    models::roles::{CustomRole, CustomRoleUpdate, RoleAssignment},
+    schema::{workspace_members, workspace_roles},
+};
+
+use super::Db;
+
+pub async fn insert_custom_role(db: &Db, role: CustomRole) -> Result<CustomRole, Error<Null>> {
+    db.run(move |conn| {
+        diesel::insert_into(workspace_roles::table)
+            .values(&role)
+            .get_result::<CustomRole>(conn)
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
+
+pub async fn update_custom_role(
+    db: &Db,
+    workspace: Uuid,
+    id: Uuid,
+    update: CustomRoleUpdate,
+) -> Result<CustomRole, Error<Null>> {
+    db.run(move |conn| {
+        diesel::update(
+            workspace_roles::table
+                .filter(workspace_roles::workspace.eq(workspace))
+                .filter(workspace_roles::id.eq(id)),
+        )
+        .set(update)
+        .get_result::<CustomRole>(conn)
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
+
+/// Deletes a custom role; its members get their built-in role back.
+pub async fn delete_custom_role(
+    db: &Db,
+    workspace: Uuid,
+    id: Uuid,
+) -> Result<CustomRole, Error<Null>> {
+    db.run(move |conn| {
+        diesel::delete(
+            workspace_roles::table
+                .filter(workspace_roles::workspace.eq(workspace))
+                .filter(workspace_roles::id.eq(id)),
+        )
+        .get_result::<CustomRole>(conn)
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
+
+/// Gives a member a built-in role, or a custom role on top of the built-in role it keeps.
+pub async fn assign_role(
+    db: &Db,
+    workspace: Uuid,
+    member: Uuid,
+    assignment: RoleAssignment,
+) -> Result<usize, Error<Null>> {
+    db.run(move |conn| {
+        let membership = workspace_members::table
+            .filter(workspace_members::workspace.eq(workspace))
+            .filter(workspace_members::member.eq(member));
+
+        match assignment {
+            RoleAssignment::Preset(role) => diesel::update(membership)
+                .set((
+                    workspace_members::role.eq(role),
+                    workspace_members::custom_role.eq(None::<Uuid>),
+                ))
+                .execute(conn),
+            RoleAssignment::Custom(id) => diesel::update(membership)
+                .set(workspace_members::custom_role.eq(id))
+                .execute(conn),
+        }
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
@@ -0,0 +1,114 @@
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
+use rocket::serde::json::Json;
+use uuid::Uuid;
+
+use crate::{
+    api::{ApiResponse, Error, Null},
+    database::pagination::{
+        queries::{meta::PaginationMetaData, tasks::TaskFilter},
+        records::PaginatedRecords,
+        request::PaginationRequest,
+        sort::TaskField,
+    },
+    models::tasks::{NewTask, Task, TaskUpdate},
+    schema::tasks,
+};
+
+use super::{pagination::queries::tasks as query_tasks, Db};
+
+pub async fn insert_task(db: &Db, task: NewTask) -> Result<Task, Error<Null>> {
+    db.run(move |conn| {
+        diesel::insert_into(tasks::table)
+            .values(&task)
+            .get_result::<Task>(conn)
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
+
+/// The task, but only if it is one of the project.
+pub async fn get_task(db: &Db, project: Uuid, id: Uuid) -> Result<Task, Error<Null>> {
+    db.run(move |conn| {
+        tasks::table
+            .filter(tasks::id.eq(id))
+            .filter(tasks::project.eq(project))
+            .first::<Task>(conn)
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
+
+pub async fn get_tasks_paginated(
+    db: &Db,
+    project: Uuid,
+    filter: TaskFilter,
+    params: Json<PaginationRequest<TaskField>>,
+) -> Result<PaginatedRecords<Task>, Error<Null>> {
+    // Extract the pagination request
+    let params = params.into_inner();
+
+    let (meta, tasks) = db
+        .run(move |conn| {
+            // Define the search string
+            let search = params.search.as_deref().unwrap_or_default();
+
+            // Build the query as COUNT to get the total
+            let total = query_tasks::build(search, project, filter)
+                .count()
+                .get_result::<i64>(conn)?;
+
+            // Calculate the pagination meta data
+            let meta = PaginationMetaData::new(total, &params);
+
+            // Build the query again for LOAD and apply sorting
+            let query = query_tasks::sort(
+                query_tasks::build(search, project, filter),
+                &params.sort_by,
+                &params.sort_dir,
+            );
+
+            // Add the offset and limit and run the query
+            let tasks: Vec<Task> = query
+                .offset(meta.record_offset)
+                .limit(meta.page_limit)
+                .load::<Task>(conn)?;
+
+            Ok((meta, tasks))
+        })
+        .await
+        .map_err(ApiResponse::from_error)?;
+
+    Ok(PaginatedRecords::<Task>::new(meta, tasks))
+}
+
+pub async fn update_task(
+    db: &Db,
+    project: Uuid,
+    id: Uuid,
+    update: TaskUpdate,
+) -> Result<Task, Error<Null>> {
+    db.run(move |conn| {
+        diesel::update(
+            tasks::table
+                .filter(tasks::id.eq(id))
+                .filter(tasks::project.eq(project)),
+        )
+        .set(update)
+        .get_result::<Task>(conn)
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
+
+pub async fn remove_task(db: &Db, project: Uuid, id: Uuid) -> Result<Task, Error<Null>> {
+    db.run(move |conn| {
+        diesel::delete(
+            tasks::table
+                .filter(tasks::id.eq(id))
+                .filter(tasks::project.eq(project)),
+        )
+        .get_result::<Task>(conn)
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
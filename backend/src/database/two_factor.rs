@@ -0,0 +1,75 @@
+use diesel::{upsert::excluded, ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl};
+use uuid::Uuid;
+
+use crate::{
+    api::{ApiResponse, Error, Null},
+    database::Db,
+    models::two_factor::TwoFactor,
+    schema::user_two_factor,
+};
+
+pub async fn get_two_factor(db: &Db, user_id: Uuid) -> Result<Option<TwoFactor>, Error<Null>> {
+    db.run(move |conn| {
+        user_two_factor::table
+            .find(user_id)
+            .first::<TwoFactor>(conn)
+            .optional()
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
+
+/// Stores a new second factor for the user, replacing any previous (unconfirmed) one.
+pub async fn upsert_two_factor(db: &Db, two_factor: TwoFactor) -> Result<usize, Error<Null>> {
+    db.run(move |conn| {
+        diesel::insert_into(user_two_factor::table)
+            .values(&two_factor)
+            .on_conflict(user_two_factor::user_id)
+            .do_update()
+            .set((
+                user_two_factor::secret.eq(excluded(user_two_factor::secret)),
+                user_two_factor::enabled.eq(excluded(user_two_factor::enabled)),
+                user_two_factor::recovery_codes.eq(excluded(user_two_factor::recovery_codes)),
+            ))
+            .execute(conn)
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
+
+pub async fn enable_two_factor(db: &Db, user_id: Uuid) -> Result<usize, Error<Null>> {
+    db.run(move |conn| {
+        diesel::update(user_two_factor::table.find(user_id))
+            .set(user_two_factor::enabled.eq(true))
+            .execute(conn)
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
+
+/// Replaces the recovery codes of the user, but only if they are still the ones that were read;
+/// returns 0 if another request used one of them in the meantime.
+pub async fn update_recovery_codes(
+    db: &Db,
+    user_id: Uuid,
+    previous: Vec<String>,
+    recovery_codes: Vec<String>,
+) -> Result<usize, Error<Null>> {
+    db.run(move |conn| {
+        diesel::update(
+            user_two_factor::table
+                .find(user_id)
+                .filter(user_two_factor::recovery_codes.eq(previous)),
+        )
+        .set(user_two_factor::recovery_codes.eq(recovery_codes))
+        .execute(conn)
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
+
+pub async fn delete_two_factor(db: &Db, user_id: Uuid) -> Result<usize, Error<Null>> {
+    db.run(move |conn| diesel::delete(user_two_factor::table.find(user_id)).execute(conn))
+        .await
+        .map_err(ApiResponse::from_error)
+}
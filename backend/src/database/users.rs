@@ -9,7 +9,7 @@ use uuid::Uuid;
 use crate::{
     api::{ApiResponse, Error, Null},
     database::{pagination::queries::meta::PaginationMetaData, Db},
-    models::users::{PublicUser, User, UserStatus, UserUpdate},
+    models::users::{PublicUser, User, UserRole, UserStatus, UserUpdate},
     schema::users,
 };
 
@@ -18,6 +18,11 @@ use super::pagination::{
     sort::UserField,
 };
 
+diesel::define_sql_function! {
+    /// Lowercases text; matches the `idx_email_lower` index on the users table
+    fn lower(x: diesel::sql_types::Text) -> diesel::sql_types::Text;
+}
+
 pub async fn setup_admin(db: &Db) -> Result<(), diesel::result::Error> {
     // Create the initial admin user
     let admin = User::init_admin();
@@ -46,8 +51,8 @@ pub async fn setup_admin(db: &Db) -> Result<(), diesel::result::Error> {
 pub async fn get_users_paginated(
     db: &Db,
     user: PublicUser,
-    status: Option<i16>,
-    role: Option<i16>,
+    status: Option<UserStatus>,
+    role: Option<UserRole>,
     params: Json<PaginationRequest<UserField>>,
 ) -> Result<PaginatedRecords<PublicUser>, Error<Null>> {
     // Extract the pagination request
@@ -59,7 +64,7 @@ pub async fn get_users_paginated(
             let search = params.search.as_deref().unwrap_or_default();
 
             // Build the query as COUNT to get the total
-            let total = query_users::build(conn, user.clone(), search, status, role)
+            let total = query_users::build(conn, user.clone(), search, status, role)?
                 .count()
                 .get_result::<i64>(conn)?;
 
@@ -67,7 +72,7 @@ pub async fn get_users_paginated(
             let meta = PaginationMetaData::new(total, &params);
 
             // Build the query again for LOAD and apply filtering
-            let mut query = query_users::build(conn, user, search, status, role);
+            let mut query = query_users::build(conn, user, search, status, role)?;
 
             // Apply sorting to the query
             query = query_users::sort(query, &params.sort_by, &params.sort_dir);
@@ -105,6 +110,19 @@ pub async fn get_user_by_username(db: &Db, username: &str) -> Result<User, Error
     .map_err(ApiResponse::from_error)
 }
 
+/// Returns the user with the email address, ignoring the case of the address.
+pub async fn get_user_by_email(db: &Db, email: &str) -> Result<User, Error<Null>> {
+    let email = email.to_lowercase();
+
+    db.run(move |conn| {
+        users::table
+            .filter(lower(users::email).eq(email))
+            .first::<User>(conn)
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
+
 pub async fn get_username_duplicates(
     db: &Db,
     base_usernames: &HashSet<String>,
@@ -150,7 +168,34 @@ pub async fn update_user_information(
     .await
 }
 
-pub async fn update_user_status(db: &Db, id: Uuid, status: i16) -> Result<PublicUser, Error<Null>> {
+/// Updates the attributes that are managed in a directory, like LDAP.
+pub async fn update_directory_attributes(
+    db: &Db,
+    id: Uuid,
+    first_name: String,
+    last_name: String,
+    email: String,
+    role: UserRole,
+) -> Result<User, Error<Null>> {
+    db.run(move |conn| {
+        diesel::update(users::table.filter(users::id.eq(id)))
+            .set((
+                users::first_name.eq(first_name),
+                users::last_name.eq(last_name),
+                users::email.eq(email),
+                users::role.eq(role),
+            ))
+            .get_result::<User>(conn)
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
+
+pub async fn update_user_status(
+    db: &Db,
+    id: Uuid,
+    status: UserStatus,
+) -> Result<PublicUser, Error<Null>> {
     db.run(move |conn| {
         diesel::update(users::table.filter(users::id.eq(id)))
             .set(users::status.eq(status))
@@ -161,7 +206,11 @@ pub async fn update_user_status(db: &Db, id: Uuid, status: i16) -> Result<Public
     .await
 }
 
-pub async fn update_user_role(db: &Db, id: Uuid, role: i16) -> Result<PublicUser, Error<Null>> {
+pub async fn update_user_role(
+    db: &Db,
+    id: Uuid,
+    role: UserRole,
+) -> Result<PublicUser, Error<Null>> {
     db.run(move |conn| {
         diesel::update(users::table.filter(users::id.eq(id)))
             .set(users::role.eq(role))
@@ -204,7 +253,7 @@ pub async fn set_user_password(
         diesel::update(users::table.filter(users::id.eq(&id)))
             .set((
                 users::password.eq(&password_hash),
-                users::status.eq(i16::from(UserStatus::Active)),
+                users::status.eq(UserStatus::Active),
             ))
             .execute(conn)
             .map_err(ApiResponse::from_error)
@@ -212,6 +261,31 @@ pub async fn set_user_password(
     .await
 }
 
+/// Replaces the password hash of a user without touching the status.
+pub async fn update_user_password(
+    db: &Db,
+    id: Uuid,
+    password_hash: String,
+) -> Result<usize, Error<Null>> {
+    db.run(move |conn| {
+        diesel::update(users::table.filter(users::id.eq(&id)))
+            .set(users::password.eq(&password_hash))
+            .execute(conn)
+            .map_err(ApiResponse::from_error)
+    })
+    .await
+}
+
+pub async fn update_user_email(db: &Db, id: Uuid, email: String) -> Result<usize, Error<Null>> {
+    db.run(move |conn| {
+        diesel::update(users::table.filter(users::id.eq(&id)))
+            .set(users::email.eq(&email))
+            .execute(conn)
+            .map_err(ApiResponse::from_error)
+    })
+    .await
+}
+
 pub async fn delete_user_by_id(db: &Db, id: Uuid) -> Result<usize, Error<Null>> {
     db.run(move |conn| diesel::delete(users::table.filter(users::id.eq(id))).execute(conn))
         .await
@@ -264,3 +338,28 @@ pub async fn get_user_ids_in_same_workspaces(
     .await
     .map_err(ApiResponse::from_error)
 }
+
+/// Returns the IDs of the users who are a member of any of the projects of which the user is a
+/// member, which is how guests and the members of their projects find each other.
+pub async fn get_user_ids_in_same_projects(db: &Db, user: Uuid) -> Result<Vec<Uuid>, Error<Null>> {
+    use crate::schema::project_members::dsl as project_members_dsl;
+
+    db.run(move |conn| {
+        conn.transaction::<Vec<Uuid>, diesel::result::Error, _>(|conn| {
+            // 1. Get project IDs where the user is a member
+            let project_ids: Vec<Uuid> = project_members_dsl::project_members
+                .filter(project_members_dsl::member.eq(user))
+                .select(project_members_dsl::project)
+                .load(conn)?;
+
+            // 2. Find users who are members of those projects
+            project_members_dsl::project_members
+                .filter(project_members_dsl::project.eq_any(&project_ids))
+                .select(project_members_dsl::member)
+                .distinct()
+                .load::<Uuid>(conn)
+        })
+    })
+    .await
+    .map_err(ApiResponse::from_error)
+}
@@ -5,6 +5,8 @@ use uuid::Uuid;
 use crate::{
     api::{ApiResponse, Error, Null},
     models::{
+        invitations::Invitation,
+        roles::CustomRole,
         users::{InvitedUser, PublicUser, User},
         workspaces::{
             NewWorkspace, Workspace, WorkspaceMember, WorkspaceRole, WorkspaceUpdate,
@@ -12,7 +14,7 @@ use crate::{
         },
         MemberInfo,
     },
-    schema::{users, workspace_members, workspaces},
+    schema::{invitations, users, workspace_members, workspace_roles, workspaces},
 };
 
 use super::Db;
@@ -53,17 +55,29 @@ pub async fn get_workspace_by_id(db: &Db, id: Uuid) -> Result<WorkspaceWithMembe
         let members = workspace_members::table
             .inner_join(users::table.on(users::id.eq(workspace_members::member)))
             .filter(workspace_members::workspace.eq(id))
-            .select((users::all_columns, workspace_members::role))
-            .load::<(User, i16)>(conn)
+            .select((
+                users::all_columns,
+                workspace_members::role,
+                workspace_members::custom_role,
+            ))
+            .load::<(User, WorkspaceRole, Option<Uuid>)>(conn)
             .map_err(ApiResponse::from_error)?
             .into_iter()
-            .map(|(user, role)| MemberInfo {
+            .map(|(user, role, custom_role)| MemberInfo {
                 user: PublicUser::from(&user),
                 role,
+                custom_role,
+                guest: false,
             })
             .collect();
 
-        Ok(WorkspaceWithMembers { workspace, members })
+        let roles = fetch_custom_roles(id, conn).map_err(ApiResponse::from_error)?;
+
+        Ok(WorkspaceWithMembers {
+            workspace,
+            members,
+            roles,
+        })
     })
     .await
 }
@@ -94,7 +108,8 @@ pub async fn insert_new_workspace(
         vec![WorkspaceMember {
             workspace: workspace.id,
             member: owner,
-            role: i16::from(WorkspaceRole::Owner),
+            role: WorkspaceRole::Owner,
+            custom_role: None,
         }],
     )
     .await
@@ -201,18 +216,41 @@ fn fetch_workspace_with_members(
         .map(|(_, membership, user)| MemberInfo {
             user: PublicUser::from(user),
             role: membership.role,
+            custom_role: membership.custom_role,
+            guest: false,
         })
         .collect();
 
+    let roles = fetch_custom_roles(id, conn)?;
+
     // Return the workspace information containing all public member information
-    Ok(WorkspaceWithMembers { workspace, members })
+    Ok(WorkspaceWithMembers {
+        workspace,
+        members,
+        roles,
+    })
+}
+
+fn fetch_custom_roles(
+    workspace: Uuid,
+    conn: &mut PgConnection,
+) -> Result<Vec<CustomRole>, diesel::result::Error> {
+    workspace_roles::table
+        .filter(workspace_roles::workspace.eq(workspace))
+        .order(workspace_roles::name.asc())
+        .load::<CustomRole>(conn)
 }
 
+/// Inserts the invited users, their memberships and their invitations, returning the users with
+/// the tokens of their invitations.
 pub async fn create_transaction_bulk_invitation(
     db: &Db,
     workspace: Uuid,
+    inviter: Uuid,
     invited_users: Vec<InvitedUser>,
-) -> Result<Vec<User>, Error<Null>> {
+    sent_at: NaiveDateTime,
+    expires_at: NaiveDateTime,
+) -> Result<Vec<(User, String)>, Error<Null>> {
     // Insert into database with a single transaction
     db.run({
         // Clone the new_users vector to move into the closure
@@ -227,8 +265,10 @@ pub async fn create_transaction_bulk_invitation(
                     .values(&insert_users)
                     .get_results::<User>(conn)?;
 
-                // Declare the workspace members
+                // Declare the workspace members, the invitations and their tokens
                 let mut workspace_members = Vec::new();
+                let mut invitations = Vec::new();
+                let mut tokens = Vec::new();
 
                 // Iterate over the inserted users
                 for user in &inserted_users {
@@ -244,7 +284,19 @@ pub async fn create_transaction_bulk_invitation(
                         workspace,
                         member: user.id,
                         role: workspace_role,
+                        custom_role: None,
                     });
+
+                    let (invitation, token) = Invitation::generate(
+                        workspace,
+                        user.id,
+                        inviter,
+                        workspace_role,
+                        sent_at,
+                        expires_at,
+                    );
+                    invitations.push(invitation);
+                    tokens.push(token);
                 }
 
                 // Insert the workspace members in the workspace_members table
@@ -252,8 +304,13 @@ pub async fn create_transaction_bulk_invitation(
                     .values(workspace_members)
                     .execute(conn)?;
 
-                // Return the inserted users
-                Ok(inserted_users)
+                // Insert the invitations in the invitations table
+                diesel::insert_into(invitations::table)
+                    .values(invitations)
+                    .execute(conn)?;
+
+                // Return the inserted users with their tokens
+                Ok(inserted_users.into_iter().zip(tokens).collect())
             })
         }
     })
@@ -54,4 +54,35 @@ impl MailClient {
         // Send the message
         self.smtp.send(message)
     }
+
+    pub fn send_password_reset(
+        &self,
+        recipient: &PublicUser,
+        token: &str,
+    ) -> Result<Response, String> {
+        // Get the password reset template
+        let template = MailTemplate::password_reset(recipient, token)?;
+
+        // Generate the message
+        let message = self.mail.from_template(recipient, template)?;
+
+        // Send the message
+        self.smtp.send(message)
+    }
+
+    /// Sends the confirmation link of an email change; to the new address of the recipient.
+    pub fn send_email_change(
+        &self,
+        recipient: &PublicUser,
+        token: &str,
+    ) -> Result<Response, String> {
+        // Get the email change template
+        let template = MailTemplate::email_change(recipient, token)?;
+
+        // Generate the message
+        let message = self.mail.from_template(recipient, template)?;
+
+        // Send the message
+        self.smtp.send(message)
+    }
 }
@@ -0,0 +1,31 @@
+use std::collections::HashMap;
+
+use lettre::message::MultiPart;
+
+use crate::email::assets::elements::HtmlElement;
+
+use super::*;
+
+impl MailTemplate {
+    pub fn email_change(recipient: &PublicUser, token: &str) -> Result<Self, String> {
+        let link = format!("https://localhost/confirm-email?token={token}");
+
+        Ok(MailTemplate {
+            subject: "Confirm your new Rustle email address".to_string(),
+            content: HtmlElement::email_change(recipient, &link)?,
+        })
+    }
+}
+
+impl HtmlElement {
+    fn email_change(recipient: &PublicUser, link: &str) -> Result<MultiPart, String> {
+        let replacements = HashMap::from([
+            ("RECIPIENT", recipient.full_name()),
+            ("CONFIRM_LINK", link.to_string()),
+        ]);
+
+        let html_content = Self::singlepart("email_change.html", replacements)?;
+
+        Ok(MultiPart::alternative().singlepart(html_content))
+    }
+}
@@ -4,7 +4,9 @@ use crate::models::users::PublicUser;
 
 use super::assets::elements::HtmlElement;
 
+pub mod email_change;
 pub mod invitation;
+pub mod password_reset;
 
 pub struct MailTemplate {
     pub subject: String,
@@ -0,0 +1,31 @@
+use std::collections::HashMap;
+
+use lettre::message::MultiPart;
+
+use crate::email::assets::elements::HtmlElement;
+
+use super::*;
+
+impl MailTemplate {
+    pub fn password_reset(recipient: &PublicUser, token: &str) -> Result<Self, String> {
+        let link = format!("https://localhost/reset-password?token={token}");
+
+        Ok(MailTemplate {
+            subject: "Reset your Rustle password".to_string(),
+            content: HtmlElement::password_reset(recipient, &link)?,
+        })
+    }
+}
+
+impl HtmlElement {
+    fn password_reset(recipient: &PublicUser, link: &str) -> Result<MultiPart, String> {
+        let replacements = HashMap::from([
+            ("RECIPIENT", recipient.full_name()),
+            ("RESET_LINK", link.to_string()),
+        ]);
+
+        let html_content = Self::singlepart("password_reset.html", replacements)?;
+
+        Ok(MultiPart::alternative().singlepart(html_content))
+    }
+}
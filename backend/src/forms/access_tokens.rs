@@ -0,0 +1,48 @@
+use std::str::FromStr;
+
+use rocket::form;
+
+use crate::models::access_tokens::Scope;
+
+#[derive(Debug, FromForm)]
+pub struct AccessTokenForm<'v> {
+    #[field(validate = len(1..=40))]
+    pub name: &'v str,
+    #[field(validate = len(1..))]
+    #[field(validate = AccessTokenForm::validate_scopes())]
+    pub scopes: Vec<&'v str>,
+    /// Tokens always expire; at most after a year
+    #[field(validate = range(1..=365))]
+    pub expires_in_days: i64,
+}
+
+impl AccessTokenForm<'_> {
+    pub fn body(&self) -> String {
+        let scopes: String = self
+            .scopes
+            .iter()
+            .enumerate()
+            .map(|(i, scope)| format!("&scopes[{i}]={scope}"))
+            .collect();
+
+        format!(
+            "name={}&expires_in_days={}{scopes}",
+            self.name, self.expires_in_days
+        )
+    }
+
+    pub fn get_scopes(&self) -> Vec<Scope> {
+        self.scopes
+            .iter()
+            .filter_map(|scope| Scope::from_str(scope).ok())
+            .collect()
+    }
+
+    fn validate_scopes<'v>(value: &[&str]) -> form::Result<'v, ()> {
+        for scope in value {
+            Scope::from_str(scope).map_err(form::Error::validation)?;
+        }
+
+        Ok(())
+    }
+}
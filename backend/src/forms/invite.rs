@@ -4,7 +4,7 @@ use regex::Regex;
 use rocket::form;
 
 use crate::models::{
-    users::{InvitedUser, UserRole},
+    users::{normalize_username, InvitedUser, UserRole, UserStatus},
     workspaces::WorkspaceRole,
 };
 
@@ -42,10 +42,7 @@ impl InvitedMultipleUsersForm<'_> {
 
         for user in self.users.iter() {
             // Define the username and the display name
-            let username = format!("{}_{}", user.first_name, user.last_name)
-                .to_lowercase()
-                .replace(' ', "_")
-                .replace('\'', "");
+            let username = normalize_username(&format!("{}_{}", user.first_name, user.last_name));
 
             base_usernames.insert(username.clone());
 
@@ -59,8 +56,8 @@ impl InvitedMultipleUsersForm<'_> {
                 first_name: user.first_name.to_string(),
                 last_name: user.last_name.to_string(),
                 email: user.email.to_string(),
-                role: i16::from(UserRole::Reviewer),
-                status: 0,
+                role: UserRole::Reviewer,
+                status: UserStatus::Invited,
                 password,
                 workspace_role: user.workspace_role,
             });
@@ -80,8 +77,7 @@ pub struct InvitedUserForm<'v> {
     pub email: &'v str,
     #[field(validate = InvitedUserForm::validate_phone())]
     pub phone: Option<&'v str>,
-    #[field(validate = InvitedUserForm::validate_workspace_role())]
-    pub workspace_role: i16,
+    pub workspace_role: WorkspaceRole,
 }
 
 impl InvitedUserForm<'_> {
@@ -114,7 +110,7 @@ impl InvitedUserForm<'_> {
     // Simple email format: matches basic email addresses like:
     /// - user123@example.com
     /// - first.last@my-domain.org
-    fn validate_email<'v>(value: &str) -> form::Result<'v, ()> {
+    pub fn validate_email<'v>(value: &str) -> form::Result<'v, ()> {
         let re = Regex::new(r"^[\w\.-]+@[\w\.-]+\.\w{2,}$")
             .map_err(|e| form::Error::validation(format!("Invalid regex: {e}")))?;
 
@@ -147,12 +143,6 @@ impl InvitedUserForm<'_> {
         Ok(())
     }
 
-    fn validate_workspace_role<'v>(value: &i16) -> form::Result<'v, ()> {
-        Ok(WorkspaceRole::try_from(*value)
-            .map(|_| ())
-            .map_err(form::Error::validation)?)
-    }
-
     pub fn body(&self) -> String {
         let phone = if let Some(number) = self.phone {
             format!("&phone={number}")
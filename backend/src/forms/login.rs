@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, FromForm, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, FromForm, Serialize, Deserialize)]
 pub struct LoginForm<'v> {
     pub username: &'v str,
     pub password: &'v str,
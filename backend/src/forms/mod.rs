@@ -1,5 +1,8 @@
+pub mod access_tokens;
 pub mod invite;
 pub mod login;
 pub mod password;
 pub mod projects;
+pub mod roles;
+pub mod two_factor;
 pub mod workspace;
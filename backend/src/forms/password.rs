@@ -1,13 +1,64 @@
+use std::sync::OnceLock;
+
 use argon2::{
     password_hash::{rand_core::OsRng, Error, PasswordHasher, SaltString},
-    Argon2, PasswordHash, PasswordVerifier,
+    PasswordHash, PasswordVerifier,
 };
+use rocket::form;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use super::invite::InvitedUserForm;
+use crate::auth::passwords::PasswordConfig;
+
+/// Request for a password reset link, sent to the given address.
+#[derive(Debug, FromForm, Serialize, Deserialize)]
+pub struct ForgotPasswordForm<'v> {
+    #[field(validate = InvitedUserForm::validate_email())]
+    pub email: &'v str,
+}
+
+impl ForgotPasswordForm<'_> {
+    pub fn body(&self) -> String {
+        format!("email={}", self.email)
+    }
+}
+
+/// Change of the password by a logged in user, who has to confirm the current password.
+#[derive(Debug, FromForm, Serialize, Deserialize)]
+pub struct ChangePasswordForm<'v> {
+    pub current: &'v str,
+    #[serde(borrow)]
+    pub new: Password<'v>,
+}
+
+impl ChangePasswordForm<'_> {
+    pub fn body(&self) -> String {
+        format!(
+            "current={}&new.first={}&new.second={}",
+            self.current, self.new.first, self.new.second
+        )
+    }
+}
+
+/// Change of the email address by a logged in user, who has to confirm the current password.
+/// The new address is only used once it's confirmed through the link sent to it.
+#[derive(Debug, FromForm, Serialize, Deserialize)]
+pub struct ChangeEmailForm<'v> {
+    #[field(validate = InvitedUserForm::validate_email())]
+    pub email: &'v str,
+    pub password: &'v str,
+}
+
+impl ChangeEmailForm<'_> {
+    pub fn body(&self) -> String {
+        format!("email={}&password={}", self.email, self.password)
+    }
+}
+
 #[derive(Debug, FromForm, Serialize, Deserialize)]
 pub struct Password<'v> {
-    #[field(validate = len(6..))]
+    #[field(validate = Password::validate_policy())]
     #[field(validate = eq(self.second))]
     pub first: &'v str,
     #[field(validate = eq(self.first))]
@@ -36,10 +87,31 @@ impl Password<'_> {
         password.hash_password()
     }
 
+    /// A hash of a random password, to verify against when there is no user, so a login for an
+    /// unknown user takes as long as one for an existing user.
+    pub fn dummy_hash() -> &'static str {
+        static DUMMY_HASH: OnceLock<String> = OnceLock::new();
+        DUMMY_HASH.get_or_init(|| Password::generate(None).unwrap_or_default())
+    }
+
+    /// New passwords have to meet the configured [`PasswordConfig`] policy.
+    fn validate_policy<'v>(value: &str) -> form::Result<'v, ()> {
+        PasswordConfig::get()
+            .check_policy(value)
+            .map_err(|e| form::Error::validation(e).into())
+    }
+
+    /// Verifies against the parameters stored in the hash, so older hashes keep working.
     pub fn verify_password(input_password: &str, stored_hash: &str) -> Result<bool, Error> {
         let password = input_password.as_bytes();
         let hash = PasswordHash::new(stored_hash)?;
-        Ok(Argon2::default().verify_password(password, &hash).is_ok())
+        let argon2 = PasswordConfig::get().argon2()?;
+        Ok(argon2.verify_password(password, &hash).is_ok())
+    }
+
+    /// Whether the stored hash was made with weaker parameters than configured.
+    pub fn needs_rehash(stored_hash: &str) -> bool {
+        PasswordConfig::get().needs_rehash(stored_hash)
     }
 
     pub fn inputs_match(&self) -> bool {
@@ -47,8 +119,8 @@ impl Password<'_> {
     }
 
     pub fn hash_password(&self) -> Result<String, argon2::password_hash::Error> {
-        // Argon2 with default params (Argon2id v19)
-        let argon2 = Argon2::default();
+        // Argon2id v19 with the configured params
+        let argon2 = PasswordConfig::get().argon2()?;
         let salt = SaltString::generate(&mut OsRng);
 
         // Hash password to PHC string ($argon2id$v=19$...)
@@ -0,0 +1,44 @@
+use std::str::FromStr;
+
+use rocket::form;
+
+use crate::models::roles::Permission;
+
+/// A custom role of a workspace, when created or replaced.
+#[derive(Debug, FromForm)]
+pub struct CustomRoleForm<'v> {
+    #[field(validate = len(1..=40))]
+    pub name: &'v str,
+    #[field(validate = CustomRoleForm::validate_permissions())]
+    pub permissions: Vec<&'v str>,
+}
+
+impl CustomRoleForm<'_> {
+    pub fn body(&self) -> String {
+        let permissions: String = self
+            .permissions
+            .iter()
+            .enumerate()
+            .map(|(i, permission)| format!("&permissions[{i}]={permission}"))
+            .collect();
+
+        format!("name={}{permissions}", self.name)
+    }
+
+    /// The permissions of the form, without duplicates and in the order of [`Permission::ALL`].
+    pub fn get_permissions(&self) -> Vec<Permission> {
+        Permission::ALL
+            .iter()
+            .filter(|permission| self.permissions.contains(&permission.to_string().as_str()))
+            .copied()
+            .collect()
+    }
+
+    fn validate_permissions<'v>(value: &[&str]) -> form::Result<'v, ()> {
+        for permission in value {
+            Permission::from_str(permission).map_err(form::Error::validation)?;
+        }
+
+        Ok(())
+    }
+}
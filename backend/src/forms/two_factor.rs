@@ -0,0 +1,54 @@
+use serde::{Deserialize, Serialize};
+
+/// A TOTP code (or a recovery code, where accepted).
+#[derive(Debug, FromForm, Serialize, Deserialize)]
+pub struct TwoFactorCodeForm<'v> {
+    #[field(validate = len(1..))]
+    pub code: &'v str,
+}
+
+impl TwoFactorCodeForm<'_> {
+    pub fn body(&self) -> String {
+        format!("code={}", self.code)
+    }
+}
+
+/// The challenge received from the login, used to enroll during the login.
+#[derive(Debug, FromForm, Serialize, Deserialize)]
+pub struct TwoFactorChallengeForm<'v> {
+    pub challenge: &'v str,
+}
+
+impl TwoFactorChallengeForm<'_> {
+    pub fn body(&self) -> String {
+        format!("challenge={}", self.challenge)
+    }
+}
+
+/// Completes the login with the challenge received from the login and a second factor.
+#[derive(Debug, FromForm, Serialize, Deserialize)]
+pub struct TwoFactorLoginForm<'v> {
+    pub challenge: &'v str,
+    #[field(validate = len(1..))]
+    pub code: &'v str,
+}
+
+impl TwoFactorLoginForm<'_> {
+    pub fn body(&self) -> String {
+        format!("challenge={}&code={}", self.challenge, self.code)
+    }
+}
+
+/// Disabling the second factor requires both the password and a second factor.
+#[derive(Debug, FromForm, Serialize, Deserialize)]
+pub struct TwoFactorDisableForm<'v> {
+    pub password: &'v str,
+    #[field(validate = len(1..))]
+    pub code: &'v str,
+}
+
+impl TwoFactorDisableForm<'_> {
+    pub fn body(&self) -> String {
+        format!("password={}&code={}", self.password, self.code)
+    }
+}
@@ -1,4 +1,4 @@
-use rocket::{fairing::AdHoc, Config};
+use rocket::{fairing::AdHoc, figment::providers::Env, Config};
 use rocket_cors::{AllowedOrigins, Cors, CorsOptions};
 use routes::{USERS, WORKSPACES};
 
@@ -28,6 +28,13 @@ pub const ENV_REDIS_URL: &str = "REDIS_URL";
 pub const ENV_DATABASE_URL: &str = "DATABASE_URL";
 pub const ENV_POSTGRES_USER: &str = "POSTGRES_USER";
 pub const ENV_POSTGRES_PASSWORD: &str = "POSTGRES_PASSWORD";
+pub const ENV_JWT_PREFIX: &str = "JWT_";
+pub const ENV_TWO_FACTOR_PREFIX: &str = "TWO_FACTOR_";
+pub const ENV_OIDC_PREFIX: &str = "OIDC_";
+pub const ENV_PASSWORD_PREFIX: &str = "PASSWORD_";
+pub const ENV_AUTH_PREFIX: &str = "AUTH_";
+pub const ENV_LDAP_PREFIX: &str = "LDAP_";
+pub const ENV_INVITATION_PREFIX: &str = "INVITATION_";
 
 pub fn env(key: &str) -> String {
     std::env::var(key).unwrap_or_else(|_| panic!("Environment variable '{key}' missing"))
@@ -36,16 +43,72 @@ pub fn env(key: &str) -> String {
 #[launch]
 fn rocket() -> _ {
     // Fetch DATABASE_URL from env and merge it into Rocket's config at runtime
-    rocket::custom(Config::figment().merge(("databases.rustle_db.url", env(ENV_DATABASE_URL))))
+    let figment = Config::figment()
+        .merge(("databases.rustle_db.url", env(ENV_DATABASE_URL)))
+        .merge(jwt_env())
+        .merge(two_factor_env())
+        .merge(oidc_env())
+        .merge(password_env())
+        .merge(auth_env())
+        .merge(ldap_env())
+        .merge(invitation_env());
+
+    rocket::custom(figment)
         .attach(create_cors())
+        .attach(auth::keys::jwt_fairing())
+        .attach(auth::two_factor::two_factor_fairing())
+        .attach(auth::oidc::oidc_fairing())
+        .attach(auth::passwords::password_fairing())
+        .attach(auth::backends::auth_backends_fairing())
+        .attach(auth::invitations::invitation_fairing())
         .attach(database::Db::fairing())
         .attach(cache::redis_fairing())
         .attach(insert_admin_user())
+        .attach(auth::impersonation::impersonation_audit_fairing())
         .mount(PROJECTS, routes::projects::routes())
         .mount(USERS, routes::users::routes())
         .mount(WORKSPACES, routes::workspaces::routes())
 }
 
+/// Maps `JWT_SECRET`, `JWT_KID`, `JWT_ALGORITHM`, `JWT_PRIVATE_KEY` and `JWT_PUBLIC_KEY` onto the
+/// signing key in the `jwt` table of Rocket's config.
+fn jwt_env() -> Env {
+    Env::prefixed(ENV_JWT_PREFIX).map(|key| format!("jwt.signing.{key}").into())
+}
+
+/// Maps `TWO_FACTOR_REQUIRED_ROLES` onto the `two_factor` table of Rocket's config.
+fn two_factor_env() -> Env {
+    Env::prefixed(ENV_TWO_FACTOR_PREFIX).map(|key| format!("two_factor.{key}").into())
+}
+
+/// Maps `OIDC_ISSUER`, `OIDC_CLIENT_ID`, `OIDC_CLIENT_SECRET`, `OIDC_REDIRECT_URI` etc. onto the
+/// `oidc` table of Rocket's config.
+fn oidc_env() -> Env {
+    Env::prefixed(ENV_OIDC_PREFIX).map(|key| format!("oidc.{key}").into())
+}
+
+/// Maps `PASSWORD_MEMORY_KIB`, `PASSWORD_MIN_LENGTH`, `PASSWORD_REQUIRED_CLASSES` etc. onto the
+/// `password` table of Rocket's config.
+fn password_env() -> Env {
+    Env::prefixed(ENV_PASSWORD_PREFIX).map(|key| format!("password.{key}").into())
+}
+
+/// Maps `AUTH_BACKENDS` onto the `auth` table of Rocket's config.
+fn auth_env() -> Env {
+    Env::prefixed(ENV_AUTH_PREFIX).map(|key| format!("auth.{key}").into())
+}
+
+/// Maps `LDAP_URL`, `LDAP_BIND_DN`, `LDAP_BASE_DN`, `LDAP_ROLE_MAPPING` etc. onto the `ldap` table
+/// of Rocket's config.
+fn ldap_env() -> Env {
+    Env::prefixed(ENV_LDAP_PREFIX).map(|key| format!("ldap.{key}").into())
+}
+
+/// Maps `INVITATION_TTL_HOURS` onto the `invitation` table of Rocket's config.
+fn invitation_env() -> Env {
+    Env::prefixed(ENV_INVITATION_PREFIX).map(|key| format!("invitation.{key}").into())
+}
+
 fn create_cors() -> Cors {
     // Allow requests only from your Vite dev server
     let allowed_origins = AllowedOrigins::some_exact(&[
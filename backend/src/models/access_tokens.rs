@@ -0,0 +1,186 @@
+use std::{
+    fmt::{Display, Formatter},
+    str::FromStr,
+};
+
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use rocket::http::Method;
+use rocket_sync_db_pools::diesel;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::{
+    cache::create_random_token,
+    routes::{PROJECTS, USERS, WORKSPACES},
+    schema::access_tokens,
+};
+
+/// Every access token starts with this prefix, which sets it apart from a JWT.
+pub const ACCESS_TOKEN_PREFIX: &str = "rustle_pat_";
+const ACCESS_TOKEN_LENGTH: usize = 48;
+
+/// A personal access token, for scripts and CI.
+///
+/// Only the SHA-256 hash of the token is stored; the token itself is shown once, when created.
+#[derive(Clone, Debug, Insertable, Queryable)]
+#[diesel(table_name = access_tokens)]
+pub struct AccessToken {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    pub token_hash: String,
+    pub scopes: Vec<String>,
+    pub expires_at: NaiveDateTime,
+    pub last_used_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+/// An [`AccessToken`] without its hash.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PublicAccessToken {
+    pub id: Uuid,
+    pub name: String,
+    pub scopes: Vec<String>,
+    pub expires_at: NaiveDateTime,
+    pub last_used_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+/// Returned once, when the token is created.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct NewAccessToken {
+    pub token: String,
+    pub info: PublicAccessToken,
+}
+
+impl AccessToken {
+    /// Generates a new token, returning the record to store and the token to hand to the user.
+    pub fn generate(
+        user_id: Uuid,
+        name: &str,
+        scopes: &[Scope],
+        created_at: NaiveDateTime,
+        expires_at: NaiveDateTime,
+    ) -> (Self, String) {
+        let token = format!(
+            "{ACCESS_TOKEN_PREFIX}{}",
+            create_random_token(ACCESS_TOKEN_LENGTH)
+        );
+
+        let access_token = AccessToken {
+            id: Uuid::new_v4(),
+            user_id,
+            name: name.to_string(),
+            token_hash: Self::hash(&token),
+            scopes: scopes.iter().map(Scope::to_string).collect(),
+            expires_at,
+            last_used_at: None,
+            created_at,
+        };
+
+        (access_token, token)
+    }
+
+    pub fn hash(token: &str) -> String {
+        format!("{:x}", Sha256::digest(token.as_bytes()))
+    }
+
+    pub fn is_expired(&self, now: NaiveDateTime) -> bool {
+        self.expires_at <= now
+    }
+
+    /// The scopes of the token; unknown scopes are ignored.
+    pub fn get_scopes(&self) -> Vec<Scope> {
+        self.scopes
+            .iter()
+            .filter_map(|scope| Scope::from_str(scope).ok())
+            .collect()
+    }
+}
+
+impl From<&AccessToken> for PublicAccessToken {
+    fn from(token: &AccessToken) -> Self {
+        PublicAccessToken {
+            id: token.id,
+            name: token.name.clone(),
+            scopes: token.scopes.clone(),
+            expires_at: token.expires_at,
+            last_used_at: token.last_used_at,
+            created_at: token.created_at,
+        }
+    }
+}
+
+/// What an access token is allowed to do, on top of what the role of the user allows.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Scope {
+    ProjectsRead,
+    ProjectsWrite,
+    UsersRead,
+    UsersWrite,
+    WorkspacesRead,
+    WorkspacesWrite,
+}
+
+impl Scope {
+    /// The scope that is needed for a request to a route, based on where the route is mounted
+    /// and whether the request only reads.
+    pub fn required_for(method: Method, base: &str) -> Option<Self> {
+        let read = method == Method::Get;
+        let base = format!("{}/", base.trim_end_matches('/'));
+
+        match base.as_str() {
+            PROJECTS if read => Some(Scope::ProjectsRead),
+            PROJECTS => Some(Scope::ProjectsWrite),
+            USERS if read => Some(Scope::UsersRead),
+            USERS => Some(Scope::UsersWrite),
+            WORKSPACES if read => Some(Scope::WorkspacesRead),
+            WORKSPACES => Some(Scope::WorkspacesWrite),
+            _ => None,
+        }
+    }
+
+    /// A write scope includes reading.
+    pub fn grants(&self, required: &Scope) -> bool {
+        self == required
+            || matches!(
+                (self, required),
+                (Scope::ProjectsWrite, Scope::ProjectsRead)
+                    | (Scope::UsersWrite, Scope::UsersRead)
+                    | (Scope::WorkspacesWrite, Scope::WorkspacesRead)
+            )
+    }
+}
+
+impl Display for Scope {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let scope = match self {
+            Scope::ProjectsRead => "projects:read",
+            Scope::ProjectsWrite => "projects:write",
+            Scope::UsersRead => "users:read",
+            Scope::UsersWrite => "users:write",
+            Scope::WorkspacesRead => "workspaces:read",
+            Scope::WorkspacesWrite => "workspaces:write",
+        };
+
+        write!(f, "{scope}")
+    }
+}
+
+impl FromStr for Scope {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "projects:read" => Ok(Scope::ProjectsRead),
+            "projects:write" => Ok(Scope::ProjectsWrite),
+            "users:read" => Ok(Scope::UsersRead),
+            "users:write" => Ok(Scope::UsersWrite),
+            "workspaces:read" => Ok(Scope::WorkspacesRead),
+            "workspaces:write" => Ok(Scope::WorkspacesWrite),
+            _ => Err(format!("Invalid scope: {value}")),
+        }
+    }
+}
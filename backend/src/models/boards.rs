@@ -0,0 +1,261 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use rocket_sync_db_pools::diesel;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    models::present,
+    schema::{board_cards, board_columns, boards},
+};
+
+/// The longest name of a board or column, as in the `boards` and `board_columns` tables.
+pub const MAX_BOARD_NAME_LENGTH: usize = 80;
+
+/// The longest title of a card, as in the `board_cards` table.
+pub const MAX_CARD_TITLE_LENGTH: usize = 200;
+
+/// The digits of a rank, in the order of their bytes.
+const RANK_DIGITS: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+#[derive(Clone, Debug, Deserialize, Queryable, Serialize)]
+#[diesel(table_name = boards)]
+pub struct Board {
+    pub id: Uuid,
+    pub project: Uuid,
+    pub name: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Clone, Debug, Deserialize, Queryable, Serialize)]
+#[diesel(table_name = board_columns)]
+pub struct BoardColumn {
+    pub id: Uuid,
+    pub board: Uuid,
+    pub name: String,
+    /// Orders the columns of the board, see [`rank_between`]
+    pub rank: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Clone, Debug, Deserialize, Queryable, Serialize)]
+#[diesel(table_name = board_cards)]
+pub struct Card {
+    pub id: Uuid,
+    pub project: Uuid,
+    pub board_column: Uuid,
+    pub title: String,
+    pub description: Option<String>,
+    /// A member of the project
+    pub assignee: Option<Uuid>,
+    /// Orders the cards of the column, see [`rank_between`]
+    pub rank: String,
+    /// Raised on every change of the card; a move names the version it is based on
+    pub version: i32,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+/// A board with its columns, and the cards of each column, in the order of their ranks.
+#[derive(Deserialize, Serialize)]
+pub struct BoardWithColumns {
+    pub board: Board,
+    pub columns: Vec<ColumnWithCards>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ColumnWithCards {
+    pub column: BoardColumn,
+    pub cards: Vec<Card>,
+}
+
+/// A board as it is created, with the names of its first columns.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct NewBoard {
+    pub name: String,
+    #[serde(default)]
+    pub columns: Vec<String>,
+}
+
+#[derive(AsChangeset, Clone, Debug, Deserialize, Serialize)]
+#[diesel(table_name = boards)]
+pub struct BoardUpdate {
+    pub name: String,
+}
+
+/// A column as it is created, after the last column of the board.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct NewColumn {
+    pub name: String,
+}
+
+#[derive(AsChangeset, Clone, Debug, Deserialize, Serialize)]
+#[diesel(table_name = board_columns)]
+pub struct ColumnUpdate {
+    pub name: String,
+}
+
+/// A card as it is created, at the bottom of the column.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct NewCard {
+    pub column: Uuid,
+    pub title: String,
+    pub description: Option<String>,
+    pub assignee: Option<Uuid>,
+}
+
+/// Only the fields that are present are updated; the assignee and description are cleared with
+/// `null`.
+#[derive(AsChangeset, Clone, Debug, Default, Deserialize, Serialize)]
+#[diesel(table_name = board_cards)]
+pub struct CardUpdate {
+    pub title: Option<String>,
+    #[serde(default, deserialize_with = "present")]
+    pub description: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    pub assignee: Option<Option<Uuid>>,
+}
+
+/// Where a column or card is moved to: right after the one named `after`, right before the one
+/// named `before`, or between both. Without either, it is moved to the end.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+pub struct Position {
+    pub after: Option<Uuid>,
+    pub before: Option<Uuid>,
+}
+
+/// Moves a card to a position in a column of the same board. The move is rejected when the card
+/// is no longer at the `version` the client has seen.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub struct CardMove {
+    pub column: Uuid,
+    pub version: i32,
+    #[serde(flatten)]
+    pub position: Position,
+}
+
+impl NewBoard {
+    pub fn validate(&self) -> Result<(), String> {
+        validate_name("board", &self.name)?;
+        self.columns
+            .iter()
+            .try_for_each(|column| validate_name("column", column))
+    }
+}
+
+impl BoardUpdate {
+    pub fn validate(&self) -> Result<(), String> {
+        validate_name("board", &self.name)
+    }
+}
+
+impl NewColumn {
+    pub fn validate(&self) -> Result<(), String> {
+        validate_name("column", &self.name)
+    }
+}
+
+impl ColumnUpdate {
+    pub fn validate(&self) -> Result<(), String> {
+        validate_name("column", &self.name)
+    }
+}
+
+impl NewCard {
+    pub fn validate(&self) -> Result<(), String> {
+        validate_title(&self.title)
+    }
+}
+
+impl CardUpdate {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.title.is_none() && self.description.is_none() && self.assignee.is_none() {
+            return Err("Nothing to update".to_string());
+        }
+
+        match &self.title {
+            Some(title) => validate_title(title),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Position {
+    pub fn validate(&self, id: Uuid) -> Result<(), String> {
+        if self.after == Some(id) || self.before == Some(id) {
+            return Err("Can't be moved next to itself".to_string());
+        }
+
+        Ok(())
+    }
+}
+
+/// A rank that sorts between `lower` and `upper`, where no lower rank means the start and no upper
+/// rank the end. Returns `None` unless `lower` sorts before `upper`.
+///
+/// Ranks are strings of [`RANK_DIGITS`], read as the fraction after the decimal point, so there is
+/// always room for another rank in between and placing a column or card never changes the ranks
+/// of its neighbours. A rank never ends with the lowest digit, which keeps room before it too.
+pub fn rank_between(lower: Option<&str>, upper: Option<&str>) -> Option<String> {
+    let lower = lower.unwrap_or_default().as_bytes();
+    match upper.map(str::as_bytes) {
+        Some(upper) if lower >= upper => None,
+        upper => Some(rank_in_between(lower, upper)),
+    }
+}
+
+/// A rank that sorts after `lower`, or the first rank without it.
+pub fn rank_after(lower: Option<&str>) -> String {
+    rank_in_between(lower.unwrap_or_default().as_bytes(), None)
+}
+
+fn rank_in_between(lower: &[u8], mut upper: Option<&[u8]>) -> String {
+    let digit = |rank: &[u8], index: usize| {
+        rank.get(index)
+            .and_then(|byte| RANK_DIGITS.iter().position(|digit| digit == byte))
+            .unwrap_or(0)
+    };
+
+    let mut rank = String::new();
+    let mut index = 0;
+    loop {
+        let low = digit(lower, index);
+        let high = upper.map_or(RANK_DIGITS.len(), |upper| digit(upper, index));
+
+        let middle = (low + high) / 2;
+        if middle > low {
+            rank.push(RANK_DIGITS[middle] as char);
+            return rank;
+        }
+
+        // No digit fits in between; once the rank is below the upper rank, any digit that follows
+        // is as well
+        rank.push(RANK_DIGITS[low] as char);
+        if high > low {
+            upper = None;
+        }
+        index += 1;
+    }
+}
+
+fn validate_name(of: &str, name: &str) -> Result<(), String> {
+    match name.trim().chars().count() {
+        0 => Err(format!("The name of a {of} can't be empty")),
+        length if length > MAX_BOARD_NAME_LENGTH => Err(format!(
+            "The name of a {of} can't be longer than {MAX_BOARD_NAME_LENGTH} characters"
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn validate_title(title: &str) -> Result<(), String> {
+    match title.trim().chars().count() {
+        0 => Err("The title of a card can't be empty".to_string()),
+        length if length > MAX_CARD_TITLE_LENGTH => Err(format!(
+            "The title of a card can't be longer than {MAX_CARD_TITLE_LENGTH} characters"
+        )),
+        _ => Ok(()),
+    }
+}
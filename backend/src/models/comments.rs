@@ -0,0 +1,219 @@
+use std::collections::HashMap;
+
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use pulldown_cmark::{html, Options, Parser};
+use rocket_sync_db_pools::diesel;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    models::users::{normalize_username, PublicUser, User},
+    schema::{comment_mentions, comments},
+};
+
+/// The longest body of a comment, in characters.
+pub const MAX_COMMENT_LENGTH: usize = 10_000;
+
+#[derive(Clone, Debug, Deserialize, Queryable, Serialize)]
+#[diesel(table_name = comments)]
+pub struct Comment {
+    pub id: Uuid,
+    pub workspace: Uuid,
+    /// The project of the thread; a comment without one is on the workspace itself
+    pub project: Option<Uuid>,
+    /// The comment this one replies to
+    pub parent: Option<Uuid>,
+    pub author: Option<Uuid>,
+    /// The Markdown as it was written
+    pub body: String,
+    pub edited_at: Option<NaiveDateTime>,
+    pub deleted_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = comments)]
+pub struct InsertableComment {
+    pub workspace: Uuid,
+    pub project: Option<Uuid>,
+    pub parent: Option<Uuid>,
+    pub author: Option<Uuid>,
+    pub body: String,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = comment_mentions)]
+pub struct CommentMention {
+    pub comment: Uuid,
+    pub mentioned: Uuid,
+}
+
+/// The thread of a workspace, or of one of its projects.
+#[derive(Clone, Copy, Debug)]
+pub struct Thread {
+    pub workspace: Uuid,
+    pub project: Option<Uuid>,
+}
+
+/// A comment as it is written, optionally as a reply to another comment of the thread.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct NewComment {
+    pub body: String,
+    pub parent: Option<Uuid>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct CommentUpdate {
+    pub body: String,
+}
+
+/// A comment as it is shown: the body of a deleted comment is left out, and the Markdown comes
+/// with its sanitized HTML.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PublicComment {
+    pub id: Uuid,
+    pub workspace: Uuid,
+    pub project: Option<Uuid>,
+    pub parent: Option<Uuid>,
+    pub author: Option<PublicUser>,
+    pub body: Option<String>,
+    pub html: Option<String>,
+    pub mentions: Vec<Uuid>,
+    pub edited: bool,
+    pub edited_at: Option<NaiveDateTime>,
+    pub deleted: bool,
+    pub created_at: NaiveDateTime,
+}
+
+/// A comment with its replies, which have replies of their own.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ThreadedComment {
+    pub comment: PublicComment,
+    pub replies: Vec<ThreadedComment>,
+}
+
+impl PublicComment {
+    pub fn from(comment: &Comment, author: Option<&User>, mentions: Vec<Uuid>) -> Self {
+        let deleted = comment.deleted_at.is_some();
+
+        PublicComment {
+            id: comment.id,
+            workspace: comment.workspace,
+            project: comment.project,
+            parent: comment.parent,
+            author: author.map(PublicUser::from),
+            body: (!deleted).then(|| comment.body.clone()),
+            html: (!deleted).then(|| render_markdown(&comment.body)),
+            mentions: if deleted { Vec::new() } else { mentions },
+            edited: comment.edited_at.is_some(),
+            edited_at: comment.edited_at,
+            deleted,
+            created_at: comment.created_at,
+        }
+    }
+}
+
+impl ThreadedComment {
+    /// Nests the comments under the comments they reply to, keeping the order they are given in.
+    pub fn thread(comments: Vec<PublicComment>) -> Vec<ThreadedComment> {
+        let mut replies: HashMap<Option<Uuid>, Vec<PublicComment>> = HashMap::new();
+        for comment in comments {
+            replies.entry(comment.parent).or_default().push(comment);
+        }
+
+        fn nest(
+            parent: Option<Uuid>,
+            replies: &mut HashMap<Option<Uuid>, Vec<PublicComment>>,
+        ) -> Vec<ThreadedComment> {
+            replies
+                .remove(&parent)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|comment| ThreadedComment {
+                    replies: nest(Some(comment.id), replies),
+                    comment,
+                })
+                .collect()
+        }
+
+        nest(None, &mut replies)
+    }
+}
+
+impl NewComment {
+    pub fn validate(&self) -> Result<(), String> {
+        validate_body(&self.body)
+    }
+}
+
+impl CommentUpdate {
+    pub fn validate(&self) -> Result<(), String> {
+        validate_body(&self.body)
+    }
+}
+
+/// Renders the Markdown to HTML without anything that runs or loads in the browser of the reader,
+/// like scripts, event handlers or raw HTML that tries either.
+pub fn render_markdown(body: &str) -> String {
+    let options =
+        Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+
+    let mut unsafe_html = String::new();
+    html::push_html(&mut unsafe_html, Parser::new_ext(body, options));
+
+    ammonia::clean(&unsafe_html)
+}
+
+/// The users mentioned in the body, in order of their first mention.
+///
+/// A mention is an `@` at the start of a word, followed by a username as
+/// [`normalize_username`] makes them. As usernames have no spaces, the mention ends at the first
+/// whitespace, and of what it says the longest username of the users counts; this way, the
+/// punctuation after a mention doesn't matter and `@jane_doe_2` isn't taken for `@jane_doe`.
+pub fn resolve_mentions<'a>(
+    body: &str,
+    users: impl IntoIterator<Item = &'a PublicUser>,
+) -> Vec<Uuid> {
+    let usernames: HashMap<String, Uuid> = users
+        .into_iter()
+        .map(|user| (normalize_username(&user.username), user.id))
+        .collect();
+
+    let mut mentioned = Vec::new();
+    let mut previous = None;
+    for (index, character) in body.char_indices() {
+        let starts_word = previous.is_none_or(|previous: char| !previous.is_alphanumeric());
+        previous = Some(character);
+        if character != '@' || !starts_word {
+            continue;
+        }
+
+        let word: String = body[index + 1..]
+            .chars()
+            .take_while(|character| !character.is_whitespace())
+            .collect();
+        let word = normalize_username(&word);
+
+        let user = word
+            .char_indices()
+            .rev()
+            .find_map(|(end, character)| usernames.get(&word[..end + character.len_utf8()]));
+        if let Some(user) = user.filter(|user| !mentioned.contains(*user)) {
+            mentioned.push(*user);
+        }
+    }
+
+    mentioned
+}
+
+fn validate_body(body: &str) -> Result<(), String> {
+    match body.trim().chars().count() {
+        0 => Err("A comment can't be empty".to_string()),
+        length if length > MAX_COMMENT_LENGTH => Err(format!(
+            "A comment can't be longer than {MAX_COMMENT_LENGTH} characters"
+        )),
+        _ => Ok(()),
+    }
+}
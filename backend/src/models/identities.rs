@@ -0,0 +1,35 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use rocket_sync_db_pools::diesel;
+use uuid::Uuid;
+
+use crate::schema::user_identities;
+
+/// Links a user to their account at an external identity provider, which is identified by the
+/// issuer and the subject (`sub`) of its ID tokens.
+#[derive(Clone, Debug, Insertable, Queryable)]
+#[diesel(table_name = user_identities)]
+pub struct UserIdentity {
+    pub issuer: String,
+    pub subject: String,
+    pub user_id: Uuid,
+    /// The email address at the provider when the identity was linked
+    pub email: Option<String>,
+    #[diesel(skip_insertion)]
+    pub created_at: NaiveDateTime,
+    #[diesel(skip_insertion)]
+    pub last_login_at: NaiveDateTime,
+}
+
+impl UserIdentity {
+    pub fn new(issuer: &str, subject: &str, user_id: Uuid, email: Option<String>) -> Self {
+        UserIdentity {
+            issuer: issuer.to_string(),
+            subject: subject.to_string(),
+            user_id,
+            email,
+            created_at: NaiveDateTime::default(),
+            last_login_at: NaiveDateTime::default(),
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use rocket_sync_db_pools::diesel;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{models::users::PublicUser, schema::impersonation_logs};
+
+/// A request made by an admin while acting as another user.
+#[derive(Clone, Debug, Deserialize, Insertable, Queryable, Serialize)]
+#[diesel(table_name = impersonation_logs)]
+pub struct ImpersonationLog {
+    pub id: Uuid,
+    /// The session of the impersonation, to tell the requests of one impersonation apart
+    pub session_id: Uuid,
+    pub admin_id: Uuid,
+    pub user_id: Uuid,
+    pub method: String,
+    pub path: String,
+    /// The HTTP status of the response
+    pub status: i16,
+    #[diesel(skip_insertion)]
+    pub created_at: NaiveDateTime,
+}
+
+impl ImpersonationLog {
+    pub fn new(
+        impersonation: &Impersonation,
+        method: &str,
+        path: &str,
+        status: u16,
+    ) -> ImpersonationLog {
+        ImpersonationLog {
+            id: Uuid::new_v4(),
+            session_id: impersonation.session_id,
+            admin_id: impersonation.admin_id,
+            user_id: impersonation.user_id,
+            method: method.to_string(),
+            path: path.to_string(),
+            status: status as i16,
+            created_at: NaiveDateTime::default(),
+        }
+    }
+}
+
+/// Who is acting as whom; kept for the duration of a request made while impersonating.
+#[derive(Clone, Copy, Debug)]
+pub struct Impersonation {
+    pub session_id: Uuid,
+    pub admin_id: Uuid,
+    pub user_id: Uuid,
+}
+
+/// Returned when an impersonation starts. The token is sent as a bearer token.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ImpersonationToken {
+    pub token: String,
+    pub user: PublicUser,
+    pub expires_at: NaiveDateTime,
+}
@@ -0,0 +1,141 @@
+use chrono::NaiveDateTime;
+use diesel::{deserialize::FromSqlRow, expression::AsExpression, prelude::*, sql_types::SmallInt};
+use rocket_sync_db_pools::diesel;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::{
+    cache::create_random_token,
+    models::{
+        users::{PublicUser, User},
+        workspaces::WorkspaceRole,
+    },
+    schema::invitations,
+};
+
+const INVITATION_TOKEN_LENGTH: usize = 64;
+
+/// An invitation of a user to a workspace.
+///
+/// Only the SHA-256 hash of the token is stored; the token itself is sent by email. A user has
+/// one invitation per workspace, which is renewed when the user is invited again.
+#[derive(Clone, Debug, Insertable, Queryable)]
+#[diesel(table_name = invitations)]
+pub struct Invitation {
+    pub id: Uuid,
+    pub workspace: Uuid,
+    pub invitee: Uuid,
+    pub inviter: Option<Uuid>,
+    pub role: WorkspaceRole,
+    pub status: InvitationStatus,
+    pub token_hash: String,
+    pub expires_at: NaiveDateTime,
+    pub sent_at: NaiveDateTime,
+    pub accepted_at: Option<NaiveDateTime>,
+    pub revoked_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+/// An [`Invitation`] without its hash, with the invited user.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PublicInvitation {
+    pub id: Uuid,
+    pub workspace: Uuid,
+    pub invitee: PublicUser,
+    pub inviter: Option<Uuid>,
+    pub role: WorkspaceRole,
+    pub status: InvitationStatus,
+    pub expires_at: NaiveDateTime,
+    pub sent_at: NaiveDateTime,
+    pub accepted_at: Option<NaiveDateTime>,
+    pub revoked_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(
+    AsExpression, Clone, Copy, Debug, Default, Deserialize, Eq, FromSqlRow, PartialEq, Serialize,
+)]
+#[diesel(sql_type = SmallInt)]
+pub enum InvitationStatus {
+    /// Sent, but the invited user hasn't set a password yet
+    #[default]
+    Pending = 0,
+    /// The invited user has set a password
+    Accepted = 1,
+    /// Withdrawn before it was accepted
+    Revoked = 2,
+    /// Not accepted in time
+    Expired = 3,
+}
+
+smallint_enum!(InvitationStatus {
+    Pending,
+    Accepted,
+    Revoked,
+    Expired
+});
+
+impl Invitation {
+    /// Generates a new invitation, returning the record to store and the token to send.
+    pub fn generate(
+        workspace: Uuid,
+        invitee: Uuid,
+        inviter: Uuid,
+        role: WorkspaceRole,
+        sent_at: NaiveDateTime,
+        expires_at: NaiveDateTime,
+    ) -> (Self, String) {
+        let token = create_random_token(INVITATION_TOKEN_LENGTH);
+
+        let invitation = Invitation {
+            id: Uuid::new_v4(),
+            workspace,
+            invitee,
+            inviter: Some(inviter),
+            role,
+            status: InvitationStatus::Pending,
+            token_hash: Self::hash(&token),
+            expires_at,
+            sent_at,
+            accepted_at: None,
+            revoked_at: None,
+            created_at: sent_at,
+            updated_at: sent_at,
+        };
+
+        (invitation, token)
+    }
+
+    pub fn hash(token: &str) -> String {
+        format!("{:x}", Sha256::digest(token.as_bytes()))
+    }
+
+    /// A pending invitation that wasn't accepted in time is expired, even when its status
+    /// hasn't been updated yet.
+    pub fn effective_status(&self, now: NaiveDateTime) -> InvitationStatus {
+        match self.status {
+            InvitationStatus::Pending if self.expires_at <= now => InvitationStatus::Expired,
+            status => status,
+        }
+    }
+}
+
+impl PublicInvitation {
+    pub fn from(invitation: &Invitation, invitee: &User, now: NaiveDateTime) -> Self {
+        PublicInvitation {
+            id: invitation.id,
+            workspace: invitation.workspace,
+            invitee: PublicUser::from(invitee),
+            inviter: invitation.inviter,
+            role: invitation.role,
+            status: invitation.effective_status(now),
+            expires_at: invitation.expires_at,
+            sent_at: invitation.sent_at,
+            accepted_at: invitation.accepted_at,
+            revoked_at: invitation.revoked_at,
+            created_at: invitation.created_at,
+        }
+    }
+}
@@ -0,0 +1,346 @@
+use std::collections::HashMap;
+
+use chrono::{Days, NaiveDate, NaiveDateTime, NaiveTime};
+use diesel::{deserialize::FromSqlRow, expression::AsExpression, prelude::*, sql_types::SmallInt};
+use rocket_sync_db_pools::diesel;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    models::{present, tasks::TaskStatus},
+    schema::{deliverable_status_changes, deliverables, iterations},
+};
+
+/// The longest name of an iteration, as in the `iterations` table.
+pub const MAX_ITERATION_NAME_LENGTH: usize = 80;
+
+/// The longest title of a deliverable, as in the `deliverables` table.
+pub const MAX_DELIVERABLE_TITLE_LENGTH: usize = 200;
+
+/// How many days a sprint runs when it is planned without an end date.
+pub const SPRINT_DAYS: u64 = 14;
+
+/// A milestone or sprint of a project.
+#[derive(Clone, Debug, Deserialize, Queryable, Serialize)]
+#[diesel(table_name = iterations)]
+pub struct Iteration {
+    pub id: Uuid,
+    pub project: Uuid,
+    pub kind: IterationKind,
+    pub name: String,
+    pub goal: Option<String>,
+    /// The first day of the iteration
+    pub start_date: NaiveDate,
+    /// The last day of the iteration
+    pub end_date: NaiveDate,
+    pub state: IterationState,
+    pub closed_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = iterations)]
+pub struct InsertableIteration {
+    pub project: Uuid,
+    pub kind: IterationKind,
+    pub name: String,
+    pub goal: Option<String>,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+}
+
+#[derive(Clone, Debug, Deserialize, Queryable, Serialize)]
+#[diesel(table_name = deliverables)]
+pub struct Deliverable {
+    pub id: Uuid,
+    pub iteration: Uuid,
+    pub title: String,
+    pub status: TaskStatus,
+    /// The unfinished deliverable of a closed iteration this one continues
+    pub rolled_over_from: Option<Uuid>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = deliverables)]
+pub struct InsertableDeliverable {
+    pub iteration: Uuid,
+    pub title: String,
+    pub status: TaskStatus,
+    pub rolled_over_from: Option<Uuid>,
+}
+
+/// A status a deliverable got, starting with the one it was added with.
+#[derive(Clone, Debug, Deserialize, Queryable, Serialize)]
+#[diesel(table_name = deliverable_status_changes)]
+pub struct StatusChange {
+    pub id: Uuid,
+    pub deliverable: Uuid,
+    pub status: TaskStatus,
+    pub changed_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = deliverable_status_changes)]
+pub struct InsertableStatusChange {
+    pub deliverable: Uuid,
+    pub status: TaskStatus,
+    pub changed_at: NaiveDateTime,
+}
+
+#[derive(AsExpression, Clone, Copy, Debug, Deserialize, Eq, FromSqlRow, PartialEq, Serialize)]
+#[diesel(sql_type = SmallInt)]
+pub enum IterationKind {
+    /// Marks a goal of the project by a date
+    Milestone = 0,
+    /// A fixed period of work of the team
+    Sprint = 1,
+}
+
+smallint_enum!(IterationKind { Milestone, Sprint });
+
+#[derive(
+    AsExpression, Clone, Copy, Debug, Default, Deserialize, Eq, FromSqlRow, PartialEq, Serialize,
+)]
+#[diesel(sql_type = SmallInt)]
+pub enum IterationState {
+    /// Planned, but not started yet
+    #[default]
+    Open = 0,
+    /// Being worked on; a project has one active sprint at a time
+    Active = 1,
+    /// Over, its deliverables no longer change
+    Closed = 2,
+}
+
+smallint_enum!(IterationState {
+    Open,
+    Active,
+    Closed
+});
+
+/// An iteration with its deliverables, from the oldest to the newest.
+#[derive(Deserialize, Serialize)]
+pub struct IterationWithDeliverables {
+    pub iteration: Iteration,
+    pub deliverables: Vec<Deliverable>,
+}
+
+/// An iteration as it is planned; a sprint without an end date runs for [`SPRINT_DAYS`].
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct NewIteration {
+    pub kind: IterationKind,
+    pub name: String,
+    pub goal: Option<String>,
+    pub start_date: NaiveDate,
+    pub end_date: Option<NaiveDate>,
+}
+
+/// Only the fields that are present are updated; the goal is cleared with `null`.
+#[derive(AsChangeset, Clone, Debug, Default, Deserialize, Serialize)]
+#[diesel(table_name = iterations)]
+pub struct IterationUpdate {
+    pub name: Option<String>,
+    #[serde(default, deserialize_with = "present")]
+    pub goal: Option<Option<String>>,
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+}
+
+/// Closes an iteration; its unfinished deliverables roll over to the iteration named
+/// `roll_over_to`, or else to the next open iteration of the same kind, if there is one.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+pub struct IterationClose {
+    pub roll_over_to: Option<Uuid>,
+}
+
+/// A closed iteration, with the deliverables that continue in the next iteration.
+#[derive(Deserialize, Serialize)]
+pub struct ClosedIteration {
+    pub iteration: Iteration,
+    pub rolled_over: Vec<Deliverable>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct NewDeliverable {
+    pub title: String,
+    #[serde(default)]
+    pub status: TaskStatus,
+}
+
+#[derive(AsChangeset, Clone, Debug, Default, Deserialize, Serialize)]
+#[diesel(table_name = deliverables)]
+pub struct DeliverableUpdate {
+    pub title: Option<String>,
+    pub status: Option<TaskStatus>,
+}
+
+/// The deliverables of an iteration at the end of one of its days.
+///
+/// The `remaining` deliverables make up the burndown series, the `done` deliverables against the
+/// `scope` make up the burnup series. Cancelled deliverables are out of scope.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct BurndownDay {
+    pub date: NaiveDate,
+    pub scope: usize,
+    pub done: usize,
+    pub remaining: usize,
+    /// What would remain when the scope of the first day burned down evenly until the last day
+    pub ideal: f64,
+}
+
+impl NewIteration {
+    pub fn validate(&self) -> Result<(), String> {
+        validate_name(&self.name)?;
+        validate_dates(self.start_date, self.end_date())
+    }
+
+    /// The iteration as it is stored, with a trimmed name and an end date.
+    pub fn into_insertable(self, project: Uuid) -> InsertableIteration {
+        InsertableIteration {
+            project,
+            kind: self.kind,
+            end_date: self.end_date(),
+            name: self.name.trim().to_string(),
+            goal: self.goal,
+            start_date: self.start_date,
+        }
+    }
+
+    fn end_date(&self) -> NaiveDate {
+        self.end_date.unwrap_or_else(|| match self.kind {
+            IterationKind::Sprint => self.start_date + Days::new(SPRINT_DAYS - 1),
+            IterationKind::Milestone => self.start_date,
+        })
+    }
+}
+
+impl IterationUpdate {
+    /// Validates the update of the iteration, including the dates it ends up with.
+    pub fn validate(&self, iteration: &Iteration) -> Result<(), String> {
+        if self.name.is_none()
+            && self.goal.is_none()
+            && self.start_date.is_none()
+            && self.end_date.is_none()
+        {
+            return Err("Nothing to update".to_string());
+        }
+
+        if let Some(name) = &self.name {
+            validate_name(name)?;
+        }
+
+        validate_dates(
+            self.start_date.unwrap_or(iteration.start_date),
+            self.end_date.unwrap_or(iteration.end_date),
+        )
+    }
+}
+
+impl NewDeliverable {
+    pub fn validate(&self) -> Result<(), String> {
+        validate_title(&self.title)
+    }
+}
+
+impl DeliverableUpdate {
+    pub fn validate(&self) -> Result<(), String> {
+        match (&self.title, self.status) {
+            (None, None) => Err("Nothing to update".to_string()),
+            (Some(title), _) => validate_title(title),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The burndown of the iteration, one day at a time, from its first day up to its last day,
+/// today or the day it was closed, whichever comes first.
+///
+/// A deliverable is counted from its first status change on, with the last status it got by the
+/// end of each day.
+pub fn burndown(
+    iteration: &Iteration,
+    changes: &[StatusChange],
+    today: NaiveDate,
+) -> Vec<BurndownDay> {
+    let mut last_day = iteration.end_date.min(today);
+    if let Some(closed_at) = iteration.closed_at {
+        last_day = last_day.min(closed_at.date());
+    }
+
+    let mut changes: Vec<&StatusChange> = changes.iter().collect();
+    changes.sort_by_key(|change| change.changed_at);
+    let mut changes = changes.into_iter().peekable();
+
+    let length = (iteration.end_date - iteration.start_date).num_days();
+    let mut statuses: HashMap<Uuid, TaskStatus> = HashMap::new();
+    let mut initial_scope = None;
+    let mut days = Vec::new();
+
+    for date in iteration
+        .start_date
+        .iter_days()
+        .take_while(|date| *date <= last_day)
+    {
+        let end_of_day = (date + Days::new(1)).and_time(NaiveTime::MIN);
+        while let Some(change) = changes.next_if(|change| change.changed_at < end_of_day) {
+            statuses.insert(change.deliverable, change.status);
+        }
+
+        let scope = statuses
+            .values()
+            .filter(|status| **status != TaskStatus::Cancelled)
+            .count();
+        let done = statuses
+            .values()
+            .filter(|status| **status == TaskStatus::Done)
+            .count();
+
+        let initial_scope = *initial_scope.get_or_insert(scope) as f64;
+        let days_left = (iteration.end_date - date).num_days();
+        let ideal = match length {
+            0 => 0.0,
+            length => initial_scope * days_left as f64 / length as f64,
+        };
+
+        days.push(BurndownDay {
+            date,
+            scope,
+            done,
+            remaining: scope - done,
+            ideal,
+        });
+    }
+
+    days
+}
+
+fn validate_name(name: &str) -> Result<(), String> {
+    match name.trim().chars().count() {
+        0 => Err("The name of an iteration can't be empty".to_string()),
+        length if length > MAX_ITERATION_NAME_LENGTH => Err(format!(
+            "The name of an iteration can't be longer than {MAX_ITERATION_NAME_LENGTH} characters"
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn validate_dates(start_date: NaiveDate, end_date: NaiveDate) -> Result<(), String> {
+    match start_date <= end_date {
+        true => Ok(()),
+        false => Err("An iteration can't end before it starts".to_string()),
+    }
+}
+
+fn validate_title(title: &str) -> Result<(), String> {
+    match title.trim().chars().count() {
+        0 => Err("The title of a deliverable can't be empty".to_string()),
+        length if length > MAX_DELIVERABLE_TITLE_LENGTH => Err(format!(
+            "The title of a deliverable can't be longer than {MAX_DELIVERABLE_TITLE_LENGTH} \
+             characters"
+        )),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,156 @@
+use std::{
+    fmt::{Display, Formatter},
+    str::FromStr,
+};
+
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use rocket_sync_db_pools::diesel;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    models::present,
+    schema::{labels, project_labels},
+};
+
+/// The longest name of a label, as in the `labels` table.
+pub const MAX_LABEL_NAME_LENGTH: usize = 40;
+
+/// A label of a workspace, which its projects are tagged with.
+#[derive(Clone, Debug, Deserialize, Queryable, Serialize)]
+#[diesel(table_name = labels)]
+pub struct Label {
+    pub id: Uuid,
+    pub workspace: Uuid,
+    pub name: String,
+    /// A hex color like `#1f883d`
+    pub color: String,
+    pub description: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = labels)]
+pub struct InsertableLabel {
+    pub workspace: Uuid,
+    pub name: String,
+    pub color: String,
+    pub description: Option<String>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = project_labels)]
+pub struct ProjectLabel {
+    pub project: Uuid,
+    pub label: Uuid,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct NewLabel {
+    pub name: String,
+    pub color: String,
+    pub description: Option<String>,
+}
+
+/// Only the fields that are present are updated; the description is cleared with `null`.
+#[derive(AsChangeset, Clone, Debug, Default, Deserialize, Serialize)]
+#[diesel(table_name = labels)]
+pub struct LabelUpdate {
+    pub name: Option<String>,
+    pub color: Option<String>,
+    #[serde(default, deserialize_with = "present")]
+    pub description: Option<Option<String>>,
+}
+
+/// Whether a project needs any or all of the labels it is filtered by.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LabelMatch {
+    #[default]
+    Any,
+    All,
+}
+
+impl NewLabel {
+    pub fn validate(&self) -> Result<(), String> {
+        validate_name(&self.name)?;
+        validate_color(&self.color)
+    }
+
+    /// The label as it is stored, with a trimmed name and a lowercase color.
+    pub fn into_insertable(self, workspace: Uuid) -> InsertableLabel {
+        InsertableLabel {
+            workspace,
+            name: self.name.trim().to_string(),
+            color: self.color.to_ascii_lowercase(),
+            description: self.description,
+        }
+    }
+}
+
+impl LabelUpdate {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(name) = &self.name {
+            validate_name(name)?;
+        }
+        if let Some(color) = &self.color {
+            validate_color(color)?;
+        }
+
+        Ok(())
+    }
+
+    /// The update as it is stored, with a trimmed name and a lowercase color.
+    pub fn normalized(self) -> Self {
+        LabelUpdate {
+            name: self.name.map(|name| name.trim().to_string()),
+            color: self.color.map(|color| color.to_ascii_lowercase()),
+            description: self.description,
+        }
+    }
+}
+
+impl Display for LabelMatch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LabelMatch::Any => write!(f, "any"),
+            LabelMatch::All => write!(f, "all"),
+        }
+    }
+}
+
+impl FromStr for LabelMatch {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "any" => Ok(LabelMatch::Any),
+            "all" => Ok(LabelMatch::All),
+            _ => Err(format!("Invalid label match: {value}")),
+        }
+    }
+}
+
+fn validate_name(name: &str) -> Result<(), String> {
+    match name.trim().chars().count() {
+        0 => Err("The name of a label can't be empty".to_string()),
+        length if length > MAX_LABEL_NAME_LENGTH => Err(format!(
+            "The name of a label can't be longer than {MAX_LABEL_NAME_LENGTH} characters"
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// A hex color of six digits, like `#1f883d`.
+fn validate_color(color: &str) -> Result<(), String> {
+    match color.strip_prefix('#') {
+        Some(digits) if digits.len() == 6 && digits.chars().all(|c| c.is_ascii_hexdigit()) => {
+            Ok(())
+        }
+        _ => Err(format!(
+            "The color of a label has to be a hex color like #1f883d, not '{color}'"
+        )),
+    }
+}
@@ -1,14 +1,152 @@
 use diesel::prelude::Queryable;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
+use uuid::Uuid;
 
 use crate::models::users::PublicUser;
 
+/// Implements everything a role or status enum needs to be used directly in the models, routes
+/// and forms, while it's stored as a `SMALLINT`:
+///
+/// * Conversions from and to `i16`, based on the discriminants of the variants
+/// * Diesel [`ToSql`](diesel::serialize::ToSql) and [`FromSql`](diesel::deserialize::FromSql)
+/// * Ordering by the discriminants, so a role can be compared with `>=`
+/// * Parsing from the name of a variant (case insensitive), for path parameters and form fields
+///
+/// The enum has to derive `AsExpression` and `FromSqlRow` with `#[diesel(sql_type = SmallInt)]`,
+/// as well as `Clone`, `Copy`, `Debug`, `Eq` and `PartialEq`.
+macro_rules! smallint_enum {
+    ($name:ident { $($variant:ident),+ $(,)? }) => {
+        impl $name {
+            pub const ALL: &'static [$name] = &[$($name::$variant),+];
+        }
+
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "{self:?}")
+            }
+        }
+
+        impl TryFrom<i16> for $name {
+            type Error = String;
+
+            fn try_from(value: i16) -> Result<Self, Self::Error> {
+                $name::ALL
+                    .iter()
+                    .find(|variant| **variant as i16 == value)
+                    .copied()
+                    .ok_or_else(|| format!("Invalid {} value: {value}", stringify!($name)))
+            }
+        }
+
+        impl From<$name> for i16 {
+            fn from(value: $name) -> Self {
+                value as i16
+            }
+        }
+
+        impl std::str::FromStr for $name {
+            type Err = String;
+
+            fn from_str(value: &str) -> Result<Self, Self::Err> {
+                $name::ALL
+                    .iter()
+                    .find(|variant| variant.to_string().eq_ignore_ascii_case(value))
+                    .copied()
+                    .ok_or_else(|| format!("Invalid {}: {value}", stringify!($name)))
+            }
+        }
+
+        impl PartialOrd for $name {
+            fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl Ord for $name {
+            fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+                (*self as i16).cmp(&(*other as i16))
+            }
+        }
+
+        impl diesel::serialize::ToSql<diesel::sql_types::SmallInt, diesel::pg::Pg> for $name {
+            fn to_sql<'b>(
+                &'b self,
+                out: &mut diesel::serialize::Output<'b, '_, diesel::pg::Pg>,
+            ) -> diesel::serialize::Result {
+                use std::io::Write;
+
+                out.write_all(&(*self as i16).to_be_bytes())?;
+                Ok(diesel::serialize::IsNull::No)
+            }
+        }
+
+        impl diesel::deserialize::FromSql<diesel::sql_types::SmallInt, diesel::pg::Pg> for $name {
+            fn from_sql(bytes: diesel::pg::PgValue<'_>) -> diesel::deserialize::Result<Self> {
+                let value = <i16 as diesel::deserialize::FromSql<
+                    diesel::sql_types::SmallInt,
+                    diesel::pg::Pg,
+                >>::from_sql(bytes)?;
+
+                Ok($name::try_from(value)?)
+            }
+        }
+
+        impl<'a> rocket::request::FromParam<'a> for $name {
+            type Error = String;
+
+            fn from_param(param: &'a str) -> Result<Self, Self::Error> {
+                param.parse()
+            }
+        }
+
+        #[rocket::async_trait]
+        impl<'v> rocket::form::FromFormField<'v> for $name {
+            fn from_value(field: rocket::form::ValueField<'v>) -> rocket::form::Result<'v, Self> {
+                field
+                    .value
+                    .parse()
+                    .map_err(|e: String| rocket::form::Error::validation(e).into())
+            }
+        }
+    };
+}
+
+pub mod access_tokens;
+pub mod boards;
+pub mod comments;
+pub mod identities;
+pub mod impersonation;
+pub mod invitations;
+pub mod iterations;
+pub mod labels;
+pub mod permissions;
 pub mod projects;
+pub mod roles;
+pub mod sessions;
+pub mod tasks;
+pub mod two_factor;
 pub mod users;
 pub mod workspaces;
 
+/// A member of a workspace or project, with its [`WorkspaceRole`](workspaces::WorkspaceRole) or
+/// [`ProjectRole`](projects::ProjectRole).
 #[derive(Deserialize, Queryable, Serialize)]
-pub struct MemberInfo {
+pub struct MemberInfo<R> {
     pub user: PublicUser,
-    pub role: i16,
+    pub role: R,
+    /// The [`CustomRole`](roles::CustomRole) of a workspace member, which replaces the role
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub custom_role: Option<Uuid>,
+    /// Whether the project member is a guest, without access to the rest of the workspace
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub guest: bool,
+}
+
+/// Tells a field that is `null` (`Some(None)`) apart from one that is missing (`None`).
+pub(crate) fn present<'de, T, D>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
 }
@@ -0,0 +1,21 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Whether a user can do something in a workspace or project.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Capability {
+    pub name: String,
+    pub allowed: bool,
+    /// The rule that granted or denied the capability; only when explained
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// What a user can do in a workspace or project, evaluated with the same policies as the routes.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct EffectivePermissions {
+    pub user: Uuid,
+    /// The [`Permission`](super::roles::Permission)s the user has through their roles
+    pub permissions: Vec<String>,
+    pub capabilities: Vec<Capability>,
+}
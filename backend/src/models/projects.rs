@@ -1,7 +1,5 @@
-use std::fmt::{Display, Formatter};
-
 use chrono::NaiveDateTime;
-use diesel::prelude::*;
+use diesel::{deserialize::FromSqlRow, expression::AsExpression, prelude::*, sql_types::SmallInt};
 use rocket_sync_db_pools::diesel;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
@@ -30,13 +28,16 @@ pub struct Project {
 pub struct ProjectMember {
     pub project: Uuid,
     pub member: Uuid,
-    pub role: i16,
+    pub role: ProjectRole,
+    /// A guest has access to the project without being a member of its workspace
+    #[serde(default)]
+    pub guest: bool,
 }
 
 #[derive(Deserialize, Serialize)]
 pub struct ProjectWithMembers {
     pub project: Project,
-    pub members: Vec<MemberInfo>,
+    pub members: Vec<MemberInfo<ProjectRole>>,
 }
 
 #[derive(Insertable)]
@@ -63,7 +64,8 @@ pub struct ProjectUpdate {
     pub image_url: Option<String>,
 }
 
-#[derive(Debug, Deserialize, PartialEq, Serialize)]
+#[derive(AsExpression, Clone, Copy, Debug, Deserialize, Eq, FromSqlRow, PartialEq, Serialize)]
+#[diesel(sql_type = SmallInt)]
 pub enum ProjectRole {
     /// Maximum privileges; only one able to delete a workspace
     Owner = 10,
@@ -77,29 +79,10 @@ pub enum ProjectRole {
     Viewer = 0,
 }
 
-impl Display for ProjectRole {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{self:?}")
-    }
-}
-
-impl TryFrom<i16> for ProjectRole {
-    type Error = String;
-
-    fn try_from(value: i16) -> Result<Self, Self::Error> {
-        match value {
-            10 => Ok(ProjectRole::Owner),
-            5 => Ok(ProjectRole::Master),
-            2 => Ok(ProjectRole::Contributor),
-            1 => Ok(ProjectRole::Stakeholder),
-            0 => Ok(ProjectRole::Viewer),
-            _ => Err(format!("Invalid ProjectRole value: {value}")),
-        }
-    }
-}
-
-impl From<ProjectRole> for i16 {
-    fn from(role: ProjectRole) -> Self {
-        role as i16
-    }
-}
+smallint_enum!(ProjectRole {
+    Owner,
+    Master,
+    Contributor,
+    Stakeholder,
+    Viewer
+});
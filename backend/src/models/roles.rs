@@ -0,0 +1,238 @@
+use std::{
+    collections::HashSet,
+    fmt::{Display, Formatter},
+    str::FromStr,
+};
+
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use rocket_sync_db_pools::diesel;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    forms::roles::CustomRoleForm,
+    models::{projects::ProjectRole, workspaces::WorkspaceRole},
+    schema::workspace_roles,
+};
+
+/// Something a member is allowed to do in a workspace or project.
+///
+/// The built-in [`WorkspaceRole`]s and [`ProjectRole`]s are presets of these permissions, while
+/// a [`CustomRole`] is any set of them.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Permission {
+    WorkspaceUpdate,
+    WorkspaceDelete,
+    MemberInvite,
+    MemberManage,
+    RoleManage,
+    LabelManage,
+    ProjectCreate,
+    ProjectUpdate,
+    ProjectDelete,
+    ProjectMemberManage,
+    TaskCreate,
+    TaskUpdate,
+    TaskDelete,
+    CommentCreate,
+}
+
+impl Permission {
+    pub const ALL: &'static [Permission] = &[
+        Permission::WorkspaceUpdate,
+        Permission::WorkspaceDelete,
+        Permission::MemberInvite,
+        Permission::MemberManage,
+        Permission::RoleManage,
+        Permission::LabelManage,
+        Permission::ProjectCreate,
+        Permission::ProjectUpdate,
+        Permission::ProjectDelete,
+        Permission::ProjectMemberManage,
+        Permission::TaskCreate,
+        Permission::TaskUpdate,
+        Permission::TaskDelete,
+        Permission::CommentCreate,
+    ];
+}
+
+impl Display for Permission {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let permission = match self {
+            Permission::WorkspaceUpdate => "workspace.update",
+            Permission::WorkspaceDelete => "workspace.delete",
+            Permission::MemberInvite => "member.invite",
+            Permission::MemberManage => "member.manage",
+            Permission::RoleManage => "role.manage",
+            Permission::LabelManage => "label.manage",
+            Permission::ProjectCreate => "project.create",
+            Permission::ProjectUpdate => "project.update",
+            Permission::ProjectDelete => "project.delete",
+            Permission::ProjectMemberManage => "project.member.manage",
+            Permission::TaskCreate => "task.create",
+            Permission::TaskUpdate => "task.update",
+            Permission::TaskDelete => "task.delete",
+            Permission::CommentCreate => "comment.create",
+        };
+
+        write!(f, "{permission}")
+    }
+}
+
+impl FromStr for Permission {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Permission::ALL
+            .iter()
+            .find(|permission| permission.to_string() == value)
+            .copied()
+            .ok_or_else(|| format!("Invalid permission: {value}"))
+    }
+}
+
+impl WorkspaceRole {
+    /// The permissions of the built-in role.
+    pub fn permissions(&self) -> &'static [Permission] {
+        match self {
+            WorkspaceRole::Owner => Permission::ALL,
+            WorkspaceRole::Manager => &[
+                Permission::WorkspaceUpdate,
+                Permission::MemberInvite,
+                Permission::MemberManage,
+                Permission::RoleManage,
+                Permission::LabelManage,
+                Permission::ProjectCreate,
+                Permission::CommentCreate,
+            ],
+            WorkspaceRole::Contributor => &[Permission::WorkspaceUpdate, Permission::CommentCreate],
+            WorkspaceRole::Stakeholder | WorkspaceRole::Viewer => &[],
+        }
+    }
+}
+
+impl ProjectRole {
+    /// The permissions of the built-in role, within the project.
+    pub fn permissions(&self) -> &'static [Permission] {
+        match self {
+            ProjectRole::Owner | ProjectRole::Master => &[
+                Permission::ProjectUpdate,
+                Permission::ProjectMemberManage,
+                Permission::TaskCreate,
+                Permission::TaskUpdate,
+                Permission::TaskDelete,
+                Permission::CommentCreate,
+            ],
+            ProjectRole::Contributor => &[
+                Permission::ProjectUpdate,
+                Permission::TaskCreate,
+                Permission::TaskUpdate,
+                Permission::CommentCreate,
+            ],
+            ProjectRole::Stakeholder | ProjectRole::Viewer => &[],
+        }
+    }
+}
+
+/// A named set of permissions, defined by the workspace, which can be assigned to its members
+/// instead of a built-in [`WorkspaceRole`].
+#[derive(Clone, Debug, Deserialize, Insertable, Queryable, Serialize)]
+#[diesel(table_name = workspace_roles)]
+pub struct CustomRole {
+    pub id: Uuid,
+    pub workspace: Uuid,
+    pub name: String,
+    pub permissions: Vec<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+impl CustomRole {
+    pub fn new(workspace: Uuid, form: &CustomRoleForm) -> Self {
+        let timestamp = chrono::Utc::now().naive_utc();
+
+        CustomRole {
+            id: Uuid::new_v4(),
+            workspace,
+            name: form.name.to_string(),
+            permissions: form
+                .get_permissions()
+                .iter()
+                .map(Permission::to_string)
+                .collect(),
+            created_at: timestamp,
+            updated_at: timestamp,
+        }
+    }
+
+    /// The permissions of the role; unknown permissions are ignored.
+    pub fn get_permissions(&self) -> HashSet<Permission> {
+        self.permissions
+            .iter()
+            .filter_map(|permission| Permission::from_str(permission).ok())
+            .collect()
+    }
+}
+
+#[derive(AsChangeset)]
+#[diesel(table_name = workspace_roles)]
+pub struct CustomRoleUpdate {
+    pub name: String,
+    pub permissions: Vec<String>,
+}
+
+impl From<&CustomRoleForm<'_>> for CustomRoleUpdate {
+    fn from(form: &CustomRoleForm) -> Self {
+        CustomRoleUpdate {
+            name: form.name.to_string(),
+            permissions: form
+                .get_permissions()
+                .iter()
+                .map(Permission::to_string)
+                .collect(),
+        }
+    }
+}
+
+/// The role given to a member: either a built-in preset or a custom role of the workspace.
+///
+/// In JSON: `{"preset": "Manager"}` or `{"custom": "<role ID>"}`.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RoleAssignment {
+    Preset(WorkspaceRole),
+    Custom(Uuid),
+}
+
+/// A built-in role with its permissions.
+#[derive(Deserialize, Serialize)]
+pub struct RolePreset {
+    pub role: WorkspaceRole,
+    pub permissions: Vec<String>,
+}
+
+/// All roles that can be given to the members of a workspace.
+#[derive(Deserialize, Serialize)]
+pub struct WorkspaceRoles {
+    pub presets: Vec<RolePreset>,
+    pub custom: Vec<CustomRole>,
+}
+
+impl WorkspaceRoles {
+    pub fn new(custom: Vec<CustomRole>) -> Self {
+        let presets = WorkspaceRole::ALL
+            .iter()
+            .map(|role| RolePreset {
+                role: *role,
+                permissions: role
+                    .permissions()
+                    .iter()
+                    .map(Permission::to_string)
+                    .collect(),
+            })
+            .collect();
+
+        WorkspaceRoles { presets, custom }
+    }
+}
@@ -0,0 +1,159 @@
+use std::{convert::Infallible, net::IpAddr};
+
+use chrono::{NaiveDateTime, Utc};
+use rocket::{
+    request::{FromRequest, Outcome},
+    Request,
+};
+use serde::{Deserialize, Serialize};
+use subtle::ConstantTimeEq;
+use uuid::Uuid;
+
+use crate::cache::create_random_token;
+
+const REFRESH_TOKEN_LENGTH: usize = 64;
+const CSRF_TOKEN_LENGTH: usize = 32;
+/// Longer user agents are cut off, as they're stored for every session.
+const USER_AGENT_MAX_LENGTH: usize = 256;
+
+/// A login session, stored in Redis for as long as its refresh token is valid.
+///
+/// Every access token carries the ID of the session it was issued for. Removing the session from
+/// the store revokes all of its access tokens and its refresh token at once.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Session {
+    pub id: Uuid,
+    pub user: Uuid,
+    /// The secret part of the current refresh token; replaced on every refresh
+    pub refresh_secret: String,
+    /// Has to be sent along with every mutating request that is authenticated by cookie
+    #[serde(default)]
+    pub csrf_token: String,
+    /// The `User-Agent` of the client that logged in
+    #[serde(default)]
+    pub user_agent: Option<String>,
+    /// The IP of the client when it last refreshed the session
+    #[serde(default)]
+    pub ip: Option<IpAddr>,
+    pub created_at: NaiveDateTime,
+    /// Updated on every refresh, so at most as old as the lifetime of an access token
+    #[serde(default)]
+    pub last_seen_at: NaiveDateTime,
+    /// The admin acting as the user, if the session was started by an impersonation
+    #[serde(default)]
+    pub impersonator: Option<Uuid>,
+}
+
+impl Session {
+    pub fn new(user: Uuid, client: &ClientInfo) -> Self {
+        let now = Utc::now().naive_utc();
+
+        Session {
+            id: Uuid::new_v4(),
+            user,
+            refresh_secret: create_random_token(REFRESH_TOKEN_LENGTH),
+            csrf_token: create_random_token(CSRF_TOKEN_LENGTH),
+            user_agent: client.user_agent.clone(),
+            ip: client.ip,
+            created_at: now,
+            last_seen_at: now,
+            impersonator: None,
+        }
+    }
+
+    /// A session for an admin to act as the user.
+    pub fn impersonate(user: Uuid, admin: Uuid, client: &ClientInfo) -> Self {
+        Session {
+            impersonator: Some(admin),
+            ..Session::new(user, client)
+        }
+    }
+
+    /// Records that the client is still using the session.
+    pub fn touch(&mut self, client: &ClientInfo) {
+        self.ip = client.ip.or(self.ip);
+        self.last_seen_at = Utc::now().naive_utc();
+    }
+
+    /// Replaces the refresh secret, invalidating the previous refresh token.
+    pub fn rotate(&mut self) {
+        self.refresh_secret = create_random_token(REFRESH_TOKEN_LENGTH);
+
+        // Sessions from before CSRF tokens were introduced get one now
+        if self.csrf_token.is_empty() {
+            self.csrf_token = create_random_token(CSRF_TOKEN_LENGTH);
+        }
+    }
+
+    /// Whether the token sent by the client matches the CSRF token of the session.
+    ///
+    /// Compared in constant time, so how long it takes doesn't tell how much of a guess is right.
+    pub fn verify_csrf_token(&self, token: Option<&str>) -> bool {
+        match token {
+            Some(token) if !self.csrf_token.is_empty() => {
+                self.csrf_token.as_bytes().ct_eq(token.as_bytes()).into()
+            }
+            _ => false,
+        }
+    }
+
+    /// The refresh token as handed to the client: `<session id>.<secret>`.
+    pub fn refresh_token(&self) -> String {
+        format!("{}.{}", self.id, self.refresh_secret)
+    }
+
+    /// Splits a refresh token into the session ID and the secret.
+    pub fn parse_refresh_token(token: &str) -> Option<(Uuid, &str)> {
+        let (id, secret) = token.split_once('.')?;
+        Some((Uuid::parse_str(id).ok()?, secret))
+    }
+}
+
+/// A session as shown to its user; without the refresh secret.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PublicSession {
+    pub id: Uuid,
+    pub user_agent: Option<String>,
+    pub ip: Option<IpAddr>,
+    pub created_at: NaiveDateTime,
+    pub last_seen_at: NaiveDateTime,
+    pub impersonator: Option<Uuid>,
+    /// Whether the request was made with this session
+    pub current: bool,
+}
+
+impl PublicSession {
+    pub fn from(session: &Session, current_session: Uuid) -> Self {
+        PublicSession {
+            id: session.id,
+            user_agent: session.user_agent.clone(),
+            ip: session.ip,
+            created_at: session.created_at,
+            last_seen_at: session.last_seen_at,
+            impersonator: session.impersonator,
+            current: session.id == current_session,
+        }
+    }
+}
+
+/// Describes the client of a request, to be recorded in its session.
+#[derive(Clone, Debug, Default)]
+pub struct ClientInfo {
+    pub user_agent: Option<String>,
+    pub ip: Option<IpAddr>,
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ClientInfo {
+    type Error = Infallible;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(ClientInfo {
+            user_agent: request
+                .headers()
+                .get_one("User-Agent")
+                .map(|agent| agent.chars().take(USER_AGENT_MAX_LENGTH).collect()),
+            ip: request.client_ip(),
+        })
+    }
+}
@@ -0,0 +1,142 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use diesel::{deserialize::FromSqlRow, expression::AsExpression, prelude::*, sql_types::SmallInt};
+use rocket_sync_db_pools::diesel;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{models::present, schema::tasks};
+
+/// The longest title of a task, as in the `tasks` table.
+pub const MAX_TASK_TITLE_LENGTH: usize = 200;
+
+#[derive(Clone, Debug, Deserialize, Queryable, Serialize)]
+#[diesel(table_name = tasks)]
+pub struct Task {
+    pub id: Uuid,
+    pub project: Uuid,
+    pub title: String,
+    pub description: Option<String>,
+    pub status: TaskStatus,
+    pub priority: TaskPriority,
+    /// A member of the project
+    pub assignee: Option<Uuid>,
+    /// The user who created the task
+    pub reporter: Option<Uuid>,
+    pub due_date: Option<NaiveDate>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+/// A task as it is created; the project and reporter follow from the request.
+#[derive(Clone, Debug, Deserialize, Insertable, Serialize)]
+#[diesel(table_name = tasks)]
+pub struct NewTask {
+    #[serde(skip)]
+    pub project: Uuid,
+    pub title: String,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub status: TaskStatus,
+    #[serde(default)]
+    pub priority: TaskPriority,
+    pub assignee: Option<Uuid>,
+    #[serde(skip)]
+    pub reporter: Option<Uuid>,
+    pub due_date: Option<NaiveDate>,
+}
+
+/// Only the fields that are present are updated; the assignee, description and due date are
+/// cleared with `null`.
+#[derive(AsChangeset, Clone, Debug, Default, Deserialize, Serialize)]
+#[diesel(table_name = tasks)]
+pub struct TaskUpdate {
+    pub title: Option<String>,
+    #[serde(default, deserialize_with = "present")]
+    pub description: Option<Option<String>>,
+    pub status: Option<TaskStatus>,
+    pub priority: Option<TaskPriority>,
+    #[serde(default, deserialize_with = "present")]
+    pub assignee: Option<Option<Uuid>>,
+    #[serde(default, deserialize_with = "present")]
+    pub due_date: Option<Option<NaiveDate>>,
+}
+
+#[derive(
+    AsExpression, Clone, Copy, Debug, Default, Deserialize, Eq, FromSqlRow, PartialEq, Serialize,
+)]
+#[diesel(sql_type = SmallInt)]
+pub enum TaskStatus {
+    /// Not started yet
+    #[default]
+    Todo = 0,
+    /// Being worked on
+    InProgress = 1,
+    /// Done, but waiting for a review
+    InReview = 2,
+    /// Finished
+    Done = 3,
+    /// Won't be done
+    Cancelled = 4,
+}
+
+smallint_enum!(TaskStatus {
+    Todo,
+    InProgress,
+    InReview,
+    Done,
+    Cancelled
+});
+
+#[derive(
+    AsExpression, Clone, Copy, Debug, Default, Deserialize, Eq, FromSqlRow, PartialEq, Serialize,
+)]
+#[diesel(sql_type = SmallInt)]
+pub enum TaskPriority {
+    Low = 0,
+    #[default]
+    Medium = 1,
+    High = 2,
+    Urgent = 3,
+}
+
+smallint_enum!(TaskPriority {
+    Low,
+    Medium,
+    High,
+    Urgent
+});
+
+impl NewTask {
+    pub fn validate(&self) -> Result<(), String> {
+        validate_title(&self.title)
+    }
+}
+
+impl TaskUpdate {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.title.is_none()
+            && self.description.is_none()
+            && self.status.is_none()
+            && self.priority.is_none()
+            && self.assignee.is_none()
+            && self.due_date.is_none()
+        {
+            return Err("Nothing to update".to_string());
+        }
+
+        match &self.title {
+            Some(title) => validate_title(title),
+            None => Ok(()),
+        }
+    }
+}
+
+fn validate_title(title: &str) -> Result<(), String> {
+    match title.trim().chars().count() {
+        0 => Err("The title of a task can't be empty".to_string()),
+        length if length > MAX_TASK_TITLE_LENGTH => Err(format!(
+            "The title of a task can't be longer than {MAX_TASK_TITLE_LENGTH} characters"
+        )),
+        _ => Ok(()),
+    }
+}
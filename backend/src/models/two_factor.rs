@@ -0,0 +1,120 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use rocket_sync_db_pools::diesel;
+use serde::{Deserialize, Serialize};
+use totp_rs::{Algorithm, Secret, TOTP};
+use uuid::Uuid;
+
+use crate::{cache::create_random_token, forms::password::Password, schema::user_two_factor};
+
+const TOTP_ISSUER: &str = "Rustle";
+const TOTP_DIGITS: usize = 6;
+const TOTP_SKEW: u8 = 1;
+const TOTP_STEP: u64 = 30;
+
+const RECOVERY_CODE_COUNT: usize = 10;
+const RECOVERY_CODE_LENGTH: usize = 10;
+
+/// The TOTP second factor of a user.
+///
+/// A second factor is created when the user starts the enrollment, but it is only `enabled`
+/// after the user proved to have set up their authenticator app by submitting a valid code.
+#[derive(Clone, Debug, Insertable, Queryable)]
+#[diesel(table_name = user_two_factor)]
+pub struct TwoFactor {
+    pub user_id: Uuid,
+    /// Base32 encoded TOTP secret
+    pub secret: String,
+    pub enabled: bool,
+    /// Argon2 hashes of the unused recovery codes
+    pub recovery_codes: Vec<String>,
+    #[diesel(skip_insertion)]
+    pub created_at: NaiveDateTime,
+    #[diesel(skip_insertion)]
+    pub updated_at: NaiveDateTime,
+}
+
+/// Everything the user needs to set up an authenticator app. Only returned once, at enrollment.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TwoFactorEnrollment {
+    pub secret: String,
+    pub otpauth_uri: String,
+    pub recovery_codes: Vec<String>,
+}
+
+/// Returned by the login when the password was correct, but a second factor is still required.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TwoFactorChallenge {
+    /// Short-lived token that has to be sent along with the code to complete the login
+    pub challenge: String,
+    /// The user has to enroll first, because their role requires a second factor
+    pub enrollment_required: bool,
+}
+
+impl TwoFactor {
+    /// Generates a new (disabled) second factor for the user, returning it along with the
+    /// information that has to be shown to the user.
+    pub fn generate(user_id: Uuid, username: &str) -> Result<(Self, TwoFactorEnrollment), String> {
+        let secret = Secret::generate_secret().to_encoded().to_string();
+        let otpauth_uri = Self::totp(&secret, username)?.get_url();
+
+        let recovery_codes: Vec<String> = (0..RECOVERY_CODE_COUNT)
+            .map(|_| create_random_token(RECOVERY_CODE_LENGTH))
+            .collect();
+
+        let hashed_codes = recovery_codes
+            .iter()
+            .map(|code| Password::generate(Some(code)))
+            .collect::<Result<Vec<String>, _>>()
+            .map_err(|e| format!("Couldn't hash recovery code: {e}"))?;
+
+        let two_factor = TwoFactor {
+            user_id,
+            secret: secret.clone(),
+            enabled: false,
+            recovery_codes: hashed_codes,
+            created_at: NaiveDateTime::default(),
+            updated_at: NaiveDateTime::default(),
+        };
+
+        let enrollment = TwoFactorEnrollment {
+            secret,
+            otpauth_uri,
+            recovery_codes,
+        };
+
+        Ok((two_factor, enrollment))
+    }
+
+    /// Checks the code against the current time step and the steps directly around it.
+    pub fn verify_code(&self, code: &str) -> Result<bool, String> {
+        // The account name is only used for the otpauth URI
+        Self::totp(&self.secret, "")?
+            .check_current(code)
+            .map_err(|e| format!("Couldn't verify code: {e}"))
+    }
+
+    /// Returns the index of the recovery code that matches the input, if any.
+    pub fn find_recovery_code(&self, code: &str) -> Option<usize> {
+        self.recovery_codes
+            .iter()
+            .position(|hash| Password::verify_password(code, hash).unwrap_or(false))
+    }
+
+    fn totp(secret: &str, username: &str) -> Result<TOTP, String> {
+        let secret = Secret::Encoded(secret.to_string())
+            .to_bytes()
+            .map_err(|e| format!("Invalid TOTP secret: {e}"))?;
+
+        TOTP::new(
+            Algorithm::SHA1,
+            TOTP_DIGITS,
+            TOTP_SKEW,
+            TOTP_STEP,
+            secret,
+            Some(TOTP_ISSUER.to_string()),
+            username.to_string(),
+        )
+        .map_err(|e| format!("Couldn't create TOTP: {e}"))
+    }
+}
@@ -1,13 +1,17 @@
-use std::fmt::{Display, Formatter};
-
 use chrono::{NaiveDateTime, Utc};
-use diesel::{prelude::*, sql_types::Text};
+use diesel::{
+    deserialize::FromSqlRow,
+    expression::AsExpression,
+    prelude::*,
+    sql_types::{SmallInt, Text},
+};
 use rocket_sync_db_pools::diesel;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::{
-    env, forms::password::Password, schema::users, ENV_POSTGRES_PASSWORD, ENV_POSTGRES_USER,
+    env, forms::password::Password, models::workspaces::WorkspaceRole, schema::users,
+    ENV_POSTGRES_PASSWORD, ENV_POSTGRES_USER,
 };
 
 #[derive(Clone, Default, Debug, Deserialize, Insertable, Queryable, QueryableByName, Serialize)]
@@ -20,8 +24,8 @@ pub struct User {
     pub last_name: String,
     pub email: String,
     pub phone: Option<String>,
-    pub role: i16,
-    pub status: i16,
+    pub role: UserRole,
+    pub status: UserStatus,
     pub job_title: Option<String>,
     pub password: String,
     pub bio: Option<String>,
@@ -40,8 +44,8 @@ impl User {
             id: Uuid::new_v4(),
             username,
             email: "admin@rustle.com".to_string(),
-            role: i16::from(UserRole::Admin),
-            status: i16::from(UserStatus::Active),
+            role: UserRole::Admin,
+            status: UserStatus::Active,
             password: Password::generate(Some(&password)).unwrap(),
             created_at: timestamp,
             updated_at: timestamp,
@@ -59,8 +63,8 @@ pub struct PublicUser {
     pub last_name: String,
     pub email: String,
     pub phone: Option<String>,
-    pub role: i16,
-    pub status: i16,
+    pub role: UserRole,
+    pub status: UserStatus,
     pub job_title: Option<String>,
     pub bio: Option<String>,
     pub avatar_url: Option<String>,
@@ -92,11 +96,11 @@ impl PublicUser {
     }
 
     pub fn is_admin(&self) -> bool {
-        self.role == i16::from(UserRole::Admin)
+        self.role == UserRole::Admin
     }
 
     pub fn is_at_least(&self, role: UserRole) -> bool {
-        self.role >= i16::from(role)
+        self.role >= role
     }
 }
 
@@ -112,6 +116,19 @@ pub struct UserUpdate {
     pub avatar_url: Option<String>,
 }
 
+/// A requested change of the email address, kept until the new address is confirmed.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct EmailChange {
+    pub user: Uuid,
+    pub email: String,
+}
+
+/// Usernames are lowercase, with underscores instead of spaces and without apostrophes; invited
+/// users get `first_last`, with a numeric suffix when it is taken.
+pub fn normalize_username(name: &str) -> String {
+    name.to_lowercase().replace(' ', "_").replace('\'', "")
+}
+
 //#[derive(Clone, Debug, Deserialize, Insertable, Queryable, QueryableByName, Serialize)]
 #[derive(Clone, Deserialize, Insertable, Serialize)]
 #[diesel(table_name = users)]
@@ -120,50 +137,39 @@ pub struct InvitedUser {
     pub first_name: String,
     pub last_name: String,
     pub email: String,
-    pub role: i16,
-    pub status: i16,
+    pub role: UserRole,
+    pub status: UserStatus,
     pub password: String,
     #[diesel(skip_insertion)]
-    pub workspace_role: i16,
+    pub workspace_role: WorkspaceRole,
 }
 
-#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[derive(
+    AsExpression, Clone, Copy, Debug, Default, Deserialize, Eq, FromSqlRow, PartialEq, Serialize,
+)]
+#[diesel(sql_type = SmallInt)]
 pub enum UserRole {
     Admin = 1000,
     Manager = 5,
     Contributor = 1,
+    #[default]
     Reviewer = 0,
 }
 
-impl Display for UserRole {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{self:?}")
-    }
-}
-
-impl TryFrom<i16> for UserRole {
-    type Error = String;
-
-    fn try_from(value: i16) -> Result<Self, Self::Error> {
-        match value {
-            3 => Ok(UserRole::Admin),
-            2 => Ok(UserRole::Manager),
-            1 => Ok(UserRole::Contributor),
-            0 => Ok(UserRole::Reviewer),
-            _ => Err(format!("Invalid UserRole value: {value}")),
-        }
-    }
-}
-
-impl From<UserRole> for i16 {
-    fn from(role: UserRole) -> Self {
-        role as i16
-    }
-}
-
-#[derive(Debug, PartialEq)]
+smallint_enum!(UserRole {
+    Admin,
+    Manager,
+    Contributor,
+    Reviewer
+});
+
+#[derive(
+    AsExpression, Clone, Copy, Debug, Default, Deserialize, Eq, FromSqlRow, PartialEq, Serialize,
+)]
+#[diesel(sql_type = SmallInt)]
 pub enum UserStatus {
-    // User created but hasn't set a password yet)
+    /// User created but hasn't set a password yet
+    #[default]
     Invited = 0,
     /// User is inactive; currently not used
     Inactive = 1,
@@ -175,23 +181,10 @@ pub enum UserStatus {
     Removed = 4,
 }
 
-impl TryFrom<i16> for UserStatus {
-    type Error = String;
-
-    fn try_from(value: i16) -> Result<Self, Self::Error> {
-        match value {
-            0 => Ok(UserStatus::Invited),
-            1 => Ok(UserStatus::Inactive),
-            2 => Ok(UserStatus::Active),
-            3 => Ok(UserStatus::Suspended),
-            4 => Ok(UserStatus::Removed),
-            _ => Err(format!("Invalid UserStatus value: {value}")),
-        }
-    }
-}
-
-impl From<UserStatus> for i16 {
-    fn from(status: UserStatus) -> Self {
-        status as i16
-    }
-}
+smallint_enum!(UserStatus {
+    Invited,
+    Inactive,
+    Active,
+    Suspended,
+    Removed
+});
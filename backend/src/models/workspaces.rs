@@ -1,7 +1,7 @@
-use std::fmt::{Display, Formatter};
+use std::collections::HashSet;
 
 use chrono::{NaiveDateTime, Utc};
-use diesel::prelude::*;
+use diesel::{deserialize::FromSqlRow, expression::AsExpression, prelude::*, sql_types::SmallInt};
 use rocket_sync_db_pools::diesel;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
@@ -11,7 +11,10 @@ use crate::{
     schema::{workspace_members, workspaces},
 };
 
-use super::MemberInfo;
+use super::{
+    roles::{CustomRole, Permission},
+    MemberInfo,
+};
 
 #[derive(Clone, Debug, Deserialize, Insertable, Queryable, Serialize)]
 #[diesel(table_name = workspaces)]
@@ -68,13 +71,35 @@ pub struct WorkspaceUpdate {
 pub struct WorkspaceMember {
     pub workspace: Uuid,
     pub member: Uuid,
-    pub role: i16,
+    pub role: WorkspaceRole,
+    #[serde(default)]
+    pub custom_role: Option<Uuid>,
 }
 
 #[derive(Deserialize, Serialize)]
 pub struct WorkspaceWithMembers {
     pub workspace: Workspace,
-    pub members: Vec<MemberInfo>,
+    pub members: Vec<MemberInfo<WorkspaceRole>>,
+    /// The custom roles defined by the workspace
+    #[serde(default)]
+    pub roles: Vec<CustomRole>,
+}
+
+impl WorkspaceWithMembers {
+    /// The permissions of a member; those of the custom role if there is one, otherwise those of
+    /// the built-in role. `None` if the user is not a member.
+    pub fn member_permissions(&self, user: Uuid) -> Option<HashSet<Permission>> {
+        let member = self.members.iter().find(|member| member.user.id == user)?;
+
+        let custom_role = member
+            .custom_role
+            .and_then(|id| self.roles.iter().find(|role| role.id == id));
+
+        Some(match custom_role {
+            Some(role) => role.get_permissions(),
+            None => member.role.permissions().iter().copied().collect(),
+        })
+    }
 }
 
 pub struct NewWorkspace {
@@ -93,7 +118,8 @@ impl NewWorkspace {
     }
 }
 
-#[derive(Debug, Deserialize, PartialEq, Serialize)]
+#[derive(AsExpression, Clone, Copy, Debug, Deserialize, Eq, FromSqlRow, PartialEq, Serialize)]
+#[diesel(sql_type = SmallInt)]
 pub enum WorkspaceRole {
     /// Maximum privileges; only one able to delete a workspace
     Owner = 10,
@@ -107,29 +133,10 @@ pub enum WorkspaceRole {
     Viewer = 0,
 }
 
-impl Display for WorkspaceRole {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{self:?}")
-    }
-}
-
-impl TryFrom<i16> for WorkspaceRole {
-    type Error = String;
-
-    fn try_from(value: i16) -> Result<Self, Self::Error> {
-        match value {
-            10 => Ok(WorkspaceRole::Owner),
-            5 => Ok(WorkspaceRole::Manager),
-            2 => Ok(WorkspaceRole::Contributor),
-            1 => Ok(WorkspaceRole::Stakeholder),
-            0 => Ok(WorkspaceRole::Viewer),
-            _ => Err(format!("Invalid WorkspaceRole value: {value}")),
-        }
-    }
-}
-
-impl From<WorkspaceRole> for i16 {
-    fn from(role: WorkspaceRole) -> Self {
-        role as i16
-    }
-}
+smallint_enum!(WorkspaceRole {
+    Owner,
+    Manager,
+    Contributor,
+    Stakeholder,
+    Viewer
+});
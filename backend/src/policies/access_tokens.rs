@@ -0,0 +1,16 @@
+use crate::{
+    api::{Error, Null},
+    models::access_tokens::Scope,
+};
+
+use super::Policy;
+
+impl Policy {
+    /// Requests made with an access token are limited to the scopes of the token. The role of
+    /// the user is still checked by the policy of the route itself, so the token can never do
+    /// more than the user.
+    pub fn access_tokens_scope(scopes: &[Scope], required: &Scope) -> Result<(), Error<Null>> {
+        Policy::rule(scopes.iter().any(|scope| scope.grants(required)))
+            .unauthorized(&format!("Token is missing the '{required}' scope"))
+    }
+}
@@ -0,0 +1,83 @@
+use uuid::Uuid;
+
+use crate::{
+    api::{Error, Null},
+    models::{
+        projects::ProjectWithMembers, roles::Permission, users::PublicUser,
+        workspaces::WorkspaceWithMembers,
+    },
+    policies::projects::has_project_permission,
+};
+
+use super::{permissions::PermissionResolver, Policy};
+
+/// BOARD PERMISSIONS:
+///
+/// 1. Boards and columns: CUD -> `project.update` / Admin
+/// 2. Boards, columns and cards: R -> Anyone who can view the project
+/// 3. Cards: C -> `task.create` / Admin
+/// 4. Cards: U -> `task.update` / Admin, which includes moving them
+/// 5. Cards: D -> `task.delete` / Admin
+///
+/// Cards are the work items on a board, so they follow the permissions of tasks.
+impl Policy {
+    /// [`Admin`](crate::models::users::UserRole::Admin), workspace member or guest of the project
+    pub fn boards_view(
+        user: &PublicUser,
+        workspace_with_members: &WorkspaceWithMembers,
+        project_with_members: &ProjectWithMembers,
+    ) -> Result<(), Error<Null>> {
+        Policy::projects_view(user, workspace_with_members, project_with_members)
+    }
+
+    /// [`Admin`](crate::models::users::UserRole::Admin) or
+    /// [`project.update`](Permission::ProjectUpdate)
+    pub async fn boards_manage(
+        project: Uuid,
+        user: PublicUser,
+        permissions: &PermissionResolver<'_>,
+    ) -> Result<(), Error<Null>> {
+        Policy::rule(user.is_admin())
+            .or(
+                has_project_permission(Permission::ProjectUpdate, project, &user, permissions)
+                    .await?,
+            )
+            .unauthorized("Not authorized to manage the boards of this project")
+    }
+
+    /// [`Admin`](crate::models::users::UserRole::Admin) or
+    /// [`task.create`](Permission::TaskCreate)
+    pub async fn cards_create(
+        project: Uuid,
+        user: PublicUser,
+        permissions: &PermissionResolver<'_>,
+    ) -> Result<(), Error<Null>> {
+        Policy::rule(user.is_admin())
+            .or(has_project_permission(Permission::TaskCreate, project, &user, permissions).await?)
+            .unauthorized("Not authorized to create cards in this project")
+    }
+
+    /// [`Admin`](crate::models::users::UserRole::Admin) or
+    /// [`task.update`](Permission::TaskUpdate)
+    pub async fn cards_update(
+        project: Uuid,
+        user: PublicUser,
+        permissions: &PermissionResolver<'_>,
+    ) -> Result<(), Error<Null>> {
+        Policy::rule(user.is_admin())
+            .or(has_project_permission(Permission::TaskUpdate, project, &user, permissions).await?)
+            .unauthorized("Not authorized to update cards in this project")
+    }
+
+    /// [`Admin`](crate::models::users::UserRole::Admin) or
+    /// [`task.delete`](Permission::TaskDelete)
+    pub async fn cards_remove(
+        project: Uuid,
+        user: PublicUser,
+        permissions: &PermissionResolver<'_>,
+    ) -> Result<(), Error<Null>> {
+        Policy::rule(user.is_admin())
+            .or(has_project_permission(Permission::TaskDelete, project, &user, permissions).await?)
+            .unauthorized("Not authorized to remove cards from this project")
+    }
+}
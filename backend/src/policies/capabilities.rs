@@ -0,0 +1,333 @@
+//! What a user can do in a workspace or project.
+//!
+//! Every capability is evaluated with the [`Policy`] the route behind it uses, so the result
+//! can't drift from what the routes allow. On request, each capability comes with the reason it
+//! was granted or denied, based on the roles of the user.
+use std::collections::HashSet;
+
+use rocket::http::Status;
+use uuid::Uuid;
+
+use crate::{
+    api::{Error, Null},
+    database::{self, Db},
+    models::{
+        permissions::{Capability, EffectivePermissions},
+        projects::ProjectWithMembers,
+        roles::Permission,
+        users::PublicUser,
+        workspaces::WorkspaceWithMembers,
+    },
+};
+
+use super::{permissions::PermissionResolver, Policy};
+
+/// The user whose capabilities are requested: the requesting user, or any user for an admin.
+/// Explaining the capabilities is for admins as well.
+pub async fn capability_subject(
+    db: &Db,
+    requester: PublicUser,
+    user: Option<Uuid>,
+    explain: bool,
+) -> Result<PublicUser, Error<Null>> {
+    let user = user.filter(|id| *id != requester.id);
+
+    if user.is_some() || explain {
+        Policy::users_explain_permissions(&requester)?;
+    }
+
+    match user {
+        Some(id) => Ok(PublicUser::from(
+            &database::users::get_user_by_id(db, id).await?,
+        )),
+        None => Ok(requester),
+    }
+}
+
+/// The capabilities of the user in the workspace.
+pub async fn workspace_capabilities(
+    workspace_with_members: &WorkspaceWithMembers,
+    user: &PublicUser,
+    resolver: &PermissionResolver<'_>,
+    explain: bool,
+) -> Result<EffectivePermissions, Error<Null>> {
+    let id = workspace_with_members.workspace.id;
+    let permissions = resolver.workspace_permissions(user.id, id).await?;
+
+    let checks = [
+        (
+            "view",
+            Policy::workspaces_view(user, workspace_with_members),
+            None,
+        ),
+        (
+            "update_info",
+            Policy::workspaces_update_info(id, user.clone(), resolver).await,
+            Some(Permission::WorkspaceUpdate),
+        ),
+        (
+            "invite_members",
+            Policy::workspaces_invite_members(id, user.clone(), resolver).await,
+            Some(Permission::MemberInvite),
+        ),
+        (
+            "manage_members",
+            Policy::workspaces_update_members(id, user.clone(), resolver).await,
+            Some(Permission::MemberManage),
+        ),
+        (
+            "manage_roles",
+            Policy::workspaces_manage_roles(id, &Default::default(), user.clone(), resolver).await,
+            Some(Permission::RoleManage),
+        ),
+        (
+            "manage_labels",
+            Policy::labels_manage(id, user.clone(), resolver).await,
+            Some(Permission::LabelManage),
+        ),
+        (
+            "create_projects",
+            Policy::projects_create(id, user.clone(), resolver).await,
+            Some(Permission::ProjectCreate),
+        ),
+        (
+            "write_comments",
+            Policy::comments_write_workspace(id, user.clone(), resolver).await,
+            Some(Permission::CommentCreate),
+        ),
+        (
+            "delete",
+            Policy::workspaces_remove(id, user.clone(), resolver).await,
+            Some(Permission::WorkspaceDelete),
+        ),
+    ];
+
+    let mut capabilities = Vec::new();
+    for (name, result, permission) in checks {
+        let allowed = is_allowed(result)?;
+        let reason =
+            explain.then(|| explain_workspace(workspace_with_members, user, permission, allowed));
+
+        capabilities.push(Capability {
+            name: name.to_string(),
+            allowed,
+            reason,
+        });
+    }
+
+    Ok(EffectivePermissions {
+        user: user.id,
+        permissions: sorted(&permissions),
+        capabilities,
+    })
+}
+
+/// The capabilities of the user in the project, which is part of the workspace.
+pub async fn project_capabilities(
+    workspace_with_members: &WorkspaceWithMembers,
+    project_with_members: &ProjectWithMembers,
+    user: &PublicUser,
+    resolver: &PermissionResolver<'_>,
+    explain: bool,
+) -> Result<EffectivePermissions, Error<Null>> {
+    let id = project_with_members.project.id;
+    let workspace = workspace_with_members.workspace.id;
+    let permissions = resolver.project_permissions(user.id, id).await?;
+
+    let checks = [
+        (
+            "view",
+            Policy::projects_view(user, workspace_with_members, project_with_members),
+            None,
+            true,
+        ),
+        (
+            "update_info",
+            Policy::projects_update_info(id, user.clone(), resolver).await,
+            Some(Permission::ProjectUpdate),
+            true,
+        ),
+        (
+            "manage_members",
+            Policy::project_update_members(id, user.clone(), resolver).await,
+            Some(Permission::ProjectMemberManage),
+            true,
+        ),
+        (
+            "create_tasks",
+            Policy::tasks_create(id, user.clone(), resolver).await,
+            Some(Permission::TaskCreate),
+            true,
+        ),
+        (
+            "update_tasks",
+            Policy::tasks_update(id, user.clone(), resolver).await,
+            Some(Permission::TaskUpdate),
+            true,
+        ),
+        (
+            "delete_tasks",
+            Policy::tasks_remove(id, user.clone(), resolver).await,
+            Some(Permission::TaskDelete),
+            true,
+        ),
+        (
+            "manage_boards",
+            Policy::boards_manage(id, user.clone(), resolver).await,
+            Some(Permission::ProjectUpdate),
+            true,
+        ),
+        (
+            "tag_labels",
+            Policy::labels_tag_project(id, user.clone(), resolver).await,
+            Some(Permission::ProjectUpdate),
+            true,
+        ),
+        (
+            "manage_iterations",
+            Policy::iterations_manage(id, user.clone(), resolver).await,
+            Some(Permission::ProjectUpdate),
+            true,
+        ),
+        (
+            "write_comments",
+            Policy::comments_write_project(id, user.clone(), resolver).await,
+            Some(Permission::CommentCreate),
+            true,
+        ),
+        (
+            "delete",
+            Policy::projects_remove(workspace, user.clone(), resolver).await,
+            Some(Permission::ProjectDelete),
+            false,
+        ),
+    ];
+
+    let mut capabilities = Vec::new();
+    for (name, result, permission, by_project_role) in checks {
+        let allowed = is_allowed(result)?;
+        let reason = explain.then(|| {
+            if by_project_role {
+                explain_project(
+                    workspace_with_members,
+                    project_with_members,
+                    user,
+                    permission,
+                    allowed,
+                )
+            } else {
+                explain_workspace(workspace_with_members, user, permission, allowed)
+            }
+        });
+
+        capabilities.push(Capability {
+            name: name.to_string(),
+            allowed,
+            reason,
+        });
+    }
+
+    Ok(EffectivePermissions {
+        user: user.id,
+        permissions: sorted(&permissions),
+        capabilities,
+    })
+}
+
+/// A denied policy is not an error here, anything else still is.
+fn is_allowed(result: Result<(), Error<Null>>) -> Result<bool, Error<Null>> {
+    match result {
+        Ok(()) => Ok(true),
+        Err(e) if e.0 == Status::Unauthorized || e.0 == Status::NotFound => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+fn sorted(permissions: &HashSet<Permission>) -> Vec<String> {
+    Permission::ALL
+        .iter()
+        .filter(|permission| permissions.contains(permission))
+        .map(Permission::to_string)
+        .collect()
+}
+
+fn explain_workspace(
+    workspace_with_members: &WorkspaceWithMembers,
+    user: &PublicUser,
+    permission: Option<Permission>,
+    allowed: bool,
+) -> String {
+    if user.is_admin() {
+        return "Granted to admins".to_string();
+    }
+
+    let Some(member) = workspace_with_members
+        .members
+        .iter()
+        .find(|member| member.user.id == user.id)
+    else {
+        return "Denied; not a member of the workspace".to_string();
+    };
+
+    let Some(permission) = permission else {
+        return "Granted to members of the workspace".to_string();
+    };
+
+    let role = match member.custom_role.and_then(|id| {
+        workspace_with_members
+            .roles
+            .iter()
+            .find(|role| role.id == id)
+    }) {
+        Some(custom_role) => format!("custom role '{}'", custom_role.name),
+        None => format!("built-in role {}", member.role),
+    };
+
+    if allowed {
+        format!("Granted by the {role}, which has {permission}")
+    } else {
+        format!("Denied; the {role} doesn't have {permission}")
+    }
+}
+
+fn explain_project(
+    workspace_with_members: &WorkspaceWithMembers,
+    project_with_members: &ProjectWithMembers,
+    user: &PublicUser,
+    permission: Option<Permission>,
+    allowed: bool,
+) -> String {
+    let member = project_with_members
+        .members
+        .iter()
+        .find(|member| member.user.id == user.id);
+
+    // Guests only have their project role, without anything from the workspace
+    if let Some(member) = member.filter(|member| member.guest && !user.is_admin()) {
+        return match permission {
+            None => "Granted to guests of the project".to_string(),
+            Some(Permission::ProjectMemberManage) => {
+                "Denied; guests don't manage the members of the project".to_string()
+            }
+            Some(permission) if allowed => format!(
+                "Granted by the project role {}, which has {permission}",
+                member.role
+            ),
+            Some(permission) => format!(
+                "Denied; the project role {} doesn't have {permission}",
+                member.role
+            ),
+        };
+    }
+
+    match (member.map(|member| member.role), permission) {
+        (Some(role), Some(permission)) if role.permissions().contains(&permission) => {
+            format!("Granted by the project role {role}, which has {permission}")
+        }
+        (Some(role), _) if !allowed && !user.is_admin() => format!(
+            "{}; nor does the project role {role}",
+            explain_workspace(workspace_with_members, user, permission, allowed)
+        ),
+        _ => explain_workspace(workspace_with_members, user, permission, allowed),
+    }
+}
@@ -0,0 +1,83 @@
+use uuid::Uuid;
+
+use crate::{
+    api::{Error, Null},
+    models::{
+        comments::Comment, projects::ProjectWithMembers, roles::Permission, users::PublicUser,
+        workspaces::WorkspaceWithMembers,
+    },
+    policies::{projects::has_project_permission, workspaces::has_workspace_permission},
+};
+
+use super::{permissions::PermissionResolver, Policy};
+
+/// COMMENT PERMISSIONS:
+///
+/// 1. Comments: R -> Anyone who can view the workspace or project
+/// 2. Comments: C -> `comment.create` in the workspace or project / Admin
+/// 3. Comments: U -> The author
+/// 4. Comments: D -> The author / Admin
+///
+/// The `comment.create` of the workspace also counts in the threads of its projects; the author
+/// only edits or deletes a comment while still able to write in the thread.
+impl Policy {
+    /// [`Admin`](crate::models::users::UserRole::Admin) or member of the workspace
+    pub fn comments_view_workspace(
+        user: &PublicUser,
+        workspace_with_members: &WorkspaceWithMembers,
+    ) -> Result<(), Error<Null>> {
+        Policy::workspaces_view(user, workspace_with_members)
+    }
+
+    /// [`Admin`](crate::models::users::UserRole::Admin), workspace member or guest of the project
+    pub fn comments_view_project(
+        user: &PublicUser,
+        workspace_with_members: &WorkspaceWithMembers,
+        project_with_members: &ProjectWithMembers,
+    ) -> Result<(), Error<Null>> {
+        Policy::projects_view(user, workspace_with_members, project_with_members)
+    }
+
+    /// [`Admin`](crate::models::users::UserRole::Admin) or
+    /// [`comment.create`](Permission::CommentCreate) in the workspace
+    pub async fn comments_write_workspace(
+        workspace: Uuid,
+        user: PublicUser,
+        permissions: &PermissionResolver<'_>,
+    ) -> Result<(), Error<Null>> {
+        Policy::rule(user.is_admin())
+            .or(
+                has_workspace_permission(Permission::CommentCreate, workspace, &user, permissions)
+                    .await?,
+            )
+            .unauthorized("Not authorized to comment on this workspace")
+    }
+
+    /// [`Admin`](crate::models::users::UserRole::Admin) or
+    /// [`comment.create`](Permission::CommentCreate) in the project or its workspace
+    pub async fn comments_write_project(
+        project: Uuid,
+        user: PublicUser,
+        permissions: &PermissionResolver<'_>,
+    ) -> Result<(), Error<Null>> {
+        Policy::rule(user.is_admin())
+            .or(
+                has_project_permission(Permission::CommentCreate, project, &user, permissions)
+                    .await?,
+            )
+            .unauthorized("Not authorized to comment on this project")
+    }
+
+    /// The author of the comment
+    pub fn comments_edit(user: &PublicUser, comment: &Comment) -> Result<(), Error<Null>> {
+        Policy::rule(comment.author == Some(user.id))
+            .unauthorized("Only the author can edit a comment")
+    }
+
+    /// [`Admin`](crate::models::users::UserRole::Admin) or the author of the comment
+    pub fn comments_remove(user: &PublicUser, comment: &Comment) -> Result<(), Error<Null>> {
+        Policy::rule(user.is_admin())
+            .or(comment.author == Some(user.id))
+            .unauthorized("Only the author can delete a comment")
+    }
+}
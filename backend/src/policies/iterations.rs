@@ -0,0 +1,85 @@
+use uuid::Uuid;
+
+use crate::{
+    api::{Error, Null},
+    models::{
+        projects::ProjectWithMembers, roles::Permission, users::PublicUser,
+        workspaces::WorkspaceWithMembers,
+    },
+    policies::projects::has_project_permission,
+};
+
+use super::{permissions::PermissionResolver, Policy};
+
+/// ITERATION PERMISSIONS:
+///
+/// 1. Milestones and sprints: R -> Anyone who can view the project, which includes the burndown
+/// 2. Milestones and sprints: C, U, D -> `project.update` / Admin, which includes starting and
+///    closing them
+/// 3. Deliverables: C -> `task.create` / Admin
+/// 4. Deliverables: U -> `task.update` / Admin
+/// 5. Deliverables: D -> `task.delete` / Admin
+///
+/// Planning the iterations is part of running the project, while their deliverables are work
+/// items that follow the permissions of tasks.
+impl Policy {
+    /// [`Admin`](crate::models::users::UserRole::Admin), workspace member or guest of the project
+    pub fn iterations_view(
+        user: &PublicUser,
+        workspace_with_members: &WorkspaceWithMembers,
+        project_with_members: &ProjectWithMembers,
+    ) -> Result<(), Error<Null>> {
+        Policy::projects_view(user, workspace_with_members, project_with_members)
+    }
+
+    /// [`Admin`](crate::models::users::UserRole::Admin) or
+    /// [`project.update`](Permission::ProjectUpdate)
+    pub async fn iterations_manage(
+        project: Uuid,
+        user: PublicUser,
+        permissions: &PermissionResolver<'_>,
+    ) -> Result<(), Error<Null>> {
+        Policy::rule(user.is_admin())
+            .or(
+                has_project_permission(Permission::ProjectUpdate, project, &user, permissions)
+                    .await?,
+            )
+            .unauthorized("Not authorized to plan the milestones and sprints of this project")
+    }
+
+    /// [`Admin`](crate::models::users::UserRole::Admin) or
+    /// [`task.create`](Permission::TaskCreate)
+    pub async fn deliverables_create(
+        project: Uuid,
+        user: PublicUser,
+        permissions: &PermissionResolver<'_>,
+    ) -> Result<(), Error<Null>> {
+        Policy::rule(user.is_admin())
+            .or(has_project_permission(Permission::TaskCreate, project, &user, permissions).await?)
+            .unauthorized("Not authorized to add deliverables in this project")
+    }
+
+    /// [`Admin`](crate::models::users::UserRole::Admin) or
+    /// [`task.update`](Permission::TaskUpdate)
+    pub async fn deliverables_update(
+        project: Uuid,
+        user: PublicUser,
+        permissions: &PermissionResolver<'_>,
+    ) -> Result<(), Error<Null>> {
+        Policy::rule(user.is_admin())
+            .or(has_project_permission(Permission::TaskUpdate, project, &user, permissions).await?)
+            .unauthorized("Not authorized to update deliverables in this project")
+    }
+
+    /// [`Admin`](crate::models::users::UserRole::Admin) or
+    /// [`task.delete`](Permission::TaskDelete)
+    pub async fn deliverables_remove(
+        project: Uuid,
+        user: PublicUser,
+        permissions: &PermissionResolver<'_>,
+    ) -> Result<(), Error<Null>> {
+        Policy::rule(user.is_admin())
+            .or(has_project_permission(Permission::TaskDelete, project, &user, permissions).await?)
+            .unauthorized("Not authorized to delete deliverables in this project")
+    }
+}
@@ -0,0 +1,70 @@
+use uuid::Uuid;
+
+use crate::{
+    api::{Error, Null},
+    models::{
+        projects::ProjectWithMembers, roles::Permission, users::PublicUser,
+        workspaces::WorkspaceWithMembers,
+    },
+    policies::{projects::has_project_permission, workspaces::has_workspace_permission},
+};
+
+use super::{permissions::PermissionResolver, Policy};
+
+/// LABEL PERMISSIONS:
+///
+/// 1. Labels: R -> Member / Admin
+/// 2. Labels: C, U, D -> `label.manage` / Admin
+/// 3. Project labels: R -> Anyone who can view the project
+/// 4. Project labels: C, D -> `project.update` / Admin
+///
+/// Labels belong to the workspace, which makes them the same for all of its projects; tagging a
+/// project with one is part of updating the project.
+impl Policy {
+    /// [`Admin`](crate::models::users::UserRole::Admin) or member of the workspace
+    pub fn labels_view(
+        user: &PublicUser,
+        workspace_with_members: &WorkspaceWithMembers,
+    ) -> Result<(), Error<Null>> {
+        Policy::workspaces_view(user, workspace_with_members)
+    }
+
+    /// [`Admin`](crate::models::users::UserRole::Admin) or
+    /// [`label.manage`](Permission::LabelManage)
+    pub async fn labels_manage(
+        workspace: Uuid,
+        user: PublicUser,
+        permissions: &PermissionResolver<'_>,
+    ) -> Result<(), Error<Null>> {
+        Policy::rule(user.is_admin())
+            .or(
+                has_workspace_permission(Permission::LabelManage, workspace, &user, permissions)
+                    .await?,
+            )
+            .unauthorized("Not authorized to manage the labels of this workspace")
+    }
+
+    /// [`Admin`](crate::models::users::UserRole::Admin), workspace member or guest of the project
+    pub fn labels_view_project(
+        user: &PublicUser,
+        workspace_with_members: &WorkspaceWithMembers,
+        project_with_members: &ProjectWithMembers,
+    ) -> Result<(), Error<Null>> {
+        Policy::projects_view(user, workspace_with_members, project_with_members)
+    }
+
+    /// [`Admin`](crate::models::users::UserRole::Admin) or
+    /// [`project.update`](Permission::ProjectUpdate)
+    pub async fn labels_tag_project(
+        project: Uuid,
+        user: PublicUser,
+        permissions: &PermissionResolver<'_>,
+    ) -> Result<(), Error<Null>> {
+        Policy::rule(user.is_admin())
+            .or(
+                has_project_permission(Permission::ProjectUpdate, project, &user, permissions)
+                    .await?,
+            )
+            .unauthorized("Not authorized to tag this project")
+    }
+}
@@ -2,7 +2,15 @@ use std::collections::VecDeque;
 
 use crate::api::{ApiResponse, Error, Null};
 
+pub mod access_tokens;
+pub mod boards;
+pub mod capabilities;
+pub mod comments;
+pub mod iterations;
+pub mod labels;
+pub mod permissions;
 pub mod projects;
+pub mod tasks;
 pub mod users;
 pub mod workspaces;
 
@@ -0,0 +1,74 @@
+use std::collections::HashSet;
+
+use rocket::State;
+use uuid::Uuid;
+
+use crate::{
+    api::{Error, Null},
+    cache::RedisMutex,
+    database::Db,
+    models::roles::Permission,
+    routes::{projects::get_project_with_members, workspaces::get_workspace_with_members},
+};
+
+/// Resolves the permissions of a user in a workspace or project on the server.
+///
+/// The permissions follow from the roles of the members of the workspace or project, which are
+/// read from the cache and fall back to the database. As the caches are refreshed whenever the
+/// members or the custom roles change, a changed role applies to the very next request.
+pub struct PermissionResolver<'a> {
+    db: &'a Db,
+    redis: &'a State<RedisMutex>,
+}
+
+impl<'a> PermissionResolver<'a> {
+    pub fn new(db: &'a Db, redis: &'a State<RedisMutex>) -> Self {
+        PermissionResolver { db, redis }
+    }
+
+    /// The permissions of the user in the workspace, from the custom role or else the
+    /// [`WorkspaceRole`](crate::models::workspaces::WorkspaceRole). Empty if the user is not a
+    /// member of the workspace.
+    pub async fn workspace_permissions(
+        &self,
+        user: Uuid,
+        workspace: Uuid,
+    ) -> Result<HashSet<Permission>, Error<Null>> {
+        let workspace_with_members =
+            get_workspace_with_members(workspace, self.db, self.redis).await?;
+
+        Ok(workspace_with_members
+            .member_permissions(user)
+            .unwrap_or_default())
+    }
+
+    /// The permissions of the user in the project: those of the
+    /// [`ProjectRole`](crate::models::projects::ProjectRole), together with those the user has
+    /// in the workspace of the project. A guest never gets
+    /// [`project.member.manage`](Permission::ProjectMemberManage).
+    pub async fn project_permissions(
+        &self,
+        user: Uuid,
+        project: Uuid,
+    ) -> Result<HashSet<Permission>, Error<Null>> {
+        let project_with_members = get_project_with_members(project, self.db, self.redis).await?;
+
+        let mut permissions = self
+            .workspace_permissions(user, project_with_members.project.workspace)
+            .await?;
+
+        if let Some(member) = project_with_members
+            .members
+            .iter()
+            .find(|member| member.user.id == user)
+        {
+            permissions.extend(member.role.permissions());
+
+            if member.guest {
+                permissions.remove(&Permission::ProjectMemberManage);
+            }
+        }
+
+        Ok(permissions)
+    }
+}
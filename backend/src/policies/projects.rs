@@ -1,108 +1,120 @@
-use rocket::http::CookieJar;
 use uuid::Uuid;
 
 use crate::{
     api::{Error, Null},
-    cookies,
     models::{
-        projects::ProjectRole,
-        users::PublicUser,
-        workspaces::{WorkspaceRole, WorkspaceWithMembers},
+        projects::ProjectWithMembers, roles::Permission, users::PublicUser,
+        workspaces::WorkspaceWithMembers,
     },
-    policies::workspaces::{user_is_member_of_workspace, workspace_role_is_at_least},
+    policies::workspaces::{has_workspace_permission, user_is_member_of_workspace},
 };
 
-use super::Policy;
+use super::{permissions::PermissionResolver, Policy};
 
 /// PROJECT PERMISSIONS:
 ///
-/// 1. Projects: C -> WorkspaceRole Master
-/// 2. Projects: R -> ProjectRole Viewer / Admin
-/// 3. Projects: U -> ProjectRole Contributer / Admin
-/// 4. Project members: U -> ProjectRole Manager / Admin
-/// 5. Project members: D -> WorkspaceRole Manager / Admin
+/// 1. Projects: C -> `project.create` in the workspace / Admin
+/// 2. Projects: R -> Workspace member / Guest of the project / Admin
+/// 3. Projects: U -> `project.update` / Admin
+/// 4. Projects: D -> `project.delete` in the workspace / Admin
+/// 5. Project members: U, D -> `project.member.manage` / Admin
+///
+/// The permissions in a project are those of the
+/// [`ProjectRole`](crate::models::projects::ProjectRole) together with those in the workspace.
+/// Guests are members of the project only; they never manage its members.
 impl Policy {
-    /// [`Admin`](crate::models::users::UserRole::Admin) or
-    /// [`Viewer`](ProjectRole::Viewer)+
+    /// [`Admin`](crate::models::users::UserRole::Admin), workspace member or guest of the project
     pub fn projects_view(
         user: &PublicUser,
         workspace_with_members: &WorkspaceWithMembers,
+        project_with_members: &ProjectWithMembers,
     ) -> Result<(), Error<Null>> {
         Policy::rule(user.is_admin())
             .or(user_is_member_of_workspace(user, workspace_with_members))
+            .or(user_is_guest_of_project(user, project_with_members))
             .not_found("Project not found")
     }
 
     /// [`Admin`](crate::models::users::UserRole::Admin) or
-    /// [`Manager`](WorkspaceRole::Manager)+
-    pub fn projects_create(
+    /// [`project.create`](Permission::ProjectCreate)
+    pub async fn projects_create(
         workspace: Uuid,
         user: PublicUser,
-        cookies: &CookieJar<'_>,
+        permissions: &PermissionResolver<'_>,
     ) -> Result<(), Error<Null>> {
         Policy::rule(user.is_admin())
-            .or(workspace_role_is_at_least(
-                WorkspaceRole::Manager,
-                workspace,
-                cookies,
-            )?)
+            .or(
+                has_workspace_permission(Permission::ProjectCreate, workspace, &user, permissions)
+                    .await?,
+            )
             .unauthorized("Not authorized to create new projects in this workspace")
     }
 
     /// [`Admin`](crate::models::users::UserRole::Admin) or
-    /// [`Contributor`](ProjectRole::Contributor)+
-    pub fn projects_update_info(
+    /// [`project.update`](Permission::ProjectUpdate)
+    pub async fn projects_update_info(
         project: Uuid,
         user: PublicUser,
-        cookies: &CookieJar<'_>,
+        permissions: &PermissionResolver<'_>,
     ) -> Result<(), Error<Null>> {
         Policy::rule(user.is_admin())
-            .or(project_role_is_at_least(
-                ProjectRole::Contributor,
-                project,
-                cookies,
-            )?)
+            .or(
+                has_project_permission(Permission::ProjectUpdate, project, &user, permissions)
+                    .await?,
+            )
             .unauthorized("Not authorized to update project information")
     }
 
     /// [`Admin`](crate::models::users::UserRole::Admin) or
-    /// [`Manager`](WorkspaceRole::Manager)+
-    pub fn projects_remove(
+    /// [`project.delete`](Permission::ProjectDelete)
+    pub async fn projects_remove(
         workspace: Uuid,
         user: PublicUser,
-        cookies: &CookieJar<'_>,
+        permissions: &PermissionResolver<'_>,
     ) -> Result<(), Error<Null>> {
         Policy::rule(user.is_admin())
-            .or(workspace_role_is_at_least(
-                WorkspaceRole::Owner,
-                workspace,
-                cookies,
-            )?)
+            .or(
+                has_workspace_permission(Permission::ProjectDelete, workspace, &user, permissions)
+                    .await?,
+            )
             .unauthorized("Not authorized to remove project")
     }
 
     /// [`Admin`](crate::models::users::UserRole::Admin) or
-    /// [`Master`](ProjectRole::Master)+
-    pub fn project_update_members(
+    /// [`project.member.manage`](Permission::ProjectMemberManage)
+    pub async fn project_update_members(
         project: Uuid,
         user: PublicUser,
-        cookies: &CookieJar<'_>,
+        permissions: &PermissionResolver<'_>,
     ) -> Result<(), Error<Null>> {
         Policy::rule(user.is_admin())
-            .or(project_role_is_at_least(
-                ProjectRole::Master,
+            .or(has_project_permission(
+                Permission::ProjectMemberManage,
                 project,
-                cookies,
-            )?)
+                &user,
+                permissions,
+            )
+            .await?)
             .unauthorized("Not authorized to add members")
     }
 }
 
-pub fn project_role_is_at_least(
-    project_role: ProjectRole,
+pub async fn has_project_permission(
+    permission: Permission,
     project: Uuid,
-    cookies: &CookieJar<'_>,
+    user: &PublicUser,
+    permissions: &PermissionResolver<'_>,
 ) -> Result<bool, Error<Null>> {
-    let actual = cookies::permissions::get_project_permission(project, cookies).unwrap_or(-1);
-    Ok(actual >= i16::from(project_role))
+    let actual = permissions.project_permissions(user.id, project).await?;
+    Ok(actual.contains(&permission))
+}
+
+pub fn user_is_guest_of_project(
+    user: &PublicUser,
+    project_with_members: &ProjectWithMembers,
+) -> bool {
+    project_with_members
+        .members
+        .iter()
+        .any(|member| member.guest && member.user.id == user.id)
 }
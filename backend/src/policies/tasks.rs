@@ -0,0 +1,68 @@
+use uuid::Uuid;
+
+use crate::{
+    api::{Error, Null},
+    models::{
+        projects::ProjectWithMembers, roles::Permission, users::PublicUser,
+        workspaces::WorkspaceWithMembers,
+    },
+    policies::projects::has_project_permission,
+};
+
+use super::{permissions::PermissionResolver, Policy};
+
+/// TASK PERMISSIONS:
+///
+/// 1. Tasks: C -> `task.create` / Admin
+/// 2. Tasks: R -> Anyone who can view the project
+/// 3. Tasks: U -> `task.update` / Admin
+/// 4. Tasks: D -> `task.delete` / Admin
+///
+/// Of the built-in project roles, owners and masters do everything, contributors create and edit
+/// tasks, while stakeholders and viewers only read them.
+impl Policy {
+    /// [`Admin`](crate::models::users::UserRole::Admin), workspace member or guest of the project
+    pub fn tasks_view(
+        user: &PublicUser,
+        workspace_with_members: &WorkspaceWithMembers,
+        project_with_members: &ProjectWithMembers,
+    ) -> Result<(), Error<Null>> {
+        Policy::projects_view(user, workspace_with_members, project_with_members)
+    }
+
+    /// [`Admin`](crate::models::users::UserRole::Admin) or
+    /// [`task.create`](Permission::TaskCreate)
+    pub async fn tasks_create(
+        project: Uuid,
+        user: PublicUser,
+        permissions: &PermissionResolver<'_>,
+    ) -> Result<(), Error<Null>> {
+        Policy::rule(user.is_admin())
+            .or(has_project_permission(Permission::TaskCreate, project, &user, permissions).await?)
+            .unauthorized("Not authorized to create tasks in this project")
+    }
+
+    /// [`Admin`](crate::models::users::UserRole::Admin) or
+    /// [`task.update`](Permission::TaskUpdate)
+    pub async fn tasks_update(
+        project: Uuid,
+        user: PublicUser,
+        permissions: &PermissionResolver<'_>,
+    ) -> Result<(), Error<Null>> {
+        Policy::rule(user.is_admin())
+            .or(has_project_permission(Permission::TaskUpdate, project, &user, permissions).await?)
+            .unauthorized("Not authorized to update tasks in this project")
+    }
+
+    /// [`Admin`](crate::models::users::UserRole::Admin) or
+    /// [`task.delete`](Permission::TaskDelete)
+    pub async fn tasks_remove(
+        project: Uuid,
+        user: PublicUser,
+        permissions: &PermissionResolver<'_>,
+    ) -> Result<(), Error<Null>> {
+        Policy::rule(user.is_admin())
+            .or(has_project_permission(Permission::TaskDelete, project, &user, permissions).await?)
+            .unauthorized("Not authorized to remove tasks from this project")
+    }
+}
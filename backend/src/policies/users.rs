@@ -3,7 +3,7 @@ use uuid::Uuid;
 use crate::{
     api::{Error, Null},
     database::{self, Db},
-    models::users::{PublicUser, UserRole},
+    models::users::{PublicUser, User, UserRole, UserStatus},
 };
 
 use super::Policy;
@@ -15,9 +15,10 @@ impl Policy {
             .or(user.id == id)
             .not_found("User not found");
 
-        // If user is not admin or self; return only if the user is in a shared workspace
+        // If user is not admin or self; return only if the user is in a shared workspace, or in a
+        // shared project for guests
         // This call if run independently to prevent an unnecessary database transaction
-        if base_policy.is_err() && user_is_in_same_workspace(db, user.id, id).await {
+        if base_policy.is_err() && user_is_in_same_workspace_or_project(db, user.id, id).await {
             return Ok(());
         }
 
@@ -41,7 +42,7 @@ impl Policy {
     }
 
     /// Policy for updating the role of a user
-    pub fn users_set_role(user: &PublicUser, role: i16) -> Result<(), Error<Null>> {
+    pub fn users_set_role(user: &PublicUser, role: UserRole) -> Result<(), Error<Null>> {
         // User is at least Manager
         Policy::rule(user.is_at_least(UserRole::Manager))
             // And cannot set a role higher as self
@@ -49,6 +50,42 @@ impl Policy {
             .unauthorized("No permission to set user role")
     }
 
+    /// Policy for unlocking a user after too many failed logins
+    pub fn users_unlock(user: &PublicUser) -> Result<(), Error<Null>> {
+        // User is admin
+        Policy::rule(user.is_admin()).unauthorized("No permission to unlock user")
+    }
+
+    /// Policy for listing and revoking the sessions of any user
+    pub fn users_manage_sessions(user: &PublicUser) -> Result<(), Error<Null>> {
+        // User is admin
+        Policy::rule(user.is_admin()).unauthorized("No permission to manage sessions")
+    }
+
+    /// Policy for acting as another user
+    pub fn users_impersonate(user: &PublicUser, target: &User) -> Result<(), Error<Null>> {
+        // User is admin
+        Policy::rule(user.is_admin())
+            // And cannot act as self or as another admin
+            .and(user.id != target.id)
+            .and(target.role != UserRole::Admin)
+            // And the target can log in
+            .and(target.status == UserStatus::Active)
+            .unauthorized("No permission to impersonate user")
+    }
+
+    /// Policy for reading the impersonation audit trail of a user
+    pub fn users_view_impersonation_logs(user: &PublicUser) -> Result<(), Error<Null>> {
+        // User is admin
+        Policy::rule(user.is_admin()).unauthorized("No permission to view impersonation logs")
+    }
+
+    /// Policy for inspecting the permissions of another user, and why they have them
+    pub fn users_explain_permissions(user: &PublicUser) -> Result<(), Error<Null>> {
+        // User is admin
+        Policy::rule(user.is_admin()).unauthorized("No permission to explain permissions")
+    }
+
     /// Policy for deleting a user from the database
     pub fn users_delete(user: &PublicUser, id: Uuid) -> Result<(), Error<Null>> {
         // User is admin
@@ -59,9 +96,16 @@ impl Policy {
     }
 }
 
-async fn user_is_in_same_workspace(db: &Db, searching_user: Uuid, searched_user: Uuid) -> bool {
+async fn user_is_in_same_workspace_or_project(
+    db: &Db,
+    searching_user: Uuid,
+    searched_user: Uuid,
+) -> bool {
     match database::users::get_user_ids_in_same_workspaces(db, searching_user).await {
-        Ok(users) => users.contains(&searched_user),
-        Err(_) => false,
+        Ok(users) if users.contains(&searched_user) => true,
+        _ => match database::users::get_user_ids_in_same_projects(db, searching_user).await {
+            Ok(users) => users.contains(&searched_user),
+            Err(_) => false,
+        },
     }
 }
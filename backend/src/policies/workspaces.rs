@@ -1,29 +1,32 @@
-use rocket::http::CookieJar;
+use std::collections::HashSet;
+
 use uuid::Uuid;
 
 use crate::{
     api::{Error, Null},
-    cookies,
     models::{
+        roles::Permission,
         users::{PublicUser, UserRole},
-        workspaces::{WorkspaceRole, WorkspaceWithMembers},
+        workspaces::WorkspaceWithMembers,
     },
 };
 
-use super::Policy;
+use super::{permissions::PermissionResolver, Policy};
 
 /// WORKSPACE PERMISSIONS:
 ///
 /// 1. Workspaces: C -> UserRole Manager
-/// 2. Workspaces: R -> WorkspaceRole Viewer / Admin
-/// 3. Workspaces: U -> WorkspaceRole Contributer / Admin
-/// 4. Workspace members: U -> WorkspaceRole Manager / Admin
-/// 5. Workspace members: D -> WorkspaceRole Owner / Admin
+/// 2. Workspaces: R -> Member / Admin
+/// 3. Workspaces: U -> `workspace.update` / Admin
+/// 4. Workspaces: D -> `workspace.delete` / Admin
+/// 5. Workspace members: C -> `member.invite` / Admin
+/// 6. Workspace members: U, D -> `member.manage` / Admin
+/// 7. Custom roles: C, U, D -> `role.manage` / Admin
 ///
-/// Missing: WorkspaceRole Stakeholder; review functionality
+/// The built-in [`WorkspaceRole`](crate::models::workspaces::WorkspaceRole)s are presets of these
+/// permissions. Nobody but an admin can grant permissions they don't have themselves.
 impl Policy {
-    /// [`Admin`](crate::models::users::UserRole::Admin) or
-    /// [`Viewer`](WorkspaceRole::Viewer)+
+    /// [`Admin`](crate::models::users::UserRole::Admin) or member
     pub fn workspaces_view(
         user: &PublicUser,
         workspace_with_members: &WorkspaceWithMembers,
@@ -40,61 +43,121 @@ impl Policy {
     }
 
     /// [`Admin`](crate::models::users::UserRole::Admin) or
-    /// [`Contributor`](WorkspaceRole::Contributor)+
-    pub fn workspaces_update_info(
+    /// [`workspace.update`](Permission::WorkspaceUpdate)
+    pub async fn workspaces_update_info(
         workspace: Uuid,
         user: PublicUser,
-        cookies: &CookieJar<'_>,
+        permissions: &PermissionResolver<'_>,
     ) -> Result<(), Error<Null>> {
         Policy::rule(user.is_admin())
-            .or(workspace_role_is_at_least(
-                WorkspaceRole::Contributor,
+            .or(has_workspace_permission(
+                Permission::WorkspaceUpdate,
                 workspace,
-                cookies,
-            )?)
+                &user,
+                permissions,
+            )
+            .await?)
             .unauthorized("Not authorized to update workspace information")
     }
 
     /// [`Admin`](crate::models::users::UserRole::Admin) or
-    /// [`Manager`](WorkspaceRole::Manager)+
-    pub fn workspaces_update_members(
+    /// [`member.invite`](Permission::MemberInvite)
+    pub async fn workspaces_invite_members(
         workspace: Uuid,
         user: PublicUser,
-        cookies: &CookieJar<'_>,
+        permissions: &PermissionResolver<'_>,
     ) -> Result<(), Error<Null>> {
         Policy::rule(user.is_admin())
-            .or(workspace_role_is_at_least(
-                WorkspaceRole::Manager,
-                workspace,
-                cookies,
-            )?)
+            .or(
+                has_workspace_permission(Permission::MemberInvite, workspace, &user, permissions)
+                    .await?,
+            )
+            .unauthorized("Not authorized to invite members")
+    }
+
+    /// [`Admin`](crate::models::users::UserRole::Admin) or
+    /// [`member.manage`](Permission::MemberManage)
+    pub async fn workspaces_update_members(
+        workspace: Uuid,
+        user: PublicUser,
+        permissions: &PermissionResolver<'_>,
+    ) -> Result<(), Error<Null>> {
+        Policy::rule(user.is_admin())
+            .or(
+                has_workspace_permission(Permission::MemberManage, workspace, &user, permissions)
+                    .await?,
+            )
             .unauthorized("Not authorized to edit members")
     }
 
     /// [`Admin`](crate::models::users::UserRole::Admin) or
-    /// [`Owner`](WorkspaceRole::Owner)
-    pub fn workspaces_remove(
+    /// [`member.manage`](Permission::MemberManage), with every permission the member has now
+    /// and will have with the new role
+    pub async fn workspaces_assign_role(
         workspace: Uuid,
+        member: Uuid,
+        granted: &HashSet<Permission>,
         user: PublicUser,
-        cookies: &CookieJar<'_>,
+        permissions: &PermissionResolver<'_>,
     ) -> Result<(), Error<Null>> {
+        let own = permissions
+            .workspace_permissions(user.id, workspace)
+            .await?;
+        let current = permissions.workspace_permissions(member, workspace).await?;
+
         Policy::rule(user.is_admin())
-            .or(workspace_role_is_at_least(
-                WorkspaceRole::Owner,
+            .or(own.contains(&Permission::MemberManage)
+                && own.is_superset(granted)
+                && own.is_superset(&current))
+            .unauthorized("Not authorized to assign this role")
+    }
+
+    /// [`Admin`](crate::models::users::UserRole::Admin) or
+    /// [`role.manage`](Permission::RoleManage), with every permission of the role
+    pub async fn workspaces_manage_roles(
+        workspace: Uuid,
+        granted: &HashSet<Permission>,
+        user: PublicUser,
+        permissions: &PermissionResolver<'_>,
+    ) -> Result<(), Error<Null>> {
+        let own = permissions
+            .workspace_permissions(user.id, workspace)
+            .await?;
+
+        Policy::rule(user.is_admin())
+            .or(own.contains(&Permission::RoleManage) && own.is_superset(granted))
+            .unauthorized("Not authorized to manage this role")
+    }
+
+    /// [`Admin`](crate::models::users::UserRole::Admin) or
+    /// [`workspace.delete`](Permission::WorkspaceDelete)
+    pub async fn workspaces_remove(
+        workspace: Uuid,
+        user: PublicUser,
+        permissions: &PermissionResolver<'_>,
+    ) -> Result<(), Error<Null>> {
+        Policy::rule(user.is_admin())
+            .or(has_workspace_permission(
+                Permission::WorkspaceDelete,
                 workspace,
-                cookies,
-            )?)
+                &user,
+                permissions,
+            )
+            .await?)
             .unauthorized("Not authorized to remove workspace")
     }
 }
 
-pub fn workspace_role_is_at_least(
-    workspace_role: WorkspaceRole,
+pub async fn has_workspace_permission(
+    permission: Permission,
     workspace: Uuid,
-    cookies: &CookieJar<'_>,
+    user: &PublicUser,
+    permissions: &PermissionResolver<'_>,
 ) -> Result<bool, Error<Null>> {
-    let actual = cookies::permissions::get_workspace_permission(workspace, cookies).unwrap_or(-1);
-    Ok(actual >= i16::from(workspace_role))
+    let actual = permissions
+        .workspace_permissions(user.id, workspace)
+        .await?;
+    Ok(actual.contains(&permission))
 }
 
 pub fn user_is_member_of_workspace(
@@ -2,10 +2,14 @@ use rocket::State;
 use uuid::Uuid;
 
 use crate::{
-    api::{Error, Null},
+    api::{ApiResponse, Error, Null},
     cache::{self, RedisMutex},
     database::{self, Db},
-    models::{projects::ProjectWithMembers, workspaces::WorkspaceWithMembers},
+    models::{
+        iterations::{Iteration, IterationState},
+        projects::ProjectWithMembers,
+        workspaces::WorkspaceWithMembers,
+    },
     routes::workspaces::get_workspace_with_members,
 };
 
@@ -18,10 +22,47 @@ pub fn routes() -> Vec<rocket::Route> {
     routes![
         get::get_paginated_projects,  // GET:     /projects?<workspace>&<user>
         get::get_project_by_id,       // GET:     /projects/<id>
+        get::get_project_permissions, // GET:     /projects/<id>/permissions?<user>&<explain>
         post::add_members_to_project, // POST:    /projects/<id>/add-members
         delete::delete_project_by_id, // DELETE:  /projects/<id>/delete
         delete::remove_member_from_project, // DELETE:  /projects/<id>/remove-member/<member>
         put::update_project,          // PUT:     /projects/<id>/update
+        get::get_paginated_tasks, // GET:     /projects/<id>/tasks?<status>&<priority>&<assignee>
+        post::create_task,        // POST:    /projects/<id>/tasks
+        get::get_task_by_id,      // GET:     /projects/<id>/tasks/<task>
+        put::update_task,         // PUT:     /projects/<id>/tasks/<task>
+        delete::delete_task,      // DELETE:  /projects/<id>/tasks/<task>
+        get::get_project_boards,  // GET:     /projects/<id>/boards
+        post::create_board,       // POST:    /projects/<id>/boards
+        get::get_board_by_id,     // GET:     /projects/<id>/boards/<board>
+        put::update_board,        // PUT:     /projects/<id>/boards/<board>
+        delete::delete_board,     // DELETE:  /projects/<id>/boards/<board>
+        post::add_board_column,   // POST:    /projects/<id>/boards/<board>/columns
+        put::update_board_column, // PUT:     /projects/<id>/boards/<board>/columns/<column>
+        post::move_board_column,  // POST:    /projects/<id>/boards/<board>/columns/<column>/move
+        delete::delete_board_column, // DELETE:  /projects/<id>/boards/<board>/columns/<column>
+        post::create_card,        // POST:    /projects/<id>/boards/<board>/cards
+        put::update_card,         // PUT:     /projects/<id>/boards/<board>/cards/<card>
+        post::move_card,          // POST:    /projects/<id>/boards/<board>/cards/<card>/move
+        delete::delete_card,      // DELETE:  /projects/<id>/boards/<board>/cards/<card>
+        get::get_project_comments, // GET:     /projects/<id>/comments
+        post::add_project_comment, // POST:    /projects/<id>/comments
+        put::edit_project_comment, // PUT:     /projects/<id>/comments/<comment>
+        delete::delete_project_comment, // DELETE:  /projects/<id>/comments/<comment>
+        get::get_project_labels,  // GET:     /projects/<id>/labels
+        put::attach_project_label, // PUT:     /projects/<id>/labels/<label>
+        delete::detach_project_label, // DELETE:  /projects/<id>/labels/<label>
+        get::get_project_iterations, // GET:     /projects/<id>/iterations?<kind>&<state>
+        post::create_iteration,   // POST:    /projects/<id>/iterations
+        get::get_iteration_by_id, // GET:     /projects/<id>/iterations/<iteration>
+        put::update_iteration,    // PUT:     /projects/<id>/iterations/<iteration>
+        delete::delete_iteration, // DELETE:  /projects/<id>/iterations/<iteration>
+        post::start_iteration,    // POST:    /projects/<id>/iterations/<iteration>/start
+        post::close_iteration,    // POST:    /projects/<id>/iterations/<iteration>/close
+        get::get_iteration_burndown, // GET:     /projects/<id>/iterations/<iteration>/burndown
+        post::add_deliverable,    // POST:    /projects/<id>/iterations/<iteration>/deliverables
+        put::update_deliverable, // PUT:     /projects/<id>/iterations/<iteration>/deliverables/<deliverable>
+        delete::delete_deliverable, // DELETE:  /projects/<id>/iterations/<iteration>/deliverables/<deliverable>
     ]
 }
 
@@ -50,7 +91,7 @@ pub async fn get_workspace_and_project(
     Ok((workspace_with_members, project_with_members))
 }
 
-async fn get_project_with_members(
+pub async fn get_project_with_members(
     id: Uuid,
     db: &Db,
     redis: &State<RedisMutex>,
@@ -70,3 +111,41 @@ async fn get_project_with_members(
         }
     })
 }
+
+/// Tasks and cards can only be assigned to members of the project.
+pub fn validate_assignee(
+    project_with_members: &ProjectWithMembers,
+    assignee: Option<Uuid>,
+) -> Result<(), Error<Null>> {
+    match assignee {
+        Some(assignee)
+            if !project_with_members
+                .members
+                .iter()
+                .any(|member| member.user.id == assignee) =>
+        {
+            Err(ApiResponse::bad_request(format!(
+                "User '{assignee}' is not a member of the project"
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// The iteration of the project, as long as it isn't closed; the milestones and sprints that are
+/// over, and their deliverables, no longer change.
+pub async fn get_changeable_iteration(
+    db: &Db,
+    project: Uuid,
+    iteration: Uuid,
+) -> Result<Iteration, Error<Null>> {
+    let iteration = database::iterations::get_iteration(db, project, iteration).await?;
+
+    match iteration.state {
+        IterationState::Closed => Err(ApiResponse::bad_request(format!(
+            "Iteration '{}' is closed",
+            iteration.name
+        ))),
+        _ => Ok(iteration),
+    }
+}
@@ -1,4 +1,5 @@
-use rocket::{http::CookieJar, State};
+use chrono::Utc;
+use rocket::State;
 use uuid::Uuid;
 
 use crate::{
@@ -6,9 +7,9 @@ use crate::{
     auth::JwtGuard,
     cache::{self, RedisMutex},
     database::{self, Db},
-    models::projects::ProjectWithMembers,
-    policies::Policy,
-    routes::projects::get_workspace_and_project,
+    models::{comments::Thread, projects::ProjectWithMembers},
+    policies::{permissions::PermissionResolver, Policy},
+    routes::projects::{get_changeable_iteration, get_workspace_and_project},
 };
 
 #[delete("/<id>/delete")]
@@ -16,9 +17,11 @@ pub async fn delete_project_by_id(
     id: Uuid,
     guard: JwtGuard,
     db: Db,
-    cookies: &CookieJar<'_>,
     redis: &State<RedisMutex>,
 ) -> Result<Success<Null>, Error<Null>> {
+    // Not in the name of someone else
+    guard.deny_impersonation()?;
+
     let user = guard.get_user();
 
     // Get the workspace ID needed to validate the policy
@@ -27,7 +30,7 @@ pub async fn delete_project_by_id(
         .map(|(w, _)| w.workspace.id)?;
 
     // Run the policy to remove a project
-    Policy::projects_remove(workspace_id, user, cookies)?;
+    Policy::projects_remove(workspace_id, user, &PermissionResolver::new(&db, redis)).await?;
 
     // Remove the project from the database (relevant records in different tables will be cascaded by Postgres)
     let project = database::projects::remove_project(&db, id).await?;
@@ -48,10 +51,19 @@ pub async fn remove_member_from_project(
     member: Uuid,
     guard: JwtGuard,
     db: Db,
-    cookies: &CookieJar<'_>,
     redis: &State<RedisMutex>,
 ) -> Result<Success<ProjectWithMembers>, Error<Null>> {
-    Policy::workspaces_update_members(id, guard.get_user(), cookies)?;
+    // Project members are managed from the workspace the project is a part of
+    let workspace_id = get_workspace_and_project(id, &db, redis)
+        .await
+        .map(|(w, _)| w.workspace.id)?;
+
+    Policy::workspaces_update_members(
+        workspace_id,
+        guard.get_user(),
+        &PermissionResolver::new(&db, redis),
+    )
+    .await?;
 
     // Remove the member from the project
     let project_with_members =
@@ -66,3 +78,175 @@ pub async fn remove_member_from_project(
         Some(project_with_members),
     ))
 }
+
+#[delete("/<id>/tasks/<task>")]
+pub async fn delete_task(
+    id: Uuid,
+    task: Uuid,
+    guard: JwtGuard,
+    db: Db,
+    redis: &State<RedisMutex>,
+) -> Result<Success<Null>, Error<Null>> {
+    Policy::tasks_remove(id, guard.get_user(), &PermissionResolver::new(&db, redis)).await?;
+
+    let task = database::tasks::remove_task(&db, id, task).await?;
+
+    Ok(ApiResponse::success(
+        format!("Task '{}' deleted", task.title),
+        None,
+    ))
+}
+
+/// Deletes the board with its columns and cards.
+#[delete("/<id>/boards/<board>")]
+pub async fn delete_board(
+    id: Uuid,
+    board: Uuid,
+    guard: JwtGuard,
+    db: Db,
+    redis: &State<RedisMutex>,
+) -> Result<Success<Null>, Error<Null>> {
+    Policy::boards_manage(id, guard.get_user(), &PermissionResolver::new(&db, redis)).await?;
+
+    let board = database::boards::remove_board(&db, id, board).await?;
+
+    Ok(ApiResponse::success(
+        format!("Board '{}' deleted", board.name),
+        None,
+    ))
+}
+
+/// Deletes the column with its cards.
+#[delete("/<id>/boards/<board>/columns/<column>")]
+pub async fn delete_board_column(
+    id: Uuid,
+    board: Uuid,
+    column: Uuid,
+    guard: JwtGuard,
+    db: Db,
+    redis: &State<RedisMutex>,
+) -> Result<Success<Null>, Error<Null>> {
+    Policy::boards_manage(id, guard.get_user(), &PermissionResolver::new(&db, redis)).await?;
+
+    // The board has to be one of the project
+    database::boards::get_board(&db, id, board).await?;
+    let column = database::boards::remove_column(&db, board, column).await?;
+
+    Ok(ApiResponse::success(
+        format!("Column '{}' deleted", column.name),
+        None,
+    ))
+}
+
+#[delete("/<id>/boards/<board>/cards/<card>")]
+pub async fn delete_card(
+    id: Uuid,
+    board: Uuid,
+    card: Uuid,
+    guard: JwtGuard,
+    db: Db,
+    redis: &State<RedisMutex>,
+) -> Result<Success<Null>, Error<Null>> {
+    Policy::cards_remove(id, guard.get_user(), &PermissionResolver::new(&db, redis)).await?;
+
+    // The board has to be one of the project
+    database::boards::get_board(&db, id, board).await?;
+    let card = database::boards::remove_card(&db, board, card).await?;
+
+    Ok(ApiResponse::success(
+        format!("Card '{}' deleted", card.title),
+        None,
+    ))
+}
+
+/// Deletes a comment, leaving a marker in its place so its replies stay in the thread.
+#[delete("/<id>/comments/<comment>")]
+pub async fn delete_project_comment(
+    id: Uuid,
+    comment: Uuid,
+    guard: JwtGuard,
+    db: Db,
+    redis: &State<RedisMutex>,
+) -> Result<Success<Null>, Error<Null>> {
+    let user = guard.get_user();
+    let (workspace_with_members, project_with_members) =
+        get_workspace_and_project(id, &db, redis).await?;
+
+    Policy::comments_view_project(&user, &workspace_with_members, &project_with_members)?;
+    Policy::comments_write_project(id, user.clone(), &PermissionResolver::new(&db, redis)).await?;
+
+    let thread = Thread {
+        workspace: workspace_with_members.workspace.id,
+        project: Some(id),
+    };
+    let comment = database::comments::get_comment(&db, thread, comment).await?;
+    Policy::comments_remove(&user, &comment)?;
+
+    if comment.deleted_at.is_some() {
+        return Err(ApiResponse::bad_request(
+            "The comment is already deleted".to_string(),
+        ));
+    }
+    database::comments::delete_comment(&db, comment.id, Utc::now().naive_utc()).await?;
+
+    Ok(ApiResponse::success("Comment deleted".to_string(), None))
+}
+
+/// Removes a label from the project, leaving the label itself in the workspace.
+#[delete("/<id>/labels/<label>")]
+pub async fn detach_project_label(
+    id: Uuid,
+    label: Uuid,
+    guard: JwtGuard,
+    db: Db,
+    redis: &State<RedisMutex>,
+) -> Result<Success<Null>, Error<Null>> {
+    Policy::labels_tag_project(id, guard.get_user(), &PermissionResolver::new(&db, redis)).await?;
+
+    database::labels::detach_label(&db, id, label).await?;
+
+    Ok(ApiResponse::success(
+        "Label removed from the project".to_string(),
+        None,
+    ))
+}
+
+/// Deletes a milestone or sprint with its deliverables.
+#[delete("/<id>/iterations/<iteration>")]
+pub async fn delete_iteration(
+    id: Uuid,
+    iteration: Uuid,
+    guard: JwtGuard,
+    db: Db,
+    redis: &State<RedisMutex>,
+) -> Result<Success<Null>, Error<Null>> {
+    Policy::iterations_manage(id, guard.get_user(), &PermissionResolver::new(&db, redis)).await?;
+
+    let iteration = database::iterations::remove_iteration(&db, id, iteration).await?;
+
+    Ok(ApiResponse::success(
+        format!("{} '{}' deleted", iteration.kind, iteration.name),
+        None,
+    ))
+}
+
+#[delete("/<id>/iterations/<iteration>/deliverables/<deliverable>")]
+pub async fn delete_deliverable(
+    id: Uuid,
+    iteration: Uuid,
+    deliverable: Uuid,
+    guard: JwtGuard,
+    db: Db,
+    redis: &State<RedisMutex>,
+) -> Result<Success<Null>, Error<Null>> {
+    Policy::deliverables_remove(id, guard.get_user(), &PermissionResolver::new(&db, redis)).await?;
+
+    let iteration = get_changeable_iteration(&db, id, iteration).await?;
+    let deliverable =
+        database::iterations::remove_deliverable(&db, iteration.id, deliverable).await?;
+
+    Ok(ApiResponse::success(
+        format!("Deliverable '{}' deleted", deliverable.title),
+        None,
+    ))
+}
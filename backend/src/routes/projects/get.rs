@@ -1,21 +1,41 @@
-use rocket::{http::CookieJar, serde::json::Json, State};
+use std::str::FromStr;
+
+use chrono::Utc;
+use rocket::{serde::json::Json, State};
 use uuid::Uuid;
 
 use crate::{
     api::{ApiResponse, Error, Null, Success},
     auth::JwtGuard,
     cache::RedisMutex,
-    cookies,
     database::{
         self,
-        pagination::{records::PaginatedRecords, request::PaginationRequest, sort::ProjectField},
+        pagination::{
+            queries::{projects::LabelFilter, tasks::TaskFilter},
+            records::PaginatedRecords,
+            request::PaginationRequest,
+            sort::{ProjectField, TaskField},
+        },
         Db,
     },
     models::{
+        boards::{Board, BoardWithColumns},
+        comments::{Thread, ThreadedComment},
+        iterations::{
+            burndown, BurndownDay, Iteration, IterationKind, IterationState,
+            IterationWithDeliverables,
+        },
+        labels::{Label, LabelMatch},
+        permissions::EffectivePermissions,
         projects::{Project, ProjectWithMembers},
+        tasks::{Task, TaskPriority, TaskStatus},
         users::UserRole,
     },
-    policies::Policy,
+    policies::{
+        capabilities::{capability_subject, project_capabilities},
+        permissions::PermissionResolver,
+        Policy,
+    },
     routes::{projects::get_workspace_and_project, workspaces::get_workspace_with_members},
 };
 
@@ -25,7 +45,6 @@ pub async fn get_project_by_id(
     id: Uuid,
     guard: JwtGuard,
     db: Db,
-    cookies: &CookieJar<'_>,
     redis: &State<RedisMutex>,
 ) -> Result<Success<ProjectWithMembers>, Error<Null>> {
     let user = guard.get_user();
@@ -35,16 +54,7 @@ pub async fn get_project_by_id(
         get_workspace_and_project(id, &db, redis).await?;
 
     // Run the policy to view a project
-    Policy::projects_view(&user, &workspace_with_members)?;
-
-    // Insert the project permissions in cookies
-    if let Some(member) = project_with_members
-        .members
-        .iter()
-        .find(|m| m.user.id == user.id)
-    {
-        cookies::permissions::insert_project_permission(id, member.role, cookies)?;
-    }
+    Policy::projects_view(&user, &workspace_with_members, &project_with_members)?;
 
     Ok(ApiResponse::success(
         format!(
@@ -56,10 +66,19 @@ pub async fn get_project_by_id(
 }
 
 //Instead of get_paginated_users, maybe browse_users or list_users_paginated — to match REST semantics more intuitively.
-#[get("/?<workspace>&<user>", format = "json", data = "<params>")]
+/// Returns the projects, optionally only those of a workspace or user, or tagged with labels; the
+/// `label` is repeated for every label, and with `label_match=all` a project needs all of them.
+#[get(
+    "/?<workspace>&<user>&<label>&<label_match>",
+    format = "json",
+    data = "<params>"
+)]
+#[allow(clippy::too_many_arguments)]
 pub async fn get_paginated_projects(
     workspace: Option<Uuid>,
     user: Option<Uuid>,
+    label: Vec<Uuid>,
+    label_match: Option<&str>,
     params: Json<PaginationRequest<ProjectField>>,
     guard: JwtGuard,
     db: Db,
@@ -69,7 +88,7 @@ pub async fn get_paginated_projects(
 
     // If no filters are applied and the user is not admin set self as user ID
     let user = match (workspace, user, auth_user.role) {
-        (None, None, role) if role != i16::from(UserRole::Admin) => Some(auth_user.id),
+        (None, None, role) if role != UserRole::Admin => Some(auth_user.id),
         _ => user,
     };
 
@@ -85,8 +104,19 @@ pub async fn get_paginated_projects(
         Policy::users_get(&db, &auth_user, id).await?;
     }
 
+    let label_filter = LabelFilter {
+        labels: label,
+        matching: label_match
+            .map(LabelMatch::from_str)
+            .transpose()
+            .map_err(ApiResponse::bad_request)?
+            .unwrap_or_default(),
+    };
+
     // Return the requested paginated result
-    let page = database::projects::get_projects_paginated(&db, workspace, user, params).await?;
+    let page =
+        database::projects::get_projects_paginated(&db, workspace, user, label_filter, params)
+            .await?;
 
     Ok(ApiResponse::success(
         format!(
@@ -97,3 +127,323 @@ pub async fn get_paginated_projects(
         Some(page),
     ))
 }
+
+/// Returns what a user can do in the project. By default for the current user; admins can ask
+/// for any user and for the reason behind every capability.
+#[get("/<id>/permissions?<user>&<explain>")]
+pub async fn get_project_permissions(
+    id: Uuid,
+    user: Option<Uuid>,
+    explain: Option<bool>,
+    guard: JwtGuard,
+    db: Db,
+    redis: &State<RedisMutex>,
+) -> Result<Success<EffectivePermissions>, Error<Null>> {
+    let explain = explain.unwrap_or(false);
+    let (workspace_with_members, project_with_members) =
+        get_workspace_and_project(id, &db, redis).await?;
+
+    // Return not found if the user can't view the project
+    Policy::projects_view(
+        &guard.get_user(),
+        &workspace_with_members,
+        &project_with_members,
+    )?;
+
+    let subject = capability_subject(&db, guard.get_user(), user, explain).await?;
+    let permissions = project_capabilities(
+        &workspace_with_members,
+        &project_with_members,
+        &subject,
+        &PermissionResolver::new(&db, redis),
+        explain,
+    )
+    .await?;
+
+    Ok(ApiResponse::success(
+        format!("Permissions of '{}'", subject.username),
+        Some(permissions),
+    ))
+}
+
+/// Returns the tasks of the project, optionally only those with a status, priority or assignee.
+#[get(
+    "/<id>/tasks?<status>&<priority>&<assignee>",
+    format = "json",
+    data = "<params>"
+)]
+#[allow(clippy::too_many_arguments)]
+pub async fn get_paginated_tasks(
+    id: Uuid,
+    status: Option<&str>,
+    priority: Option<&str>,
+    assignee: Option<Uuid>,
+    params: Json<PaginationRequest<TaskField>>,
+    guard: JwtGuard,
+    db: Db,
+    redis: &State<RedisMutex>,
+) -> Result<Success<PaginatedRecords<Task>>, Error<Null>> {
+    let (workspace_with_members, project_with_members) =
+        get_workspace_and_project(id, &db, redis).await?;
+
+    // Return not found if the user can't view the project
+    Policy::tasks_view(
+        &guard.get_user(),
+        &workspace_with_members,
+        &project_with_members,
+    )?;
+
+    let filter = TaskFilter {
+        status: status
+            .map(TaskStatus::from_str)
+            .transpose()
+            .map_err(ApiResponse::bad_request)?,
+        priority: priority
+            .map(TaskPriority::from_str)
+            .transpose()
+            .map_err(ApiResponse::bad_request)?,
+        assignee,
+    };
+
+    let page = database::tasks::get_tasks_paginated(&db, id, filter, params).await?;
+
+    Ok(ApiResponse::success(
+        format!(
+            "{} of {} tasks shown",
+            page.records_on_page(),
+            page.total_records(),
+        ),
+        Some(page),
+    ))
+}
+
+#[get("/<id>/tasks/<task>")]
+pub async fn get_task_by_id(
+    id: Uuid,
+    task: Uuid,
+    guard: JwtGuard,
+    db: Db,
+    redis: &State<RedisMutex>,
+) -> Result<Success<Task>, Error<Null>> {
+    let (workspace_with_members, project_with_members) =
+        get_workspace_and_project(id, &db, redis).await?;
+
+    // Return not found if the user can't view the project
+    Policy::tasks_view(
+        &guard.get_user(),
+        &workspace_with_members,
+        &project_with_members,
+    )?;
+
+    let task = database::tasks::get_task(&db, id, task).await?;
+
+    Ok(ApiResponse::success(
+        format!("Task '{}' from database", task.title),
+        Some(task),
+    ))
+}
+
+#[get("/<id>/boards")]
+pub async fn get_project_boards(
+    id: Uuid,
+    guard: JwtGuard,
+    db: Db,
+    redis: &State<RedisMutex>,
+) -> Result<Success<Vec<Board>>, Error<Null>> {
+    let (workspace_with_members, project_with_members) =
+        get_workspace_and_project(id, &db, redis).await?;
+
+    // Return not found if the user can't view the project
+    Policy::boards_view(
+        &guard.get_user(),
+        &workspace_with_members,
+        &project_with_members,
+    )?;
+
+    let boards = database::boards::get_boards(&db, id).await?;
+
+    Ok(ApiResponse::success(
+        format!("{} boards found", boards.len()),
+        Some(boards),
+    ))
+}
+
+/// Returns the board with its columns and their cards, in order.
+#[get("/<id>/boards/<board>")]
+pub async fn get_board_by_id(
+    id: Uuid,
+    board: Uuid,
+    guard: JwtGuard,
+    db: Db,
+    redis: &State<RedisMutex>,
+) -> Result<Success<BoardWithColumns>, Error<Null>> {
+    let (workspace_with_members, project_with_members) =
+        get_workspace_and_project(id, &db, redis).await?;
+
+    // Return not found if the user can't view the project
+    Policy::boards_view(
+        &guard.get_user(),
+        &workspace_with_members,
+        &project_with_members,
+    )?;
+
+    let board = database::boards::get_board_with_columns(&db, id, board).await?;
+
+    Ok(ApiResponse::success(
+        format!("Board '{}' from database", board.board.name),
+        Some(board),
+    ))
+}
+
+/// Returns the comments on the project, with the replies nested under the comments they answer.
+#[get("/<id>/comments")]
+pub async fn get_project_comments(
+    id: Uuid,
+    guard: JwtGuard,
+    db: Db,
+    redis: &State<RedisMutex>,
+) -> Result<Success<Vec<ThreadedComment>>, Error<Null>> {
+    let (workspace_with_members, project_with_members) =
+        get_workspace_and_project(id, &db, redis).await?;
+
+    // Return not found if the user can't view the project
+    Policy::comments_view_project(
+        &guard.get_user(),
+        &workspace_with_members,
+        &project_with_members,
+    )?;
+
+    let thread = Thread {
+        workspace: workspace_with_members.workspace.id,
+        project: Some(id),
+    };
+    let comments = database::comments::get_thread(&db, thread).await?;
+    let total = comments.len();
+
+    Ok(ApiResponse::success(
+        format!("{total} comments found"),
+        Some(ThreadedComment::thread(comments)),
+    ))
+}
+
+/// Returns the labels the project is tagged with, by name.
+#[get("/<id>/labels")]
+pub async fn get_project_labels(
+    id: Uuid,
+    guard: JwtGuard,
+    db: Db,
+    redis: &State<RedisMutex>,
+) -> Result<Success<Vec<Label>>, Error<Null>> {
+    let (workspace_with_members, project_with_members) =
+        get_workspace_and_project(id, &db, redis).await?;
+
+    // Return not found if the user can't view the project
+    Policy::labels_view_project(
+        &guard.get_user(),
+        &workspace_with_members,
+        &project_with_members,
+    )?;
+
+    let labels = database::labels::get_project_labels(&db, id).await?;
+
+    Ok(ApiResponse::success(
+        format!("{} labels found", labels.len()),
+        Some(labels),
+    ))
+}
+
+/// Returns the milestones and sprints of the project by start date, optionally only those of a
+/// kind or in a state.
+#[get("/<id>/iterations?<kind>&<state>")]
+pub async fn get_project_iterations(
+    id: Uuid,
+    kind: Option<&str>,
+    state: Option<&str>,
+    guard: JwtGuard,
+    db: Db,
+    redis: &State<RedisMutex>,
+) -> Result<Success<Vec<Iteration>>, Error<Null>> {
+    let (workspace_with_members, project_with_members) =
+        get_workspace_and_project(id, &db, redis).await?;
+
+    // Return not found if the user can't view the project
+    Policy::iterations_view(
+        &guard.get_user(),
+        &workspace_with_members,
+        &project_with_members,
+    )?;
+
+    let kind = kind
+        .map(IterationKind::from_str)
+        .transpose()
+        .map_err(ApiResponse::bad_request)?;
+    let state = state
+        .map(IterationState::from_str)
+        .transpose()
+        .map_err(ApiResponse::bad_request)?;
+
+    let iterations = database::iterations::get_iterations(&db, id, kind, state).await?;
+
+    Ok(ApiResponse::success(
+        format!("{} iterations found", iterations.len()),
+        Some(iterations),
+    ))
+}
+
+/// Returns a milestone or sprint with its deliverables.
+#[get("/<id>/iterations/<iteration>")]
+pub async fn get_iteration_by_id(
+    id: Uuid,
+    iteration: Uuid,
+    guard: JwtGuard,
+    db: Db,
+    redis: &State<RedisMutex>,
+) -> Result<Success<IterationWithDeliverables>, Error<Null>> {
+    let (workspace_with_members, project_with_members) =
+        get_workspace_and_project(id, &db, redis).await?;
+
+    // Return not found if the user can't view the project
+    Policy::iterations_view(
+        &guard.get_user(),
+        &workspace_with_members,
+        &project_with_members,
+    )?;
+
+    let iteration =
+        database::iterations::get_iteration_with_deliverables(&db, id, iteration).await?;
+
+    Ok(ApiResponse::success(
+        format!("Iteration '{}' from database", iteration.iteration.name),
+        Some(iteration),
+    ))
+}
+
+/// Returns the burndown and burnup series of a milestone or sprint, with a day for every day
+/// it ran so far.
+#[get("/<id>/iterations/<iteration>/burndown")]
+pub async fn get_iteration_burndown(
+    id: Uuid,
+    iteration: Uuid,
+    guard: JwtGuard,
+    db: Db,
+    redis: &State<RedisMutex>,
+) -> Result<Success<Vec<BurndownDay>>, Error<Null>> {
+    let (workspace_with_members, project_with_members) =
+        get_workspace_and_project(id, &db, redis).await?;
+
+    // Return not found if the user can't view the project
+    Policy::iterations_view(
+        &guard.get_user(),
+        &workspace_with_members,
+        &project_with_members,
+    )?;
+
+    let iteration = database::iterations::get_iteration(&db, id, iteration).await?;
+    let changes = database::iterations::get_status_changes(&db, iteration.id).await?;
+    let days = burndown(&iteration, &changes, Utc::now().date_naive());
+
+    Ok(ApiResponse::success(
+        format!("Burndown of iteration '{}'", iteration.name),
+        Some(days),
+    ))
+}
@@ -1,4 +1,5 @@
-use rocket::{http::CookieJar, serde::json::Json, State};
+use chrono::Utc;
+use rocket::{serde::json::Json, State};
 use uuid::Uuid;
 
 use crate::{
@@ -6,8 +7,19 @@ use crate::{
     auth::JwtGuard,
     cache::{self, RedisMutex},
     database::{self, Db},
-    models::projects::{ProjectMember, ProjectWithMembers},
-    policies::Policy,
+    models::{
+        boards::{
+            BoardColumn, BoardWithColumns, Card, CardMove, NewBoard, NewCard, NewColumn, Position,
+        },
+        comments::{resolve_mentions, InsertableComment, NewComment, PublicComment, Thread},
+        iterations::{
+            ClosedIteration, Deliverable, Iteration, IterationClose, NewDeliverable, NewIteration,
+        },
+        projects::{ProjectMember, ProjectWithMembers},
+        tasks::{NewTask, Task},
+    },
+    policies::{permissions::PermissionResolver, Policy},
+    routes::projects::{get_changeable_iteration, get_workspace_and_project, validate_assignee},
 };
 
 #[post("/<id>/add-members", format = "json", data = "<members>")]
@@ -15,18 +27,48 @@ pub async fn add_members_to_project(
     id: Uuid,
     members: Json<Vec<ProjectMember>>,
     guard: JwtGuard,
-    cookies: &CookieJar<'_>,
     redis: &State<RedisMutex>,
     db: Db,
 ) -> Result<Success<ProjectWithMembers>, Error<Null>> {
     // Only allow this function if the user is admin or the project permissions are sufficient.
-    Policy::project_update_members(id, guard.get_user(), cookies)?;
+    Policy::project_update_members(id, guard.get_user(), &PermissionResolver::new(&db, redis))
+        .await?;
 
     // Cannot add an empty vector
     if members.is_empty() {
         return Err(ApiResponse::bad_request("No members to add".to_string()));
     }
 
+    // Members of the workspace are added as regular members, anyone else only as a guest
+    let (workspace_with_members, _) = get_workspace_and_project(id, &db, redis).await?;
+    for member in members.iter() {
+        if member.project != id {
+            return Err(ApiResponse::bad_request(format!(
+                "Member '{}' is not added to project '{id}'",
+                member.member
+            )));
+        }
+
+        let in_workspace = workspace_with_members
+            .members
+            .iter()
+            .any(|workspace_member| workspace_member.user.id == member.member);
+
+        if member.guest && in_workspace {
+            return Err(ApiResponse::bad_request(format!(
+                "User '{}' is a member of the workspace and cannot be added as a guest",
+                member.member
+            )));
+        }
+
+        if !member.guest && !in_workspace {
+            return Err(ApiResponse::bad_request(format!(
+                "User '{}' is not a member of the workspace; add the user as a guest",
+                member.member
+            )));
+        }
+    }
+
     // Extract the members length before going out of scope
     let members_len = members.len();
 
@@ -46,3 +88,329 @@ pub async fn add_members_to_project(
         Some(project_with_members),
     ))
 }
+
+/// Creates a task in the project, reported by the current user.
+#[post("/<id>/tasks", format = "json", data = "<task>")]
+pub async fn create_task(
+    id: Uuid,
+    task: Json<NewTask>,
+    guard: JwtGuard,
+    db: Db,
+    redis: &State<RedisMutex>,
+) -> Result<Success<Task>, Error<Null>> {
+    let user = guard.get_user();
+
+    Policy::tasks_create(id, user.clone(), &PermissionResolver::new(&db, redis)).await?;
+
+    task.validate().map_err(ApiResponse::bad_request)?;
+
+    // Tasks are only assigned to members of the project
+    let (_, project_with_members) = get_workspace_and_project(id, &db, redis).await?;
+    validate_assignee(&project_with_members, task.assignee)?;
+
+    let new_task = NewTask {
+        project: id,
+        reporter: Some(user.id),
+        ..task.into_inner()
+    };
+    let task = database::tasks::insert_task(&db, new_task).await?;
+
+    Ok(ApiResponse::success(
+        format!("Task '{}' created", task.title),
+        Some(task),
+    ))
+}
+
+/// Creates a board in the project, with the columns that are given.
+#[post("/<id>/boards", format = "json", data = "<board>")]
+pub async fn create_board(
+    id: Uuid,
+    board: Json<NewBoard>,
+    guard: JwtGuard,
+    db: Db,
+    redis: &State<RedisMutex>,
+) -> Result<Success<BoardWithColumns>, Error<Null>> {
+    Policy::boards_manage(id, guard.get_user(), &PermissionResolver::new(&db, redis)).await?;
+
+    board.validate().map_err(ApiResponse::bad_request)?;
+
+    let board = database::boards::insert_board(&db, id, board.into_inner()).await?;
+
+    Ok(ApiResponse::success(
+        format!("Board '{}' created", board.board.name),
+        Some(board),
+    ))
+}
+
+/// Adds a column at the end of the board.
+#[post("/<id>/boards/<board>/columns", format = "json", data = "<column>")]
+pub async fn add_board_column(
+    id: Uuid,
+    board: Uuid,
+    column: Json<NewColumn>,
+    guard: JwtGuard,
+    db: Db,
+    redis: &State<RedisMutex>,
+) -> Result<Success<BoardColumn>, Error<Null>> {
+    Policy::boards_manage(id, guard.get_user(), &PermissionResolver::new(&db, redis)).await?;
+
+    column.validate().map_err(ApiResponse::bad_request)?;
+
+    // The board has to be one of the project
+    database::boards::get_board(&db, id, board).await?;
+    let column = database::boards::insert_column(&db, board, column.into_inner().name).await?;
+
+    Ok(ApiResponse::success(
+        format!("Column '{}' added", column.name),
+        Some(column),
+    ))
+}
+
+/// Moves a column of the board between its neighbours.
+#[post(
+    "/<id>/boards/<board>/columns/<column>/move",
+    format = "json",
+    data = "<position>"
+)]
+pub async fn move_board_column(
+    id: Uuid,
+    board: Uuid,
+    column: Uuid,
+    position: Json<Position>,
+    guard: JwtGuard,
+    db: Db,
+    redis: &State<RedisMutex>,
+) -> Result<Success<BoardColumn>, Error<Null>> {
+    Policy::boards_manage(id, guard.get_user(), &PermissionResolver::new(&db, redis)).await?;
+
+    position
+        .validate(column)
+        .map_err(ApiResponse::bad_request)?;
+
+    // The board has to be one of the project
+    database::boards::get_board(&db, id, board).await?;
+    let column = database::boards::move_column(&db, board, column, position.into_inner()).await?;
+
+    Ok(ApiResponse::success(
+        format!("Column '{}' moved", column.name),
+        Some(column),
+    ))
+}
+
+/// Creates a card at the bottom of a column of the board.
+#[post("/<id>/boards/<board>/cards", format = "json", data = "<card>")]
+pub async fn create_card(
+    id: Uuid,
+    board: Uuid,
+    card: Json<NewCard>,
+    guard: JwtGuard,
+    db: Db,
+    redis: &State<RedisMutex>,
+) -> Result<Success<Card>, Error<Null>> {
+    Policy::cards_create(id, guard.get_user(), &PermissionResolver::new(&db, redis)).await?;
+
+    card.validate().map_err(ApiResponse::bad_request)?;
+
+    // Cards are only assigned to members of the project
+    let (_, project_with_members) = get_workspace_and_project(id, &db, redis).await?;
+    validate_assignee(&project_with_members, card.assignee)?;
+
+    // The board has to be one of the project
+    database::boards::get_board(&db, id, board).await?;
+    let card = database::boards::insert_card(&db, id, board, card.into_inner()).await?;
+
+    Ok(ApiResponse::success(
+        format!("Card '{}' created", card.title),
+        Some(card),
+    ))
+}
+
+/// Moves a card to a position in a column of the board, as long as the card is still at the
+/// version of the move; otherwise the move is rejected with a conflict.
+#[post(
+    "/<id>/boards/<board>/cards/<card>/move",
+    format = "json",
+    data = "<movement>"
+)]
+pub async fn move_card(
+    id: Uuid,
+    board: Uuid,
+    card: Uuid,
+    movement: Json<CardMove>,
+    guard: JwtGuard,
+    db: Db,
+    redis: &State<RedisMutex>,
+) -> Result<Success<Card>, Error<Null>> {
+    Policy::cards_update(id, guard.get_user(), &PermissionResolver::new(&db, redis)).await?;
+
+    movement
+        .position
+        .validate(card)
+        .map_err(ApiResponse::bad_request)?;
+
+    // The board has to be one of the project
+    database::boards::get_board(&db, id, board).await?;
+    let card = database::boards::move_card(&db, board, card, movement.into_inner()).await?;
+
+    Ok(ApiResponse::success(
+        format!("Card '{}' moved", card.title),
+        Some(card),
+    ))
+}
+
+/// Comments on the project, or replies to one of its comments; the members of the workspace
+/// mentioned with `@username` are stored with the comment.
+#[post("/<id>/comments", format = "json", data = "<comment>")]
+pub async fn add_project_comment(
+    id: Uuid,
+    comment: Json<NewComment>,
+    guard: JwtGuard,
+    db: Db,
+    redis: &State<RedisMutex>,
+) -> Result<Success<PublicComment>, Error<Null>> {
+    let user = guard.get_user();
+    let (workspace_with_members, project_with_members) =
+        get_workspace_and_project(id, &db, redis).await?;
+
+    Policy::comments_view_project(&user, &workspace_with_members, &project_with_members)?;
+    Policy::comments_write_project(id, user.clone(), &PermissionResolver::new(&db, redis)).await?;
+
+    comment.validate().map_err(ApiResponse::bad_request)?;
+
+    let comment = comment.into_inner();
+    let mentions = resolve_mentions(
+        &comment.body,
+        workspace_with_members
+            .members
+            .iter()
+            .map(|member| &member.user),
+    );
+    let workspace = workspace_with_members.workspace.id;
+    let thread = Thread {
+        workspace,
+        project: Some(id),
+    };
+    let new_comment = InsertableComment {
+        workspace,
+        project: Some(id),
+        parent: comment.parent,
+        author: Some(user.id),
+        body: comment.body,
+    };
+    let comment = database::comments::insert_comment(&db, thread, new_comment, mentions).await?;
+
+    Ok(ApiResponse::success(
+        "Comment added".to_string(),
+        Some(comment),
+    ))
+}
+
+/// Plans a milestone or sprint; a sprint without an end date runs for two weeks.
+#[post("/<id>/iterations", format = "json", data = "<iteration>")]
+pub async fn create_iteration(
+    id: Uuid,
+    iteration: Json<NewIteration>,
+    guard: JwtGuard,
+    db: Db,
+    redis: &State<RedisMutex>,
+) -> Result<Success<Iteration>, Error<Null>> {
+    Policy::iterations_manage(id, guard.get_user(), &PermissionResolver::new(&db, redis)).await?;
+
+    iteration.validate().map_err(ApiResponse::bad_request)?;
+
+    let iteration =
+        database::iterations::insert_iteration(&db, iteration.into_inner().into_insertable(id))
+            .await?;
+
+    Ok(ApiResponse::success(
+        format!("{} '{}' created", iteration.kind, iteration.name),
+        Some(iteration),
+    ))
+}
+
+/// Starts an open milestone or sprint; a project runs one sprint at a time.
+#[post("/<id>/iterations/<iteration>/start")]
+pub async fn start_iteration(
+    id: Uuid,
+    iteration: Uuid,
+    guard: JwtGuard,
+    db: Db,
+    redis: &State<RedisMutex>,
+) -> Result<Success<Iteration>, Error<Null>> {
+    Policy::iterations_manage(id, guard.get_user(), &PermissionResolver::new(&db, redis)).await?;
+
+    let iteration = database::iterations::start_iteration(&db, id, iteration).await?;
+
+    Ok(ApiResponse::success(
+        format!("{} '{}' started", iteration.kind, iteration.name),
+        Some(iteration),
+    ))
+}
+
+/// Closes a milestone or sprint, rolling its unfinished deliverables over to the next one.
+#[post(
+    "/<id>/iterations/<iteration>/close",
+    format = "json",
+    data = "<close>"
+)]
+pub async fn close_iteration(
+    id: Uuid,
+    iteration: Uuid,
+    close: Json<IterationClose>,
+    guard: JwtGuard,
+    db: Db,
+    redis: &State<RedisMutex>,
+) -> Result<Success<ClosedIteration>, Error<Null>> {
+    Policy::iterations_manage(id, guard.get_user(), &PermissionResolver::new(&db, redis)).await?;
+
+    let closed = database::iterations::close_iteration(
+        &db,
+        id,
+        iteration,
+        close.roll_over_to,
+        Utc::now().naive_utc(),
+    )
+    .await?;
+
+    Ok(ApiResponse::success(
+        format!(
+            "{} '{}' closed, {} deliverables rolled over",
+            closed.iteration.kind,
+            closed.iteration.name,
+            closed.rolled_over.len()
+        ),
+        Some(closed),
+    ))
+}
+
+#[post(
+    "/<id>/iterations/<iteration>/deliverables",
+    format = "json",
+    data = "<deliverable>"
+)]
+pub async fn add_deliverable(
+    id: Uuid,
+    iteration: Uuid,
+    deliverable: Json<NewDeliverable>,
+    guard: JwtGuard,
+    db: Db,
+    redis: &State<RedisMutex>,
+) -> Result<Success<Deliverable>, Error<Null>> {
+    Policy::deliverables_create(id, guard.get_user(), &PermissionResolver::new(&db, redis)).await?;
+
+    deliverable.validate().map_err(ApiResponse::bad_request)?;
+
+    let iteration = get_changeable_iteration(&db, id, iteration).await?;
+    let deliverable = database::iterations::insert_deliverable(
+        &db,
+        iteration.id,
+        deliverable.into_inner(),
+        Utc::now().naive_utc(),
+    )
+    .await?;
+
+    Ok(ApiResponse::success(
+        format!("Deliverable '{}' added", deliverable.title),
+        Some(deliverable),
+    ))
+}
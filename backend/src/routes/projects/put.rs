@@ -1,4 +1,5 @@
-use rocket::{http::CookieJar, serde::json::Json, State};
+use chrono::Utc;
+use rocket::{serde::json::Json, State};
 use uuid::Uuid;
 
 use crate::{
@@ -6,8 +7,16 @@ use crate::{
     auth::JwtGuard,
     cache::{self, RedisMutex},
     database::{self, Db},
-    models::projects::{Project, ProjectUpdate},
-    policies::Policy,
+    models::{
+        boards::{Board, BoardColumn, BoardUpdate, Card, CardUpdate, ColumnUpdate},
+        comments::{resolve_mentions, CommentUpdate, PublicComment, Thread},
+        iterations::{Deliverable, DeliverableUpdate, Iteration, IterationUpdate},
+        labels::Label,
+        projects::{Project, ProjectUpdate},
+        tasks::{Task, TaskUpdate},
+    },
+    policies::{permissions::PermissionResolver, Policy},
+    routes::projects::{get_changeable_iteration, get_workspace_and_project, validate_assignee},
 };
 
 #[put("/<id>/update", format = "json", data = "<update>")]
@@ -16,11 +25,11 @@ pub async fn update_project(
     update: Json<ProjectUpdate>,
     guard: JwtGuard,
     db: Db,
-    cookies: &CookieJar<'_>,
     redis: &State<RedisMutex>,
 ) -> Result<Success<Project>, Error<Null>> {
     // Check if the user is authorized to perform this action
-    Policy::projects_update_info(id, guard.get_user(), cookies)?;
+    Policy::projects_update_info(id, guard.get_user(), &PermissionResolver::new(&db, redis))
+        .await?;
 
     // Update the project information in the database
     let updated_project =
@@ -36,3 +45,247 @@ pub async fn update_project(
         Some(updated_project),
     ))
 }
+
+/// Updates the fields of the task that are present.
+#[put("/<id>/tasks/<task>", format = "json", data = "<update>")]
+pub async fn update_task(
+    id: Uuid,
+    task: Uuid,
+    update: Json<TaskUpdate>,
+    guard: JwtGuard,
+    db: Db,
+    redis: &State<RedisMutex>,
+) -> Result<Success<Task>, Error<Null>> {
+    Policy::tasks_update(id, guard.get_user(), &PermissionResolver::new(&db, redis)).await?;
+
+    update.validate().map_err(ApiResponse::bad_request)?;
+
+    // Tasks are only assigned to members of the project
+    if let Some(assignee) = update.assignee {
+        let (_, project_with_members) = get_workspace_and_project(id, &db, redis).await?;
+        validate_assignee(&project_with_members, assignee)?;
+    }
+
+    let updated_task = database::tasks::update_task(&db, id, task, update.into_inner()).await?;
+
+    Ok(ApiResponse::success(
+        "Task updated successfully".to_string(),
+        Some(updated_task),
+    ))
+}
+
+#[put("/<id>/boards/<board>", format = "json", data = "<update>")]
+pub async fn update_board(
+    id: Uuid,
+    board: Uuid,
+    update: Json<BoardUpdate>,
+    guard: JwtGuard,
+    db: Db,
+    redis: &State<RedisMutex>,
+) -> Result<Success<Board>, Error<Null>> {
+    Policy::boards_manage(id, guard.get_user(), &PermissionResolver::new(&db, redis)).await?;
+
+    update.validate().map_err(ApiResponse::bad_request)?;
+
+    let updated_board = database::boards::update_board(&db, id, board, update.into_inner()).await?;
+
+    Ok(ApiResponse::success(
+        "Board updated successfully".to_string(),
+        Some(updated_board),
+    ))
+}
+
+#[put(
+    "/<id>/boards/<board>/columns/<column>",
+    format = "json",
+    data = "<update>"
+)]
+pub async fn update_board_column(
+    id: Uuid,
+    board: Uuid,
+    column: Uuid,
+    update: Json<ColumnUpdate>,
+    guard: JwtGuard,
+    db: Db,
+    redis: &State<RedisMutex>,
+) -> Result<Success<BoardColumn>, Error<Null>> {
+    Policy::boards_manage(id, guard.get_user(), &PermissionResolver::new(&db, redis)).await?;
+
+    update.validate().map_err(ApiResponse::bad_request)?;
+
+    // The board has to be one of the project
+    database::boards::get_board(&db, id, board).await?;
+    let updated_column =
+        database::boards::update_column(&db, board, column, update.into_inner()).await?;
+
+    Ok(ApiResponse::success(
+        "Column updated successfully".to_string(),
+        Some(updated_column),
+    ))
+}
+
+/// Updates the fields of the card that are present; moving a card is a separate action.
+#[put(
+    "/<id>/boards/<board>/cards/<card>",
+    format = "json",
+    data = "<update>"
+)]
+pub async fn update_card(
+    id: Uuid,
+    board: Uuid,
+    card: Uuid,
+    update: Json<CardUpdate>,
+    guard: JwtGuard,
+    db: Db,
+    redis: &State<RedisMutex>,
+) -> Result<Success<Card>, Error<Null>> {
+    Policy::cards_update(id, guard.get_user(), &PermissionResolver::new(&db, redis)).await?;
+
+    update.validate().map_err(ApiResponse::bad_request)?;
+
+    // Cards are only assigned to members of the project
+    if let Some(assignee) = update.assignee {
+        let (_, project_with_members) = get_workspace_and_project(id, &db, redis).await?;
+        validate_assignee(&project_with_members, assignee)?;
+    }
+
+    // The board has to be one of the project
+    database::boards::get_board(&db, id, board).await?;
+    let updated_card = database::boards::update_card(&db, board, card, update.into_inner()).await?;
+
+    Ok(ApiResponse::success(
+        "Card updated successfully".to_string(),
+        Some(updated_card),
+    ))
+}
+
+/// Replaces the body of a comment by its author, which marks the comment as edited.
+#[put("/<id>/comments/<comment>", format = "json", data = "<update>")]
+pub async fn edit_project_comment(
+    id: Uuid,
+    comment: Uuid,
+    update: Json<CommentUpdate>,
+    guard: JwtGuard,
+    db: Db,
+    redis: &State<RedisMutex>,
+) -> Result<Success<PublicComment>, Error<Null>> {
+    let user = guard.get_user();
+    let (workspace_with_members, project_with_members) =
+        get_workspace_and_project(id, &db, redis).await?;
+
+    Policy::comments_view_project(&user, &workspace_with_members, &project_with_members)?;
+    Policy::comments_write_project(id, user.clone(), &PermissionResolver::new(&db, redis)).await?;
+
+    let thread = Thread {
+        workspace: workspace_with_members.workspace.id,
+        project: Some(id),
+    };
+    let comment = database::comments::get_comment(&db, thread, comment).await?;
+    Policy::comments_edit(&user, &comment)?;
+
+    if comment.deleted_at.is_some() {
+        return Err(ApiResponse::bad_request(
+            "A deleted comment can't be edited".to_string(),
+        ));
+    }
+    update.validate().map_err(ApiResponse::bad_request)?;
+
+    let body = update.into_inner().body;
+    let mentions = resolve_mentions(
+        &body,
+        workspace_with_members
+            .members
+            .iter()
+            .map(|member| &member.user),
+    );
+    let comment =
+        database::comments::edit_comment(&db, comment.id, body, mentions, Utc::now().naive_utc())
+            .await?;
+
+    Ok(ApiResponse::success(
+        "Comment edited".to_string(),
+        Some(comment),
+    ))
+}
+
+/// Tags the project with a label of its workspace.
+#[put("/<id>/labels/<label>")]
+pub async fn attach_project_label(
+    id: Uuid,
+    label: Uuid,
+    guard: JwtGuard,
+    db: Db,
+    redis: &State<RedisMutex>,
+) -> Result<Success<Label>, Error<Null>> {
+    let (workspace_with_members, _) = get_workspace_and_project(id, &db, redis).await?;
+
+    Policy::labels_tag_project(id, guard.get_user(), &PermissionResolver::new(&db, redis)).await?;
+
+    let label =
+        database::labels::attach_label(&db, workspace_with_members.workspace.id, id, label).await?;
+
+    Ok(ApiResponse::success(
+        format!("Project tagged with label '{}'", label.name),
+        Some(label),
+    ))
+}
+
+#[put("/<id>/iterations/<iteration>", format = "json", data = "<update>")]
+pub async fn update_iteration(
+    id: Uuid,
+    iteration: Uuid,
+    update: Json<IterationUpdate>,
+    guard: JwtGuard,
+    db: Db,
+    redis: &State<RedisMutex>,
+) -> Result<Success<Iteration>, Error<Null>> {
+    Policy::iterations_manage(id, guard.get_user(), &PermissionResolver::new(&db, redis)).await?;
+
+    let iteration = get_changeable_iteration(&db, id, iteration).await?;
+    update
+        .validate(&iteration)
+        .map_err(ApiResponse::bad_request)?;
+
+    let iteration =
+        database::iterations::update_iteration(&db, id, iteration.id, update.into_inner()).await?;
+
+    Ok(ApiResponse::success(
+        format!("{} '{}' updated", iteration.kind, iteration.name),
+        Some(iteration),
+    ))
+}
+
+/// Updates a deliverable; every change of its status counts towards the burndown.
+#[put(
+    "/<id>/iterations/<iteration>/deliverables/<deliverable>",
+    format = "json",
+    data = "<update>"
+)]
+pub async fn update_deliverable(
+    id: Uuid,
+    iteration: Uuid,
+    deliverable: Uuid,
+    update: Json<DeliverableUpdate>,
+    guard: JwtGuard,
+    db: Db,
+    redis: &State<RedisMutex>,
+) -> Result<Success<Deliverable>, Error<Null>> {
+    Policy::deliverables_update(id, guard.get_user(), &PermissionResolver::new(&db, redis)).await?;
+
+    update.validate().map_err(ApiResponse::bad_request)?;
+
+    let iteration = get_changeable_iteration(&db, id, iteration).await?;
+    let deliverable = database::iterations::update_deliverable(
+        &db,
+        iteration.id,
+        deliverable,
+        update.into_inner(),
+        Utc::now().naive_utc(),
+    )
+    .await?;
+
+    Ok(ApiResponse::success(
+        format!("Deliverable '{}' updated", deliverable.title),
+        Some(deliverable),
+    ))
+}
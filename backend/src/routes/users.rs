@@ -5,18 +5,41 @@ mod put;
 
 pub fn routes() -> Vec<rocket::Route> {
     routes![
-        get::get_self_from_token,       // GET:     /user/me
-        get::get_paginated_users,       // GET:     /user?<status>&<role>
-        get::get_user_by_username,      // GET:     /user/<username>
-        post::inject_user,              // POST:    /user/create
-        put::remove_user,               // PUT:     /user/remove/<id>
-        put::suspend_user,              // PUT:     /user/suspend/<id>
-        put::update_user,               // PUT:     /user/update/<id>
-        put::update_role,               // PUT:     /user/update/<id>/<role>
-        delete::delete_user_by_id,      // DELETE:  /user/delete/<id>
-        get::get_invited_user,          // GET:     /user/invite/get/<token>
-        put::set_password_after_invite, // PUT:     /user/invite/set/<token>
-        post::login_by_form,            // POST:    /user/login
-        post::logout,                   // POST:    /user/logout
+        get::get_self_from_token,         // GET:     /user/me
+        get::get_paginated_users,         // GET:     /user?<status>&<role>
+        get::get_user_by_username,        // GET:     /user/<username>
+        post::inject_user,                // POST:    /user/create
+        put::remove_user,                 // PUT:     /user/remove/<id>
+        put::suspend_user,                // PUT:     /user/suspend/<id>
+        put::unlock_user,                 // PUT:     /user/unlock/<id>
+        put::update_user,                 // PUT:     /user/update/<id>
+        put::update_role,                 // PUT:     /user/update/<id>/<role>
+        delete::delete_user_by_id,        // DELETE:  /user/delete/<id>
+        get::get_invited_user,            // GET:     /user/invite/get/<token>
+        put::set_password_after_invite,   // PUT:     /user/invite/set/<token>
+        post::forgot_password,            // POST:    /user/password/forgot
+        put::reset_password,              // PUT:     /user/password/reset/<token>
+        put::change_password,             // PUT:     /user/me/password
+        post::change_email,               // POST:    /user/me/email
+        put::confirm_email_change,        // PUT:     /user/email/confirm/<token>
+        post::login_by_form,              // POST:    /user/login
+        post::login_second_factor,        // POST:    /user/login/2fa
+        post::login_enroll_second_factor, // POST:    /user/login/2fa/enroll
+        get::login_oidc,                  // GET:     /user/login/oidc
+        get::login_oidc_callback,         // GET:     /user/login/oidc/callback?<code>&<state>
+        post::enroll_two_factor,          // POST:    /user/2fa/enroll
+        post::verify_two_factor,          // POST:    /user/2fa/verify
+        post::disable_two_factor,         // POST:    /user/2fa/disable
+        get::get_access_tokens,           // GET:     /user/tokens
+        post::create_access_token,        // POST:    /user/tokens
+        delete::delete_access_token,      // DELETE:  /user/tokens/<id>
+        get::get_sessions,                // GET:     /user/me/sessions
+        delete::revoke_session,           // DELETE:  /user/me/sessions/<id>
+        get::get_user_sessions,           // GET:     /user/sessions/<id>
+        delete::revoke_user_sessions,     // DELETE:  /user/sessions/<id>
+        post::impersonate_user,           // POST:    /user/impersonate/<id>
+        get::get_impersonation_logs,      // GET:     /user/impersonate/<id>/logs
+        post::refresh,                    // POST:    /user/refresh
+        post::logout,                     // POST:    /user/logout
     ]
 }
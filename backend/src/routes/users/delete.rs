@@ -1,8 +1,10 @@
+use rocket::{http::CookieJar, State};
 use uuid::Uuid;
 
 use crate::{
     api::{ApiResponse, Error, Null, Success},
     auth::JwtGuard,
+    cache::{self, RedisMutex},
     database::{self, Db},
     policies::Policy,
 };
@@ -32,7 +34,11 @@ pub async fn delete_user_by_id(
     id: Uuid,
     guard: JwtGuard,
     db: Db,
+    redis: &State<RedisMutex>,
 ) -> Result<Success<Null>, Error<Null>> {
+    // Not in the name of someone else
+    guard.deny_impersonation()?;
+
     // Get user cookie
     let user = guard.get_user();
 
@@ -44,8 +50,76 @@ pub async fn delete_user_by_id(
 
     // If there are any deleted rows, it means the user is successfully deleted
     if deleted_rows > 0 {
+        // Revoke all sessions of the deleted user
+        cache::sessions::remove_user_sessions(redis, id).await?;
+        cache::users::remove_user_cache(redis, id).await;
+
         Ok(ApiResponse::success(format!("User '{id}' deleted"), None))
     } else {
         Err(ApiResponse::not_found(format!("User '{id}' not found")))
     }
 }
+
+/// Revokes one of the personal access tokens of the current user.
+#[delete("/tokens/<id>")]
+pub async fn delete_access_token(
+    id: Uuid,
+    guard: JwtGuard,
+    db: Db,
+) -> Result<Success<Null>, Error<Null>> {
+    guard.require_session()?;
+    guard.deny_impersonation()?;
+
+    match database::access_tokens::delete_access_token(&db, id, guard.get_user().id).await? {
+        0 => Err(ApiResponse::not_found(format!(
+            "Access token '{id}' not found"
+        ))),
+        _ => Ok(ApiResponse::success(
+            format!("Access token '{id}' revoked"),
+            None,
+        )),
+    }
+}
+
+/// Revokes one of the sessions of the current user, e.g. on a device that was lost. Revoking the
+/// current session is the same as logging out.
+#[delete("/me/sessions/<id>")]
+pub async fn revoke_session(
+    id: Uuid,
+    guard: JwtGuard,
+    cookies: &CookieJar<'_>,
+    redis: &State<RedisMutex>,
+) -> Result<Success<Null>, Error<Null>> {
+    guard.require_session()?;
+    guard.deny_impersonation()?;
+
+    let user = guard.get_user();
+
+    // Only the sessions of the user itself can be revoked here
+    match cache::sessions::get_session(redis, id).await? {
+        Some(session) if session.user == user.id => {}
+        _ => return Err(ApiResponse::not_found(format!("Session '{id}' not found"))),
+    }
+
+    cache::sessions::remove_session(redis, user.id, id).await?;
+
+    if id == guard.get_session_id() {
+        JwtGuard::clear(cookies);
+    }
+
+    Ok(ApiResponse::success("Session revoked".to_string(), None))
+}
+
+/// Revokes every session of a user, logging them out on all devices; for admins.
+#[delete("/sessions/<id>")]
+pub async fn revoke_user_sessions(
+    id: Uuid,
+    guard: JwtGuard,
+    redis: &State<RedisMutex>,
+) -> Result<Success<Null>, Error<Null>> {
+    Policy::users_manage_sessions(&guard.get_user())?;
+
+    cache::sessions::remove_user_sessions(redis, id)
+        .await
+        .map(|()| ApiResponse::success("All sessions of the user revoked".to_string(), None))
+}
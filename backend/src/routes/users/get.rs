@@ -1,15 +1,36 @@
-use rocket::{serde::json::Json, State};
+use rocket::{
+    http::{Cookie, CookieJar, SameSite},
+    response::Redirect,
+    serde::json::Json,
+    time::Duration,
+    State,
+};
+use subtle::ConstantTimeEq;
+use uuid::Uuid;
 
 use crate::{
     api::{ApiResponse, Error, Null, Success},
-    auth::JwtGuard,
+    auth::{
+        invitations::find_pending_invitation,
+        keys::JwtKeys,
+        oidc::{self, OidcProvider},
+        two_factor::{start_second_factor_challenge, TwoFactorConfig},
+        JwtGuard,
+    },
     cache::{self, RedisMutex},
+    cookies::OIDC_STATE_COOKIE,
     database::{
         self,
         pagination::{records::PaginatedRecords, request::PaginationRequest, sort::UserField},
         Db,
     },
-    models::users::{PublicUser, UserStatus},
+    models::{
+        access_tokens::PublicAccessToken,
+        impersonation::ImpersonationLog,
+        sessions::{ClientInfo, PublicSession},
+        two_factor::TwoFactorChallenge,
+        users::{PublicUser, UserRole, UserStatus},
+    },
     policies::Policy,
 };
 
@@ -27,8 +48,8 @@ pub fn get_self_from_token(guard: JwtGuard) -> Result<Success<PublicUser>, Error
 //Instead of get_paginated_users, maybe browse_users or list_users_paginated — to match REST semantics more intuitively.
 #[get("/?<status>&<role>", format = "json", data = "<params>")]
 pub async fn get_paginated_users(
-    status: Option<i16>,
-    role: Option<i16>,
+    status: Option<UserStatus>,
+    role: Option<UserRole>,
     params: Json<PaginationRequest<UserField>>,
     guard: JwtGuard,
     db: Db,
@@ -65,13 +86,10 @@ pub async fn get_user_by_username(
 }
 
 #[get("/invite/get/<token>")]
-pub async fn get_invited_user(
-    token: &str,
-    db: Db,
-    redis: &State<RedisMutex>,
-) -> Result<Success<Vec<String>>, Error<Null>> {
-    // Get the user ID from the cache (should be a UUID at this stage)
-    let user_id = cache::users::get_invite_token(redis, token).await?;
+pub async fn get_invited_user(token: &str, db: Db) -> Result<Success<Vec<String>>, Error<Null>> {
+    // Get the invitation from the database; gone when it can't be accepted anymore
+    let invitation = find_pending_invitation(&db, token).await?;
+    let user_id = invitation.invitee;
 
     // Get the user from the database
     let user = database::users::get_user_by_id(&db, user_id).await?;
@@ -79,12 +97,190 @@ pub async fn get_invited_user(
     // Return not found if the user is not of status invited
     // > Returning not found avoids leaking user existence or status, preventing malicious actors
     // > from probing valid invitation tokens.
-    if user.status != i16::from(UserStatus::Invited) {
+    if user.status != UserStatus::Invited {
         return Err(ApiResponse::not_found(format!(
             "User '{user_id}' not found",
         )));
     }
 
     // Return success response
-    Ok(ApiResponse::success("User set in cache".to_string(), None))
+    Ok(ApiResponse::success("Invitation found".to_string(), None))
+}
+
+/// Lists the personal access tokens of the current user, without the tokens themselves.
+#[get("/tokens")]
+pub async fn get_access_tokens(
+    guard: JwtGuard,
+    db: Db,
+) -> Result<Success<Vec<PublicAccessToken>>, Error<Null>> {
+    guard.require_session()?;
+
+    let tokens = database::access_tokens::get_access_tokens_by_user(&db, guard.get_user().id)
+        .await?
+        .iter()
+        .map(PublicAccessToken::from)
+        .collect::<Vec<PublicAccessToken>>();
+
+    Ok(ApiResponse::success(
+        format!("{} access tokens found", tokens.len()),
+        Some(tokens),
+    ))
+}
+
+/// Lists where the current user is logged in; the most recently used session first.
+#[get("/me/sessions")]
+pub async fn get_sessions(
+    guard: JwtGuard,
+    redis: &State<RedisMutex>,
+) -> Result<Success<Vec<PublicSession>>, Error<Null>> {
+    // An access token doesn't belong to any session
+    guard.require_session()?;
+
+    let sessions = list_sessions(redis, guard.get_user().id, guard.get_session_id()).await?;
+
+    Ok(ApiResponse::success(
+        format!("{} sessions found", sessions.len()),
+        Some(sessions),
+    ))
+}
+
+/// Lists where a user is logged in; for admins.
+#[get("/sessions/<id>")]
+pub async fn get_user_sessions(
+    id: Uuid,
+    guard: JwtGuard,
+    redis: &State<RedisMutex>,
+) -> Result<Success<Vec<PublicSession>>, Error<Null>> {
+    Policy::users_manage_sessions(&guard.get_user())?;
+
+    let sessions = list_sessions(redis, id, guard.get_session_id()).await?;
+
+    Ok(ApiResponse::success(
+        format!("{} sessions found", sessions.len()),
+        Some(sessions),
+    ))
+}
+
+/// Lists the requests made while impersonating, as or by the user; for admins.
+#[get("/impersonate/<id>/logs")]
+pub async fn get_impersonation_logs(
+    id: Uuid,
+    guard: JwtGuard,
+    db: Db,
+) -> Result<Success<Vec<ImpersonationLog>>, Error<Null>> {
+    Policy::users_view_impersonation_logs(&guard.get_user())?;
+
+    let logs = database::impersonation::get_impersonation_logs(&db, id).await?;
+
+    Ok(ApiResponse::success(
+        format!("{} impersonation logs found", logs.len()),
+        Some(logs),
+    ))
+}
+
+async fn list_sessions(
+    redis: &State<RedisMutex>,
+    user_id: Uuid,
+    current_session: Uuid,
+) -> Result<Vec<PublicSession>, Error<Null>> {
+    let mut sessions = cache::sessions::get_user_sessions(redis, user_id)
+        .await?
+        .iter()
+        .map(|session| PublicSession::from(session, current_session))
+        .collect::<Vec<_>>();
+
+    sessions.sort_by_key(|session| std::cmp::Reverse(session.last_seen_at));
+
+    Ok(sessions)
+}
+
+/// Starts a single sign-on login by sending the browser to the identity provider.
+#[get("/login/oidc")]
+pub async fn login_oidc(
+    cookies: &CookieJar<'_>,
+    provider: &State<OidcProvider>,
+    redis: &State<RedisMutex>,
+) -> Result<Redirect, Error<Null>> {
+    let (url, state, login) = provider.authorization_url().await?;
+
+    // Keep the nonce and the PKCE verifier until the provider redirects back
+    cache::users::add_oidc_login(redis, &state, &login).await?;
+
+    // Lax, as the provider sends the browser back with a top-level navigation
+    cookies.add_private(
+        Cookie::build((OIDC_STATE_COOKIE, state))
+            .http_only(true)
+            .same_site(SameSite::Lax)
+            .secure(false) // TODO!: Set to 'true' along with the token cookies
+            .path("/")
+            .max_age(Duration::minutes(5))
+            .build(),
+    );
+
+    Ok(Redirect::to(url))
+}
+
+/// Completes a single sign-on login, where the identity provider redirects the browser to.
+///
+/// Only the browser that started the login can complete it, so a callback URL with the code of
+/// someone else doesn't log the browser in as them.
+///
+/// If the user has a second factor, or their role requires one, no cookies are issued yet, like
+/// with the password login. The browser is sent on with the challenge in the fragment instead,
+/// e.g. `/dashboard#challenge=...&enrollment_required=false`, to be completed at `/login/2fa`.
+#[get("/login/oidc/callback?<code>&<state>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn login_oidc_callback(
+    code: &str,
+    state: &str,
+    client: ClientInfo,
+    db: Db,
+    cookies: &CookieJar<'_>,
+    keys: &State<JwtKeys>,
+    provider: &State<OidcProvider>,
+    redis: &State<RedisMutex>,
+    two_factor_config: &State<TwoFactorConfig>,
+) -> Result<Redirect, Error<Null>> {
+    let started = cookies.get_private(OIDC_STATE_COOKIE);
+    cookies.remove_private(OIDC_STATE_COOKIE);
+
+    match started {
+        Some(started) if bool::from(started.value().as_bytes().ct_eq(state.as_bytes())) => {}
+        _ => {
+            return Err(ApiResponse::unauthorized(
+                "The login wasn't started by this browser".to_string(),
+            ))
+        }
+    }
+
+    let login = cache::users::take_oidc_login(redis, state).await?;
+    let claims = provider.exchange_code(code, &login).await?;
+
+    let config = provider.config()?;
+    let user = oidc::find_or_provision_user(&db, config, &claims).await?;
+
+    if user.status != UserStatus::Active {
+        return Err(ApiResponse::unauthorized(
+            "Only active users can log in".to_string(),
+        ));
+    }
+
+    // The identity provider doesn't replace the second factor
+    if let Some(TwoFactorChallenge {
+        challenge,
+        enrollment_required,
+    }) = start_second_factor_challenge(&db, redis, two_factor_config, &user).await?
+    {
+        return Ok(Redirect::to(format!(
+            "{}#challenge={challenge}&enrollment_required={enrollment_required}",
+            config.post_login_redirect
+        )));
+    }
+
+    // Add the user to the JWT guard
+    JwtGuard::secure(&user, &client, cookies, keys, redis)
+        .await
+        .map_err(ApiResponse::internal_server_error)?;
+
+    Ok(Redirect::to(config.post_login_redirect.clone()))
 }
@@ -1,54 +1,571 @@
 use crate::{
     api::{ApiResponse, Error, Null, Success},
-    auth::JwtGuard,
-    cookies::TOKEN_COOKIE,
-    database::{users as database, Db},
-    forms::{login::LoginForm, password::Password},
-    models::users::{User, UserStatus},
+    auth::{
+        backends::AuthBackends,
+        keys::JwtKeys,
+        lockout,
+        two_factor::{start_second_factor_challenge, verify_second_factor, TwoFactorConfig},
+        JwtGuard,
+    },
+    cache::{self, RedisMutex},
+    cookies::REFRESH_COOKIE,
+    database::{
+        access_tokens as access_tokens_database, impersonation as impersonation_database,
+        two_factor as two_factor_database, users as database, Db,
+    },
+    email::MailClient,
+    forms::{
+        access_tokens::AccessTokenForm,
+        login::LoginForm,
+        password::{ChangeEmailForm, ForgotPasswordForm, Password},
+        two_factor::{
+            TwoFactorChallengeForm, TwoFactorCodeForm, TwoFactorDisableForm, TwoFactorLoginForm,
+        },
+    },
+    models::{
+        access_tokens::{AccessToken, NewAccessToken, PublicAccessToken},
+        impersonation::{Impersonation, ImpersonationLog, ImpersonationToken},
+        sessions::{ClientInfo, Session},
+        two_factor::{TwoFactor, TwoFactorChallenge, TwoFactorEnrollment},
+        users::{EmailChange, PublicUser, User, UserStatus},
+    },
+    policies::Policy,
+    routes::USERS,
 };
-use rocket::{form::Form, http::CookieJar, serde::json::Json};
+use rocket::{
+    form::Form,
+    http::{CookieJar, Status},
+    serde::json::Json,
+    State,
+};
+use subtle::ConstantTimeEq;
 use uuid::Uuid;
 
+/// Maximum number of codes that can be tried for a single login challenge.
+const TWO_FACTOR_ATTEMPT_LIMIT: u64 = 5;
+
+/// Logs the user in with a username and password.
+///
+/// If the user has a second factor, or their role requires one, no cookies are issued yet.
+/// Instead a short-lived challenge is returned, which has to be completed at `/login/2fa`.
+///
+/// Failed attempts are counted per username and client IP (see [`lockout`]). Unknown users,
+/// inactive users and wrong passwords all get the same response.
 #[post("/login", data = "<credentials>")]
+#[allow(clippy::too_many_arguments)]
 pub async fn login_by_form(
     credentials: Form<LoginForm<'_>>,
+    client: ClientInfo,
+    db: Db,
+    cookies: &CookieJar<'_>,
+    keys: &State<JwtKeys>,
+    redis: &State<RedisMutex>,
+    two_factor_config: &State<TwoFactorConfig>,
+    backends: &State<AuthBackends>,
+) -> Result<Success<TwoFactorChallenge>, Error<Null>> {
+    // Refuse the attempt while the username or the IP is blocked
+    let ip = client.ip;
+    lockout::check_login_allowed(redis, credentials.username, ip).await?;
+
+    // Check the credentials with the configured backends, in order
+    let user = match backends
+        .authenticate(&db, redis, credentials.username, credentials.password)
+        .await
+    {
+        Ok(Some(user)) => user,
+        Ok(None) => {
+            lockout::register_login_failure(redis, credentials.username, ip).await?;
+            return Err(ApiResponse::unauthorized(
+                "Invalid username or password".to_string(),
+            ));
+        }
+        // A backend that is down still counts the attempt, or it would lift the lockout
+        Err(e) => {
+            lockout::register_login_failure(redis, credentials.username, ip).await?;
+            return Err(e);
+        }
+    };
+
+    // The password is correct, so start counting from zero again
+    lockout::reset_login_failures(redis, credentials.username).await;
+
+    // Check whether a second factor has to be provided before the login is complete
+    if let Some(challenge) =
+        start_second_factor_challenge(&db, redis, two_factor_config, &user).await?
+    {
+        return Ok(ApiResponse::success(
+            "Second factor required".to_string(),
+            Some(challenge),
+        ));
+    }
+
+    // Add the user to the JWT guard
+    JwtGuard::secure(&user, &client, cookies, keys, redis)
+        .await
+        .map_err(ApiResponse::internal_server_error)?;
+
+    Ok(ApiResponse::success("Login successful".to_string(), None))
+}
+
+/// Completes a login that requires a second factor.
+///
+/// Accepts a TOTP code or one of the recovery codes. If the user is enrolling during the login,
+/// a valid TOTP code also enables the second factor.
+#[post("/login/2fa", data = "<form>")]
+pub async fn login_second_factor(
+    form: Form<TwoFactorLoginForm<'_>>,
+    client: ClientInfo,
     db: Db,
     cookies: &CookieJar<'_>,
+    keys: &State<JwtKeys>,
+    redis: &State<RedisMutex>,
 ) -> Result<Success<Null>, Error<Null>> {
-    // Get the user from the database
-    let user = database::get_user_by_username(&db, credentials.username).await?;
+    // Get the user from the challenge
+    let user_id = cache::users::get_two_factor_challenge(redis, form.challenge).await?;
 
-    // Return not found if the user is not active
-    if user.status != i16::from(UserStatus::Active) {
-        return Err(ApiResponse::not_found(format!(
-            "User '{}' not found",
+    // Limit the number of codes that can be tried; the user has to log in again afterwards
+    if cache::users::count_two_factor_attempt(redis, form.challenge).await?
+        > TWO_FACTOR_ATTEMPT_LIMIT
+    {
+        cache::users::remove_two_factor_challenge(redis, form.challenge).await?;
+        return Err(ApiResponse::too_many_requests(
+            "Too many attempts; log in again".to_string(),
+        ));
+    }
+
+    // The user may have been suspended since the password was verified
+    let user = database::get_user_by_id(&db, user_id).await?;
+    if user.status != UserStatus::Active {
+        cache::users::remove_two_factor_challenge(redis, form.challenge).await?;
+        return Err(ApiResponse::unauthorized(format!(
+            "User '{}' is not active",
             user.username
         )));
     }
 
+    let two_factor = two_factor_database::get_two_factor(&db, user_id)
+        .await?
+        .ok_or_else(|| ApiResponse::bad_request("No second factor enrolled".to_string()))?;
+
+    if !verify_second_factor(&db, redis, &two_factor, form.code, true).await? {
+        return Err(ApiResponse::unauthorized("Invalid code".to_string()));
+    }
+
+    // Finish the enrollment that was started during the login
+    if !two_factor.enabled {
+        two_factor_database::enable_two_factor(&db, user_id).await?;
+    }
+
+    // The challenge can only be completed once
+    cache::users::remove_two_factor_challenge(redis, form.challenge).await?;
+
+    // Add the user to the JWT guard
+    JwtGuard::secure(&user, &client, cookies, keys, redis)
+        .await
+        .map_err(ApiResponse::internal_server_error)?;
+
+    Ok(ApiResponse::success("Login successful".to_string(), None))
+}
+
+/// Starts the enrollment for a user whose role requires a second factor, but who doesn't have
+/// one yet. The login is completed by sending a code from the authenticator app to `/login/2fa`.
+#[post("/login/2fa/enroll", data = "<form>")]
+pub async fn login_enroll_second_factor(
+    form: Form<TwoFactorChallengeForm<'_>>,
+    db: Db,
+    redis: &State<RedisMutex>,
+) -> Result<Success<TwoFactorEnrollment>, Error<Null>> {
+    // Get the user from the challenge
+    let user_id = cache::users::get_two_factor_challenge(redis, form.challenge).await?;
+    let user = database::get_user_by_id(&db, user_id).await?;
+
+    enroll_second_factor(&db, user.id, &user.username).await
+}
+
+/// Starts the enrollment of a second factor for the current user.
+///
+/// The second factor is only enabled after a valid code is sent to `/2fa/verify`.
+#[post("/2fa/enroll")]
+pub async fn enroll_two_factor(
+    guard: JwtGuard,
+    db: Db,
+) -> Result<Success<TwoFactorEnrollment>, Error<Null>> {
+    guard.require_session()?;
+    guard.deny_impersonation()?;
+
+    let user = guard.get_user();
+
+    enroll_second_factor(&db, user.id, &user.username).await
+}
+
+/// Enables the second factor of the current user after the enrollment.
+#[post("/2fa/verify", data = "<form>")]
+pub async fn verify_two_factor(
+    form: Form<TwoFactorCodeForm<'_>>,
+    guard: JwtGuard,
+    db: Db,
+    redis: &State<RedisMutex>,
+) -> Result<Success<Null>, Error<Null>> {
+    guard.require_session()?;
+    guard.deny_impersonation()?;
+
+    let user_id = guard.get_user().id;
+
+    let two_factor = two_factor_database::get_two_factor(&db, user_id)
+        .await?
+        .ok_or_else(|| ApiResponse::not_found("No second factor enrolled".to_string()))?;
+
+    if two_factor.enabled {
+        return Err(ApiResponse::bad_request(
+            "Two-factor authentication is already enabled".to_string(),
+        ));
+    }
+
+    // Only a TOTP code proves that the authenticator app has been set up
+    if !verify_second_factor(&db, redis, &two_factor, form.code, false).await? {
+        return Err(ApiResponse::bad_request("Invalid code".to_string()));
+    }
+
+    two_factor_database::enable_two_factor(&db, user_id).await?;
+
+    Ok(ApiResponse::success(
+        "Two-factor authentication enabled".to_string(),
+        None,
+    ))
+}
+
+/// Removes the second factor of the current user, unless their role requires one.
+#[post("/2fa/disable", data = "<form>")]
+pub async fn disable_two_factor(
+    form: Form<TwoFactorDisableForm<'_>>,
+    guard: JwtGuard,
+    db: Db,
+    redis: &State<RedisMutex>,
+    two_factor_config: &State<TwoFactorConfig>,
+) -> Result<Success<Null>, Error<Null>> {
+    guard.require_session()?;
+    guard.deny_impersonation()?;
+
+    let user = database::get_user_by_id(&db, guard.get_user().id).await?;
+
+    if two_factor_config.is_required(user.role) {
+        return Err(ApiResponse::bad_request(
+            "Two-factor authentication is required for this role".to_string(),
+        ));
+    }
+
     // Validate if the given password is correct
-    if !Password::verify_password(credentials.password, &user.password).map_err(|e| {
+    if !Password::verify_password(form.password, &user.password).map_err(|e| {
         ApiResponse::internal_server_error(format!("Password verification failed: {}", e))
     })? {
         return Err(ApiResponse::bad_request("Invalid password".to_string()));
     };
 
-    // Add the user to the JWT guard
-    JwtGuard::secure(&user, cookies)
+    let two_factor = two_factor_database::get_two_factor(&db, user.id)
+        .await?
+        .ok_or_else(|| ApiResponse::not_found("No second factor enrolled".to_string()))?;
+
+    if !verify_second_factor(&db, redis, &two_factor, form.code, true).await? {
+        return Err(ApiResponse::bad_request("Invalid code".to_string()));
+    }
+
+    two_factor_database::delete_two_factor(&db, user.id).await?;
+
+    Ok(ApiResponse::success(
+        "Two-factor authentication disabled".to_string(),
+        None,
+    ))
+}
+
+/// Generates and stores a new (disabled) second factor, unless one is already enabled.
+async fn enroll_second_factor(
+    db: &Db,
+    user_id: Uuid,
+    username: &str,
+) -> Result<Success<TwoFactorEnrollment>, Error<Null>> {
+    if two_factor_database::get_two_factor(db, user_id)
+        .await?
+        .is_some_and(|two_factor| two_factor.enabled)
+    {
+        return Err(ApiResponse::bad_request(
+            "Two-factor authentication is already enabled".to_string(),
+        ));
+    }
+
+    let (two_factor, enrollment) =
+        TwoFactor::generate(user_id, username).map_err(ApiResponse::internal_server_error)?;
+
+    two_factor_database::upsert_two_factor(db, two_factor).await?;
+
+    Ok(ApiResponse::success(
+        "Scan the QR code or enter the secret in your authenticator app".to_string(),
+        Some(enrollment),
+    ))
+}
+
+/// Exchanges the refresh token cookie for a new access token and a new refresh token.
+///
+/// Refresh tokens can be used only once. Presenting a refresh token that has already been
+/// rotated means it was copied, so the whole session is revoked.
+#[post("/refresh")]
+pub async fn refresh(
+    client: ClientInfo,
+    db: Db,
+    cookies: &CookieJar<'_>,
+    keys: &State<JwtKeys>,
+    redis: &State<RedisMutex>,
+) -> Result<Success<Null>, Error<Null>> {
+    // Get the session ID and the secret from the refresh token
+    let refresh_token = cookies
+        .get_private(REFRESH_COOKIE)
+        .map(|cookie| cookie.value().to_string())
+        .ok_or_else(|| ApiResponse::unauthorized("No refresh token provided".to_string()))?;
+
+    let Some((session_id, secret)) = Session::parse_refresh_token(&refresh_token) else {
+        JwtGuard::clear(cookies);
+        return Err(ApiResponse::unauthorized(
+            "Invalid refresh token".to_string(),
+        ));
+    };
+
+    // The session must still exist
+    let Some(session) = cache::sessions::get_session(redis, session_id).await? else {
+        JwtGuard::clear(cookies);
+        return Err(ApiResponse::unauthorized("Session revoked".to_string()));
+    };
+
+    // Reuse of an old refresh token; revoke the session
+    if !bool::from(session.refresh_secret.as_bytes().ct_eq(secret.as_bytes())) {
+        cache::sessions::remove_session(redis, session.user, session.id).await?;
+        JwtGuard::clear(cookies);
+        return Err(ApiResponse::unauthorized(
+            "Refresh token reused".to_string(),
+        ));
+    }
+
+    // Get the latest user information for the new access token
+    let user = database::get_user_by_id(&db, session.user).await?;
+
+    // Inactive users cannot keep their session
+    if user.status != UserStatus::Active {
+        cache::sessions::remove_session(redis, session.user, session.id).await?;
+        JwtGuard::clear(cookies);
+        return Err(ApiResponse::unauthorized("Session revoked".to_string()));
+    }
+
+    // Rotate the refresh token and issue a new access token, unless revoked in the meantime
+    let renewed = JwtGuard::renew(&user, session, &client, cookies, keys, redis)
         .await
         .map_err(ApiResponse::internal_server_error)?;
 
-    Ok(ApiResponse::success("Login successful".to_string(), None))
+    if !renewed {
+        JwtGuard::clear(cookies);
+        return Err(ApiResponse::unauthorized("Session revoked".to_string()));
+    }
+
+    Ok(ApiResponse::success("Token refreshed".to_string(), None))
 }
 
 #[post("/logout")]
-pub fn logout(_guard: JwtGuard, cookies: &CookieJar<'_>) -> Success<String> {
-    cookies.remove_private(TOKEN_COOKIE);
+pub async fn logout(
+    guard: JwtGuard,
+    cookies: &CookieJar<'_>,
+    redis: &State<RedisMutex>,
+) -> Result<Success<Null>, Error<Null>> {
+    // Remove the session, so copies of the tokens can't be used anymore
+    cache::sessions::remove_session(redis, guard.get_user().id, guard.get_session_id()).await?;
+
+    JwtGuard::clear(cookies);
 
-    ApiResponse::success(
+    Ok(ApiResponse::success(
         "Logout successful - token and user info removed".to_string(),
         None,
-    )
+    ))
+}
+
+/// Maximum number of password reset requests per email address per hour.
+const PASSWORD_RESET_REQUEST_LIMIT: u64 = 3;
+
+/// Sends a password reset link to the address, if it belongs to an active user.
+///
+/// The response is the same whether the address is known or not, so the endpoint can't be used
+/// to find out which addresses have an account.
+#[post("/password/forgot", data = "<form>")]
+pub async fn forgot_password(
+    form: Form<ForgotPasswordForm<'_>>,
+    db: Db,
+    redis: &State<RedisMutex>,
+) -> Result<Success<Null>, Error<Null>> {
+    // Limit the number of emails that can be sent to a single address
+    if cache::users::count_password_reset_request(redis, form.email).await?
+        > PASSWORD_RESET_REQUEST_LIMIT
+    {
+        return Err(ApiResponse::too_many_requests(
+            "Too many password reset requests; try again later".to_string(),
+        ));
+    }
+
+    // Only active users can reset their password
+    if let Ok(user) = database::get_user_by_email(&db, form.email).await {
+        if user.status == UserStatus::Active {
+            // Create a random token with a length of 64 characters
+            let token = cache::create_random_token(64);
+
+            // Add the token to the redis cache; containing the user ID
+            cache::users::add_password_reset_token(redis, &token, user.id).await?;
+
+            // Send the reset link to the user
+            let recipient = PublicUser::from(&user);
+            tokio::task::spawn_blocking(move || {
+                let _ = MailClient::no_reply().send_password_reset(&recipient, &token);
+            });
+        }
+    }
+
+    Ok(ApiResponse::success(
+        "If the address belongs to an account, a password reset link has been sent".to_string(),
+        None,
+    ))
+}
+
+/// Requests a change of the email address of the current user, who has to confirm the current
+/// password.
+///
+/// The address is not changed yet; a confirmation link is sent to the new address, which has to
+/// be confirmed at `/email/confirm/<token>`.
+#[post("/me/email", data = "<form>")]
+pub async fn change_email(
+    form: Form<ChangeEmailForm<'_>>,
+    guard: JwtGuard,
+    db: Db,
+    redis: &State<RedisMutex>,
+) -> Result<Success<Null>, Error<Null>> {
+    // Access tokens can't be used to change the email address
+    guard.require_session()?;
+    guard.deny_impersonation()?;
+
+    let user = database::get_user_by_id(&db, guard.get_user().id).await?;
+
+    // Verify the current password
+    let verified = Password::verify_password(form.password, &user.password).map_err(|e| {
+        ApiResponse::internal_server_error(format!("Password verification failed: {}", e))
+    })?;
+
+    if !verified {
+        return Err(ApiResponse::unauthorized(
+            "Current password is incorrect".to_string(),
+        ));
+    }
+
+    // The address must not belong to any user yet
+    if database::get_user_by_email(&db, form.email).await.is_ok() {
+        return Err(ApiResponse::conflict(
+            "Email address is already in use".to_string(),
+            form.email.to_string(),
+        ));
+    }
+
+    // Create a random token with a length of 64 characters
+    let token = cache::create_random_token(64);
+
+    // Add the token to the redis cache; containing the user ID and the new address
+    let change = EmailChange {
+        user: user.id,
+        email: form.email.to_string(),
+    };
+    cache::users::add_email_change_token(redis, &token, &change).await?;
+
+    // Send the confirmation link to the new address
+    let mut recipient = PublicUser::from(&user);
+    recipient.email = change.email;
+    tokio::task::spawn_blocking(move || {
+        let _ = MailClient::no_reply().send_email_change(&recipient, &token);
+    });
+
+    Ok(ApiResponse::success(
+        "A confirmation link has been sent to the new email address".to_string(),
+        None,
+    ))
+}
+
+/// Creates a personal access token for the current user.
+///
+/// The token is only returned in this response; only its hash is stored.
+#[post("/tokens", data = "<form>")]
+pub async fn create_access_token(
+    form: Form<AccessTokenForm<'_>>,
+    guard: JwtGuard,
+    db: Db,
+) -> Result<Success<NewAccessToken>, Error<Null>> {
+    // Access tokens can't be used to create new access tokens
+    guard.require_session()?;
+    guard.deny_impersonation()?;
+
+    let created_at = chrono::Utc::now().naive_utc();
+    let expires_at = created_at + chrono::Duration::days(form.expires_in_days);
+
+    let (access_token, token) = AccessToken::generate(
+        guard.get_user().id,
+        form.name,
+        &form.get_scopes(),
+        created_at,
+        expires_at,
+    );
+
+    let info = PublicAccessToken::from(&access_token);
+
+    access_tokens_database::insert_access_token(&db, access_token).await?;
+
+    Ok(ApiResponse::success(
+        format!("Access token '{}' created", info.name),
+        Some(NewAccessToken { token, info }),
+    ))
+}
+
+/// Starts acting as another user; for admins.
+///
+/// Returns a bearer token for the user, which expires after 30 minutes and can't be refreshed.
+/// Every request made with it is recorded in the impersonation logs.
+#[post("/impersonate/<id>")]
+pub async fn impersonate_user(
+    id: Uuid,
+    guard: JwtGuard,
+    client: ClientInfo,
+    db: Db,
+    keys: &State<JwtKeys>,
+    redis: &State<RedisMutex>,
+) -> Result<Success<ImpersonationToken>, Error<Null>> {
+    // Only a logged in admin, not someone already acting as another user
+    guard.require_session()?;
+    guard.deny_impersonation()?;
+
+    let admin = guard.get_user();
+    let user = database::get_user_by_id(&db, id).await?;
+
+    Policy::users_impersonate(&admin, &user)?;
+
+    let (token, expires_at) = JwtGuard::impersonate(&user, &admin, &client, keys, redis)
+        .await
+        .map_err(ApiResponse::internal_server_error)?;
+
+    // The requests made with the token are logged by the audit fairing, the start is logged here
+    let impersonation = Impersonation {
+        session_id: guard.get_session_id(),
+        admin_id: admin.id,
+        user_id: user.id,
+    };
+    let path = format!("{USERS}impersonate/{id}");
+    let log = ImpersonationLog::new(&impersonation, "POST", &path, Status::Ok.code);
+    impersonation_database::insert_impersonation_log(&db, log).await?;
+
+    Ok(ApiResponse::success(
+        format!("Impersonating user {}", user.username),
+        Some(ImpersonationToken {
+            token,
+            user: PublicUser::from(&user),
+            expires_at,
+        }),
+    ))
 }
 
 #[post("/create", format = "json", data = "<user>")]
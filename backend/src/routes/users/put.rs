@@ -1,12 +1,13 @@
+use chrono::Utc;
 use rocket::{form::Form, serde::json::Json, State};
 use uuid::Uuid;
 
 use crate::{
     api::{ApiResponse, Error, Null, Success},
-    auth::JwtGuard,
-    cache::{self, users::get_invite_token, RedisMutex},
+    auth::{invitations::find_pending_invitation, lockout, JwtGuard},
+    cache::{self, RedisMutex},
     database::{self, Db},
-    forms::password::Password,
+    forms::password::{ChangePasswordForm, Password},
     models::users::{PublicUser, UserRole, UserStatus, UserUpdate},
     policies::Policy,
 };
@@ -17,6 +18,7 @@ pub async fn update_user(
     update: Json<UserUpdate>,
     guard: JwtGuard,
     db: Db,
+    redis: &State<RedisMutex>,
 ) -> Result<Success<PublicUser>, Error<Null>> {
     // Check if the user is authorized to perform this action
     Policy::users_update_info(&guard.get_user(), id)?;
@@ -25,6 +27,9 @@ pub async fn update_user(
     let updated_user =
         database::users::update_user_information(&db, id, update.clone().into_inner()).await?;
 
+    // Make sure the next request of the user sees the new information
+    cache::users::remove_user_cache(redis, id).await;
+
     // Return a success response
     Ok(ApiResponse::success(
         "User updated successfully".to_string(),
@@ -39,8 +44,9 @@ pub async fn set_password_after_invite(
     db: Db,
     redis: &State<RedisMutex>,
 ) -> Result<Success<Vec<String>>, Error<Null>> {
-    // Get the user from the redis cache
-    let user_id = get_invite_token(redis, token).await?;
+    // Get the invitation from the database; gone when it can't be accepted anymore
+    let invitation = find_pending_invitation(&db, token).await?;
+    let user_id = invitation.invitee;
 
     // Verify that the password input match
     if !form.inputs_match() {
@@ -54,39 +60,185 @@ pub async fn set_password_after_invite(
         .hash_password()
         .map_err(|e| ApiResponse::internal_server_error(format!("Couldn't hash password: {e}")))?;
 
-    // Update the user and increment the UserStatus
-    if database::users::set_user_password(&db, user_id, password_hash).await? == 0 {
+    // Activate the user and accept the invitation, after which the link can't be used again
+    database::invitations::accept_invitation(
+        &db,
+        invitation,
+        password_hash,
+        Utc::now().naive_utc(),
+    )
+    .await
+    .map_err(|_| ApiResponse::bad_request(format!("User '{user_id}' not affected")))?;
+
+    // A new password ends any existing session
+    cache::sessions::remove_user_sessions(redis, user_id).await?;
+    cache::users::remove_user_cache(redis, user_id).await;
+
+    Ok(ApiResponse::success(
+        format!("User '{user_id}' successfully activated"),
+        None,
+    ))
+}
+
+#[put("/password/reset/<token>", data = "<form>")]
+pub async fn reset_password(
+    token: &str,
+    form: Form<Password<'_>>,
+    db: Db,
+    redis: &State<RedisMutex>,
+) -> Result<Success<Null>, Error<Null>> {
+    // Verify that the password input match
+    if !form.inputs_match() {
+        return Err(ApiResponse::bad_request(
+            "Password inputs do not match".to_string(),
+        ));
+    };
+
+    // Take the user from the redis cache; the token can only be used once
+    let user_id = cache::users::take_password_reset_token(redis, token).await?;
+
+    // The user may have been suspended or removed since the link was sent
+    let user = database::users::get_user_by_id(&db, user_id).await?;
+    if user.status != UserStatus::Active {
+        return Err(ApiResponse::bad_request(format!(
+            "User '{}' is not active",
+            user.username
+        )));
+    }
+
+    // Hash the provided password
+    let password_hash = form
+        .hash_password()
+        .map_err(|e| ApiResponse::internal_server_error(format!("Couldn't hash password: {e}")))?;
+
+    // Replace the password of the user
+    if database::users::update_user_password(&db, user_id, password_hash).await? == 0 {
         return Err(ApiResponse::bad_request(format!(
             "User '{user_id}' not affected"
         )));
     }
 
-    // Remove the invitation token from the cache
-    cache::users::remove_invite_token(redis, token)
+    // A new password ends any existing session
+    cache::sessions::remove_user_sessions(redis, user_id)
         .await
-        .map(|()| ApiResponse::success(format!("User '{user_id}' successfully activated"), None))
+        .map(|()| ApiResponse::success("Password reset successfully".to_string(), None))
+}
+
+/// Changes the password of the current user, who has to confirm the current password.
+///
+/// Every other session of the user is revoked; the session of the request stays logged in.
+#[put("/me/password", data = "<form>")]
+pub async fn change_password(
+    form: Form<ChangePasswordForm<'_>>,
+    guard: JwtGuard,
+    db: Db,
+    redis: &State<RedisMutex>,
+) -> Result<Success<Null>, Error<Null>> {
+    // Access tokens can't be used to change the password
+    guard.require_session()?;
+    guard.deny_impersonation()?;
+
+    let user = database::users::get_user_by_id(&db, guard.get_user().id).await?;
+
+    // Verify the current password
+    let verified = Password::verify_password(form.current, &user.password).map_err(|e| {
+        ApiResponse::internal_server_error(format!("Password verification failed: {}", e))
+    })?;
+
+    if !verified {
+        return Err(ApiResponse::unauthorized(
+            "Current password is incorrect".to_string(),
+        ));
+    }
+
+    // Hash the new password
+    let password_hash = form
+        .new
+        .hash_password()
+        .map_err(|e| ApiResponse::internal_server_error(format!("Couldn't hash password: {e}")))?;
+
+    // Replace the password of the user
+    if database::users::update_user_password(&db, user.id, password_hash).await? == 0 {
+        return Err(ApiResponse::bad_request(format!(
+            "User '{}' not affected",
+            user.id
+        )));
+    }
+
+    // Log out everywhere else, in case the old password was known to someone else
+    cache::sessions::remove_other_user_sessions(redis, user.id, guard.get_session_id())
+        .await
+        .map(|()| ApiResponse::success("Password changed successfully".to_string(), None))
+}
+
+/// Confirms an email change with the token from the link that was sent to the new address.
+#[put("/email/confirm/<token>")]
+pub async fn confirm_email_change(
+    token: &str,
+    db: Db,
+    redis: &State<RedisMutex>,
+) -> Result<Success<Null>, Error<Null>> {
+    // Get the requested change from the redis cache
+    let change = cache::users::get_email_change_token(redis, token).await?;
+
+    // The address may have been taken since the link was sent
+    if database::users::get_user_by_email(&db, &change.email)
+        .await
+        .is_ok()
+    {
+        cache::users::remove_email_change_token(redis, token).await?;
+        return Err(ApiResponse::conflict(
+            "Email address is already in use".to_string(),
+            change.email,
+        ));
+    }
+
+    // Replace the email address of the user
+    if database::users::update_user_email(&db, change.user, change.email).await? == 0 {
+        return Err(ApiResponse::bad_request(format!(
+            "User '{}' not affected",
+            change.user
+        )));
+    }
+
+    // The token can only be used once
+    cache::users::remove_email_change_token(redis, token).await?;
+
+    // Make sure the next request of the user sees the new address
+    cache::users::remove_user_cache(redis, change.user).await;
+
+    Ok(ApiResponse::success(
+        "Email address changed successfully".to_string(),
+        None,
+    ))
 }
 
 #[put("/update/<id>/<role>")]
 pub async fn update_role(
     id: Uuid,
-    role: i16,
+    role: Result<UserRole, String>,
     guard: JwtGuard,
     db: Db,
+    redis: &State<RedisMutex>,
 ) -> Result<Success<PublicUser>, Error<Null>> {
+    guard.deny_impersonation()?;
+
     let user = guard.get_user();
 
+    // Verify the validity of the user role
+    let role = role.map_err(ApiResponse::bad_request)?;
+
     // Check if the user is authorized to perform this action
     Policy::users_set_role(&user, role)?;
 
-    // Verify the validity of the user role
-    let user_role = UserRole::try_from(role).map_err(ApiResponse::bad_request)?;
-
     // Update the user role
     let updated_user = database::users::update_user_role(&db, id, role).await?;
 
+    // The new role applies to the next request of the user
+    cache::users::remove_user_cache(redis, id).await;
+
     Ok(ApiResponse::success(
-        format!("User role: {user_role:?}"),
+        format!("User role: {role:?}"),
         Some(updated_user),
     ))
 }
@@ -96,8 +248,11 @@ pub async fn suspend_user(
     id: Uuid,
     guard: JwtGuard,
     db: Db,
+    redis: &State<RedisMutex>,
 ) -> Result<Success<PublicUser>, Error<Null>> {
-    user_status_update(&db, id, &guard.get_user(), UserStatus::Suspended).await
+    guard.deny_impersonation()?;
+
+    user_status_update(&db, redis, id, &guard.get_user(), UserStatus::Suspended).await
 }
 
 #[put("/remove/<id>")]
@@ -105,12 +260,40 @@ pub async fn remove_user(
     id: Uuid,
     guard: JwtGuard,
     db: Db,
+    redis: &State<RedisMutex>,
 ) -> Result<Success<PublicUser>, Error<Null>> {
-    user_status_update(&db, id, &guard.get_user(), UserStatus::Removed).await
+    guard.deny_impersonation()?;
+
+    user_status_update(&db, redis, id, &guard.get_user(), UserStatus::Removed).await
+}
+
+/// Lifts the temporary lock after too many failed logins, before it expires.
+#[put("/unlock/<id>")]
+pub async fn unlock_user(
+    id: Uuid,
+    guard: JwtGuard,
+    db: Db,
+    redis: &State<RedisMutex>,
+) -> Result<Success<PublicUser>, Error<Null>> {
+    guard.deny_impersonation()?;
+
+    // Only admins can unlock accounts
+    Policy::users_unlock(&guard.get_user())?;
+
+    // The failures are counted by username
+    let user = database::users::get_user_by_id(&db, id).await?;
+
+    lockout::reset_login_failures(redis, &user.username).await;
+
+    Ok(ApiResponse::success(
+        format!("User '{}' unlocked", user.username),
+        Some(PublicUser::from(&user)),
+    ))
 }
 
 async fn user_status_update(
     db: &Db,
+    redis: &State<RedisMutex>,
     id: Uuid,
     user: &PublicUser,
     status: UserStatus,
@@ -122,7 +305,11 @@ async fn user_status_update(
     let message = format!("User status: {status:?}");
 
     // Update the user status
-    let updated_user = database::users::update_user_status(db, id, i16::from(status)).await?;
+    let updated_user = database::users::update_user_status(db, id, status).await?;
+
+    // Revoke all sessions of the user immediately
+    cache::sessions::remove_user_sessions(redis, id).await?;
+    cache::users::remove_user_cache(redis, id).await;
 
     Ok(ApiResponse::success(message, Some(updated_user)))
 }
@@ -2,10 +2,10 @@ use rocket::State;
 use uuid::Uuid;
 
 use crate::{
-    api::{Error, Null},
+    api::{ApiResponse, Error, Null},
     cache::{self, RedisMutex},
     database::{self, Db},
-    models::workspaces::WorkspaceWithMembers,
+    models::{roles::CustomRole, workspaces::WorkspaceWithMembers},
 };
 
 mod delete;
@@ -25,6 +25,22 @@ pub fn routes() -> Vec<rocket::Route> {
         put::update_workspace,                // PUT:     /workspaces/<id>/update
         post::reinvite_user_by_id,            // POST:    /workspaces/<id>/re-invite/<member>")]
         delete::remove_member_from_workspace, // DELETE:  /workspaces/<id>/remove-member/<member>
+        put::assign_role_to_member,           // PUT:     /workspaces/<id>/assign-role/<member>
+        get::get_workspace_permissions, // GET:     /workspaces/<id>/permissions?<user>&<explain>
+        get::get_workspace_roles,       // GET:     /workspaces/<id>/roles
+        post::create_custom_role,       // POST:    /workspaces/<id>/roles
+        put::update_custom_role,        // PUT:     /workspaces/<id>/roles/<role>
+        delete::delete_custom_role,     // DELETE:  /workspaces/<id>/roles/<role>
+        get::get_workspace_invitations, // GET:     /workspaces/<id>/invitations?<status>
+        delete::revoke_invitation,      // DELETE:  /workspaces/<id>/invitations/<invitation>
+        get::get_workspace_comments,    // GET:     /workspaces/<id>/comments
+        post::add_workspace_comment,    // POST:    /workspaces/<id>/comments
+        put::edit_workspace_comment,    // PUT:     /workspaces/<id>/comments/<comment>
+        delete::delete_workspace_comment, // DELETE:  /workspaces/<id>/comments/<comment>
+        get::get_workspace_labels,      // GET:     /workspaces/<id>/labels
+        post::create_workspace_label,   // POST:    /workspaces/<id>/labels
+        put::update_workspace_label,    // PUT:     /workspaces/<id>/labels/<label>
+        delete::delete_workspace_label, // DELETE:  /workspaces/<id>/labels/<label>
     ]
 }
 
@@ -51,3 +67,15 @@ pub async fn get_workspace_with_members(
         },
     )
 }
+
+/// A custom role of the workspace.
+pub fn find_custom_role(
+    workspace_with_members: &WorkspaceWithMembers,
+    role: Uuid,
+) -> Result<&CustomRole, Error<Null>> {
+    workspace_with_members
+        .roles
+        .iter()
+        .find(|custom_role| custom_role.id == role)
+        .ok_or_else(|| ApiResponse::not_found(format!("Role '{role}' not found")))
+}
@@ -1,4 +1,5 @@
-use rocket::{http::CookieJar, State};
+use chrono::Utc;
+use rocket::{http::Status, response::status::Custom, State};
 use uuid::Uuid;
 
 use crate::{
@@ -6,8 +7,9 @@ use crate::{
     auth::JwtGuard,
     cache::{self, RedisMutex},
     database::{self, Db},
-    models::workspaces::WorkspaceWithMembers,
-    policies::Policy,
+    models::{comments::Thread, invitations::InvitationStatus, workspaces::WorkspaceWithMembers},
+    policies::{permissions::PermissionResolver, Policy},
+    routes::workspaces::{find_custom_role, get_workspace_with_members},
 };
 
 /// Deletes a [`Workspace`] and related
@@ -17,10 +19,12 @@ pub async fn delete_workspace_by_id(
     id: Uuid,
     guard: JwtGuard,
     db: Db,
-    cookies: &CookieJar<'_>,
     redis: &State<RedisMutex>,
 ) -> Result<Success<Null>, Error<Null>> {
-    Policy::workspaces_remove(id, guard.get_user(), cookies)?;
+    // Not in the name of someone else
+    guard.deny_impersonation()?;
+
+    Policy::workspaces_remove(id, guard.get_user(), &PermissionResolver::new(&db, redis)).await?;
 
     // Remove the workspace from the database (relevant records
     // in different tables will be cascaded by Postgres)
@@ -42,10 +46,10 @@ pub async fn remove_member_from_workspace(
     member: Uuid,
     guard: JwtGuard,
     db: Db,
-    cookies: &CookieJar<'_>,
     redis: &State<RedisMutex>,
 ) -> Result<Success<WorkspaceWithMembers>, Error<Null>> {
-    Policy::workspaces_update_members(id, guard.get_user(), cookies)?;
+    Policy::workspaces_update_members(id, guard.get_user(), &PermissionResolver::new(&db, redis))
+        .await?;
 
     // Remove the member from the workspace
     let workspace_with_members =
@@ -60,3 +64,136 @@ pub async fn remove_member_from_workspace(
         Some(workspace_with_members),
     ))
 }
+
+/// Deletes a custom role; the members who had it get their built-in role back.
+#[delete("/<id>/roles/<role>")]
+pub async fn delete_custom_role(
+    id: Uuid,
+    role: Uuid,
+    guard: JwtGuard,
+    db: Db,
+    redis: &State<RedisMutex>,
+) -> Result<Success<Null>, Error<Null>> {
+    let workspace_with_members = get_workspace_with_members(id, &db, redis).await?;
+    let current = find_custom_role(&workspace_with_members, role)?;
+
+    Policy::workspaces_manage_roles(
+        id,
+        &current.get_permissions(),
+        guard.get_user(),
+        &PermissionResolver::new(&db, redis),
+    )
+    .await?;
+
+    let deleted_role = database::roles::delete_custom_role(&db, id, role).await?;
+
+    // The roles and the members are part of the cached workspace
+    cache::workspaces::remove_workspace_cache(redis, id).await;
+
+    Ok(ApiResponse::success(
+        format!("Role '{}' deleted", deleted_role.name),
+        None,
+    ))
+}
+
+/// Revokes a pending invitation, which also removes the invited user from the workspace.
+#[delete("/<id>/invitations/<invitation>")]
+pub async fn revoke_invitation(
+    id: Uuid,
+    invitation: Uuid,
+    guard: JwtGuard,
+    db: Db,
+    redis: &State<RedisMutex>,
+) -> Result<Success<Null>, Error<Null>> {
+    Policy::workspaces_invite_members(id, guard.get_user(), &PermissionResolver::new(&db, redis))
+        .await?;
+
+    let invitation = database::invitations::get_invitation(&db, id, invitation).await?;
+
+    // An accepted invitation made a member, who is removed as such
+    if !matches!(
+        invitation.status,
+        InvitationStatus::Pending | InvitationStatus::Expired
+    ) {
+        return Err(ApiResponse::bad_request(format!(
+            "Invitation '{}' is {}",
+            invitation.id,
+            invitation.status.to_string().to_lowercase()
+        )));
+    }
+
+    // Not found if it was accepted since it was read
+    let invitation_id = invitation.id;
+    let revoked = database::invitations::revoke_invitation(&db, invitation, Utc::now().naive_utc())
+        .await
+        .map_err(|e| match e {
+            Custom(status, _) if status == Status::NotFound => ApiResponse::bad_request(format!(
+                "Invitation '{invitation_id}' is no longer pending"
+            )),
+            e => e,
+        })?;
+
+    // The invited user is no longer a member of the workspace
+    cache::workspaces::remove_workspace_cache(redis, id).await;
+
+    Ok(ApiResponse::success(
+        format!("Invitation '{}' revoked", revoked.id),
+        None,
+    ))
+}
+
+/// Deletes a comment, leaving a marker in its place so its replies stay in the thread.
+#[delete("/<id>/comments/<comment>")]
+pub async fn delete_workspace_comment(
+    id: Uuid,
+    comment: Uuid,
+    guard: JwtGuard,
+    db: Db,
+    redis: &State<RedisMutex>,
+) -> Result<Success<Null>, Error<Null>> {
+    let user = guard.get_user();
+    let workspace_with_members = get_workspace_with_members(id, &db, redis).await?;
+
+    Policy::comments_view_workspace(&user, &workspace_with_members)?;
+    Policy::comments_write_workspace(id, user.clone(), &PermissionResolver::new(&db, redis))
+        .await?;
+
+    let thread = Thread {
+        workspace: id,
+        project: None,
+    };
+    let comment = database::comments::get_comment(&db, thread, comment).await?;
+    Policy::comments_remove(&user, &comment)?;
+
+    if comment.deleted_at.is_some() {
+        return Err(ApiResponse::bad_request(
+            "The comment is already deleted".to_string(),
+        ));
+    }
+    database::comments::delete_comment(&db, comment.id, Utc::now().naive_utc()).await?;
+
+    Ok(ApiResponse::success("Comment deleted".to_string(), None))
+}
+
+/// Deletes a label of the workspace, which removes it from every project tagged with it.
+#[delete("/<id>/labels/<label>")]
+pub async fn delete_workspace_label(
+    id: Uuid,
+    label: Uuid,
+    guard: JwtGuard,
+    db: Db,
+    redis: &State<RedisMutex>,
+) -> Result<Success<Null>, Error<Null>> {
+    let user = guard.get_user();
+    let workspace_with_members = get_workspace_with_members(id, &db, redis).await?;
+
+    Policy::labels_view(&user, &workspace_with_members)?;
+    Policy::labels_manage(id, user, &PermissionResolver::new(&db, redis)).await?;
+
+    let label = database::labels::remove_label(&db, id, label).await?;
+
+    Ok(ApiResponse::success(
+        format!("Label '{}' deleted", label.name),
+        None,
+    ))
+}
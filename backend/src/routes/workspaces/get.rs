@@ -1,14 +1,27 @@
-use rocket::{http::CookieJar, State};
+use std::str::FromStr;
+
+use chrono::Utc;
+use rocket::State;
 use uuid::Uuid;
 
 use crate::{
     api::{ApiResponse, Error, Null, Success},
     auth::JwtGuard,
     cache::RedisMutex,
-    cookies,
     database::{self, Db},
-    models::workspaces::{Workspace, WorkspaceWithMembers},
-    policies::Policy,
+    models::{
+        comments::{Thread, ThreadedComment},
+        invitations::{InvitationStatus, PublicInvitation},
+        labels::Label,
+        permissions::EffectivePermissions,
+        roles::WorkspaceRoles,
+        workspaces::{Workspace, WorkspaceWithMembers},
+    },
+    policies::{
+        capabilities::{capability_subject, workspace_capabilities},
+        permissions::PermissionResolver,
+        Policy,
+    },
     routes::workspaces::get_workspace_with_members,
 };
 
@@ -36,7 +49,6 @@ pub async fn get_workspace_by_id(
     id: Uuid,
     guard: JwtGuard,
     db: Db,
-    cookies: &CookieJar<'_>,
     redis: &State<RedisMutex>,
 ) -> Result<Success<WorkspaceWithMembers>, Error<Null>> {
     let user = guard.get_user();
@@ -47,15 +59,6 @@ pub async fn get_workspace_by_id(
     // Return not found if the user is not an admin or a member
     Policy::workspaces_view(&user, &workspace_with_members)?;
 
-    // Insert the workspace permissions in cookies
-    if let Some(member) = workspace_with_members
-        .members
-        .iter()
-        .find(|m| m.user.id == user.id)
-    {
-        cookies::permissions::insert_workspace_permission(id, member.role, cookies)?;
-    }
-
     Ok(ApiResponse::success(
         format!(
             "Workspace '{}' from database",
@@ -64,3 +67,135 @@ pub async fn get_workspace_by_id(
         Some(workspace_with_members),
     ))
 }
+
+/// Returns the roles that can be given to the members of a workspace: the built-in presets and
+/// the custom roles of the workspace.
+#[get("/<id>/roles")]
+pub async fn get_workspace_roles(
+    id: Uuid,
+    guard: JwtGuard,
+    db: Db,
+    redis: &State<RedisMutex>,
+) -> Result<Success<WorkspaceRoles>, Error<Null>> {
+    let workspace_with_members = get_workspace_with_members(id, &db, redis).await?;
+
+    // Return not found if the user is not an admin or a member
+    Policy::workspaces_view(&guard.get_user(), &workspace_with_members)?;
+
+    Ok(ApiResponse::success(
+        format!("{} custom roles found", workspace_with_members.roles.len()),
+        Some(WorkspaceRoles::new(workspace_with_members.roles)),
+    ))
+}
+
+/// Returns what a user can do in the workspace. By default for the current user; admins can ask
+/// for any user and for the reason behind every capability.
+#[get("/<id>/permissions?<user>&<explain>")]
+pub async fn get_workspace_permissions(
+    id: Uuid,
+    user: Option<Uuid>,
+    explain: Option<bool>,
+    guard: JwtGuard,
+    db: Db,
+    redis: &State<RedisMutex>,
+) -> Result<Success<EffectivePermissions>, Error<Null>> {
+    let explain = explain.unwrap_or(false);
+    let workspace_with_members = get_workspace_with_members(id, &db, redis).await?;
+
+    // Return not found if the user is not an admin or a member
+    Policy::workspaces_view(&guard.get_user(), &workspace_with_members)?;
+
+    let subject = capability_subject(&db, guard.get_user(), user, explain).await?;
+    let permissions = workspace_capabilities(
+        &workspace_with_members,
+        &subject,
+        &PermissionResolver::new(&db, redis),
+        explain,
+    )
+    .await?;
+
+    Ok(ApiResponse::success(
+        format!("Permissions of '{}'", subject.username),
+        Some(permissions),
+    ))
+}
+
+/// Lists the invitations of the workspace, optionally only those with the given status.
+#[get("/<id>/invitations?<status>")]
+pub async fn get_workspace_invitations(
+    id: Uuid,
+    status: Option<&str>,
+    guard: JwtGuard,
+    db: Db,
+    redis: &State<RedisMutex>,
+) -> Result<Success<Vec<PublicInvitation>>, Error<Null>> {
+    Policy::workspaces_invite_members(id, guard.get_user(), &PermissionResolver::new(&db, redis))
+        .await?;
+
+    let status = status
+        .map(InvitationStatus::from_str)
+        .transpose()
+        .map_err(ApiResponse::bad_request)?;
+
+    // Invitations that weren't accepted in time are listed as expired
+    let now = Utc::now().naive_utc();
+    database::invitations::expire_invitations(&db, id, now).await?;
+
+    let invitations = database::invitations::get_invitations_by_workspace(&db, id, status)
+        .await?
+        .iter()
+        .map(|(invitation, invitee)| PublicInvitation::from(invitation, invitee, now))
+        .collect::<Vec<PublicInvitation>>();
+
+    Ok(ApiResponse::success(
+        format!("{} invitations found", invitations.len()),
+        Some(invitations),
+    ))
+}
+
+/// Returns the comments on the workspace, with the replies nested under the comments they answer.
+#[get("/<id>/comments")]
+pub async fn get_workspace_comments(
+    id: Uuid,
+    guard: JwtGuard,
+    db: Db,
+    redis: &State<RedisMutex>,
+) -> Result<Success<Vec<ThreadedComment>>, Error<Null>> {
+    let workspace_with_members = get_workspace_with_members(id, &db, redis).await?;
+
+    // Return not found if the user can't view the workspace
+    Policy::comments_view_workspace(&guard.get_user(), &workspace_with_members)?;
+
+    let thread = Thread {
+        workspace: id,
+        project: None,
+    };
+    let comments = database::comments::get_thread(&db, thread).await?;
+    let total = comments.len();
+
+    Ok(ApiResponse::success(
+        format!("{total} comments found"),
+        Some(ThreadedComment::thread(comments)),
+    ))
+}
+
+/// Returns the labels of the workspace, by name.
+#[get("/<id>/labels")]
+pub async fn get_workspace_labels(
+    id: Uuid,
+    guard: JwtGuard,
+    db: Db,
+    redis: &State<RedisMutex>,
+) -> Result<Success<Vec<Label>>, Error<Null>> {
+    let workspace_with_members = get_workspace_with_members(id, &db, redis).await?;
+
+    // Return not found if the user can't view the workspace
+    Policy::labels_view(&guard.get_user(), &workspace_with_members)?;
+
+    let labels = database::labels::get_labels(&db, id).await?;
+
+    Ok(ApiResponse::success(
+        format!("{} labels found", labels.len()),
+        Some(labels),
+    ))
+}
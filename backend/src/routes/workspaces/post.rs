@@ -1,24 +1,30 @@
 use std::collections::HashSet;
 
-use rocket::{form::Form, http::CookieJar, serde::json::Json, State};
+use chrono::Utc;
+use rocket::{form::Form, serde::json::Json, State};
 use uuid::Uuid;
 
 use crate::{
     api::{ApiResponse, Error, Null, Success},
-    auth::JwtGuard,
+    auth::{invitations::InvitationConfig, JwtGuard},
     cache::{self, RedisMutex},
-    cookies,
     database::{self, Db},
     email::MailClient,
     forms::{
-        invite::InvitedMultipleUsersForm, projects::NewProjectForm, workspace::NewWorkspaceForm,
+        invite::InvitedMultipleUsersForm, projects::NewProjectForm, roles::CustomRoleForm,
+        workspace::NewWorkspaceForm,
     },
     models::{
-        projects::{NewProject, ProjectRole, ProjectWithMembers},
+        comments::{resolve_mentions, InsertableComment, NewComment, PublicComment, Thread},
+        invitations::Invitation,
+        labels::{Label, NewLabel},
+        projects::{NewProject, ProjectWithMembers},
+        roles::CustomRole,
         users::{InvitedUser, PublicUser, UserStatus},
-        workspaces::{NewWorkspace, WorkspaceMember, WorkspaceRole, WorkspaceWithMembers},
+        workspaces::{NewWorkspace, WorkspaceMember, WorkspaceWithMembers},
     },
-    policies::Policy,
+    policies::{permissions::PermissionResolver, Policy},
+    routes::workspaces::get_workspace_with_members,
 };
 
 const MAX_SIMILAR_USERNAMES: usize = 100;
@@ -27,7 +33,6 @@ const MAX_SIMILAR_USERNAMES: usize = 100;
 pub async fn create_new_workspace_by_form(
     form: Form<NewWorkspaceForm>,
     guard: JwtGuard,
-    cookies: &CookieJar<'_>,
     redis: &State<RedisMutex>,
     db: Db,
 ) -> Result<Success<WorkspaceWithMembers>, Error<Null>> {
@@ -47,13 +52,6 @@ pub async fn create_new_workspace_by_form(
     // Add the workspace information to the cache
     cache::workspaces::add_workspace_cache(redis, &workspace_with_members).await;
 
-    // Add the workspace permission to cookies
-    cookies::permissions::insert_workspace_permission(
-        workspace_with_members.workspace.id,
-        i16::from(WorkspaceRole::Owner),
-        cookies,
-    )?;
-
     // Return success response
     Ok(ApiResponse::success(
         format!(
@@ -69,20 +67,21 @@ pub async fn add_members_to_workspace(
     id: Uuid,
     members: Json<Vec<WorkspaceMember>>,
     guard: JwtGuard,
-    cookies: &CookieJar<'_>,
     redis: &State<RedisMutex>,
     db: Db,
 ) -> Result<Success<WorkspaceWithMembers>, Error<Null>> {
     // Only allow this function if the user is admin or the workspace permissions are sufficient.
-    Policy::workspaces_update_members(id, guard.get_user(), cookies)?;
+    Policy::workspaces_update_members(id, guard.get_user(), &PermissionResolver::new(&db, redis))
+        .await?;
 
     // Cannot add an empty vector
     if members.is_empty() {
         return Err(ApiResponse::bad_request("No members to add".to_string()));
     }
 
-    // Extract the members length before going out of scope
+    // Extract the members length and IDs before going out of scope
     let members_len = members.len();
+    let member_ids = members.iter().map(|member| member.member).collect();
 
     // Add members to the workspace in the database
     let workspace_with_members =
@@ -91,6 +90,11 @@ pub async fn add_members_to_workspace(
     // Update the workspace information in the cache
     cache::workspaces::add_workspace_cache(redis, &workspace_with_members).await;
 
+    // Guests of its projects are now regular members of those projects
+    for project in database::projects::remove_guest_status(&db, id, member_ids).await? {
+        cache::projects::remove_project_cache(redis, project).await;
+    }
+
     // Return success response
     Ok(ApiResponse::success(
         format!(
@@ -107,11 +111,12 @@ pub async fn invite_new_users_to_workspace(
     guard: JwtGuard,
     form: Form<InvitedMultipleUsersForm<'_>>,
     db: Db,
-    cookies: &CookieJar<'_>,
     redis: &State<RedisMutex>,
+    invitation_config: &State<InvitationConfig>,
 ) -> Result<Success<Vec<String>>, Error<Null>> {
     // Only allow this function if the user is admin or the workspace permissions are sufficient.
-    Policy::workspaces_update_members(id, guard.get_user(), cookies)?;
+    Policy::workspaces_invite_members(id, guard.get_user(), &PermissionResolver::new(&db, redis))
+        .await?;
 
     // Create a vector of Users and a HashSet of base usernames from the form
     let (mut invited_users, base_usernames) = form
@@ -126,27 +131,34 @@ pub async fn invite_new_users_to_workspace(
     assign_unique_usernames(&mut invited_users, &mut existing_usernames)
         .map_err(ApiResponse::bad_request)?;
 
-    // Insert the new users into the database in a single transaction
-    let inserted_users =
-        database::workspaces::create_transaction_bulk_invitation(&db, id, invited_users).await?;
+    // Insert the new users and their invitations into the database in a single transaction
+    let inviter = guard.get_user();
+    let sent_at = Utc::now().naive_utc();
+    let inserted_users = database::workspaces::create_transaction_bulk_invitation(
+        &db,
+        id,
+        inviter.id,
+        invited_users,
+        sent_at,
+        invitation_config.expires_at(sent_at),
+    )
+    .await?;
+
+    // The invited users are members now, so the cached members are outdated
+    cache::workspaces::remove_workspace_cache(redis, id).await;
 
     // Declare a vector to keep the tokens
     let mut tokens = Vec::new();
 
     // Loop through the collection of new users
-    for user in &inserted_users {
-        // Create a random token with a length of 64 characters
-        let token = cache::create_random_token(64);
-
+    for (user, token) in &inserted_users {
         // Save the token for the response
         tokens.push(token.clone());
 
-        // Add the token to the redis cache; containing the user ID
-        cache::users::add_invite_token(redis, &token, user.id).await?;
-
-        let inviter = guard.get_user();
+        let inviter = inviter.clone();
         let recipient = PublicUser::from(user);
         let workspace_name = get_workspace_name(id, &db, redis).await?;
+        let token = token.clone();
 
         // Send an invitation email to the new users, containing the token
         tokio::task::spawn_blocking(move || {
@@ -166,6 +178,8 @@ pub async fn invite_new_users_to_workspace(
     ))
 }
 
+/// Sends a new link to an invited user, renewing the invitation to the workspace.
+///
 /// TODO!:
 /// - Adding space/project functionality
 /// - Inviting only when a certain role in space
@@ -175,42 +189,67 @@ pub async fn reinvite_user_by_id(
     member: Uuid,
     guard: JwtGuard,
     db: Db,
-    cookies: &CookieJar<'_>,
     redis: &State<RedisMutex>,
+    invitation_config: &State<InvitationConfig>,
 ) -> Result<Success<String>, Error<Null>> {
     // Only allow this function if the user is admin or the workspace permissions are sufficient.
-    Policy::workspaces_update_members(id, guard.get_user(), cookies)?;
+    Policy::workspaces_invite_members(id, guard.get_user(), &PermissionResolver::new(&db, redis))
+        .await?;
 
     // Get the user from the database
     let user = database::users::get_user_by_id(&db, member).await?;
 
-    // Extract the user status
-    let user_status =
-        UserStatus::try_from(user.status).map_err(|e| ApiResponse::conflict(e, String::new()))?;
-
     // Make sure the user status is still on invited
-    if !matches!(user_status, UserStatus::Invited) {
+    if user.status != UserStatus::Invited {
         return Err(ApiResponse::bad_request(format!(
-            "User {} has status {user_status:?}",
-            user.username,
+            "User {} has status {:?}",
+            user.username, user.status,
         )));
     };
 
-    // Create a random token with a length of 64 characters
-    let token = cache::create_random_token(64);
+    // The role of the previous invitation, or of the membership of a user invited before
+    // invitations were stored
+    let role = match database::invitations::get_invitation_of_user(&db, id, member).await? {
+        Some(invitation) => invitation.role,
+        None => get_workspace_with_members(id, &db, redis)
+            .await?
+            .members
+            .iter()
+            .find(|workspace_member| workspace_member.user.id == member)
+            .map(|workspace_member| workspace_member.role)
+            .ok_or_else(|| ApiResponse::not_found(format!("No invitation of '{member}' found")))?,
+    };
 
-    // Add the token to the redis cache; containing the user ID
-    cache::users::add_invite_token(redis, &token, user.id).await?;
+    // The same invitation gets a new token and expiry
+    let inviter = guard.get_user();
+    let sent_at = Utc::now().naive_utc();
+    let (invitation, token) = Invitation::generate(
+        id,
+        member,
+        inviter.id,
+        role,
+        sent_at,
+        invitation_config.expires_at(sent_at),
+    );
+    database::invitations::renew_invitation(&db, invitation).await?;
+
+    // A revoked invitation removed the user from the workspace
+    cache::workspaces::remove_workspace_cache(redis, id).await;
 
     // Get the required information for the invitation email
-    let inviter = guard.get_user();
     let recipient = PublicUser::from(&user);
     let workspace_name = get_workspace_name(id, &db, redis).await?;
 
-    // Send the email
-    MailClient::no_reply()
-        .send_invitation(&inviter, &recipient, &workspace_name, &token)
-        .map_err(ApiResponse::internal_server_error)?;
+    // Send the email; like the first invitation, the invitation is stored either way
+    let email_token = token.clone();
+    tokio::task::spawn_blocking(move || {
+        let _ = MailClient::no_reply().send_invitation(
+            &inviter,
+            &recipient,
+            &workspace_name,
+            &email_token,
+        );
+    });
 
     Ok(ApiResponse::success(
         format!("{} invited", user.username),
@@ -269,12 +308,11 @@ pub async fn create_new_project_by_form(
     id: Uuid,
     form: Form<NewProjectForm>,
     guard: JwtGuard,
-    cookies: &CookieJar<'_>,
     redis: &State<RedisMutex>,
     db: Db,
 ) -> Result<Success<ProjectWithMembers>, Error<Null>> {
     // Validate user permissions
-    Policy::projects_create(id, guard.get_user(), cookies)?;
+    Policy::projects_create(id, guard.get_user(), &PermissionResolver::new(&db, redis)).await?;
 
     // Extract the important information from the form
     let new_project = NewProject::from_form(form.into_inner());
@@ -285,16 +323,110 @@ pub async fn create_new_project_by_form(
     // Add the project information to the cache
     cache::projects::add_project_cache(redis, &project_with_members).await;
 
-    // Add the project permission to cookies
-    cookies::permissions::insert_project_permission(
-        project_with_members.project.id,
-        i16::from(ProjectRole::Owner),
-        cookies,
-    )?;
-
     // Return success response
     Ok(ApiResponse::success(
         format!("Project created: '{}'", project_with_members.project.name),
         Some(project_with_members),
     ))
 }
+
+/// Defines a custom role in the workspace, which can then be assigned to members.
+#[post("/<id>/roles", data = "<form>")]
+pub async fn create_custom_role(
+    id: Uuid,
+    form: Form<CustomRoleForm<'_>>,
+    guard: JwtGuard,
+    redis: &State<RedisMutex>,
+    db: Db,
+) -> Result<Success<CustomRole>, Error<Null>> {
+    let role = CustomRole::new(id, &form);
+
+    Policy::workspaces_manage_roles(
+        id,
+        &role.get_permissions(),
+        guard.get_user(),
+        &PermissionResolver::new(&db, redis),
+    )
+    .await?;
+
+    let role = database::roles::insert_custom_role(&db, role).await?;
+
+    // The roles are part of the cached workspace
+    cache::workspaces::remove_workspace_cache(redis, id).await;
+
+    Ok(ApiResponse::success(
+        format!("Role '{}' created", role.name),
+        Some(role),
+    ))
+}
+
+/// Comments on the workspace, or replies to one of its comments; the members mentioned with
+/// `@username` are stored with the comment.
+#[post("/<id>/comments", format = "json", data = "<comment>")]
+pub async fn add_workspace_comment(
+    id: Uuid,
+    comment: Json<NewComment>,
+    guard: JwtGuard,
+    db: Db,
+    redis: &State<RedisMutex>,
+) -> Result<Success<PublicComment>, Error<Null>> {
+    let user = guard.get_user();
+    let workspace_with_members = get_workspace_with_members(id, &db, redis).await?;
+
+    Policy::comments_view_workspace(&user, &workspace_with_members)?;
+    Policy::comments_write_workspace(id, user.clone(), &PermissionResolver::new(&db, redis))
+        .await?;
+
+    comment.validate().map_err(ApiResponse::bad_request)?;
+
+    let comment = comment.into_inner();
+    let mentions = resolve_mentions(
+        &comment.body,
+        workspace_with_members
+            .members
+            .iter()
+            .map(|member| &member.user),
+    );
+    let thread = Thread {
+        workspace: id,
+        project: None,
+    };
+    let new_comment = InsertableComment {
+        workspace: id,
+        project: None,
+        parent: comment.parent,
+        author: Some(user.id),
+        body: comment.body,
+    };
+    let comment = database::comments::insert_comment(&db, thread, new_comment, mentions).await?;
+
+    Ok(ApiResponse::success(
+        "Comment added".to_string(),
+        Some(comment),
+    ))
+}
+
+/// Adds a label to the workspace, which its projects can then be tagged with.
+#[post("/<id>/labels", format = "json", data = "<label>")]
+pub async fn create_workspace_label(
+    id: Uuid,
+    label: Json<NewLabel>,
+    guard: JwtGuard,
+    db: Db,
+    redis: &State<RedisMutex>,
+) -> Result<Success<Label>, Error<Null>> {
+    let user = guard.get_user();
+    let workspace_with_members = get_workspace_with_members(id, &db, redis).await?;
+
+    Policy::labels_view(&user, &workspace_with_members)?;
+    Policy::labels_manage(id, user, &PermissionResolver::new(&db, redis)).await?;
+
+    label.validate().map_err(ApiResponse::bad_request)?;
+
+    let label = database::labels::insert_label(&db, label.into_inner().into_insertable(id)).await?;
+
+    Ok(ApiResponse::success(
+        format!("Label '{}' created", label.name),
+        Some(label),
+    ))
+}
@@ -1,4 +1,5 @@
-use rocket::{http::CookieJar, serde::json::Json, State};
+use chrono::Utc;
+use rocket::{form::Form, serde::json::Json, State};
 use uuid::Uuid;
 
 use crate::{
@@ -6,8 +7,15 @@ use crate::{
     auth::JwtGuard,
     cache::{self, RedisMutex},
     database::{self, Db},
-    models::workspaces::{Workspace, WorkspaceUpdate},
-    policies::Policy,
+    forms::roles::CustomRoleForm,
+    models::{
+        comments::{resolve_mentions, CommentUpdate, PublicComment, Thread},
+        labels::{Label, LabelUpdate},
+        roles::{CustomRole, CustomRoleUpdate, RoleAssignment},
+        workspaces::{Workspace, WorkspaceUpdate, WorkspaceWithMembers},
+    },
+    policies::{permissions::PermissionResolver, Policy},
+    routes::workspaces::{find_custom_role, get_workspace_with_members},
 };
 
 #[put("/<id>/update", format = "json", data = "<update>")]
@@ -16,11 +24,11 @@ pub async fn update_workspace(
     update: Json<WorkspaceUpdate>,
     guard: JwtGuard,
     db: Db,
-    cookies: &CookieJar<'_>,
     redis: &State<RedisMutex>,
 ) -> Result<Success<Workspace>, Error<Null>> {
     // Check if the user is authorized to perform this action
-    Policy::workspaces_update_info(id, guard.get_user(), cookies)?;
+    Policy::workspaces_update_info(id, guard.get_user(), &PermissionResolver::new(&db, redis))
+        .await?;
 
     // Update the workspace information in the database
     let updated_workspace =
@@ -36,3 +44,160 @@ pub async fn update_workspace(
         Some(updated_workspace),
     ))
 }
+
+/// Replaces the name and the permissions of a custom role.
+#[put("/<id>/roles/<role>", data = "<form>")]
+pub async fn update_custom_role(
+    id: Uuid,
+    role: Uuid,
+    form: Form<CustomRoleForm<'_>>,
+    guard: JwtGuard,
+    db: Db,
+    redis: &State<RedisMutex>,
+) -> Result<Success<CustomRole>, Error<Null>> {
+    let workspace_with_members = get_workspace_with_members(id, &db, redis).await?;
+    let current = find_custom_role(&workspace_with_members, role)?;
+
+    // Both the permissions taken away and the ones given have to be held by the user
+    let mut granted = current.get_permissions();
+    granted.extend(form.get_permissions());
+
+    Policy::workspaces_manage_roles(
+        id,
+        &granted,
+        guard.get_user(),
+        &PermissionResolver::new(&db, redis),
+    )
+    .await?;
+
+    let updated_role =
+        database::roles::update_custom_role(&db, id, role, CustomRoleUpdate::from(&*form)).await?;
+
+    // The roles are part of the cached workspace
+    cache::workspaces::remove_workspace_cache(redis, id).await;
+
+    Ok(ApiResponse::success(
+        format!("Role '{}' updated", updated_role.name),
+        Some(updated_role),
+    ))
+}
+
+/// Gives a member a built-in role, or one of the custom roles of the workspace.
+#[put("/<id>/assign-role/<member>", format = "json", data = "<assignment>")]
+pub async fn assign_role_to_member(
+    id: Uuid,
+    member: Uuid,
+    assignment: Json<RoleAssignment>,
+    guard: JwtGuard,
+    db: Db,
+    redis: &State<RedisMutex>,
+) -> Result<Success<WorkspaceWithMembers>, Error<Null>> {
+    let workspace_with_members = get_workspace_with_members(id, &db, redis).await?;
+
+    let granted = match *assignment {
+        RoleAssignment::Preset(role) => role.permissions().iter().copied().collect(),
+        RoleAssignment::Custom(role) => {
+            find_custom_role(&workspace_with_members, role)?.get_permissions()
+        }
+    };
+
+    Policy::workspaces_assign_role(
+        id,
+        member,
+        &granted,
+        guard.get_user(),
+        &PermissionResolver::new(&db, redis),
+    )
+    .await?;
+
+    if database::roles::assign_role(&db, id, member, assignment.into_inner()).await? == 0 {
+        return Err(ApiResponse::not_found(format!(
+            "Member '{member}' not found"
+        )));
+    }
+
+    // Refresh the workspace in the cache, so the role applies to the next request
+    cache::workspaces::remove_workspace_cache(redis, id).await;
+    let workspace_with_members = get_workspace_with_members(id, &db, redis).await?;
+
+    Ok(ApiResponse::success(
+        format!("Role of member '{member}' updated"),
+        Some(workspace_with_members),
+    ))
+}
+
+/// Replaces the body of a comment by its author, which marks the comment as edited.
+#[put("/<id>/comments/<comment>", format = "json", data = "<update>")]
+pub async fn edit_workspace_comment(
+    id: Uuid,
+    comment: Uuid,
+    update: Json<CommentUpdate>,
+    guard: JwtGuard,
+    db: Db,
+    redis: &State<RedisMutex>,
+) -> Result<Success<PublicComment>, Error<Null>> {
+    let user = guard.get_user();
+    let workspace_with_members = get_workspace_with_members(id, &db, redis).await?;
+
+    Policy::comments_view_workspace(&user, &workspace_with_members)?;
+    Policy::comments_write_workspace(id, user.clone(), &PermissionResolver::new(&db, redis))
+        .await?;
+
+    let thread = Thread {
+        workspace: id,
+        project: None,
+    };
+    let comment = database::comments::get_comment(&db, thread, comment).await?;
+    Policy::comments_edit(&user, &comment)?;
+
+    if comment.deleted_at.is_some() {
+        return Err(ApiResponse::bad_request(
+            "A deleted comment can't be edited".to_string(),
+        ));
+    }
+    update.validate().map_err(ApiResponse::bad_request)?;
+
+    let body = update.into_inner().body;
+    let mentions = resolve_mentions(
+        &body,
+        workspace_with_members
+            .members
+            .iter()
+            .map(|member| &member.user),
+    );
+    let comment =
+        database::comments::edit_comment(&db, comment.id, body, mentions, Utc::now().naive_utc())
+            .await?;
+
+    Ok(ApiResponse::success(
+        "Comment edited".to_string(),
+        Some(comment),
+    ))
+}
+
+/// Updates a label of the workspace, which every project tagged with it shows right away.
+#[put("/<id>/labels/<label>", format = "json", data = "<update>")]
+pub async fn update_workspace_label(
+    id: Uuid,
+    label: Uuid,
+    update: Json<LabelUpdate>,
+    guard: JwtGuard,
+    db: Db,
+    redis: &State<RedisMutex>,
+) -> Result<Success<Label>, Error<Null>> {
+    let user = guard.get_user();
+    let workspace_with_members = get_workspace_with_members(id, &db, redis).await?;
+
+    Policy::labels_view(&user, &workspace_with_members)?;
+    Policy::labels_manage(id, user, &PermissionResolver::new(&db, redis)).await?;
+
+    update.validate().map_err(ApiResponse::bad_request)?;
+
+    let label =
+        database::labels::update_label(&db, id, label, update.into_inner().normalized()).await?;
+
+    Ok(ApiResponse::success(
+        format!("Label '{}' updated", label.name),
+        Some(label),
+    ))
+}
@@ -1,10 +1,178 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    access_tokens (id) {
+        id -> Uuid,
+        user_id -> Uuid,
+        #[max_length = 40]
+        name -> Varchar,
+        token_hash -> Text,
+        scopes -> Array<Text>,
+        expires_at -> Timestamp,
+        last_used_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    board_cards (id) {
+        id -> Uuid,
+        project -> Uuid,
+        board_column -> Uuid,
+        #[max_length = 200]
+        title -> Varchar,
+        description -> Nullable<Text>,
+        assignee -> Nullable<Uuid>,
+        rank -> Text,
+        version -> Int4,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    board_columns (id) {
+        id -> Uuid,
+        board -> Uuid,
+        #[max_length = 80]
+        name -> Varchar,
+        rank -> Text,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    boards (id) {
+        id -> Uuid,
+        project -> Uuid,
+        #[max_length = 80]
+        name -> Varchar,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    comment_mentions (comment, mentioned) {
+        comment -> Uuid,
+        mentioned -> Uuid,
+    }
+}
+
+diesel::table! {
+    comments (id) {
+        id -> Uuid,
+        workspace -> Uuid,
+        project -> Nullable<Uuid>,
+        parent -> Nullable<Uuid>,
+        author -> Nullable<Uuid>,
+        body -> Text,
+        edited_at -> Nullable<Timestamp>,
+        deleted_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    deliverable_status_changes (id) {
+        id -> Uuid,
+        deliverable -> Uuid,
+        status -> Int2,
+        changed_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    deliverables (id) {
+        id -> Uuid,
+        iteration -> Uuid,
+        #[max_length = 200]
+        title -> Varchar,
+        status -> Int2,
+        rolled_over_from -> Nullable<Uuid>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    impersonation_logs (id) {
+        id -> Uuid,
+        session_id -> Uuid,
+        admin_id -> Uuid,
+        user_id -> Uuid,
+        #[max_length = 10]
+        method -> Varchar,
+        path -> Text,
+        status -> Int2,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    invitations (id) {
+        id -> Uuid,
+        workspace -> Uuid,
+        invitee -> Uuid,
+        inviter -> Nullable<Uuid>,
+        role -> Int2,
+        status -> Int2,
+        token_hash -> Text,
+        expires_at -> Timestamp,
+        sent_at -> Timestamp,
+        accepted_at -> Nullable<Timestamp>,
+        revoked_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    iterations (id) {
+        id -> Uuid,
+        project -> Uuid,
+        kind -> Int2,
+        #[max_length = 80]
+        name -> Varchar,
+        goal -> Nullable<Text>,
+        start_date -> Date,
+        end_date -> Date,
+        state -> Int2,
+        closed_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    labels (id) {
+        id -> Uuid,
+        workspace -> Uuid,
+        #[max_length = 40]
+        name -> Varchar,
+        #[max_length = 7]
+        color -> Varchar,
+        description -> Nullable<Text>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    project_labels (project, label) {
+        project -> Uuid,
+        label -> Uuid,
+    }
+}
+
 diesel::table! {
     project_members (project, member) {
         project -> Uuid,
         member -> Uuid,
         role -> Int2,
+        guest -> Bool,
     }
 }
 
@@ -22,6 +190,23 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    tasks (id) {
+        id -> Uuid,
+        project -> Uuid,
+        #[max_length = 200]
+        title -> Varchar,
+        description -> Nullable<Text>,
+        status -> Int2,
+        priority -> Int2,
+        assignee -> Nullable<Uuid>,
+        reporter -> Nullable<Uuid>,
+        due_date -> Nullable<Date>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     users (id) {
         id -> Uuid,
@@ -47,11 +232,47 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    user_identities (issuer, subject) {
+        issuer -> Text,
+        subject -> Text,
+        user_id -> Uuid,
+        #[max_length = 100]
+        email -> Nullable<Varchar>,
+        created_at -> Timestamp,
+        last_login_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    user_two_factor (user_id) {
+        user_id -> Uuid,
+        secret -> Text,
+        enabled -> Bool,
+        recovery_codes -> Array<Text>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     workspace_members (workspace, member) {
         workspace -> Uuid,
         member -> Uuid,
         role -> Int2,
+        custom_role -> Nullable<Uuid>,
+    }
+}
+
+diesel::table! {
+    workspace_roles (id) {
+        id -> Uuid,
+        workspace -> Uuid,
+        #[max_length = 40]
+        name -> Varchar,
+        permissions -> Array<Text>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
     }
 }
 
@@ -68,16 +289,56 @@ diesel::table! {
     }
 }
 
+diesel::joinable!(access_tokens -> users (user_id));
+diesel::joinable!(board_cards -> board_columns (board_column));
+diesel::joinable!(board_cards -> projects (project));
+diesel::joinable!(board_columns -> boards (board));
+diesel::joinable!(boards -> projects (project));
+diesel::joinable!(comment_mentions -> comments (comment));
+diesel::joinable!(comment_mentions -> users (mentioned));
+diesel::joinable!(comments -> projects (project));
+diesel::joinable!(comments -> users (author));
+diesel::joinable!(comments -> workspaces (workspace));
+diesel::joinable!(deliverable_status_changes -> deliverables (deliverable));
+diesel::joinable!(deliverables -> iterations (iteration));
+diesel::joinable!(invitations -> workspaces (workspace));
+diesel::joinable!(iterations -> projects (project));
+diesel::joinable!(labels -> workspaces (workspace));
+diesel::joinable!(project_labels -> labels (label));
+diesel::joinable!(project_labels -> projects (project));
 diesel::joinable!(project_members -> projects (project));
 diesel::joinable!(project_members -> users (member));
 diesel::joinable!(projects -> workspaces (workspace));
+diesel::joinable!(tasks -> projects (project));
+diesel::joinable!(tasks -> users (reporter));
+diesel::joinable!(user_identities -> users (user_id));
+diesel::joinable!(user_two_factor -> users (user_id));
 diesel::joinable!(workspace_members -> users (member));
+diesel::joinable!(workspace_members -> workspace_roles (custom_role));
 diesel::joinable!(workspace_members -> workspaces (workspace));
+diesel::joinable!(workspace_roles -> workspaces (workspace));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    access_tokens,
+    board_cards,
+    board_columns,
+    boards,
+    comment_mentions,
+    comments,
+    deliverable_status_changes,
+    deliverables,
+    impersonation_logs,
+    invitations,
+    iterations,
+    labels,
+    project_labels,
     project_members,
     projects,
+    tasks,
+    user_identities,
+    user_two_factor,
     users,
     workspace_members,
+    workspace_roles,
     workspaces,
 );
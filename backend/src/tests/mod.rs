@@ -1,5 +1,5 @@
 use rocket::{
-    http::Status,
+    http::{Header, Status},
     local::{
         asynchronous::Client as AsyncClient,
         blocking::{Client, LocalRequest},
@@ -7,6 +7,8 @@ use rocket::{
 };
 use serde_json::Value;
 
+use crate::cookies::{CSRF_COOKIE, CSRF_HEADER};
+
 pub mod projects;
 pub mod users;
 pub mod workspaces;
@@ -21,6 +23,30 @@ pub async fn async_test_client() -> AsyncClient {
         .expect("valid rocket instance")
 }
 
+/// The CSRF header for the session the client is logged in with; to be sent along with every
+/// mutating request.
+pub fn csrf(client: &Client) -> Header<'static> {
+    csrf_header(
+        client
+            .cookies()
+            .get(CSRF_COOKIE)
+            .map(|cookie| cookie.value()),
+    )
+}
+
+pub fn async_csrf(client: &AsyncClient) -> Header<'static> {
+    csrf_header(
+        client
+            .cookies()
+            .get(CSRF_COOKIE)
+            .map(|cookie| cookie.value()),
+    )
+}
+
+fn csrf_header(token: Option<&str>) -> Header<'static> {
+    Header::new(CSRF_HEADER, token.unwrap_or_default().to_string())
+}
+
 fn root_route(base: &str) -> String {
     let mut route = base.to_string();
     route.pop(); // Remove the tailing slash; it will invalidate the endpoint
@@ -1,16 +1,35 @@
+use rocket::{
+    http::{ContentType, Status},
+    local::blocking::Client,
+};
+use uuid::Uuid;
+
 use crate::{
-    routes::PROJECTS,
-    tests::{root_route, workspaces::TARGETED_WORKSPACE},
+    api::ApiResponse,
+    forms::projects::NewProjectForm,
+    models::projects::{ProjectMember, ProjectRole, ProjectWithMembers},
+    routes::{PROJECTS, WORKSPACES},
+    tests::{csrf, root_route, workspaces::TARGETED_WORKSPACE},
 };
 
 #[cfg(test)]
 mod adding_and_updating;
 #[cfg(test)]
+mod boards;
+#[cfg(test)]
+mod comments;
+#[cfg(test)]
 mod deleting_projects;
 #[cfg(test)]
 mod getting_projects;
 #[cfg(test)]
+mod guests;
+#[cfg(test)]
+mod iterations;
+#[cfg(test)]
 mod member_management;
+#[cfg(test)]
+mod tasks;
 
 const TARGETED_PROJECT: &str = "3465a06a-994f-4467-a6c4-3e949cf5e21b";
 
@@ -46,3 +65,47 @@ fn route_projects_add_member() -> String {
 fn route_projects_remove_member(id: &str) -> String {
     format!("{PROJECTS}{TARGETED_PROJECT}/remove-member/{id}")
 }
+
+/// Creates a project in the workspace, by a user allowed to.
+pub fn create_project(client: &Client, workspace: Uuid) -> Uuid {
+    let new_project = NewProjectForm {
+        name: "Test Project".to_string(),
+        description: None,
+    };
+
+    client
+        .post(format!("{WORKSPACES}{workspace}/new_project"))
+        .header(csrf(client))
+        .header(ContentType::Form)
+        .body(new_project.body())
+        .dispatch()
+        .into_json::<ApiResponse<ProjectWithMembers>>()
+        .unwrap()
+        .data
+        .unwrap()
+        .project
+        .id
+}
+
+pub fn add_project_member(
+    client: &Client,
+    project: Uuid,
+    member: Uuid,
+    role: ProjectRole,
+    guest: bool,
+) -> Status {
+    let new_member = ProjectMember {
+        project,
+        member,
+        role,
+        guest,
+    };
+
+    client
+        .post(format!("{PROJECTS}{project}/add-members"))
+        .header(csrf(client))
+        .header(ContentType::JSON)
+        .body(serde_json::to_string(&[new_member]).unwrap())
+        .dispatch()
+        .status()
+}
@@ -3,6 +3,7 @@ use rocket::http::ContentType;
 use crate::{
     forms::projects::NewProjectForm,
     tests::{
+        csrf,
         projects::route_projects_create,
         response_ok, test_client,
         users::{login, ADMIN_LOGIN},
@@ -24,6 +25,7 @@ fn new_project_by_form() {
     response_ok(
         client
             .post(route_projects_create())
+            .header(csrf(&client))
             .body(new_project.body())
             .header(ContentType::Form),
     );
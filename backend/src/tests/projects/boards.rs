@@ -0,0 +1,276 @@
+use rocket::{
+    http::{ContentType, Status},
+    local::blocking::Client,
+};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::{
+    api::ApiResponse,
+    models::{
+        boards::{rank_after, rank_between, BoardWithColumns, Card},
+        projects::ProjectRole,
+        workspaces::WorkspaceRole,
+    },
+    routes::{PROJECTS, WORKSPACES},
+    tests::{
+        csrf,
+        projects::{add_project_member, create_project},
+        response_ok, test_client,
+        users::{client_of, inject_user, login, logout, remove_user, ADMIN_LOGIN},
+        workspaces::{add_member, create_workspace},
+    },
+};
+
+#[test]
+fn ranks_always_fit_in_between() {
+    let first = rank_after(None);
+    let last = rank_after(Some(&first));
+    assert!(first < last);
+
+    // Squeezing in before the same neighbour over and over only grows the ranks slowly
+    let mut upper = first.clone();
+    for _ in 0..100 {
+        let rank = rank_between(None, Some(&upper)).unwrap();
+        assert!(rank < upper);
+        assert!(!rank.ends_with('0'));
+        upper = rank;
+    }
+    assert!(upper.len() < 25);
+
+    let mut lower = first;
+    for _ in 0..100 {
+        let rank = rank_between(Some(&lower), Some(&last)).unwrap();
+        assert!(lower < rank && rank < last);
+        lower = rank;
+    }
+
+    assert_eq!(rank_between(Some(&last), Some(&last)), None);
+    assert_eq!(rank_between(Some(&last), Some(&lower)), None);
+}
+
+#[test]
+fn cards_are_moved_between_columns_one_at_a_time() {
+    let admin = test_client();
+    let (viewer_id, viewer_name) = inject_user(&admin, "board_viewer");
+
+    login(&admin, ADMIN_LOGIN);
+    let workspace = create_workspace(&admin);
+    add_member(&admin, workspace, viewer_id, WorkspaceRole::Viewer);
+    let project = create_project(&admin, workspace);
+    assert_eq!(
+        add_project_member(&admin, project, viewer_id, ProjectRole::Viewer, false),
+        Status::Ok
+    );
+
+    let response = admin
+        .post(route_boards(project))
+        .header(csrf(&admin))
+        .header(ContentType::JSON)
+        .body(json!({ "name": "Sprint", "columns": ["To do", "Doing", "Done"] }).to_string())
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let board = response
+        .into_json::<ApiResponse<BoardWithColumns>>()
+        .unwrap()
+        .data
+        .unwrap();
+    let columns: Vec<Uuid> = board.columns.iter().map(|c| c.column.id).collect();
+    let board = board.board.id;
+
+    // New cards are added at the bottom of their column
+    let cards: Vec<Card> = ["Design", "Build", "Ship"]
+        .into_iter()
+        .map(|title| create_card(&admin, project, board, columns[0], title))
+        .collect();
+    assert_eq!(
+        titles(&admin, project, board, 0),
+        ["Design", "Build", "Ship"]
+    );
+
+    // A move places the card between its neighbours and only changes the card itself
+    let (status, moved) = move_card(
+        &admin,
+        project,
+        board,
+        cards[2].id,
+        json!({ "column": columns[0], "version": cards[2].version, "after": cards[0].id }),
+    );
+    assert_eq!(status, Status::Ok);
+    let moved = moved.unwrap();
+    assert_eq!(moved.version, cards[2].version + 1);
+    assert_eq!(
+        titles(&admin, project, board, 0),
+        ["Design", "Ship", "Build"]
+    );
+    let unchanged = get_board(&admin, project, board).columns[0].cards.clone();
+    assert_eq!(unchanged[0].rank, cards[0].rank);
+    assert_eq!(unchanged[2].rank, cards[1].rank);
+
+    // A move based on an outdated version of the card is rejected
+    let (status, _) = move_card(
+        &admin,
+        project,
+        board,
+        cards[2].id,
+        json!({ "column": columns[1], "version": cards[2].version }),
+    );
+    assert_eq!(status, Status::Conflict);
+    assert!(get_board(&admin, project, board).columns[1]
+        .cards
+        .is_empty());
+
+    // Cards move to another column, but only next to cards of that column and in order
+    let (status, _) = move_card(
+        &admin,
+        project,
+        board,
+        cards[2].id,
+        json!({ "column": columns[1], "version": moved.version }),
+    );
+    assert_eq!(status, Status::Ok);
+    let (status, _) = move_card(
+        &admin,
+        project,
+        board,
+        cards[1].id,
+        json!({ "column": columns[1], "version": cards[1].version, "after": cards[0].id }),
+    );
+    assert_eq!(status, Status::NotFound);
+    let (status, _) = move_card(
+        &admin,
+        project,
+        board,
+        cards[0].id,
+        json!({ "column": columns[0], "version": cards[0].version, "after": cards[0].id }),
+    );
+    assert_eq!(status, Status::BadRequest);
+    let (status, _) = move_card(
+        &admin,
+        project,
+        board,
+        cards[1].id,
+        json!({ "column": columns[1], "version": cards[1].version, "before": cards[2].id }),
+    );
+    assert_eq!(status, Status::Ok);
+    assert_eq!(titles(&admin, project, board, 0), ["Design"]);
+    assert_eq!(titles(&admin, project, board, 1), ["Build", "Ship"]);
+
+    // Columns are reordered the same way
+    response_ok(
+        admin
+            .post(format!(
+                "{}/columns/{}/move",
+                route_board(project, board),
+                columns[2]
+            ))
+            .header(csrf(&admin))
+            .header(ContentType::JSON)
+            .body(json!({ "before": columns[0] }).to_string()),
+    );
+    let order: Vec<String> = get_board(&admin, project, board)
+        .columns
+        .into_iter()
+        .map(|column| column.column.name)
+        .collect();
+    assert_eq!(order, ["Done", "To do", "Doing"]);
+
+    // A viewer sees the board, but doesn't change it
+    let viewer = client_of(&viewer_name);
+    assert_eq!(get_board(&viewer, project, board).columns.len(), 3);
+    let (status, _) = move_card(
+        &viewer,
+        project,
+        board,
+        cards[0].id,
+        json!({ "column": columns[1], "version": cards[0].version }),
+    );
+    assert_eq!(status, Status::Unauthorized);
+    let response = viewer
+        .delete(route_board(project, board))
+        .header(csrf(&viewer))
+        .dispatch();
+    assert_eq!(response.status(), Status::Unauthorized);
+    logout(&viewer);
+
+    response_ok(
+        admin
+            .delete(route_board(project, board))
+            .header(csrf(&admin)),
+    );
+    let response = admin.get(route_board(project, board)).dispatch();
+    assert_eq!(response.status(), Status::NotFound);
+
+    response_ok(
+        admin
+            .delete(format!("{WORKSPACES}{workspace}/delete"))
+            .header(csrf(&admin)),
+    );
+    logout(&admin);
+    remove_user(&admin, viewer_id);
+}
+
+fn route_boards(project: Uuid) -> String {
+    format!("{PROJECTS}{project}/boards")
+}
+
+fn route_board(project: Uuid, board: Uuid) -> String {
+    format!("{PROJECTS}{project}/boards/{board}")
+}
+
+fn get_board(client: &Client, project: Uuid, board: Uuid) -> BoardWithColumns {
+    client
+        .get(route_board(project, board))
+        .dispatch()
+        .into_json::<ApiResponse<BoardWithColumns>>()
+        .unwrap()
+        .data
+        .unwrap()
+}
+
+/// The titles of the cards in the column at the index, from top to bottom.
+fn titles(client: &Client, project: Uuid, board: Uuid, column: usize) -> Vec<String> {
+    get_board(client, project, board)
+        .columns
+        .remove(column)
+        .cards
+        .into_iter()
+        .map(|card| card.title)
+        .collect()
+}
+
+fn create_card(client: &Client, project: Uuid, board: Uuid, column: Uuid, title: &str) -> Card {
+    client
+        .post(format!("{}/cards", route_board(project, board)))
+        .header(csrf(client))
+        .header(ContentType::JSON)
+        .body(json!({ "column": column, "title": title }).to_string())
+        .dispatch()
+        .into_json::<ApiResponse<Card>>()
+        .unwrap()
+        .data
+        .unwrap()
+}
+
+fn move_card(
+    client: &Client,
+    project: Uuid,
+    board: Uuid,
+    card: Uuid,
+    movement: Value,
+) -> (Status, Option<Card>) {
+    let response = client
+        .post(format!("{}/cards/{card}/move", route_board(project, board)))
+        .header(csrf(client))
+        .header(ContentType::JSON)
+        .body(movement.to_string())
+        .dispatch();
+
+    let status = response.status();
+    (
+        status,
+        response
+            .into_json::<ApiResponse<Card>>()
+            .and_then(|response| response.data),
+    )
+}
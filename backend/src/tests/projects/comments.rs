@@ -0,0 +1,288 @@
+use rocket::{
+    http::{ContentType, Status},
+    local::blocking::Client,
+};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::{
+    api::ApiResponse,
+    models::{
+        comments::{resolve_mentions, PublicComment, ThreadedComment},
+        permissions::EffectivePermissions,
+        projects::ProjectRole,
+        roles::RoleAssignment,
+        users::{PublicUser, User},
+        workspaces::WorkspaceRole,
+    },
+    routes::{PROJECTS, WORKSPACES},
+    tests::{
+        csrf,
+        projects::{add_project_member, create_project},
+        response_ok, test_client,
+        users::{client_of, inject_user, login, logout, remove_user, ADMIN_LOGIN},
+        workspaces::{add_member, assign_role, create_role, create_workspace},
+    },
+};
+
+#[test]
+fn mentions_follow_the_username_rules() {
+    let users: Vec<PublicUser> = ["jane_doe", "jane_doe_2", "john_o.neil"]
+        .into_iter()
+        .map(|username| {
+            PublicUser::from(&User {
+                id: Uuid::new_v4(),
+                username: username.to_string(),
+                ..Default::default()
+            })
+        })
+        .collect();
+    let mentions = |body: &str| resolve_mentions(body, &users);
+
+    assert_eq!(mentions("@jane_doe_2, hi"), [users[1].id]);
+    assert_eq!(mentions("Ask @Jane_Doe."), [users[0].id]);
+    assert_eq!(
+        mentions("@jane_doe's turn, then @john_o.neil"),
+        [users[0].id, users[2].id]
+    );
+    assert_eq!(
+        mentions("@john_o.neil @jane_doe @john_o.neil"),
+        [users[2].id, users[0].id]
+    );
+    assert!(mentions("jane@jane_doe or @ jane_doe or @someone").is_empty());
+}
+
+#[test]
+fn contributors_discuss_projects_in_threads() {
+    let admin = test_client();
+    let (contributor_id, contributor_name) = inject_user(&admin, "comment_contributor");
+    let (viewer_id, viewer_name) = inject_user(&admin, "comment_viewer");
+    let (outsider_id, outsider_name) = inject_user(&admin, "comment_outsider");
+
+    login(&admin, ADMIN_LOGIN);
+    let workspace = create_workspace(&admin);
+    add_member(&admin, workspace, contributor_id, WorkspaceRole::Viewer);
+    add_member(&admin, workspace, viewer_id, WorkspaceRole::Viewer);
+    let project = create_project(&admin, workspace);
+    for (member, role) in [
+        (contributor_id, ProjectRole::Contributor),
+        (viewer_id, ProjectRole::Viewer),
+    ] {
+        assert_eq!(
+            add_project_member(&admin, project, member, role, false),
+            Status::Ok
+        );
+    }
+
+    let contributor = client_of(&contributor_name);
+    let viewer = client_of(&viewer_name);
+    let outsider = client_of(&outsider_name);
+    let project_comments = format!("{PROJECTS}{project}/comments");
+
+    // Members of the workspace are mentioned, and the Markdown is rendered without scripts
+    let (status, comment) = post_comment(
+        &contributor,
+        &project_comments,
+        json!({
+            "body": format!(
+                "**Ready** for @{viewer_name} and @{outsider_name} <script>alert(1)</script>"
+            ),
+        }),
+    );
+    assert_eq!(status, Status::Ok);
+    let comment = comment.unwrap();
+    assert_eq!(comment.mentions, [viewer_id]);
+    let html = comment.html.unwrap();
+    assert!(html.contains("<strong>Ready</strong>"));
+    assert!(!html.contains("<script"));
+    assert!(comment.body.unwrap().contains("<script>"));
+
+    // Viewers read along, but only contributors and above write
+    let (status, _) = post_comment(&viewer, &project_comments, json!({ "body": "Me too" }));
+    assert_eq!(status, Status::Unauthorized);
+    let response = outsider.get(&project_comments).dispatch();
+    assert_eq!(response.status(), Status::NotFound);
+
+    let (status, reply) = post_comment(
+        &admin,
+        &project_comments,
+        json!({ "body": "Thanks!", "parent": comment.id }),
+    );
+    assert_eq!(status, Status::Ok);
+    let reply = reply.unwrap();
+
+    // Only the author edits a comment, which is then marked as edited
+    let comment_route = format!("{project_comments}/{}", comment.id);
+    assert_eq!(
+        edit_comment(&admin, &comment_route, "Not mine").0,
+        Status::Unauthorized
+    );
+    let (status, edited) = edit_comment(&contributor, &comment_route, "**Done**");
+    assert_eq!(status, Status::Ok);
+    let edited = edited.unwrap();
+    assert!(edited.edited);
+    assert!(edited.mentions.is_empty());
+
+    // A deleted comment leaves a marker, so its replies stay in place
+    response_ok(
+        contributor
+            .delete(&comment_route)
+            .header(csrf(&contributor)),
+    );
+    let thread = get_thread(&viewer, &project_comments);
+    assert_eq!(thread.len(), 1);
+    assert!(thread[0].comment.deleted);
+    assert_eq!(thread[0].comment.body, None);
+    assert_eq!(thread[0].comment.html, None);
+    assert_eq!(thread[0].replies.len(), 1);
+    assert_eq!(thread[0].replies[0].comment.id, reply.id);
+    assert_eq!(
+        edit_comment(&contributor, &comment_route, "Back again").0,
+        Status::BadRequest
+    );
+
+    // The workspace has a thread of its own, for its contributors and above
+    let workspace_comments = format!("{WORKSPACES}{workspace}/comments");
+    assert!(get_thread(&admin, &workspace_comments).is_empty());
+    let (status, _) = post_comment(&contributor, &workspace_comments, json!({ "body": "Hi" }));
+    assert_eq!(status, Status::Unauthorized);
+    let (status, _) = post_comment(
+        &admin,
+        &workspace_comments,
+        json!({ "body": "Welcome", "parent": reply.id }),
+    );
+    assert_eq!(status, Status::NotFound);
+    let (status, _) = post_comment(&admin, &workspace_comments, json!({ "body": "Welcome" }));
+    assert_eq!(status, Status::Ok);
+    assert_eq!(get_thread(&viewer, &workspace_comments).len(), 1);
+    assert_eq!(get_thread(&viewer, &project_comments).len(), 1);
+
+    for client in [&contributor, &viewer, &outsider] {
+        logout(client);
+    }
+    response_ok(
+        admin
+            .delete(format!("{WORKSPACES}{workspace}/delete"))
+            .header(csrf(&admin)),
+    );
+    logout(&admin);
+    for user_id in [contributor_id, viewer_id, outsider_id] {
+        remove_user(&admin, user_id);
+    }
+}
+
+#[test]
+fn comments_are_written_with_the_comment_permission() {
+    let admin = test_client();
+    let (manager_id, manager_name) = inject_user(&admin, "comment_manager");
+    let (restricted_id, restricted_name) = inject_user(&admin, "comment_restricted");
+
+    login(&admin, ADMIN_LOGIN);
+    let workspace = create_workspace(&admin);
+    add_member(&admin, workspace, manager_id, WorkspaceRole::Manager);
+    add_member(&admin, workspace, restricted_id, WorkspaceRole::Contributor);
+    let project = create_project(&admin, workspace);
+
+    // A custom role without `comment.create` replaces the preset the member had
+    let role = create_role(&admin, workspace, "Editor", &["workspace.update"]).unwrap();
+    assert_eq!(
+        assign_role(
+            &admin,
+            workspace,
+            restricted_id,
+            RoleAssignment::Custom(role.id)
+        ),
+        Status::Ok
+    );
+
+    let manager = client_of(&manager_name);
+    let restricted = client_of(&restricted_name);
+    let workspace_comments = format!("{WORKSPACES}{workspace}/comments");
+    let project_comments = format!("{PROJECTS}{project}/comments");
+
+    let (status, _) = post_comment(&restricted, &workspace_comments, json!({ "body": "Hi" }));
+    assert_eq!(status, Status::Unauthorized);
+    assert!(!can_write_comments(
+        &restricted,
+        &format!("{WORKSPACES}{workspace}/permissions")
+    ));
+
+    // The permission in the workspace counts in the threads of its projects
+    let (status, _) = post_comment(&manager, &project_comments, json!({ "body": "On it" }));
+    assert_eq!(status, Status::Ok);
+    assert!(can_write_comments(
+        &manager,
+        &format!("{PROJECTS}{project}/permissions")
+    ));
+
+    for client in [&manager, &restricted] {
+        logout(client);
+    }
+    response_ok(
+        admin
+            .delete(format!("{WORKSPACES}{workspace}/delete"))
+            .header(csrf(&admin)),
+    );
+    logout(&admin);
+    for user_id in [manager_id, restricted_id] {
+        remove_user(&admin, user_id);
+    }
+}
+
+/// Whether the permissions at the route allow writing comments.
+fn can_write_comments(client: &Client, route: &str) -> bool {
+    client
+        .get(route)
+        .dispatch()
+        .into_json::<ApiResponse<EffectivePermissions>>()
+        .unwrap()
+        .data
+        .unwrap()
+        .capabilities
+        .iter()
+        .any(|capability| capability.name == "write_comments" && capability.allowed)
+}
+
+fn get_thread(client: &Client, route: &str) -> Vec<ThreadedComment> {
+    client
+        .get(route)
+        .dispatch()
+        .into_json::<ApiResponse<Vec<ThreadedComment>>>()
+        .unwrap()
+        .data
+        .unwrap()
+}
+
+fn post_comment(client: &Client, route: &str, comment: Value) -> (Status, Option<PublicComment>) {
+    let response = client
+        .post(route)
+        .header(csrf(client))
+        .header(ContentType::JSON)
+        .body(comment.to_string())
+        .dispatch();
+
+    let status = response.status();
+    (
+        status,
+        response
+            .into_json::<ApiResponse<PublicComment>>()
+            .and_then(|response| response.data),
+    )
+}
+
+fn edit_comment(client: &Client, route: &str, body: &str) -> (Status, Option<PublicComment>) {
+    let response = client
+        .put(route)
+        .header(csrf(client))
+        .header(ContentType::JSON)
+        .body(json!({ "body": body }).to_string())
+        .dispatch();
+
+    let status = response.status();
+    (
+        status,
+        response
+            .into_json::<ApiResponse<PublicComment>>()
+            .and_then(|response| response.data),
+    )
+}
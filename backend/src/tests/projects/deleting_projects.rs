@@ -1,4 +1,5 @@
 use crate::tests::{
+    csrf,
     projects::route_projects_delete,
     response_ok, test_client,
     users::{login, ADMIN_LOGIN},
@@ -8,5 +9,5 @@ use crate::tests::{
 fn delete_existing_project_by_id() {
     let client = test_client();
     login(&client, ADMIN_LOGIN);
-    response_ok(client.delete(route_projects_delete()));
+    response_ok(client.delete(route_projects_delete()).header(csrf(&client)));
 }
@@ -0,0 +1,124 @@
+use rocket::{
+    http::{ContentType, Status},
+    local::blocking::Client,
+};
+use serde_json::Value;
+
+use crate::{
+    api::ApiResponse,
+    database::pagination::{request::PaginationRequest, sort::UserField},
+    models::{
+        projects::{ProjectRole, ProjectWithMembers},
+        workspaces::WorkspaceRole,
+    },
+    routes::{PROJECTS, USERS, WORKSPACES},
+    tests::{
+        csrf,
+        projects::{add_project_member, create_project},
+        response_not_found, response_ok, root_route, test_client,
+        users::{client_of, inject_user, login, logout, remove_user, ADMIN_LOGIN},
+        workspaces::{add_member, create_workspace},
+    },
+};
+
+#[test]
+fn guests_only_see_their_projects() {
+    let admin = test_client();
+    let (guest_id, guest_name) = inject_user(&admin, "guest");
+    let (member_id, member_name) = inject_user(&admin, "guest_member");
+    let (other_id, other_name) = inject_user(&admin, "guest_other");
+
+    login(&admin, ADMIN_LOGIN);
+    let workspace = create_workspace(&admin);
+    add_member(&admin, workspace, member_id, WorkspaceRole::Contributor);
+    add_member(&admin, workspace, other_id, WorkspaceRole::Contributor);
+    let project = create_project(&admin, workspace);
+    let other_project = create_project(&admin, workspace);
+
+    // Outside the workspace only as a guest, and members of the workspace never as a guest
+    assert_eq!(
+        add_project_member(&admin, project, guest_id, ProjectRole::Owner, false),
+        Status::BadRequest
+    );
+    assert_eq!(
+        add_project_member(&admin, project, other_id, ProjectRole::Viewer, true),
+        Status::BadRequest
+    );
+    assert_eq!(
+        add_project_member(&admin, project, guest_id, ProjectRole::Owner, true),
+        Status::Ok
+    );
+    assert_eq!(
+        add_project_member(&admin, project, member_id, ProjectRole::Contributor, false),
+        Status::Ok
+    );
+
+    let guest = client_of(&guest_name);
+
+    // The guest sees the project, but not the workspace or its other projects
+    response_ok(guest.get(format!("{PROJECTS}{project}")));
+    response_not_found(guest.get(format!("{WORKSPACES}{workspace}")));
+    response_not_found(guest.get(format!("{PROJECTS}{other_project}")));
+
+    // Only the members of the project are found, by and of the guest
+    response_ok(guest.get(format!("{USERS}{member_name}")));
+    response_not_found(guest.get(format!("{USERS}{other_name}")));
+    assert_eq!(search_users(&guest, &other_name), 0);
+
+    let other = client_of(&other_name);
+    response_not_found(other.get(format!("{USERS}{guest_name}")));
+    assert_eq!(search_users(&other, &guest_name), 0);
+    logout(&other);
+
+    // Even as owner of the project, a guest doesn't manage its members
+    assert_eq!(
+        add_project_member(&guest, project, other_id, ProjectRole::Viewer, false),
+        Status::Unauthorized
+    );
+
+    // Once a member of the workspace, the guest is a regular member of the project
+    add_member(&admin, workspace, guest_id, WorkspaceRole::Viewer);
+    let project_with_members = admin
+        .get(format!("{PROJECTS}{project}"))
+        .dispatch()
+        .into_json::<ApiResponse<ProjectWithMembers>>()
+        .unwrap()
+        .data
+        .unwrap();
+    assert!(project_with_members
+        .members
+        .iter()
+        .all(|member| !member.guest));
+
+    logout(&guest);
+    response_ok(
+        admin
+            .delete(format!("{WORKSPACES}{workspace}/delete"))
+            .header(csrf(&admin)),
+    );
+    logout(&admin);
+    for user_id in [guest_id, member_id, other_id] {
+        remove_user(&admin, user_id);
+    }
+}
+
+/// The number of users found by the search.
+fn search_users(client: &Client, search: &str) -> i64 {
+    let params = PaginationRequest::<UserField> {
+        page: None,
+        limit: None,
+        search: Some(search.to_string()),
+        sort_by: None,
+        sort_dir: None,
+    };
+
+    client
+        .get(root_route(USERS))
+        .header(ContentType::JSON)
+        .body(serde_json::to_string(&params).unwrap())
+        .dispatch()
+        .into_json::<Value>()
+        .unwrap()["data"]["total"]
+        .as_i64()
+        .unwrap()
+}
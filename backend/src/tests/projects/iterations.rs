@@ -0,0 +1,357 @@
+use chrono::{Days, NaiveDate, Utc};
+use rocket::{
+    http::{ContentType, Status},
+    local::blocking::Client,
+};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::{
+    api::ApiResponse,
+    models::{
+        iterations::{
+            burndown, BurndownDay, ClosedIteration, Deliverable, Iteration, IterationKind,
+            IterationState, IterationWithDeliverables, StatusChange,
+        },
+        projects::ProjectRole,
+        tasks::TaskStatus,
+        workspaces::WorkspaceRole,
+    },
+    routes::{PROJECTS, WORKSPACES},
+    tests::{
+        csrf,
+        projects::{add_project_member, create_project},
+        response_ok, test_client,
+        users::{client_of, inject_user, login, logout, remove_user, ADMIN_LOGIN},
+        workspaces::{add_member, create_workspace},
+    },
+};
+
+#[test]
+fn burndown_follows_the_status_changes() {
+    let day = |day: u32| NaiveDate::from_ymd_opt(2025, 3, day).unwrap();
+    let sprint = Iteration {
+        id: Uuid::new_v4(),
+        project: Uuid::new_v4(),
+        kind: IterationKind::Sprint,
+        name: "Sprint 1".to_string(),
+        goal: None,
+        start_date: day(3),
+        end_date: day(7),
+        state: IterationState::Active,
+        closed_at: None,
+        created_at: day(1).and_hms_opt(9, 0, 0).unwrap(),
+        updated_at: day(1).and_hms_opt(9, 0, 0).unwrap(),
+    };
+
+    let deliverables: Vec<Uuid> = (0..4).map(|_| Uuid::new_v4()).collect();
+    let change = |deliverable: usize, status: TaskStatus, day: NaiveDate, hour: u32| StatusChange {
+        id: Uuid::new_v4(),
+        deliverable: deliverables[deliverable],
+        status,
+        changed_at: day.and_hms_opt(hour, 0, 0).unwrap(),
+    };
+    let changes = [
+        // Three deliverables are planned before the sprint starts
+        change(0, TaskStatus::Todo, day(2), 10),
+        change(1, TaskStatus::Todo, day(2), 10),
+        change(2, TaskStatus::Todo, day(2), 11),
+        // One is done on the first day, after some work
+        change(0, TaskStatus::Done, day(3), 17),
+        change(0, TaskStatus::InProgress, day(3), 9),
+        // One is added on the third day, another one cancelled
+        change(3, TaskStatus::Todo, day(5), 8),
+        change(1, TaskStatus::Cancelled, day(5), 23),
+        // One is reopened and done again the next day
+        change(0, TaskStatus::InReview, day(6), 12),
+        change(0, TaskStatus::Done, day(7), 0),
+    ];
+
+    let series = |days: &[BurndownDay]| -> Vec<(usize, usize, usize)> {
+        days.iter()
+            .map(|day| (day.scope, day.done, day.remaining))
+            .collect()
+    };
+
+    let days = burndown(&sprint, &changes, day(20));
+    assert_eq!(
+        days.iter().map(|day| day.date).collect::<Vec<_>>(),
+        (3..=7).map(day).collect::<Vec<_>>()
+    );
+    assert_eq!(
+        series(&days),
+        [(3, 1, 2), (3, 1, 2), (3, 1, 2), (3, 0, 3), (3, 1, 2)]
+    );
+    assert_eq!(
+        days.iter().map(|day| day.ideal).collect::<Vec<_>>(),
+        [3.0, 2.25, 1.5, 0.75, 0.0]
+    );
+
+    // The series stop at today, or at the day the iteration was closed
+    assert_eq!(burndown(&sprint, &changes, day(4)).len(), 2);
+    assert!(burndown(&sprint, &changes, day(2)).is_empty());
+    let closed = Iteration {
+        state: IterationState::Closed,
+        closed_at: day(5).and_hms_opt(18, 0, 0),
+        ..sprint
+    };
+    assert_eq!(series(&burndown(&closed, &changes, day(20))).len(), 3);
+}
+
+#[test]
+fn sprints_roll_unfinished_deliverables_over() {
+    let admin = test_client();
+    let (viewer_id, viewer_name) = inject_user(&admin, "iteration_viewer");
+    let (contributor_id, contributor_name) = inject_user(&admin, "iteration_contributor");
+
+    login(&admin, ADMIN_LOGIN);
+    let workspace = create_workspace(&admin);
+    add_member(&admin, workspace, viewer_id, WorkspaceRole::Viewer);
+    add_member(&admin, workspace, contributor_id, WorkspaceRole::Viewer);
+    let project = create_project(&admin, workspace);
+    for (member, role) in [
+        (viewer_id, ProjectRole::Viewer),
+        (contributor_id, ProjectRole::Contributor),
+    ] {
+        assert_eq!(
+            add_project_member(&admin, project, member, role, false),
+            Status::Ok
+        );
+    }
+
+    let viewer = client_of(&viewer_name);
+    let contributor = client_of(&contributor_name);
+    let iterations = format!("{PROJECTS}{project}/iterations");
+    let today = Utc::now().date_naive();
+    let started = today - Days::new(3);
+
+    // Sprints run for two weeks unless they end otherwise, and only members who update the
+    // project plan them
+    let (status, _) = create_iteration(
+        &viewer,
+        project,
+        json!({ "kind": "Sprint", "name": "Sprint 1", "start_date": started }),
+    );
+    assert_eq!(status, Status::Unauthorized);
+    let (status, first) = create_iteration(
+        &contributor,
+        project,
+        json!({
+            "kind": "Sprint",
+            "name": "Sprint 1",
+            "goal": "Ship the login",
+            "start_date": started,
+        }),
+    );
+    assert_eq!(status, Status::Ok);
+    let first = first.unwrap();
+    assert_eq!(first.end_date, started + Days::new(13));
+    assert_eq!(first.state, IterationState::Open);
+    let (_, second) = create_iteration(
+        &contributor,
+        project,
+        json!({ "kind": "Sprint", "name": "Sprint 2", "start_date": started + Days::new(14) }),
+    );
+    let second = second.unwrap();
+    let (status, _) = create_iteration(
+        &contributor,
+        project,
+        json!({
+            "kind": "Milestone",
+            "name": "Beta",
+            "start_date": today,
+            "end_date": today - Days::new(1),
+        }),
+    );
+    assert_eq!(status, Status::BadRequest);
+
+    // One sprint runs at a time
+    response_ok(
+        contributor
+            .post(format!("{iterations}/{}/start", first.id))
+            .header(csrf(&contributor)),
+    );
+    for (iteration, expected) in [
+        (first.id, Status::BadRequest),
+        (second.id, Status::Conflict),
+    ] {
+        let response = contributor
+            .post(format!("{iterations}/{iteration}/start"))
+            .header(csrf(&contributor))
+            .dispatch();
+        assert_eq!(response.status(), expected);
+    }
+
+    let deliverables: Vec<Deliverable> = ["Design", "Build", "Test"]
+        .into_iter()
+        .map(|title| add_deliverable(&contributor, project, first.id, title))
+        .collect();
+    for (deliverable, status) in [(&deliverables[0], "Done"), (&deliverables[1], "InProgress")] {
+        response_ok(
+            contributor
+                .put(format!(
+                    "{iterations}/{}/deliverables/{}",
+                    first.id, deliverable.id
+                ))
+                .header(csrf(&contributor))
+                .header(ContentType::JSON)
+                .body(json!({ "status": status }).to_string()),
+        );
+    }
+
+    // The burndown has a day for every day of the sprint so far
+    let days = get_burndown(&viewer, project, first.id);
+    assert_eq!(days.len(), 4);
+    assert_eq!(days[0].scope, 0);
+    let last = days.last().unwrap();
+    assert_eq!((last.scope, last.done, last.remaining), (3, 1, 2));
+
+    // Closing the sprint continues its unfinished deliverables in the next one
+    let response = contributor
+        .post(format!("{iterations}/{}/close", first.id))
+        .header(csrf(&contributor))
+        .header(ContentType::JSON)
+        .body(json!({}).to_string())
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let closed = response
+        .into_json::<ApiResponse<ClosedIteration>>()
+        .unwrap()
+        .data
+        .unwrap();
+    assert_eq!(closed.iteration.state, IterationState::Closed);
+    let rolled_over: Vec<(Option<Uuid>, Uuid, TaskStatus)> = closed
+        .rolled_over
+        .iter()
+        .map(|deliverable| {
+            (
+                deliverable.rolled_over_from,
+                deliverable.iteration,
+                deliverable.status,
+            )
+        })
+        .collect();
+    assert_eq!(
+        rolled_over,
+        [
+            (Some(deliverables[1].id), second.id, TaskStatus::InProgress),
+            (Some(deliverables[2].id), second.id, TaskStatus::Todo),
+        ]
+    );
+
+    // The closed sprint keeps its deliverables as they were, and no longer changes
+    let sprint = get_iteration(&viewer, project, first.id);
+    assert_eq!(sprint.deliverables.len(), 3);
+    assert_eq!(sprint.deliverables[1].status, TaskStatus::InProgress);
+    assert_eq!(get_burndown(&viewer, project, first.id), days);
+    assert_eq!(
+        get_iteration(&viewer, project, second.id)
+            .deliverables
+            .len(),
+        2
+    );
+    let response = contributor
+        .post(format!("{iterations}/{}/deliverables", first.id))
+        .header(csrf(&contributor))
+        .header(ContentType::JSON)
+        .body(json!({ "title": "Late" }).to_string())
+        .dispatch();
+    assert_eq!(response.status(), Status::BadRequest);
+    let response = contributor
+        .post(format!("{iterations}/{}/close", first.id))
+        .header(csrf(&contributor))
+        .header(ContentType::JSON)
+        .body(json!({}).to_string())
+        .dispatch();
+    assert_eq!(response.status(), Status::BadRequest);
+
+    // With the first sprint closed, the next one starts
+    response_ok(
+        contributor
+            .post(format!("{iterations}/{}/start", second.id))
+            .header(csrf(&contributor)),
+    );
+    let response = viewer
+        .get(format!("{iterations}?kind=sprint&state=active"))
+        .dispatch();
+    let active = response
+        .into_json::<ApiResponse<Vec<Iteration>>>()
+        .unwrap()
+        .data
+        .unwrap();
+    assert_eq!(active.len(), 1);
+    assert_eq!(active[0].id, second.id);
+    let response = viewer.get(format!("{iterations}?kind=epic")).dispatch();
+    assert_eq!(response.status(), Status::BadRequest);
+
+    for client in [&viewer, &contributor] {
+        logout(client);
+    }
+    response_ok(
+        admin
+            .delete(format!("{WORKSPACES}{workspace}/delete"))
+            .header(csrf(&admin)),
+    );
+    logout(&admin);
+    for user_id in [viewer_id, contributor_id] {
+        remove_user(&admin, user_id);
+    }
+}
+
+fn create_iteration(
+    client: &Client,
+    project: Uuid,
+    iteration: Value,
+) -> (Status, Option<Iteration>) {
+    let response = client
+        .post(format!("{PROJECTS}{project}/iterations"))
+        .header(csrf(client))
+        .header(ContentType::JSON)
+        .body(iteration.to_string())
+        .dispatch();
+
+    let status = response.status();
+    (
+        status,
+        response
+            .into_json::<ApiResponse<Iteration>>()
+            .and_then(|response| response.data),
+    )
+}
+
+fn get_iteration(client: &Client, project: Uuid, iteration: Uuid) -> IterationWithDeliverables {
+    client
+        .get(format!("{PROJECTS}{project}/iterations/{iteration}"))
+        .dispatch()
+        .into_json::<ApiResponse<IterationWithDeliverables>>()
+        .unwrap()
+        .data
+        .unwrap()
+}
+
+fn get_burndown(client: &Client, project: Uuid, iteration: Uuid) -> Vec<BurndownDay> {
+    client
+        .get(format!(
+            "{PROJECTS}{project}/iterations/{iteration}/burndown"
+        ))
+        .dispatch()
+        .into_json::<ApiResponse<Vec<BurndownDay>>>()
+        .unwrap()
+        .data
+        .unwrap()
+}
+
+fn add_deliverable(client: &Client, project: Uuid, iteration: Uuid, title: &str) -> Deliverable {
+    client
+        .post(format!(
+            "{PROJECTS}{project}/iterations/{iteration}/deliverables"
+        ))
+        .header(csrf(client))
+        .header(ContentType::JSON)
+        .body(json!({ "title": title }).to_string())
+        .dispatch()
+        .into_json::<ApiResponse<Deliverable>>()
+        .unwrap()
+        .data
+        .unwrap()
+}
@@ -6,6 +6,7 @@ use uuid::Uuid;
 use crate::{
     models::projects::{ProjectMember, ProjectRole},
     tests::{
+        csrf,
         projects::{route_projects_add_member, route_projects_remove_member, TARGETED_PROJECT},
         response_ok, test_client,
         users::{login, ADMIN_LOGIN},
@@ -23,7 +24,8 @@ fn add_member_to_project() {
     let new_member = ProjectMember {
         project: Uuid::from_str(TARGETED_PROJECT).unwrap(),
         member: Uuid::from_str(TARGETED_MEMBER).unwrap(),
-        role: i16::from(ProjectRole::Contributor),
+        role: ProjectRole::Contributor,
+        guest: false,
     };
 
     // Serialize the workspace update
@@ -32,6 +34,7 @@ fn add_member_to_project() {
     response_ok(
         client
             .post(route_projects_add_member())
+            .header(csrf(&client))
             .header(ContentType::JSON)
             .body(payload),
     );
@@ -41,5 +44,9 @@ fn add_member_to_project() {
 fn remove_member_from_workspace() {
     let client = test_client();
     login(&client, ADMIN_LOGIN);
-    response_ok(client.delete(route_projects_remove_member(TARGETED_MEMBER)));
+    response_ok(
+        client
+            .delete(route_projects_remove_member(TARGETED_MEMBER))
+            .header(csrf(&client)),
+    );
 }
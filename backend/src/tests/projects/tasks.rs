@@ -0,0 +1,200 @@
+use rocket::{
+    http::{ContentType, Status},
+    local::blocking::Client,
+};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::{
+    api::ApiResponse,
+    database::pagination::{request::PaginationRequest, sort::TaskField},
+    models::{
+        projects::ProjectRole,
+        tasks::{Task, TaskPriority, TaskStatus},
+        workspaces::WorkspaceRole,
+    },
+    routes::{PROJECTS, WORKSPACES},
+    tests::{
+        csrf,
+        projects::{add_project_member, create_project},
+        response_ok, test_client,
+        users::{client_of, inject_user, login, logout, remove_user, ADMIN_LOGIN},
+        workspaces::{add_member, create_workspace},
+    },
+};
+
+#[test]
+fn project_roles_decide_who_manages_tasks() {
+    let admin = test_client();
+    let (contributor_id, contributor_name) = inject_user(&admin, "task_contributor");
+    let (viewer_id, viewer_name) = inject_user(&admin, "task_viewer");
+    let (outsider_id, _) = inject_user(&admin, "task_outsider");
+
+    login(&admin, ADMIN_LOGIN);
+    let workspace = create_workspace(&admin);
+    add_member(&admin, workspace, contributor_id, WorkspaceRole::Viewer);
+    add_member(&admin, workspace, viewer_id, WorkspaceRole::Viewer);
+    let project = create_project(&admin, workspace);
+    for (member, role) in [
+        (contributor_id, ProjectRole::Contributor),
+        (viewer_id, ProjectRole::Viewer),
+    ] {
+        assert_eq!(
+            add_project_member(&admin, project, member, role, false),
+            Status::Ok
+        );
+    }
+
+    let contributor = client_of(&contributor_name);
+    let viewer = client_of(&viewer_name);
+
+    // A contributor creates tasks, but only assigns them to members of the project
+    let (status, _) = create_task(
+        &contributor,
+        project,
+        json!({ "title": "Outsider", "assignee": outsider_id }),
+    );
+    assert_eq!(status, Status::BadRequest);
+    let (status, _) = create_task(&contributor, project, json!({ "title": " " }));
+    assert_eq!(status, Status::BadRequest);
+
+    let (status, task) = create_task(
+        &contributor,
+        project,
+        json!({
+            "title": "Write the release notes",
+            "priority": "High",
+            "assignee": viewer_id,
+            "due_date": "2030-01-31",
+        }),
+    );
+    assert_eq!(status, Status::Ok);
+    let task = task.unwrap();
+    assert_eq!(task.status, TaskStatus::Todo);
+    assert_eq!(task.priority, TaskPriority::High);
+    assert_eq!(task.reporter, Some(contributor_id));
+    create_task(
+        &contributor,
+        project,
+        json!({ "title": "Plan the next sprint" }),
+    );
+
+    // A viewer reads the tasks, but doesn't create or change them
+    assert_eq!(
+        create_task(&viewer, project, json!({ "title": "Not allowed" })).0,
+        Status::Unauthorized
+    );
+    assert_eq!(
+        update_task(&viewer, project, task.id, json!({ "status": "Done" })).0,
+        Status::Unauthorized
+    );
+    response_ok(viewer.get(route_task(project, task.id)));
+    assert_eq!(total_tasks(&viewer, project, ""), 2);
+    assert_eq!(total_tasks(&viewer, project, "?priority=high"), 1);
+    assert_eq!(
+        total_tasks(&viewer, project, &format!("?assignee={viewer_id}")),
+        1
+    );
+
+    // Only the fields that are sent are updated, and null clears a field
+    let (status, updated) = update_task(
+        &contributor,
+        project,
+        task.id,
+        json!({ "status": "InProgress", "assignee": null }),
+    );
+    assert_eq!(status, Status::Ok);
+    let updated = updated.unwrap();
+    assert_eq!(updated.status, TaskStatus::InProgress);
+    assert_eq!(updated.assignee, None);
+    assert_eq!(updated.title, task.title);
+    assert_eq!(updated.due_date, task.due_date);
+    assert_eq!(total_tasks(&viewer, project, "?status=inprogress"), 1);
+
+    // Deleting tasks is up to the masters and owners of the project
+    let response = contributor
+        .delete(route_task(project, task.id))
+        .header(csrf(&contributor))
+        .dispatch();
+    assert_eq!(response.status(), Status::Unauthorized);
+    response_ok(
+        admin
+            .delete(route_task(project, task.id))
+            .header(csrf(&admin)),
+    );
+    assert_eq!(total_tasks(&viewer, project, ""), 1);
+
+    logout(&contributor);
+    logout(&viewer);
+    response_ok(
+        admin
+            .delete(format!("{WORKSPACES}{workspace}/delete"))
+            .header(csrf(&admin)),
+    );
+    logout(&admin);
+    for user_id in [contributor_id, viewer_id, outsider_id] {
+        remove_user(&admin, user_id);
+    }
+}
+
+fn route_tasks(project: Uuid) -> String {
+    format!("{PROJECTS}{project}/tasks")
+}
+
+fn route_task(project: Uuid, task: Uuid) -> String {
+    format!("{PROJECTS}{project}/tasks/{task}")
+}
+
+fn create_task(client: &Client, project: Uuid, task: Value) -> (Status, Option<Task>) {
+    let response = client
+        .post(route_tasks(project))
+        .header(csrf(client))
+        .header(ContentType::JSON)
+        .body(task.to_string())
+        .dispatch();
+
+    let status = response.status();
+    (
+        status,
+        response
+            .into_json::<ApiResponse<Task>>()
+            .and_then(|response| response.data),
+    )
+}
+
+fn update_task(
+    client: &Client,
+    project: Uuid,
+    task: Uuid,
+    update: Value,
+) -> (Status, Option<Task>) {
+    let response = client
+        .put(route_task(project, task))
+        .header(csrf(client))
+        .header(ContentType::JSON)
+        .body(update.to_string())
+        .dispatch();
+
+    let status = response.status();
+    (
+        status,
+        response
+            .into_json::<ApiResponse<Task>>()
+            .and_then(|response| response.data),
+    )
+}
+
+/// The number of tasks found with the filters of the query.
+fn total_tasks(client: &Client, project: Uuid, query: &str) -> i64 {
+    let params = PaginationRequest::<TaskField>::new();
+
+    client
+        .get(format!("{}{query}", route_tasks(project)))
+        .header(ContentType::JSON)
+        .body(serde_json::to_string(&params).unwrap())
+        .dispatch()
+        .into_json::<Value>()
+        .unwrap()["data"]["total"]
+        .as_i64()
+        .unwrap()
+}
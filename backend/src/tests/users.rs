@@ -1,23 +1,60 @@
+use chrono::Utc;
 use rocket::{
     http::{ContentType, Status},
     local::{
         asynchronous::{Client as AsyncClient, LocalResponse as AsyncLocalResponse},
         blocking::{Client, LocalResponse},
     },
+    Orbit, Rocket, State,
+};
+use uuid::Uuid;
+
+use crate::{
+    api::ApiResponse,
+    cache::{self, RedisMutex},
+    cookies::TOKEN_COOKIE,
+    forms::{login::LoginForm, password::Password},
+    models::users::{PublicUser, User, UserRole, UserStatus},
+    routes::USERS,
+    tests::{csrf, root_route, test_client},
 };
 
-use crate::{cookies::TOKEN_COOKIE, forms::login::LoginForm, routes::USERS, tests::root_route};
-
+#[cfg(test)]
+mod access_tokens;
+#[cfg(test)]
+mod changing_credentials;
+#[cfg(test)]
+mod csrf;
 #[cfg(test)]
 mod deleting_users;
 #[cfg(test)]
 mod getting_users;
 #[cfg(test)]
+mod impersonation;
+#[cfg(test)]
 mod injecting_users;
 #[cfg(test)]
 mod invitation_flow;
 #[cfg(test)]
+mod ldap;
+#[cfg(test)]
+mod lockout;
+#[cfg(test)]
 mod login_logout;
+#[cfg(test)]
+mod oidc;
+#[cfg(test)]
+mod password_reset;
+#[cfg(test)]
+mod passwords;
+#[cfg(test)]
+mod revalidation;
+#[cfg(test)]
+mod roles;
+#[cfg(test)]
+mod sessions;
+#[cfg(test)]
+mod two_factor;
 
 fn route_users_all() -> String {
     root_route(USERS)
@@ -42,18 +79,102 @@ fn route_users_admin_inject_users() -> String {
     format!("{USERS}create")
 }
 
+fn route_users_remove(id: &str) -> String {
+    format!("{USERS}remove/{id}")
+}
+
+fn route_users_suspend(id: &str) -> String {
+    format!("{USERS}suspend/{id}")
+}
+
+fn route_users_unlock(id: &str) -> String {
+    format!("{USERS}unlock/{id}")
+}
+
+fn route_users_update_role(id: &str, role: UserRole) -> String {
+    format!("{USERS}update/{id}/{role}")
+}
+
 fn route_users_delete(id: &str) -> String {
     format!("{USERS}delete/{id}")
 }
 
+fn route_users_me() -> String {
+    format!("{USERS}me")
+}
+
 fn route_users_login() -> String {
     format!("{USERS}login")
 }
 
+fn route_users_refresh() -> String {
+    format!("{USERS}refresh")
+}
+
 fn route_users_logout() -> String {
     format!("{USERS}logout")
 }
 
+fn route_users_two_factor_login() -> String {
+    format!("{USERS}login/2fa")
+}
+
+fn route_users_two_factor_login_enroll() -> String {
+    format!("{USERS}login/2fa/enroll")
+}
+
+fn route_users_two_factor_enroll() -> String {
+    format!("{USERS}2fa/enroll")
+}
+
+fn route_users_two_factor_verify() -> String {
+    format!("{USERS}2fa/verify")
+}
+
+fn route_users_two_factor_disable() -> String {
+    format!("{USERS}2fa/disable")
+}
+
+fn route_users_access_tokens() -> String {
+    format!("{USERS}tokens")
+}
+
+fn route_users_access_token(id: &str) -> String {
+    format!("{USERS}tokens/{id}")
+}
+
+fn route_users_forgot_password() -> String {
+    format!("{USERS}password/forgot")
+}
+
+fn route_users_reset_password(token: &str) -> String {
+    format!("{USERS}password/reset/{token}")
+}
+
+fn route_users_change_password() -> String {
+    format!("{USERS}me/password")
+}
+
+fn route_users_change_email() -> String {
+    format!("{USERS}me/email")
+}
+
+fn route_users_confirm_email(token: &str) -> String {
+    format!("{USERS}email/confirm/{token}")
+}
+
+fn route_users_own_sessions() -> String {
+    format!("{USERS}me/sessions")
+}
+
+fn route_users_own_session(id: &str) -> String {
+    format!("{USERS}me/sessions/{id}")
+}
+
+fn route_users_sessions(id: &str) -> String {
+    format!("{USERS}sessions/{id}")
+}
+
 const ROUTE_GET: &str = "/user/";
 const ROUTE_INVITE_GET: &str = "/user/invite/get/";
 const ROUTE_INVITE_SET: &str = "/user/invite/set/";
@@ -62,7 +183,7 @@ const ADMIN_USERNAME: &str = "admin";
 const ADMIN_PASSWORD: &str = "SYqujNZNmvEw2Ajk";
 
 const DEFAULT_USERNAME: &str = "test_user";
-const DEFAULT_PASSWORD: &str = "strong_password";
+pub const DEFAULT_PASSWORD: &str = "strong_password";
 
 const INVITED_USER_1_FIRST_NAME: &str = "Lucas";
 const INVITED_USER_1_LAST_NAME: &str = "Bennett";
@@ -116,7 +237,10 @@ pub fn login(client: &Client, login_form: LoginForm) {
 }
 
 pub fn logout(client: &Client) {
-    let logout_response = client.post(route_users_logout()).dispatch();
+    let logout_response = client
+        .post(route_users_logout())
+        .header(csrf(client))
+        .dispatch();
 
     // Assert that the logout request was successful
     assert_eq!(logout_response.status(), Status::Ok);
@@ -125,6 +249,38 @@ pub fn logout(client: &Client) {
     assert_authorized_cookies(logout_response, false);
 }
 
+/// A client of its own, on which the user is logged in with the default password.
+pub fn client_of(username: &str) -> Client {
+    let client = test_client();
+    login(
+        &client,
+        LoginForm {
+            username,
+            password: DEFAULT_PASSWORD,
+        },
+    );
+    client
+}
+
+/// The user logged in on the client.
+pub fn get_self(client: &Client) -> PublicUser {
+    client
+        .get(route_users_me())
+        .dispatch()
+        .into_json::<ApiResponse<PublicUser>>()
+        .unwrap()
+        .data
+        .unwrap()
+}
+
+/// The Redis of the rocket of a blocking or asynchronous client.
+pub fn get_redis(rocket: &Rocket<Orbit>) -> &State<RedisMutex> {
+    rocket
+        .state::<RedisMutex>()
+        .expect("Redis state should be available")
+        .into()
+}
+
 fn assert_authorized_cookies(response: LocalResponse<'_>, available: bool) {
     // Get the cookies after the response
     let cookies = response.cookies();
@@ -165,3 +321,78 @@ fn async_assert_authorized_cookies(response: AsyncLocalResponse<'_>, available:
         assert!(token_cookie.is_none());
     }
 }
+
+/// Injects an active [`UserRole::Reviewer`] with a random username (starting with the prefix) and
+/// the default password. Returns the ID and the username.
+pub fn inject_user(client: &Client, prefix: &str) -> (Uuid, String) {
+    inject_user_with_email(client, prefix, None)
+}
+
+/// Like [`inject_user`], with the email address instead of one made from the username.
+pub fn inject_user_with_email(
+    client: &Client,
+    prefix: &str,
+    email: Option<&str>,
+) -> (Uuid, String) {
+    let username = format!("{prefix}_{}", cache::create_random_token(8).to_lowercase());
+
+    let user = User {
+        id: Uuid::new_v4(),
+        username: username.clone(),
+        first_name: "Injected".to_string(),
+        last_name: "User".to_string(),
+        email: email
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("{username}@example.com")),
+        role: UserRole::Reviewer,
+        status: UserStatus::Active,
+        password: Password::generate(Some(DEFAULT_PASSWORD)).unwrap(),
+        created_at: Utc::now().naive_utc(),
+        updated_at: Utc::now().naive_utc(),
+        ..Default::default()
+    };
+
+    let response = client
+        .post(route_users_admin_inject_users())
+        .header(csrf(client))
+        .header(ContentType::JSON)
+        .body(serde_json::to_string(&user).unwrap())
+        .dispatch();
+
+    assert_eq!(response.status(), Status::Ok);
+
+    // The ID is generated by the route, so look the user up
+    login(client, ADMIN_LOGIN);
+    let user = client
+        .get(format!("{ROUTE_GET}{username}"))
+        .dispatch()
+        .into_json::<ApiResponse<PublicUser>>()
+        .unwrap()
+        .data
+        .unwrap();
+    logout(client);
+
+    (user.id, username)
+}
+
+/// Deletes the user as admin.
+pub fn remove_user(client: &Client, user_id: Uuid) {
+    login(client, ADMIN_LOGIN);
+
+    let response = client
+        .delete(route_users_delete(&user_id.to_string()))
+        .header(csrf(client))
+        .dispatch();
+
+    assert_eq!(response.status(), Status::Ok);
+
+    logout(client);
+}
+
+fn route_users_impersonate(id: &str) -> String {
+    format!("{USERS}impersonate/{id}")
+}
+
+fn route_users_impersonation_logs(id: &str) -> String {
+    format!("{USERS}impersonate/{id}/logs")
+}
@@ -0,0 +1,131 @@
+use rocket::{
+    http::{ContentType, Header, Status},
+    local::blocking::Client,
+};
+
+use super::{
+    login, route_users_access_token, route_users_access_tokens, route_users_logout, route_users_me,
+    DEFAULT_LOGIN,
+};
+use crate::{
+    api::ApiResponse,
+    forms::access_tokens::AccessTokenForm,
+    models::access_tokens::NewAccessToken,
+    routes::WORKSPACES,
+    tests::{csrf, root_route, test_client},
+};
+
+#[test]
+fn access_token_is_limited_to_its_scopes() {
+    let client = test_client();
+    login(&client, DEFAULT_LOGIN);
+
+    let new_token = create_access_token(&client, vec!["users:read"]);
+    assert!(new_token.token.starts_with("rustle_pat_"));
+
+    // A client without cookies, like a script
+    let script = test_client();
+    let bearer = || Header::new("Authorization", format!("Bearer {}", new_token.token));
+
+    // Reading users is within the scope
+    let response = script.get(route_users_me()).header(bearer()).dispatch();
+    assert_eq!(response.status(), Status::Ok);
+
+    // Writing users and reading workspaces is not
+    let response = script
+        .post(route_users_logout())
+        .header(bearer())
+        .dispatch();
+    assert_eq!(response.status(), Status::Unauthorized);
+
+    let response = script
+        .get(root_route(WORKSPACES))
+        .header(bearer())
+        .dispatch();
+    assert_eq!(response.status(), Status::Unauthorized);
+
+    // Access tokens can't manage access tokens, even when the scope would allow it
+    let response = script
+        .get(route_users_access_tokens())
+        .header(bearer())
+        .dispatch();
+    assert_eq!(response.status(), Status::Unauthorized);
+
+    // Revoke the token with the session; the token stops working right away
+    let response = client
+        .delete(route_users_access_token(&new_token.info.id.to_string()))
+        .header(csrf(&client))
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+
+    let response = script.get(route_users_me()).header(bearer()).dispatch();
+    assert_eq!(response.status(), Status::Unauthorized);
+}
+
+#[test]
+fn write_scope_includes_read() {
+    let client = test_client();
+    login(&client, DEFAULT_LOGIN);
+
+    let new_token = create_access_token(&client, vec!["workspaces:write"]);
+
+    let script = test_client();
+    let response = script
+        .get(root_route(WORKSPACES))
+        .header(Header::new(
+            "Authorization",
+            format!("Bearer {}", new_token.token),
+        ))
+        .dispatch();
+
+    assert_eq!(response.status(), Status::Ok);
+
+    client
+        .delete(route_users_access_token(&new_token.info.id.to_string()))
+        .header(csrf(&client))
+        .dispatch();
+}
+
+#[test]
+fn create_access_token_with_invalid_scope() {
+    let client = test_client();
+    login(&client, DEFAULT_LOGIN);
+
+    let form = AccessTokenForm {
+        name: "ci",
+        scopes: vec!["everything:write"],
+        expires_in_days: 30,
+    };
+
+    let response = client
+        .post(route_users_access_tokens())
+        .header(csrf(&client))
+        .header(ContentType::Form)
+        .body(form.body())
+        .dispatch();
+
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+}
+
+fn create_access_token(client: &Client, scopes: Vec<&str>) -> NewAccessToken {
+    let form = AccessTokenForm {
+        name: "ci",
+        scopes,
+        expires_in_days: 30,
+    };
+
+    let response = client
+        .post(route_users_access_tokens())
+        .header(csrf(client))
+        .header(ContentType::Form)
+        .body(form.body())
+        .dispatch();
+
+    assert_eq!(response.status(), Status::Ok);
+
+    response
+        .into_json::<ApiResponse<NewAccessToken>>()
+        .unwrap()
+        .data
+        .unwrap()
+}
@@ -0,0 +1,152 @@
+use rocket::{
+    http::{ContentType, Status},
+    local::blocking::Client,
+    tokio::runtime,
+};
+
+use super::{
+    get_redis, get_self, inject_user, login, logout, remove_user, route_users_change_email,
+    route_users_change_password, route_users_confirm_email, route_users_me, DEFAULT_PASSWORD,
+};
+use crate::{
+    cache,
+    forms::{
+        login::LoginForm,
+        password::{ChangeEmailForm, ChangePasswordForm, Password},
+    },
+    models::users::EmailChange,
+    tests::{csrf, test_client},
+};
+
+const NEW_PASSWORD: &str = "another_strong_password";
+
+#[test]
+fn change_password_revokes_other_sessions() {
+    let client = test_client();
+    let (user_id, username) = inject_user(&client, "credentials");
+
+    let credentials = LoginForm {
+        username: &username,
+        password: DEFAULT_PASSWORD,
+    };
+
+    let other = test_client();
+    login(&other, credentials);
+    login(&client, credentials);
+
+    // The current password has to be confirmed
+    let response = change_password(&client, "wrong_password");
+    assert_eq!(response, Status::Unauthorized);
+
+    let response = change_password(&client, DEFAULT_PASSWORD);
+    assert_eq!(response, Status::Ok);
+
+    // The session that changed the password stays logged in, the other one doesn't
+    assert_eq!(client.get(route_users_me()).dispatch().status(), Status::Ok);
+    assert_eq!(
+        other.get(route_users_me()).dispatch().status(),
+        Status::Unauthorized
+    );
+    logout(&client);
+
+    // Only the new password is accepted from now on
+    login(
+        &client,
+        LoginForm {
+            username: &username,
+            password: NEW_PASSWORD,
+        },
+    );
+    logout(&client);
+
+    remove_user(&client, user_id);
+}
+
+#[test]
+fn change_email_after_confirmation() {
+    let client = test_client();
+    let (user_id, username) = inject_user(&client, "credentials");
+    let old_email = format!("{username}@example.com");
+    let new_email = format!("new_{username}@example.com");
+
+    login(
+        &client,
+        LoginForm {
+            username: &username,
+            password: DEFAULT_PASSWORD,
+        },
+    );
+
+    // The current password has to be confirmed, and the address must be free
+    assert_eq!(
+        change_email(&client, &new_email, "wrong_password"),
+        Status::Unauthorized
+    );
+    assert_eq!(
+        change_email(&client, "admin@rustle.com", DEFAULT_PASSWORD),
+        Status::Conflict
+    );
+
+    // The address isn't changed before it's confirmed
+    assert_eq!(
+        change_email(&client, &new_email, DEFAULT_PASSWORD),
+        Status::Ok
+    );
+    assert_eq!(get_self(&client).email, old_email);
+
+    // Add a token for the change, as the one from the email can't be read
+    let token = cache::create_random_token(64);
+    let change = EmailChange {
+        user: user_id,
+        email: new_email.clone(),
+    };
+    runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+        .block_on(cache::users::add_email_change_token(
+            get_redis(client.rocket()),
+            &token,
+            &change,
+        ))
+        .unwrap();
+
+    let response = client.put(route_users_confirm_email(&token)).dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(get_self(&client).email, new_email);
+
+    // The token can only be used once
+    let response = client.put(route_users_confirm_email(&token)).dispatch();
+    assert_eq!(response.status(), Status::NotFound);
+
+    logout(&client);
+    remove_user(&client, user_id);
+}
+
+fn change_password(client: &Client, current: &str) -> Status {
+    let form = ChangePasswordForm {
+        current,
+        new: Password {
+            first: NEW_PASSWORD,
+            second: NEW_PASSWORD,
+        },
+    };
+
+    client
+        .put(route_users_change_password())
+        .header(csrf(client))
+        .header(ContentType::Form)
+        .body(form.body())
+        .dispatch()
+        .status()
+}
+
+fn change_email(client: &Client, email: &str, password: &str) -> Status {
+    client
+        .post(route_users_change_email())
+        .header(csrf(client))
+        .header(ContentType::Form)
+        .body(ChangeEmailForm { email, password }.body())
+        .dispatch()
+        .status()
+}
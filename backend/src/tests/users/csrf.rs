@@ -0,0 +1,79 @@
+use rocket::{
+    http::{ContentType, Header, Status},
+    local::blocking::{Client, LocalRequest},
+};
+use uuid::Uuid;
+
+use super::{inject_user, login, logout, remove_user, route_users_me, DEFAULT_PASSWORD};
+use crate::{
+    cookies::{CSRF_HEADER, TOKEN_COOKIE},
+    forms::login::LoginForm,
+    models::users::UserUpdate,
+    routes::USERS,
+    tests::{csrf, test_client},
+};
+
+#[test]
+fn mutating_requests_by_cookie_require_csrf_token() {
+    let client = test_client();
+    let (user_id, username) = inject_user(&client, "csrf");
+
+    login(
+        &client,
+        LoginForm {
+            username: &username,
+            password: DEFAULT_PASSWORD,
+        },
+    );
+
+    // Reading doesn't need the token
+    assert_eq!(client.get(route_users_me()).dispatch().status(), Status::Ok);
+
+    // Without the token, or with the wrong one, the request is refused
+    let response = update_bio(&client, user_id).dispatch();
+    assert_eq!(response.status(), Status::Forbidden);
+
+    let response = update_bio(&client, user_id)
+        .header(Header::new(CSRF_HEADER, "forged"))
+        .dispatch();
+    assert_eq!(response.status(), Status::Forbidden);
+
+    let response = update_bio(&client, user_id)
+        .header(csrf(&client))
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+
+    // A request with a bearer token can't be made by the browser on its own
+    let token = client
+        .cookies()
+        .get_private(TOKEN_COOKIE)
+        .unwrap()
+        .value()
+        .to_string();
+
+    let script = test_client();
+    let response = update_bio(&script, user_id)
+        .header(Header::new("Authorization", format!("Bearer {token}")))
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+
+    logout(&client);
+    remove_user(&client, user_id);
+}
+
+fn update_bio(client: &Client, user_id: Uuid) -> LocalRequest<'_> {
+    let update = UserUpdate {
+        username: None,
+        first_name: None,
+        last_name: None,
+        phone: None,
+        job_title: None,
+        bio: Some("Protected against cross-site requests".to_string()),
+        avatar_url: None,
+    };
+
+    client
+        .put(format!("{USERS}update/{user_id}"))
+        .header(ContentType::JSON)
+        .body(serde_json::to_string(&update).unwrap())
+}
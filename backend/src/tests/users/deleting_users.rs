@@ -4,7 +4,7 @@ use super::{login, ADMIN_LOGIN, ADMIN_USERNAME, DEFAULT_USERNAME};
 use crate::{
     database::pagination::{request::PaginationRequest, sort::UserField},
     tests::{
-        response_ok, test_client,
+        csrf, response_ok, test_client,
         users::{route_users_all, route_users_delete},
     },
 };
@@ -15,7 +15,11 @@ const TARGETED_USER: &str = "77987439-2fed-4d45-9f5d-4c02c66eb265";
 fn delete_existing_user_by_id() {
     let client = test_client();
     login(&client, ADMIN_LOGIN);
-    response_ok(client.delete(route_users_delete(TARGETED_USER)));
+    response_ok(
+        client
+            .delete(route_users_delete(TARGETED_USER))
+            .header(csrf(&client)),
+    );
 }
 
 #[test]
@@ -61,7 +65,10 @@ fn delete_all_users_except_for_admin_and_test_user() {
             }
 
             // Delete the user
-            let response = client.delete(route_users_delete(user_id)).dispatch();
+            let response = client
+                .delete(route_users_delete(user_id))
+                .header(csrf(&client))
+                .dispatch();
             assert_eq!(response.status(), Status::Ok);
         }
     }
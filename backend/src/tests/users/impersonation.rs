@@ -0,0 +1,186 @@
+use rocket::{
+    http::{Header, Status},
+    local::blocking::Client,
+};
+
+use super::{
+    get_self, inject_user, login, logout, remove_user, route_users_by_name, route_users_delete,
+    route_users_impersonate, route_users_impersonation_logs, route_users_me, route_users_remove,
+    route_users_suspend, route_users_unlock, route_users_update_role, ADMIN_LOGIN,
+    DEFAULT_PASSWORD,
+};
+use crate::{
+    api::ApiResponse,
+    forms::login::LoginForm,
+    models::{
+        impersonation::{ImpersonationLog, ImpersonationToken},
+        users::{PublicUser, UserRole, UserStatus},
+    },
+    tests::{csrf, response_ok, response_unauthorized, test_client},
+};
+
+#[test]
+fn admin_acts_as_user_with_an_audit_trail() {
+    let admin = test_client();
+    let (user_id, username) = inject_user(&admin, "impersonated");
+
+    login(&admin, ADMIN_LOGIN);
+    let impersonation = impersonate(&admin, &user_id.to_string());
+    assert_eq!(impersonation.user.username, username);
+
+    // The token acts as the user, from another client without the cookies of the admin
+    let client = test_client();
+    let bearer = || Header::new("Authorization", format!("Bearer {}", impersonation.token));
+
+    let me = client
+        .get(route_users_me())
+        .header(bearer())
+        .dispatch()
+        .into_json::<ApiResponse<PublicUser>>()
+        .unwrap()
+        .data
+        .unwrap();
+    assert_eq!(me.id, user_id);
+
+    // Even though a user may delete self, not while an admin acts as the user
+    let response = client
+        .delete(route_users_delete(&user_id.to_string()))
+        .header(bearer())
+        .dispatch();
+    assert_eq!(response.status(), Status::Forbidden);
+
+    // The start and every request made with the token are logged
+    let logs = get_logs(&admin, &user_id.to_string());
+    assert_eq!(logs.len(), 3);
+    assert!(logs.iter().all(|log| log.user_id == user_id));
+    assert!(logs
+        .iter()
+        .any(|log| log.method == "DELETE" && log.status == 403));
+    assert!(logs
+        .iter()
+        .any(|log| log.method == "GET" && log.path == route_users_me() && log.status == 200));
+
+    logout(&admin);
+    remove_user(&admin, user_id);
+}
+
+#[test]
+fn impersonation_is_only_for_admins_acting_as_others() {
+    let client = test_client();
+    let (user_id, username) = inject_user(&client, "impersonating");
+    let (other_id, _) = inject_user(&client, "impersonated");
+
+    // A user can't act as someone else, nor read the logs
+    login(
+        &client,
+        LoginForm {
+            username: &username,
+            password: DEFAULT_PASSWORD,
+        },
+    );
+    response_unauthorized(
+        client
+            .post(route_users_impersonate(&other_id.to_string()))
+            .header(csrf(&client)),
+    );
+    response_unauthorized(client.get(route_users_impersonation_logs(&other_id.to_string())));
+    logout(&client);
+
+    // An admin can't act as self, or as another admin
+    login(&client, ADMIN_LOGIN);
+    let admin = get_self(&client);
+    response_unauthorized(
+        client
+            .post(route_users_impersonate(&admin.id.to_string()))
+            .header(csrf(&client)),
+    );
+
+    // An impersonation token can't start another impersonation
+    let impersonation = impersonate(&client, &user_id.to_string());
+    let impersonating = test_client();
+    let response = impersonating
+        .post(route_users_impersonate(&other_id.to_string()))
+        .header(Header::new(
+            "Authorization",
+            format!("Bearer {}", impersonation.token),
+        ))
+        .dispatch();
+    assert_eq!(response.status(), Status::Forbidden);
+    logout(&client);
+
+    remove_user(&client, user_id);
+    remove_user(&client, other_id);
+}
+
+#[test]
+fn users_are_not_managed_while_acting_as_a_manager() {
+    let client = test_client();
+    let (manager_id, _) = inject_user(&client, "impersonated");
+    let (other_id, other_name) = inject_user(&client, "managed");
+
+    login(&client, ADMIN_LOGIN);
+    response_ok(
+        client
+            .put(route_users_update_role(
+                &manager_id.to_string(),
+                UserRole::Manager,
+            ))
+            .header(csrf(&client)),
+    );
+    let impersonation = impersonate(&client, &manager_id.to_string());
+
+    // The manager may remove, suspend or re-role users, but not an admin in the name of one
+    let impersonating = test_client();
+    let other = other_id.to_string();
+    for route in [
+        route_users_remove(&other),
+        route_users_suspend(&other),
+        route_users_update_role(&other, UserRole::Reviewer),
+        route_users_unlock(&other),
+    ] {
+        let response = impersonating
+            .put(route)
+            .header(Header::new(
+                "Authorization",
+                format!("Bearer {}", impersonation.token),
+            ))
+            .dispatch();
+        assert_eq!(response.status(), Status::Forbidden);
+    }
+    let other = client
+        .get(route_users_by_name(&other_name))
+        .dispatch()
+        .into_json::<ApiResponse<PublicUser>>()
+        .unwrap()
+        .data
+        .unwrap();
+    assert_eq!(other.status, UserStatus::Active);
+
+    logout(&client);
+    remove_user(&client, manager_id);
+    remove_user(&client, other_id);
+}
+
+fn impersonate(client: &Client, id: &str) -> ImpersonationToken {
+    let response = client
+        .post(route_users_impersonate(id))
+        .header(csrf(client))
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+
+    response
+        .into_json::<ApiResponse<ImpersonationToken>>()
+        .unwrap()
+        .data
+        .unwrap()
+}
+
+fn get_logs(client: &Client, id: &str) -> Vec<ImpersonationLog> {
+    client
+        .get(route_users_impersonation_logs(id))
+        .dispatch()
+        .into_json::<ApiResponse<Vec<ImpersonationLog>>>()
+        .unwrap()
+        .data
+        .unwrap()
+}
@@ -6,7 +6,7 @@ use super::{ADMIN_PASSWORD, ADMIN_USERNAME, DEFAULT_PASSWORD, DEFAULT_USERNAME};
 use crate::{
     forms::password::Password,
     models::users::{User, UserRole, UserStatus},
-    tests::{response_ok, test_client, users::route_users_admin_inject_users},
+    tests::{csrf, response_ok, test_client, users::route_users_admin_inject_users},
 };
 
 #[test]
@@ -67,8 +67,8 @@ fn user_injection(
         last_name: last_name.to_string(),
         email: email.to_string(),
         phone: None,
-        role: i16::from(role),
-        status: i16::from(status),
+        role,
+        status,
         job_title: None,
         password: Password::generate(password).unwrap(),
         bio: None,
@@ -83,6 +83,7 @@ fn user_injection(
     response_ok(
         client
             .post(route_users_admin_inject_users())
+            .header(csrf(&client))
             .header(ContentType::JSON)
             .body(payload),
     );
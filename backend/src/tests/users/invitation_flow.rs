@@ -1,26 +1,27 @@
 use std::str::FromStr;
 
+use chrono::{Duration, Utc};
 use rocket::{
     http::{ContentType, Status},
     local::asynchronous::Client,
-    State,
 };
 use uuid::Uuid;
 
 use super::{async_login, DEFAULT_LOGIN, DEFAULT_PASSWORD};
 use crate::{
     api::ApiResponse,
-    cache::{self, RedisMutex},
+    database::{self, Db},
     forms::{
         invite::{InvitedMultipleUsersForm, InvitedUserForm},
         password::Password,
     },
     models::{
+        invitations::{Invitation, InvitationStatus},
         users::{PublicUser, UserStatus},
         workspaces::WorkspaceRole,
     },
     tests::{
-        async_test_client,
+        async_csrf, async_test_client,
         users::{
             route_users_logout, ADMIN_LOGIN, DUPLICATE_USER_1_EMAIL_ADDR,
             DUPLICATE_USER_2_EMAIL_ADDR, INVITED_USER_1_EMAIL_ADDR, INVITED_USER_1_FIRST_NAME,
@@ -46,35 +47,35 @@ async fn invite_new_users_by_form() {
                 last_name: INVITED_USER_1_LAST_NAME,
                 email: INVITED_USER_1_EMAIL_ADDR,
                 phone: Some("0031699748558"),
-                workspace_role: i16::from(WorkspaceRole::Contributor),
+                workspace_role: WorkspaceRole::Contributor,
             },
             InvitedUserForm {
                 first_name: INVITED_USER_1_FIRST_NAME,
                 last_name: INVITED_USER_1_LAST_NAME,
                 email: DUPLICATE_USER_1_EMAIL_ADDR,
                 phone: None,
-                workspace_role: i16::from(WorkspaceRole::Manager),
+                workspace_role: WorkspaceRole::Manager,
             },
             InvitedUserForm {
                 first_name: INVITED_USER_2_FIRST_NAME,
                 last_name: INVITED_USER_2_LAST_NAME,
                 email: INVITED_USER_2_EMAIL_ADDR,
                 phone: Some("0683650773"),
-                workspace_role: i16::from(WorkspaceRole::Stakeholder),
+                workspace_role: WorkspaceRole::Stakeholder,
             },
             InvitedUserForm {
                 first_name: INVITED_USER_2_FIRST_NAME,
                 last_name: INVITED_USER_2_LAST_NAME,
                 email: DUPLICATE_USER_2_EMAIL_ADDR,
                 phone: None,
-                workspace_role: i16::from(WorkspaceRole::Viewer),
+                workspace_role: WorkspaceRole::Viewer,
             },
             InvitedUserForm {
                 first_name: INVITED_USER_3_FIRST_NAME,
                 last_name: INVITED_USER_3_LAST_NAME,
                 email: INVITED_USER_3_EMAIL_ADDR,
                 phone: None,
-                workspace_role: i16::from(WorkspaceRole::Viewer),
+                workspace_role: WorkspaceRole::Viewer,
             },
         ],
     };
@@ -82,6 +83,7 @@ async fn invite_new_users_by_form() {
     // Send submit request
     let response = client
         .post(route_workspaces_invite_to_workspace())
+        .header(async_csrf(&client))
         .body(invitation.body())
         .header(ContentType::Form)
         .dispatch()
@@ -99,17 +101,15 @@ async fn invite_new_users_by_form() {
     // Assert the submit request was successful
     assert_eq!(status, Status::Ok);
 
-    // Get the redis cache
-    let redis: &State<RedisMutex> = client
-        .rocket()
-        .state::<RedisMutex>()
-        .expect("Redis state should be available")
-        .into();
+    let db = Db::get_one(client.rocket()).await.unwrap();
 
     // Loop through the tokens from the response
     for token in invitation_response.data.unwrap() {
-        // Attempt getting the token from the cache
-        assert!(cache::users::get_invite_token(redis, &token).await.is_ok());
+        // Every token belongs to a pending invitation
+        assert_eq!(
+            invitation_status(&db, &token).await,
+            InvitationStatus::Pending
+        );
     }
 }
 
@@ -125,6 +125,7 @@ async fn reinvite_user_by_id() {
         .post(format!(
             "/workspaces/{TARGETED_WORKSPACE}/re-invite/{user_id}"
         ))
+        .header(async_csrf(&client))
         .dispatch()
         .await;
 
@@ -136,21 +137,18 @@ async fn reinvite_user_by_id() {
 
     let token = deserialized_response.unwrap().data.unwrap();
 
-    // Get the redis cache
-    let redis: &State<RedisMutex> = client
-        .rocket()
-        .state::<RedisMutex>()
-        .expect("Redis state should be available")
-        .into();
-
-    assert!(cache::users::get_invite_token(redis, &token).await.is_ok());
+    let db = Db::get_one(client.rocket()).await.unwrap();
+    assert_eq!(
+        invitation_status(&db, &token).await,
+        InvitationStatus::Pending
+    );
 }
 
 #[tokio::test]
 async fn set_password_after_receiving_invite() {
     let client = async_test_client().await;
 
-    let (redis, token) = add_token_to_cache(&client, INVITED_USER_1_USERNAME.to_string()).await;
+    let (db, token) = add_invitation(&client, INVITED_USER_1_USERNAME.to_string()).await;
 
     // User clicks the link: The token should be recovered
     let response = client
@@ -175,10 +173,16 @@ async fn set_password_after_receiving_invite() {
 
     assert_eq!(response.status(), Status::Ok);
 
-    // Verify that the cache doesn't have the invitation token anymore
-    assert!(cache::users::get_invite_token(&redis, &token)
-        .await
-        .is_err());
+    // Verify that the invitation is accepted, so the link can't be used again
+    assert_eq!(
+        invitation_status(&db, &token).await,
+        InvitationStatus::Accepted
+    );
+    let response = client
+        .get(format!("{ROUTE_INVITE_GET}{token}"))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Gone);
 
     // Login as newly created user
     async_login(&client, INVITED_USER_1_LOGIN).await;
@@ -202,31 +206,43 @@ async fn set_password_after_receiving_invite() {
     let public_user = deserialized_response.unwrap().data.unwrap();
 
     // Verify that the user is now active
-    assert_eq!(public_user.status, i16::from(UserStatus::Active));
+    assert_eq!(public_user.status, UserStatus::Active);
 }
 
-async fn add_token_to_cache(client: &Client, username: String) -> (&State<RedisMutex>, String) {
-    // Get the redis cache
-    let redis: &State<RedisMutex> = client
-        .rocket()
-        .state::<RedisMutex>()
-        .expect("Redis state should be available")
-        .into();
-
-    // Generate a random token
-    let token = cache::create_random_token(64);
+async fn add_invitation(client: &Client, username: String) -> (Db, String) {
+    let db = Db::get_one(client.rocket()).await.unwrap();
 
     let user_id = get_invited_user_id(client, &username).await;
 
     // Convert the user ID to a UUID
     let user_id = Uuid::from_str(&user_id).unwrap();
 
-    // Add the token to the cache
-    assert!(cache::users::add_invite_token(&redis, &token, user_id)
+    // Invite the user to the workspace, with a new token
+    let sent_at = Utc::now().naive_utc();
+    let (invitation, token) = Invitation::generate(
+        Uuid::from_str(TARGETED_WORKSPACE).unwrap(),
+        user_id,
+        user_id,
+        WorkspaceRole::Contributor,
+        sent_at,
+        sent_at + Duration::hours(1),
+    );
+    let invitation = Invitation {
+        inviter: None,
+        ..invitation
+    };
+    assert!(database::invitations::renew_invitation(&db, invitation)
         .await
         .is_ok());
 
-    (redis, token)
+    (db, token)
+}
+
+async fn invitation_status(db: &Db, token: &str) -> InvitationStatus {
+    database::invitations::get_invitation_by_hash(db, Invitation::hash(token))
+        .await
+        .unwrap()
+        .status
 }
 
 async fn get_invited_user_id(client: &Client, username: &str) -> String {
@@ -249,10 +265,14 @@ async fn get_invited_user_id(client: &Client, username: &str) -> String {
     let public_user = deserialized_response.unwrap().data.unwrap();
 
     // Verify that the user is invited
-    assert_eq!(public_user.status, i16::from(UserStatus::Invited));
+    assert_eq!(public_user.status, UserStatus::Invited);
 
     // Log out
-    let logout_response = client.post(route_users_logout()).dispatch().await;
+    let logout_response = client
+        .post(route_users_logout())
+        .header(async_csrf(client))
+        .dispatch()
+        .await;
 
     // Assert that the logout request was handled succesfully
     assert_eq!(logout_response.status(), Status::Ok);
@@ -0,0 +1,219 @@
+use rocket::{
+    http::{ContentType, Status},
+    local::blocking::Client,
+};
+use serde_json::{json, Value};
+
+use super::{
+    get_self, inject_user, inject_user_with_email, login, logout, remove_user, route_users_login,
+    route_users_me, ADMIN_LOGIN, DEFAULT_PASSWORD,
+};
+use crate::{
+    forms::login::LoginForm,
+    models::users::{PublicUser, UserRole},
+    tests::test_client,
+};
+
+/// Nothing listens here, so every connection is refused right away.
+const UNREACHABLE_URL: &str = "ldap://127.0.0.1:1";
+
+const BASE_DN: &str = "dc=rustle,dc=local";
+const MANAGERS_GROUP: &str = "cn=managers,ou=groups,dc=rustle,dc=local";
+const LDAP_PASSWORD: &str = "strong_password";
+
+#[test]
+fn local_users_log_in_when_the_directory_is_down() {
+    let client = ldap_client(json!(["ldap", "password"]), UNREACHABLE_URL);
+
+    // The password backend is tried after the directory fails
+    login(&client, ADMIN_LOGIN);
+    logout(&client);
+}
+
+#[test]
+fn failed_logins_are_counted_when_the_directory_is_down() {
+    let client = ldap_client(json!(["ldap", "password"]), UNREACHABLE_URL);
+    let (user_id, username) = inject_user(&client, "ldap_lockout");
+
+    let wrong_password = LoginForm {
+        username: &username,
+        password: "wrong_password",
+    };
+
+    // The free attempts and the first one that causes a delay, while the directory can't tell
+    for _ in 0..4 {
+        assert_eq!(login_status(&client, wrong_password), Status::BadGateway);
+    }
+
+    // Even the correct password is refused while the username is blocked
+    let credentials = LoginForm {
+        username: &username,
+        password: DEFAULT_PASSWORD,
+    };
+    assert_eq!(login_status(&client, credentials), Status::TooManyRequests);
+
+    remove_user(&client, user_id);
+}
+
+#[test]
+fn only_the_selected_backends_are_used() {
+    let client = ldap_client(json!(["ldap"]), UNREACHABLE_URL);
+    let admin = test_client();
+    let (user_id, username) = inject_user(&admin, "ldap_local");
+
+    // Without the password backend, a local user can't log in
+    let credentials = LoginForm {
+        username: &username,
+        password: DEFAULT_PASSWORD,
+    };
+    assert_eq!(login_status(&client, credentials), Status::BadGateway);
+
+    remove_user(&admin, user_id);
+}
+
+#[test]
+#[ignore = "needs the OpenLDAP container of docker-compose.dev.yml"]
+fn directory_users_are_provisioned_with_the_role_of_their_groups() {
+    let url = std::env::var("TEST_LDAP_URL").unwrap_or("ldap://localhost:389".to_string());
+    let client = ldap_client(json!(["ldap", "password"]), &url);
+
+    // A wrong password is refused by the directory
+    let wrong_password = LoginForm {
+        username: "ldap_manager",
+        password: "wrong_password",
+    };
+    assert_eq!(login_status(&client, wrong_password), Status::Unauthorized);
+
+    // The name, email address and role come from the directory
+    let manager = login_as(&client, "ldap_manager");
+    assert_eq!(manager.first_name, "Lena");
+    assert_eq!(manager.last_name, "Hart");
+    assert_eq!(manager.email, "ldap_manager@rustle.local");
+    assert_eq!(manager.role, UserRole::Manager);
+    logout(&client);
+
+    // Members of none of the mapped groups get the default role
+    let member = login_as(&client, "ldap_member");
+    assert_eq!(member.role, UserRole::Reviewer);
+    logout(&client);
+
+    // The second login finds the linked user instead of provisioning another
+    assert_eq!(login_as(&client, "ldap_manager").id, manager.id);
+
+    // A role lost in the directory ends the sessions that still have it
+    let demoted = ldap_client_with_roles(
+        json!(["ldap", "password"]),
+        &url,
+        json!({ MANAGERS_GROUP: "Contributor" }),
+    );
+    assert_eq!(
+        login_as(&demoted, "ldap_manager").role,
+        UserRole::Contributor
+    );
+    assert_eq!(
+        client.get(route_users_me()).dispatch().status(),
+        Status::Unauthorized
+    );
+    logout(&demoted);
+
+    remove_user(&client, manager.id);
+    remove_user(&client, member.id);
+}
+
+#[test]
+#[ignore = "needs the OpenLDAP container of docker-compose.dev.yml"]
+fn directory_users_are_linked_by_email_only_when_enabled() {
+    let url = std::env::var("TEST_LDAP_URL").unwrap_or("ldap://localhost:389".to_string());
+    let admin = test_client();
+    let (local_id, _) =
+        inject_user_with_email(&admin, "ldap_local", Some("ldap_manager@rustle.local"));
+
+    // Without linking, the account isn't matched, nor provisioned with the address in use
+    let client = ldap_client_linking(json!(["ldap"]), &url, false);
+    let credentials = LoginForm {
+        username: "ldap_manager",
+        password: LDAP_PASSWORD,
+    };
+    assert_ne!(login_status(&client, credentials), Status::Ok);
+
+    // The linked user gets the name and role of the directory on the first login
+    let client = ldap_client_linking(json!(["ldap"]), &url, true);
+    let manager = login_as(&client, "ldap_manager");
+    assert_eq!(manager.id, local_id);
+    assert_eq!(manager.first_name, "Lena");
+    assert_eq!(manager.role, UserRole::Manager);
+    logout(&client);
+
+    remove_user(&admin, local_id);
+}
+
+/// A client of the application, with the given backends and directory.
+fn ldap_client(backends: Value, url: &str) -> Client {
+    ldap_client_with_roles(backends, url, json!({ MANAGERS_GROUP: "Manager" }))
+}
+
+/// A client of the application, with the given backends, directory and role mapping.
+fn ldap_client_with_roles(backends: Value, url: &str, role_mapping: Value) -> Client {
+    ldap_client_with(backends, url, role_mapping, false)
+}
+
+/// A client of the application, with the given backends and directory, that may link accounts
+/// to existing users by their email address.
+fn ldap_client_linking(backends: Value, url: &str, link_by_email: bool) -> Client {
+    ldap_client_with(
+        backends,
+        url,
+        json!({ MANAGERS_GROUP: "Manager" }),
+        link_by_email,
+    )
+}
+
+fn ldap_client_with(
+    backends: Value,
+    url: &str,
+    role_mapping: Value,
+    link_by_email: bool,
+) -> Client {
+    let rocket = crate::rocket();
+    let figment = rocket
+        .figment()
+        .clone()
+        .merge(("auth", json!({ "backends": backends })))
+        .merge((
+            "ldap",
+            json!({
+                "url": url,
+                "bind_dn": format!("cn=admin,{BASE_DN}"),
+                "bind_password": "SYqujNZNmvEw2Ajk",
+                "base_dn": format!("ou=people,{BASE_DN}"),
+                "group_base_dn": format!("ou=groups,{BASE_DN}"),
+                "role_mapping": role_mapping,
+                "provision_users": true,
+                "link_by_email": link_by_email,
+                "default_role": "Reviewer",
+            }),
+        ));
+
+    Client::tracked(rocket.configure(figment)).expect("valid rocket instance")
+}
+
+fn login_status(client: &Client, login_form: LoginForm) -> Status {
+    client
+        .post(route_users_login())
+        .header(ContentType::Form)
+        .body(login_form.body())
+        .dispatch()
+        .status()
+}
+
+fn login_as(client: &Client, username: &str) -> PublicUser {
+    login(
+        client,
+        LoginForm {
+            username,
+            password: LDAP_PASSWORD,
+        },
+    );
+
+    get_self(client)
+}
@@ -0,0 +1,106 @@
+use rocket::{
+    http::{ContentType, Status},
+    local::blocking::Client,
+};
+use uuid::Uuid;
+
+use super::{
+    inject_user, login, logout, remove_user, route_users_login, route_users_unlock, ADMIN_LOGIN,
+    DEFAULT_PASSWORD,
+};
+use crate::{
+    api::ApiResponse,
+    cache::create_random_token,
+    forms::login::LoginForm,
+    tests::{csrf, response_not_found, test_client},
+};
+
+#[test]
+fn unknown_user_and_wrong_password_get_the_same_response() {
+    let client = test_client();
+    let (user_id, username) = inject_user(&client, "lockout");
+
+    let wrong_password = attempt_login(
+        &client,
+        LoginForm {
+            username: &username,
+            password: "wrong_password",
+        },
+    );
+    // A fresh username each run, as the failures are counted for unknown usernames as well
+    let unknown_username = format!("lockout_{}", create_random_token(8).to_lowercase());
+    let unknown_user = attempt_login(
+        &client,
+        LoginForm {
+            username: &unknown_username,
+            password: "wrong_password",
+        },
+    );
+
+    assert_eq!(wrong_password, unknown_user);
+    assert_eq!(wrong_password.0, Status::Unauthorized);
+
+    remove_user(&client, user_id);
+}
+
+#[test]
+fn account_is_blocked_after_failures_until_unlocked() {
+    let client = test_client();
+    let (user_id, username) = inject_user(&client, "lockout");
+
+    let wrong_credentials = LoginForm {
+        username: &username,
+        password: "wrong_password",
+    };
+    let credentials = LoginForm {
+        username: &username,
+        password: DEFAULT_PASSWORD,
+    };
+
+    // The free attempts and the first one that causes a delay
+    for _ in 0..4 {
+        let (status, _) = attempt_login(&client, wrong_credentials);
+        assert_eq!(status, Status::Unauthorized);
+    }
+
+    // Even the correct password is refused while the username is blocked
+    let (status, _) = attempt_login(&client, credentials);
+    assert_eq!(status, Status::TooManyRequests);
+
+    // An admin can lift the block
+    login(&client, ADMIN_LOGIN);
+    let response = client
+        .put(route_users_unlock(&user_id.to_string()))
+        .header(csrf(&client))
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+
+    // Unlocking a user that doesn't exist
+    response_not_found(
+        client
+            .put(route_users_unlock(&Uuid::new_v4().to_string()))
+            .header(csrf(&client)),
+    );
+    logout(&client);
+
+    login(&client, credentials);
+    logout(&client);
+
+    remove_user(&client, user_id);
+}
+
+fn attempt_login(client: &Client, credentials: LoginForm) -> (Status, String) {
+    let response = client
+        .post(route_users_login())
+        .header(ContentType::Form)
+        .body(credentials.body())
+        .dispatch();
+
+    let status = response.status();
+    let message = response
+        .into_json::<ApiResponse<()>>()
+        .map(|body| body.message)
+        .unwrap_or_default();
+
+    (status, message)
+}
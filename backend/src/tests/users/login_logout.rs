@@ -1,12 +1,17 @@
-use rocket::http::ContentType;
+use jsonwebtoken::{encode, EncodingKey, Header};
+use rocket::http::{ContentType, Header as HttpHeader};
+use uuid::Uuid;
 
 use crate::{
+    auth::Claims,
+    cookies::{REFRESH_COOKIE, TOKEN_COOKIE},
     forms::login::LoginForm,
     tests::{
-        response_not_found, response_ok, response_unauthorized, test_client,
+        csrf, response_ok, response_unauthorized, test_client,
         users::{
-            login, logout, route_users_by_name, route_users_login, route_users_logout, ADMIN_LOGIN,
-            DEFAULT_LOGIN, INVITED_USER_2_USERNAME,
+            get_self, login, logout, route_users_by_name, route_users_login, route_users_logout,
+            route_users_me, route_users_refresh, ADMIN_LOGIN, DEFAULT_LOGIN,
+            INVITED_USER_2_USERNAME,
         },
     },
 };
@@ -32,7 +37,7 @@ fn login_then_logout(login_form: LoginForm) {
 #[test]
 fn logout_without_being_logged_in() {
     let client = test_client();
-    response_unauthorized(client.post(route_users_logout()));
+    response_unauthorized(client.post(route_users_logout()).header(csrf(&client)));
 }
 
 #[test]
@@ -49,11 +54,86 @@ fn login_attempt_by_invited_user() {
     // d) Logout
     logout(&client);
 
-    // Attempt login as invited user
-    response_not_found(
+    // Attempt login as invited user; gets the same response as an unknown user
+    response_unauthorized(
         client
             .post(route_users_login())
             .header(ContentType::Form)
             .body(INVITED_USER_2_LOGIN.body()),
     );
 }
+
+#[test]
+fn request_with_forged_token() {
+    let client = test_client();
+
+    // Login to get a real user in the claims
+    login(&client, ADMIN_LOGIN);
+    let user = get_self(&client);
+    logout(&client);
+
+    // Sign the claims with a key that is not known to the server
+    let claims = Claims {
+        sub: user.id,
+        user,
+        sid: Uuid::new_v4(),
+        exp: (chrono::Utc::now().timestamp() + 3600) as usize,
+        impersonator: None,
+    };
+    let header = Header {
+        kid: Some("default".to_string()),
+        ..Default::default()
+    };
+    let token = encode(&header, &claims, &EncodingKey::from_secret(b"forged")).unwrap();
+
+    response_unauthorized(
+        client
+            .get(route_users_me())
+            .header(HttpHeader::new("Authorization", format!("Bearer {token}"))),
+    );
+}
+
+#[test]
+fn refresh_token_rotation() {
+    let client = test_client();
+    login(&client, DEFAULT_LOGIN);
+
+    // Keep the first refresh token
+    let first_refresh_token = client.cookies().get_private(REFRESH_COOKIE).unwrap();
+
+    // Refreshing issues a new refresh token
+    response_ok(client.post(route_users_refresh()));
+    let second_refresh_token = client.cookies().get_private(REFRESH_COOKIE).unwrap();
+    assert_ne!(first_refresh_token.value(), second_refresh_token.value());
+
+    // The new access token is accepted
+    response_ok(client.get(route_users_me()));
+
+    // Replaying the first refresh token from somewhere else revokes the session
+    let other_client = test_client();
+    response_unauthorized(
+        other_client
+            .post(route_users_refresh())
+            .private_cookie(first_refresh_token),
+    );
+    response_unauthorized(client.get(route_users_me()));
+}
+
+#[test]
+fn copied_token_rejected_after_logout() {
+    let client = test_client();
+    login(&client, DEFAULT_LOGIN);
+
+    // Copy the access token before logging out
+    let token = client.cookies().get_private(TOKEN_COOKIE).unwrap();
+    logout(&client);
+
+    // The copied token belongs to a revoked session
+    response_unauthorized(client.get(route_users_me()).header(HttpHeader::new(
+        "Authorization",
+        format!("Bearer {}", token.value()),
+    )));
+
+    // The refresh token is gone as well
+    response_unauthorized(client.post(route_users_refresh()));
+}
@@ -0,0 +1,512 @@
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    sync::{Arc, Mutex},
+    thread,
+};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use jsonwebtoken::{encode, EncodingKey, Header};
+use reqwest::Url;
+use rocket::{
+    http::{ContentType, Status},
+    local::blocking::Client,
+};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+use super::{
+    get_self, inject_user, logout, remove_user, route_users_me, route_users_two_factor_login,
+    route_users_two_factor_login_enroll, two_factor::current_code,
+};
+use crate::{
+    api::ApiResponse,
+    cache::create_random_token,
+    forms::two_factor::{TwoFactorChallengeForm, TwoFactorLoginForm},
+    models::{
+        two_factor::TwoFactorEnrollment,
+        users::{PublicUser, UserRole},
+    },
+    routes::USERS,
+    tests::{response_not_found, response_unauthorized, test_client},
+};
+
+const CLIENT_ID: &str = "rustle";
+const CLIENT_SECRET: &str = "mock_client_secret_that_is_long_enough";
+const REDIRECT_URI: &str = "http://localhost:8000/user/login/oidc/callback";
+const POST_LOGIN_REDIRECT: &str = "http://localhost:5173/dashboard";
+const AUTHORIZATION_CODE: &str = "mock_authorization_code";
+
+fn route_users_login_oidc() -> String {
+    format!("{USERS}login/oidc")
+}
+
+fn route_users_login_oidc_callback(code: &str, state: &str) -> String {
+    format!("{USERS}login/oidc/callback?code={code}&state={state}")
+}
+
+#[test]
+fn sso_login_provisions_and_links_a_new_user() {
+    let provider = MockProvider::start();
+    let client = provider.client(true);
+    let subject = create_random_token(16);
+    let username = format!("sso_{}", create_random_token(8).to_lowercase());
+    let email = format!("{username}@example.com");
+
+    provider.set_identity(&subject, &email, &username);
+
+    // The first login creates the user, with the default role
+    let user = sso_login(&client, &provider);
+    assert_eq!(user.username, username);
+    assert_eq!(user.email, email);
+    assert_eq!(user.role, UserRole::Manager);
+    logout(&client);
+
+    // The next login finds the same user by the identity, even when the email changed
+    provider.set_identity(&subject, &format!("new_{email}"), &username);
+    assert_eq!(sso_login(&client, &provider).id, user.id);
+    logout(&client);
+
+    remove_user(&client, user.id);
+}
+
+#[test]
+fn sso_login_links_an_existing_user_by_email() {
+    let provider = MockProvider::start();
+    let client = provider.client(false);
+    let (user_id, username) = inject_user(&client, "sso");
+
+    provider.set_identity(
+        &create_random_token(16),
+        &format!("{username}@example.com"),
+        "someone_else",
+    );
+
+    assert_eq!(sso_login(&client, &provider).id, user_id);
+    logout(&client);
+
+    // Without provisioning, an unknown identity is refused
+    provider.set_identity(&create_random_token(16), "unknown@example.com", "unknown");
+
+    let state = start_login(&client, &provider);
+    let response = client
+        .get(route_users_login_oidc_callback(AUTHORIZATION_CODE, &state))
+        .dispatch();
+    assert_eq!(response.status(), Status::Unauthorized);
+
+    remove_user(&client, user_id);
+}
+
+#[test]
+fn sso_login_needs_a_verified_email_to_link_a_user() {
+    let provider = MockProvider::start();
+    let client = provider.client(true);
+    let (user_id, username) = inject_user(&client, "sso");
+
+    // A provider that doesn't say the email is verified can't take over the user with it
+    for email_verified in [None, Some(false)] {
+        provider.set_identity(
+            &create_random_token(16),
+            &format!("{username}@example.com"),
+            "someone_else",
+        );
+        provider.state.lock().unwrap().email_verified = email_verified;
+
+        let state = start_login(&client, &provider);
+        let response = client
+            .get(route_users_login_oidc_callback(AUTHORIZATION_CODE, &state))
+            .dispatch();
+        assert_eq!(response.status(), Status::Unauthorized);
+        response_unauthorized(client.get(route_users_me()));
+    }
+
+    remove_user(&client, user_id);
+}
+
+#[test]
+fn sso_login_requires_the_second_factor_of_the_role() {
+    let provider = MockProvider::start();
+    let client = provider.client_requiring_second_factor(true, &[UserRole::Manager]);
+    let username = format!("sso_{}", create_random_token(8).to_lowercase());
+
+    provider.set_identity(
+        &create_random_token(16),
+        &format!("{username}@example.com"),
+        &username,
+    );
+
+    // The provider alone only results in a challenge, without the auth cookie
+    let state = start_login(&client, &provider);
+    let response = client
+        .get(route_users_login_oidc_callback(AUTHORIZATION_CODE, &state))
+        .dispatch();
+    assert_eq!(response.status(), Status::SeeOther);
+    let location = response.headers().get_one("Location").unwrap().to_string();
+    let (redirect, fragment) = location.split_once('#').unwrap();
+    assert_eq!(redirect, POST_LOGIN_REDIRECT);
+    let params: HashMap<String, String> = Url::parse(&format!("http://localhost/?{fragment}"))
+        .unwrap()
+        .query_pairs()
+        .into_owned()
+        .collect();
+    assert_eq!(params["enrollment_required"], "true");
+    response_unauthorized(client.get(route_users_me()));
+
+    // The login is completed by enrolling, like with a password
+    let challenge = &params["challenge"];
+    let enrollment = client
+        .post(route_users_two_factor_login_enroll())
+        .header(ContentType::Form)
+        .body(TwoFactorChallengeForm { challenge }.body())
+        .dispatch()
+        .into_json::<ApiResponse<TwoFactorEnrollment>>()
+        .unwrap()
+        .data
+        .unwrap();
+    let response = client
+        .post(route_users_two_factor_login())
+        .header(ContentType::Form)
+        .body(
+            TwoFactorLoginForm {
+                challenge,
+                code: &current_code(&enrollment.secret),
+            }
+            .body(),
+        )
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+
+    let user = get_self(&client);
+    assert_eq!(user.username, username);
+    logout(&client);
+
+    remove_user(&client, user.id);
+}
+
+#[test]
+fn sso_login_state_is_used_once() {
+    let provider = MockProvider::start();
+    let client = provider.client(true);
+
+    provider.set_identity(
+        &create_random_token(16),
+        &format!("sso_{}@example.com", create_random_token(8).to_lowercase()),
+        &format!("sso_{}", create_random_token(8).to_lowercase()),
+    );
+
+    // A wrong code is refused by the provider
+    let state = start_login(&client, &provider);
+    let response = client
+        .get(route_users_login_oidc_callback("wrong_code", &state))
+        .dispatch();
+    assert_eq!(response.status(), Status::Unauthorized);
+
+    // The state was consumed by the failed attempt
+    let response = client
+        .get(route_users_login_oidc_callback(AUTHORIZATION_CODE, &state))
+        .dispatch();
+    assert_eq!(response.status(), Status::Unauthorized);
+
+    // An unknown state
+    let response = client
+        .get(route_users_login_oidc_callback(
+            AUTHORIZATION_CODE,
+            &create_random_token(32),
+        ))
+        .dispatch();
+    assert_eq!(response.status(), Status::Unauthorized);
+}
+
+#[test]
+fn sso_login_is_completed_by_the_browser_that_started_it() {
+    let provider = MockProvider::start();
+    let attacker = provider.client(true);
+    let victim = provider.client(true);
+
+    let username = format!("sso_{}", create_random_token(8).to_lowercase());
+    provider.set_identity(
+        &create_random_token(16),
+        &format!("{username}@example.com"),
+        &username,
+    );
+
+    // The callback URL of a login started elsewhere doesn't log the victim in, whether the
+    // victim started a login of their own or not
+    let state = start_login(&attacker, &provider);
+    let response = victim
+        .get(route_users_login_oidc_callback(AUTHORIZATION_CODE, &state))
+        .dispatch();
+    assert_eq!(response.status(), Status::Unauthorized);
+
+    start_login(&victim, &provider);
+    let state = start_login(&attacker, &provider);
+    let response = victim
+        .get(route_users_login_oidc_callback(AUTHORIZATION_CODE, &state))
+        .dispatch();
+    assert_eq!(response.status(), Status::Unauthorized);
+    response_unauthorized(victim.get(route_users_me()));
+
+    // The browser that started the login completes it
+    let response = attacker
+        .get(route_users_login_oidc_callback(AUTHORIZATION_CODE, &state))
+        .dispatch();
+    assert_eq!(response.status(), Status::SeeOther);
+    let user = get_self(&attacker);
+    assert_eq!(user.username, username);
+
+    logout(&attacker);
+    remove_user(&attacker, user.id);
+}
+
+#[test]
+fn sso_login_refuses_id_tokens_signed_with_the_client_secret_by_default() {
+    let provider = MockProvider::start();
+    let client = provider.client_without_secret_signatures();
+
+    provider.set_identity(
+        &create_random_token(16),
+        &format!("sso_{}@example.com", create_random_token(8).to_lowercase()),
+        &format!("sso_{}", create_random_token(8).to_lowercase()),
+    );
+
+    // The provider supports HS256, but the header of the token doesn't get to choose it
+    let state = start_login(&client, &provider);
+    let response = client
+        .get(route_users_login_oidc_callback(AUTHORIZATION_CODE, &state))
+        .dispatch();
+    assert_eq!(response.status(), Status::Unauthorized);
+    response_unauthorized(client.get(route_users_me()));
+}
+
+#[test]
+fn sso_login_is_not_found_without_provider() {
+    let client = test_client();
+
+    response_not_found(client.get(route_users_login_oidc()));
+}
+
+/// Starts the login and returns the state, after checking the redirect to the provider.
+fn start_login(client: &Client, provider: &MockProvider) -> String {
+    let response = client.get(route_users_login_oidc()).dispatch();
+    assert_eq!(response.status(), Status::SeeOther);
+
+    let location = Url::parse(response.headers().get_one("Location").unwrap()).unwrap();
+    assert_eq!(location.path(), "/authorize");
+
+    let params: HashMap<String, String> = location.query_pairs().into_owned().collect();
+    assert_eq!(params["client_id"], CLIENT_ID);
+    assert_eq!(params["redirect_uri"], REDIRECT_URI);
+    assert_eq!(params["code_challenge_method"], "S256");
+
+    // What the provider would remember for the code
+    let mut state = provider.state.lock().unwrap();
+    state.code_challenge = params["code_challenge"].clone();
+    state.nonce = params["nonce"].clone();
+
+    params["state"].clone()
+}
+
+/// Completes a login at the provider and returns the logged in user.
+fn sso_login(client: &Client, provider: &MockProvider) -> PublicUser {
+    let state = start_login(client, provider);
+
+    let response = client
+        .get(route_users_login_oidc_callback(AUTHORIZATION_CODE, &state))
+        .dispatch();
+    assert_eq!(response.status(), Status::SeeOther);
+    assert_eq!(
+        response.headers().get_one("Location"),
+        Some(POST_LOGIN_REDIRECT)
+    );
+
+    get_self(client)
+}
+
+#[derive(Default)]
+struct MockState {
+    code_challenge: String,
+    nonce: String,
+    subject: String,
+    email: String,
+    /// Left out of the ID token when `None`
+    email_verified: Option<bool>,
+    username: String,
+}
+
+/// A minimal identity provider on a local port, with discovery and a token endpoint that issues
+/// ID tokens signed with the client secret, which the clients have to allow explicitly.
+struct MockProvider {
+    issuer: String,
+    state: Arc<Mutex<MockState>>,
+}
+
+impl MockProvider {
+    fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let issuer = format!("http://{}", listener.local_addr().unwrap());
+        let state = Arc::new(Mutex::new(MockState::default()));
+
+        let provider = MockProvider {
+            issuer: issuer.clone(),
+            state: state.clone(),
+        };
+
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                handle_request(stream, &issuer, &state);
+            }
+        });
+
+        provider
+    }
+
+    /// A client of the application, configured with the provider.
+    fn client(&self, provision_users: bool) -> Client {
+        self.client_requiring_second_factor(provision_users, &[])
+    }
+
+    /// A client of the application, configured with the provider and the roles that can't log
+    /// in without a second factor.
+    fn client_requiring_second_factor(&self, provision_users: bool, roles: &[UserRole]) -> Client {
+        let mut config = self.config(provision_users);
+        config["id_token_signing_algs"] = json!(["HS256"]);
+
+        let rocket = crate::rocket();
+        let figment = rocket
+            .figment()
+            .clone()
+            .merge(("oidc", config))
+            .merge(("two_factor", json!({ "required_roles": roles })));
+
+        Client::tracked(rocket.configure(figment)).expect("valid rocket instance")
+    }
+
+    /// A client of the application that only accepts the algorithms the provider supports.
+    fn client_without_secret_signatures(&self) -> Client {
+        let rocket = crate::rocket();
+        let figment = rocket.figment().clone().merge(("oidc", self.config(true)));
+
+        Client::tracked(rocket.configure(figment)).expect("valid rocket instance")
+    }
+
+    fn config(&self, provision_users: bool) -> Value {
+        json!({
+            "issuer": self.issuer,
+            "client_id": CLIENT_ID,
+            "client_secret": CLIENT_SECRET,
+            "redirect_uri": REDIRECT_URI,
+            "post_login_redirect": POST_LOGIN_REDIRECT,
+            "provision_users": provision_users,
+            "default_role": "Manager",
+        })
+    }
+
+    fn set_identity(&self, subject: &str, email: &str, username: &str) {
+        let mut state = self.state.lock().unwrap();
+        state.subject = subject.to_string();
+        state.email = email.to_string();
+        state.email_verified = Some(true);
+        state.username = username.to_string();
+    }
+}
+
+fn handle_request(mut stream: TcpStream, issuer: &str, state: &Mutex<MockState>) {
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).unwrap();
+
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        reader.read_line(&mut header).unwrap();
+
+        if header.trim().is_empty() {
+            break;
+        }
+
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap();
+            }
+        }
+    }
+
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).unwrap();
+
+    let path = request_line.split_whitespace().nth(1).unwrap_or_default();
+
+    let (status, response) = match path {
+        "/.well-known/openid-configuration" => (
+            "200 OK",
+            json!({
+                "issuer": issuer,
+                "authorization_endpoint": format!("{issuer}/authorize"),
+                "token_endpoint": format!("{issuer}/token"),
+                "jwks_uri": format!("{issuer}/jwks"),
+                "id_token_signing_alg_values_supported": ["RS256", "HS256"],
+            }),
+        ),
+        "/token" => token_response(&body, issuer, &state.lock().unwrap()),
+        _ => ("404 Not Found", json!({})),
+    };
+
+    let response = response.to_string();
+    let _ = write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{response}",
+        response.len()
+    );
+}
+
+/// Issues an ID token if the code and the PKCE verifier are right.
+fn token_response(body: &[u8], issuer: &str, state: &MockState) -> (&'static str, Value) {
+    let params: HashMap<String, String> = Url::parse(&format!(
+        "http://localhost/?{}",
+        String::from_utf8_lossy(body)
+    ))
+    .unwrap()
+    .query_pairs()
+    .into_owned()
+    .collect();
+
+    let challenge = params
+        .get("code_verifier")
+        .map(|verifier| URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes())));
+
+    if params.get("code").map(String::as_str) != Some(AUTHORIZATION_CODE)
+        || params.get("client_secret").map(String::as_str) != Some(CLIENT_SECRET)
+        || challenge.as_ref() != Some(&state.code_challenge)
+    {
+        return ("400 Bad Request", json!({ "error": "invalid_grant" }));
+    }
+
+    let mut claims = json!({
+        "iss": issuer,
+        "sub": state.subject,
+        "aud": CLIENT_ID,
+        "exp": chrono::Utc::now().timestamp() + 300,
+        "nonce": state.nonce,
+        "email": state.email,
+        "preferred_username": state.username,
+        "given_name": "Single",
+        "family_name": "Sign-On",
+    });
+    if let Some(email_verified) = state.email_verified {
+        claims["email_verified"] = json!(email_verified);
+    }
+
+    let id_token = encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(CLIENT_SECRET.as_bytes()),
+    )
+    .unwrap();
+
+    (
+        "200 OK",
+        json!({ "id_token": id_token, "token_type": "Bearer" }),
+    )
+}
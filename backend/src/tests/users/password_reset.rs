@@ -0,0 +1,186 @@
+use rocket::{
+    http::{ContentType, Status},
+    local::asynchronous::Client,
+    State,
+};
+use uuid::Uuid;
+
+use super::{
+    async_login, get_redis, route_users_forgot_password, route_users_logout,
+    route_users_reset_password, ADMIN_LOGIN, DEFAULT_LOGIN, DEFAULT_PASSWORD, DEFAULT_USERNAME,
+    ROUTE_GET,
+};
+use crate::{
+    api::{ApiResponse, Null},
+    cache::{self, users::cache_key_password_reset_requests, RedisMutex},
+    forms::password::{ForgotPasswordForm, Password},
+    models::users::PublicUser,
+    tests::{async_csrf, async_test_client},
+};
+
+const DEFAULT_EMAIL_ADDR: &str = "test_user@example.com";
+const UNKNOWN_EMAIL_ADDR: &str = "nobody@example.com";
+
+#[tokio::test]
+async fn forgot_password_does_not_reveal_accounts() {
+    let client = async_test_client().await;
+    let redis = get_redis(client.rocket());
+
+    let mut messages = Vec::new();
+
+    for email in [DEFAULT_EMAIL_ADDR, UNKNOWN_EMAIL_ADDR] {
+        // Start without earlier requests for the address
+        reset_request_count(redis, email).await;
+
+        let response = client
+            .post(route_users_forgot_password())
+            .header(ContentType::Form)
+            .body(ForgotPasswordForm { email }.body())
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let response = response.into_json::<ApiResponse<Null>>().await.unwrap();
+        messages.push(response.message);
+    }
+
+    // Known and unknown addresses get the exact same response
+    assert_eq!(messages[0], messages[1]);
+}
+
+#[tokio::test]
+async fn forgot_password_is_rate_limited() {
+    let client = async_test_client().await;
+    let redis = get_redis(client.rocket());
+
+    let email = format!("{}@example.com", cache::create_random_token(16));
+    reset_request_count(redis, &email).await;
+
+    let mut statuses = Vec::new();
+
+    for _ in 0..4 {
+        let response = client
+            .post(route_users_forgot_password())
+            .header(ContentType::Form)
+            .body(ForgotPasswordForm { email: &email }.body())
+            .dispatch()
+            .await;
+
+        statuses.push(response.status());
+    }
+
+    assert_eq!(
+        statuses,
+        vec![Status::Ok, Status::Ok, Status::Ok, Status::TooManyRequests]
+    );
+}
+
+#[tokio::test]
+async fn reset_password_with_token() {
+    let client = async_test_client().await;
+    let redis = get_redis(client.rocket());
+
+    // Add a reset token for the default user to the cache
+    let user_id = get_user_id(&client, DEFAULT_USERNAME).await;
+    let token = cache::create_random_token(64);
+    assert!(
+        cache::users::add_password_reset_token(redis, &token, user_id)
+            .await
+            .is_ok()
+    );
+
+    let password = Password {
+        first: DEFAULT_PASSWORD,
+        second: DEFAULT_PASSWORD,
+    };
+
+    let response = client
+        .put(route_users_reset_password(&token))
+        .header(ContentType::Form)
+        .body(password.body())
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::Ok);
+
+    // The token can only be used once
+    let response = client
+        .put(route_users_reset_password(&token))
+        .header(ContentType::Form)
+        .body(password.body())
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::NotFound);
+
+    // The user can log in with the new password
+    async_login(&client, DEFAULT_LOGIN).await;
+}
+
+#[tokio::test]
+async fn a_reset_token_is_used_by_one_of_concurrent_requests() {
+    let client = async_test_client().await;
+    let redis = get_redis(client.rocket());
+
+    let user_id = get_user_id(&client, DEFAULT_USERNAME).await;
+    let token = cache::create_random_token(64);
+    cache::users::add_password_reset_token(redis, &token, user_id)
+        .await
+        .unwrap();
+
+    let password = Password {
+        first: DEFAULT_PASSWORD,
+        second: DEFAULT_PASSWORD,
+    };
+    let reset = || {
+        client
+            .put(route_users_reset_password(&token))
+            .header(ContentType::Form)
+            .body(password.body())
+            .dispatch()
+    };
+
+    // Both requests arrive before either of them replaced the password
+    let (first, second) = tokio::join!(reset(), reset());
+    let mut statuses = [first.status(), second.status()];
+    statuses.sort_by_key(|status| status.code);
+    assert_eq!(statuses, [Status::Ok, Status::NotFound]);
+}
+
+async fn reset_request_count(redis: &State<RedisMutex>, email: &str) {
+    assert!(redis
+        .lock()
+        .await
+        .remove_from_cache(&cache_key_password_reset_requests(email))
+        .await
+        .is_ok());
+}
+
+async fn get_user_id(client: &Client, username: &str) -> Uuid {
+    // Login as admin
+    async_login(client, ADMIN_LOGIN).await;
+
+    let response = client
+        .get(format!("{ROUTE_GET}{username}"))
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::Ok);
+
+    let user = response
+        .into_json::<ApiResponse<PublicUser>>()
+        .await
+        .unwrap()
+        .data
+        .unwrap();
+
+    // Logout again, the reset itself doesn't need a session
+    client
+        .post(route_users_logout())
+        .header(async_csrf(client))
+        .dispatch()
+        .await;
+
+    user.id
+}
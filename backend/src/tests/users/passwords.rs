@@ -0,0 +1,170 @@
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHasher, SaltString},
+    Algorithm, Argon2, Params, Version,
+};
+use rocket::{
+    error::ErrorKind,
+    http::{ContentType, Status},
+    local::blocking::Client,
+    tokio::runtime,
+};
+use serde_json::json;
+use uuid::Uuid;
+
+use super::{
+    inject_user, login, logout, remove_user, route_users_change_password, DEFAULT_PASSWORD,
+};
+use crate::{
+    auth::passwords::{CharacterClass, PasswordConfig},
+    database::{self, Db},
+    forms::{
+        login::LoginForm,
+        password::{ChangePasswordForm, Password},
+    },
+    tests::{csrf, test_client},
+};
+
+#[test]
+fn weaker_hash_is_upgraded_on_login() {
+    let client = test_client();
+    let (user_id, username) = inject_user(&client, "rehash");
+
+    // A hash from before the parameters were raised
+    let salt = SaltString::generate(&mut OsRng);
+    let weak_hash = Argon2::new(
+        Algorithm::Argon2id,
+        Version::V0x13,
+        Params::new(Params::MIN_M_COST, 1, 1, None).unwrap(),
+    )
+    .hash_password(DEFAULT_PASSWORD.as_bytes(), &salt)
+    .unwrap()
+    .to_string();
+    assert!(Password::needs_rehash(&weak_hash));
+
+    block_on_db(&client, |db| async move {
+        database::users::update_user_password(&db, user_id, weak_hash).await
+    })
+    .unwrap();
+
+    let credentials = LoginForm {
+        username: &username,
+        password: DEFAULT_PASSWORD,
+    };
+
+    // The old hash still verifies, and is replaced by one with the current parameters
+    login(&client, credentials);
+    logout(&client);
+
+    let stored_hash = get_password_hash(&client, user_id);
+    assert!(!Password::needs_rehash(&stored_hash));
+    assert!(Password::verify_password(DEFAULT_PASSWORD, &stored_hash).unwrap());
+
+    login(&client, credentials);
+    logout(&client);
+
+    // A hash with the current parameters is left alone
+    assert_eq!(get_password_hash(&client, user_id), stored_hash);
+
+    remove_user(&client, user_id);
+}
+
+#[test]
+fn new_passwords_must_meet_the_policy() {
+    let client = test_client();
+    let (user_id, username) = inject_user(&client, "policy");
+
+    login(
+        &client,
+        LoginForm {
+            username: &username,
+            password: DEFAULT_PASSWORD,
+        },
+    );
+
+    // Too short, and on the list of common passwords
+    assert_eq!(
+        change_password(&client, "short"),
+        Status::UnprocessableEntity
+    );
+    assert_eq!(
+        change_password(&client, "Password123"),
+        Status::UnprocessableEntity
+    );
+    assert_eq!(
+        change_password(&client, "a_long_and_uncommon_one"),
+        Status::Ok
+    );
+
+    logout(&client);
+    remove_user(&client, user_id);
+}
+
+#[test]
+fn policy_requires_the_configured_character_classes() {
+    let config = PasswordConfig {
+        min_length: 10,
+        required_classes: vec![CharacterClass::Uppercase, CharacterClass::Digit],
+        ..Default::default()
+    };
+
+    assert!(config.check_policy("Short1").is_err());
+    assert!(config.check_policy("no_uppercase_1").is_err());
+    assert!(config.check_policy("No_digits_at_all").is_err());
+    assert!(config.check_policy("Has_all_classes_1").is_ok());
+}
+
+#[test]
+fn other_password_parameters_fail_to_ignite() {
+    // The parameters of the tests are in use once a client has ignited
+    test_client();
+
+    let rocket = crate::rocket();
+    let figment = rocket
+        .figment()
+        .clone()
+        .merge(("password", json!({ "memory_kib": 8192 })));
+    let Err(error) = Client::tracked(rocket.configure(figment)) else {
+        panic!("ignition should fail");
+    };
+    assert!(matches!(error.kind(), ErrorKind::FailedFairings(_)));
+}
+
+fn change_password(client: &Client, new: &str) -> Status {
+    let form = ChangePasswordForm {
+        current: DEFAULT_PASSWORD,
+        new: Password {
+            first: new,
+            second: new,
+        },
+    };
+
+    client
+        .put(route_users_change_password())
+        .header(csrf(client))
+        .header(ContentType::Form)
+        .body(form.body())
+        .dispatch()
+        .status()
+}
+
+fn get_password_hash(client: &Client, user_id: Uuid) -> String {
+    block_on_db(client, |db| async move {
+        database::users::get_user_by_id(&db, user_id).await
+    })
+    .unwrap()
+    .password
+}
+
+fn block_on_db<F, T>(client: &Client, f: impl FnOnce(Db) -> F) -> T
+where
+    F: std::future::Future<Output = T>,
+{
+    runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+        .block_on(async {
+            let db = Db::get_one(client.rocket()).await.unwrap();
+            f(db).await
+        })
+}
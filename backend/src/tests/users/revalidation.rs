@@ -0,0 +1,54 @@
+use rocket::http::Status;
+
+use super::{
+    get_self, inject_user, login, logout, remove_user, route_users_me, route_users_suspend,
+    route_users_update_role, ADMIN_LOGIN, DEFAULT_PASSWORD,
+};
+use crate::{
+    forms::login::LoginForm,
+    models::users::UserRole,
+    tests::{csrf, test_client},
+};
+
+#[test]
+fn role_change_applies_to_existing_token() {
+    let admin = test_client();
+    let (user_id, username) = inject_user(&admin, "revalidation");
+
+    let client = test_client();
+    login(
+        &client,
+        LoginForm {
+            username: &username,
+            password: DEFAULT_PASSWORD,
+        },
+    );
+    assert_eq!(get_self(&client).role, UserRole::Reviewer);
+
+    // Promote the user while their token is still valid
+    login(&admin, ADMIN_LOGIN);
+    let response = admin
+        .put(route_users_update_role(
+            &user_id.to_string(),
+            UserRole::Contributor,
+        ))
+        .header(csrf(&admin))
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+
+    // The same token now carries the new role
+    assert_eq!(get_self(&client).role, UserRole::Contributor);
+
+    // After a suspension the token is rejected right away
+    let response = admin
+        .put(route_users_suspend(&user_id.to_string()))
+        .header(csrf(&admin))
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    logout(&admin);
+
+    let response = client.get(route_users_me()).dispatch();
+    assert_eq!(response.status(), Status::Unauthorized);
+
+    remove_user(&admin, user_id);
+}
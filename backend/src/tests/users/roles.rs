@@ -0,0 +1,71 @@
+use rocket::{http::Status, local::blocking::Client};
+use serde_json::Value;
+
+use super::{
+    inject_user, login, logout, remove_user, route_users_me, ADMIN_LOGIN, DEFAULT_PASSWORD,
+};
+use crate::{
+    forms::login::LoginForm,
+    routes::USERS,
+    tests::{csrf, test_client},
+};
+
+#[test]
+fn roles_and_statuses_are_serialized_by_name() {
+    let client = test_client();
+    login(&client, ADMIN_LOGIN);
+
+    // The admin role is stored as 1000 and has to come back as the same role
+    let user = get_self_json(&client);
+    assert_eq!(user["role"], "Admin");
+    assert_eq!(user["status"], "Active");
+
+    logout(&client);
+}
+
+#[test]
+fn role_is_validated_by_name() {
+    let admin = test_client();
+    let (user_id, username) = inject_user(&admin, "roles");
+
+    login(&admin, ADMIN_LOGIN);
+
+    // Unknown roles and raw values are rejected
+    for role in ["Superuser", "1000"] {
+        let response = admin
+            .put(format!("{USERS}update/{user_id}/{role}"))
+            .header(csrf(&admin))
+            .dispatch();
+        assert_eq!(response.status(), Status::BadRequest);
+    }
+
+    // The name is case insensitive
+    let response = admin
+        .put(format!("{USERS}update/{user_id}/manager"))
+        .header(csrf(&admin))
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    logout(&admin);
+
+    let client = test_client();
+    login(
+        &client,
+        LoginForm {
+            username: &username,
+            password: DEFAULT_PASSWORD,
+        },
+    );
+    assert_eq!(get_self_json(&client)["role"], "Manager");
+    logout(&client);
+
+    remove_user(&admin, user_id);
+}
+
+fn get_self_json(client: &Client) -> Value {
+    client
+        .get(route_users_me())
+        .dispatch()
+        .into_json::<Value>()
+        .unwrap()["data"]
+        .clone()
+}
@@ -0,0 +1,153 @@
+use rocket::{
+    http::{ContentType, Header, Status},
+    local::blocking::Client,
+    tokio::runtime,
+};
+use uuid::Uuid;
+
+use super::{
+    get_redis, inject_user, login, logout, remove_user, route_users_login, route_users_me,
+    route_users_own_session, route_users_own_sessions, route_users_sessions, ADMIN_LOGIN,
+    DEFAULT_PASSWORD,
+};
+use crate::{
+    api::ApiResponse,
+    cache,
+    forms::login::LoginForm,
+    models::sessions::{ClientInfo, PublicSession, Session},
+    tests::{csrf, response_not_found, response_ok, response_unauthorized, test_client},
+};
+
+const USER_AGENT: &str = "Rustle Test Device";
+
+#[test]
+fn sessions_are_listed_and_revoked_per_device() {
+    let client = test_client();
+    let (user_id, username) = inject_user(&client, "sessions");
+    let credentials = LoginForm {
+        username: &username,
+        password: DEFAULT_PASSWORD,
+    };
+
+    let device = test_client();
+    login_with_user_agent(&device, credentials);
+    login(&client, credentials);
+
+    // Both sessions are listed, with the device they were started on
+    let sessions = get_sessions(&client, route_users_own_sessions());
+    assert_eq!(sessions.len(), 2);
+    assert_eq!(sessions.iter().filter(|session| session.current).count(), 1);
+
+    let other = sessions.iter().find(|session| !session.current).unwrap();
+    assert_eq!(other.user_agent.as_deref(), Some(USER_AGENT));
+
+    // Revoking the other session logs the device out
+    response_ok(
+        client
+            .delete(route_users_own_session(&other.id.to_string()))
+            .header(csrf(&client)),
+    );
+    response_unauthorized(device.get(route_users_me()));
+    assert_eq!(get_sessions(&client, route_users_own_sessions()).len(), 1);
+
+    // Sessions that don't exist, or belong to someone else
+    response_not_found(
+        client
+            .delete(route_users_own_session(&Uuid::new_v4().to_string()))
+            .header(csrf(&client)),
+    );
+
+    logout(&client);
+    remove_user(&client, user_id);
+}
+
+#[test]
+fn admin_revokes_all_sessions_of_a_user() {
+    let admin = test_client();
+    let (user_id, username) = inject_user(&admin, "sessions");
+
+    let client = test_client();
+    login(
+        &client,
+        LoginForm {
+            username: &username,
+            password: DEFAULT_PASSWORD,
+        },
+    );
+
+    // Only admins can manage the sessions of other users
+    response_unauthorized(client.get(route_users_sessions(&user_id.to_string())));
+    response_unauthorized(
+        client
+            .delete(route_users_sessions(&user_id.to_string()))
+            .header(csrf(&client)),
+    );
+
+    login(&admin, ADMIN_LOGIN);
+    let sessions = get_sessions(&admin, route_users_sessions(&user_id.to_string()));
+    assert_eq!(sessions.len(), 1);
+
+    response_ok(
+        admin
+            .delete(route_users_sessions(&user_id.to_string()))
+            .header(csrf(&admin)),
+    );
+    response_unauthorized(client.get(route_users_me()));
+    assert!(get_sessions(&admin, route_users_sessions(&user_id.to_string())).is_empty());
+
+    logout(&admin);
+    remove_user(&admin, user_id);
+}
+
+#[test]
+fn a_revoked_session_is_not_written_back_by_a_refresh() {
+    let client = test_client();
+    let redis = get_redis(client.rocket());
+    let mut session = Session::new(Uuid::new_v4(), &ClientInfo::default());
+
+    runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+        .block_on(async {
+            cache::sessions::add_session(redis, &session).await.unwrap();
+            session.rotate();
+            assert!(cache::sessions::replace_session(redis, &session)
+                .await
+                .unwrap());
+
+            // Revoked between reading and rotating it
+            cache::sessions::remove_session(redis, session.user, session.id)
+                .await
+                .unwrap();
+            session.rotate();
+            assert!(!cache::sessions::replace_session(redis, &session)
+                .await
+                .unwrap());
+            assert!(cache::sessions::get_session(redis, session.id)
+                .await
+                .unwrap()
+                .is_none());
+        });
+}
+
+fn login_with_user_agent(client: &Client, credentials: LoginForm) {
+    let response = client
+        .post(route_users_login())
+        .header(ContentType::Form)
+        .header(Header::new("User-Agent", USER_AGENT))
+        .body(credentials.body())
+        .dispatch();
+
+    assert_eq!(response.status(), Status::Ok);
+}
+
+fn get_sessions(client: &Client, route: String) -> Vec<PublicSession> {
+    client
+        .get(route)
+        .dispatch()
+        .into_json::<ApiResponse<Vec<PublicSession>>>()
+        .unwrap()
+        .data
+        .unwrap()
+}
@@ -0,0 +1,282 @@
+use rocket::{
+    http::{ContentType, Status},
+    local::blocking::Client,
+    tokio::runtime,
+};
+use totp_rs::{Algorithm, Secret, TOTP};
+
+use super::{
+    get_redis, inject_user, login, logout, remove_user, route_users_login, route_users_me,
+    route_users_two_factor_disable, route_users_two_factor_enroll, route_users_two_factor_login,
+    route_users_two_factor_verify, DEFAULT_PASSWORD,
+};
+use crate::{
+    api::ApiResponse,
+    auth::two_factor::verify_second_factor,
+    database::{two_factor::get_two_factor, Db},
+    forms::{
+        login::LoginForm,
+        two_factor::{TwoFactorCodeForm, TwoFactorDisableForm, TwoFactorLoginForm},
+    },
+    models::two_factor::{TwoFactorChallenge, TwoFactorEnrollment},
+    tests::{csrf, test_client},
+};
+
+#[test]
+fn login_with_second_factor() {
+    let client = test_client();
+    let (user_id, username) = inject_user(&client, "two_factor");
+
+    let credentials = LoginForm {
+        username: &username,
+        password: DEFAULT_PASSWORD,
+    };
+
+    // Without a second factor the login is completed right away
+    login(&client, credentials);
+
+    // Enroll and confirm the enrollment with a code from the "authenticator app"
+    let enrollment = enable_two_factor(&client);
+
+    logout(&client);
+
+    // Now the password alone only results in a challenge, without the auth cookie
+    let challenge = request_challenge(&client, &credentials);
+    assert!(!challenge.enrollment_required);
+    assert_eq!(
+        client.get(route_users_me()).dispatch().status(),
+        Status::Unauthorized
+    );
+
+    // An invalid code is rejected
+    let response = client
+        .post(route_users_two_factor_login())
+        .header(ContentType::Form)
+        .body(
+            TwoFactorLoginForm {
+                challenge: &challenge.challenge,
+                code: "000000x",
+            }
+            .body(),
+        )
+        .dispatch();
+
+    assert_eq!(response.status(), Status::Unauthorized);
+
+    // The TOTP code was already used for the verification, so use a recovery code
+    let recovery_code = &enrollment.recovery_codes[0];
+    let response = client
+        .post(route_users_two_factor_login())
+        .header(ContentType::Form)
+        .body(
+            TwoFactorLoginForm {
+                challenge: &challenge.challenge,
+                code: recovery_code,
+            }
+            .body(),
+        )
+        .dispatch();
+
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(client.get(route_users_me()).dispatch().status(), Status::Ok);
+
+    logout(&client);
+
+    // The challenge and the recovery code can only be used once
+    let response = client
+        .post(route_users_two_factor_login())
+        .header(ContentType::Form)
+        .body(
+            TwoFactorLoginForm {
+                challenge: &challenge.challenge,
+                code: recovery_code,
+            }
+            .body(),
+        )
+        .dispatch();
+
+    assert_eq!(response.status(), Status::Unauthorized);
+
+    let challenge = request_challenge(&client, &credentials);
+    let response = client
+        .post(route_users_two_factor_login())
+        .header(ContentType::Form)
+        .body(
+            TwoFactorLoginForm {
+                challenge: &challenge.challenge,
+                code: recovery_code,
+            }
+            .body(),
+        )
+        .dispatch();
+
+    assert_eq!(response.status(), Status::Unauthorized);
+
+    // Complete the login with another recovery code and disable the second factor
+    let response = client
+        .post(route_users_two_factor_login())
+        .header(ContentType::Form)
+        .body(
+            TwoFactorLoginForm {
+                challenge: &challenge.challenge,
+                code: &enrollment.recovery_codes[1],
+            }
+            .body(),
+        )
+        .dispatch();
+
+    assert_eq!(response.status(), Status::Ok);
+
+    let response = client
+        .post(route_users_two_factor_disable())
+        .header(csrf(&client))
+        .header(ContentType::Form)
+        .body(
+            TwoFactorDisableForm {
+                password: DEFAULT_PASSWORD,
+                code: &enrollment.recovery_codes[2],
+            }
+            .body(),
+        )
+        .dispatch();
+
+    assert_eq!(response.status(), Status::Ok);
+
+    // The password is enough again
+    logout(&client);
+    login(&client, credentials);
+    logout(&client);
+
+    remove_user(&client, user_id);
+}
+
+#[test]
+fn too_many_second_factor_attempts() {
+    let client = test_client();
+    let (user_id, username) = inject_user(&client, "two_factor");
+
+    let credentials = LoginForm {
+        username: &username,
+        password: DEFAULT_PASSWORD,
+    };
+
+    login(&client, credentials);
+    enable_two_factor(&client);
+    logout(&client);
+
+    let challenge = request_challenge(&client, &credentials);
+
+    let mut statuses = Vec::new();
+
+    for _ in 0..6 {
+        let response = client
+            .post(route_users_two_factor_login())
+            .header(ContentType::Form)
+            .body(
+                TwoFactorLoginForm {
+                    challenge: &challenge.challenge,
+                    code: "000000x",
+                }
+                .body(),
+            )
+            .dispatch();
+
+        statuses.push(response.status());
+    }
+
+    assert_eq!(statuses[4], Status::Unauthorized);
+    assert_eq!(statuses[5], Status::TooManyRequests);
+
+    remove_user(&client, user_id);
+}
+
+#[test]
+fn a_recovery_code_is_used_only_once_by_concurrent_logins() {
+    let client = test_client();
+    let (user_id, username) = inject_user(&client, "two_factor");
+
+    login(
+        &client,
+        LoginForm {
+            username: &username,
+            password: DEFAULT_PASSWORD,
+        },
+    );
+    let enrollment = enable_two_factor(&client);
+    logout(&client);
+
+    // Both logins read the second factor before either of them used the code
+    let (first, second) = runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+        .block_on(async {
+            let db = Db::get_one(client.rocket()).await.unwrap();
+            let redis = get_redis(client.rocket());
+            let two_factor = get_two_factor(&db, user_id).await.unwrap().unwrap();
+            let code = &enrollment.recovery_codes[0];
+
+            (
+                verify_second_factor(&db, redis, &two_factor, code, true)
+                    .await
+                    .unwrap(),
+                verify_second_factor(&db, redis, &two_factor, code, true)
+                    .await
+                    .unwrap(),
+            )
+        });
+    assert!(first);
+    assert!(!second);
+
+    remove_user(&client, user_id);
+}
+
+fn enable_two_factor(client: &Client) -> TwoFactorEnrollment {
+    let enrollment = client
+        .post(route_users_two_factor_enroll())
+        .header(csrf(client))
+        .dispatch()
+        .into_json::<ApiResponse<TwoFactorEnrollment>>()
+        .unwrap()
+        .data
+        .unwrap();
+
+    assert!(enrollment.otpauth_uri.starts_with("otpauth://totp/"));
+    assert_eq!(enrollment.recovery_codes.len(), 10);
+
+    let code = current_code(&enrollment.secret);
+    let response = client
+        .post(route_users_two_factor_verify())
+        .header(csrf(client))
+        .header(ContentType::Form)
+        .body(TwoFactorCodeForm { code: &code }.body())
+        .dispatch();
+
+    assert_eq!(response.status(), Status::Ok);
+
+    enrollment
+}
+
+fn request_challenge(client: &Client, credentials: &LoginForm) -> TwoFactorChallenge {
+    let response = client
+        .post(route_users_login())
+        .header(ContentType::Form)
+        .body(credentials.body())
+        .dispatch();
+
+    assert_eq!(response.status(), Status::Ok);
+
+    response
+        .into_json::<ApiResponse<TwoFactorChallenge>>()
+        .unwrap()
+        .data
+        .expect("A second factor should be required")
+}
+
+pub(super) fn current_code(secret: &str) -> String {
+    let secret = Secret::Encoded(secret.to_string()).to_bytes().unwrap();
+    TOTP::new(Algorithm::SHA1, 6, 1, 30, secret, None, String::new())
+        .unwrap()
+        .generate_current()
+        .unwrap()
+}
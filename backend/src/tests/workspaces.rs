@@ -1,11 +1,34 @@
-use crate::{routes::WORKSPACES, tests::root_route};
+use rocket::{
+    http::{ContentType, Status},
+    local::blocking::Client,
+};
+use uuid::Uuid;
+
+use crate::{
+    api::ApiResponse,
+    forms::{roles::CustomRoleForm, workspace::NewWorkspaceForm},
+    models::{
+        roles::{CustomRole, RoleAssignment},
+        workspaces::{WorkspaceMember, WorkspaceRole, WorkspaceWithMembers},
+    },
+    routes::WORKSPACES,
+    tests::{csrf, response_ok, root_route},
+};
 
 #[cfg(test)]
 mod adding_and_updating;
 #[cfg(test)]
+mod custom_roles;
+#[cfg(test)]
 mod getting_workspaces;
 #[cfg(test)]
+mod invitations;
+#[cfg(test)]
+mod labels;
+#[cfg(test)]
 mod member_management;
+#[cfg(test)]
+mod permissions;
 
 pub const TARGETED_WORKSPACE: &str = "7fa5257b-e02b-4f6f-be9f-8f579fb64147";
 
@@ -36,3 +59,97 @@ fn route_workspaces_add_member() -> String {
 fn route_workspaces_remove_member(user_id: &str) -> String {
     format!("{WORKSPACES}{TARGETED_WORKSPACE}/remove-member/{user_id}")
 }
+
+/// Creates a workspace of which the logged in user is the owner.
+pub fn create_workspace(client: &Client) -> Uuid {
+    let new_workspace = NewWorkspaceForm {
+        name: "Test Workspace".to_string(),
+        description: None,
+    };
+
+    client
+        .post(route_workspaces_new())
+        .header(csrf(client))
+        .header(ContentType::Form)
+        .body(new_workspace.body())
+        .dispatch()
+        .into_json::<ApiResponse<WorkspaceWithMembers>>()
+        .unwrap()
+        .data
+        .unwrap()
+        .workspace
+        .id
+}
+
+pub fn add_member(client: &Client, workspace: Uuid, member: Uuid, role: WorkspaceRole) {
+    let new_member = WorkspaceMember {
+        workspace,
+        member,
+        role,
+        custom_role: None,
+    };
+
+    response_ok(
+        client
+            .post(format!("{WORKSPACES}{workspace}/add-members"))
+            .header(csrf(client))
+            .header(ContentType::JSON)
+            .body(serde_json::to_string(&[new_member]).unwrap()),
+    );
+}
+
+fn route_workspaces_roles(workspace: Uuid) -> String {
+    format!("{WORKSPACES}{workspace}/roles")
+}
+
+fn route_workspaces_role(workspace: Uuid, role: Uuid) -> String {
+    format!("{WORKSPACES}{workspace}/roles/{role}")
+}
+
+fn route_workspaces_assign_role(workspace: Uuid, member: Uuid) -> String {
+    format!("{WORKSPACES}{workspace}/assign-role/{member}")
+}
+
+pub fn create_role(
+    client: &Client,
+    workspace: Uuid,
+    name: &str,
+    permissions: &[&str],
+) -> Result<CustomRole, Status> {
+    let form = CustomRoleForm {
+        name,
+        permissions: permissions.to_vec(),
+    };
+
+    let response = client
+        .post(route_workspaces_roles(workspace))
+        .header(csrf(client))
+        .header(ContentType::Form)
+        .body(form.body())
+        .dispatch();
+
+    if response.status() != Status::Ok {
+        return Err(response.status());
+    }
+
+    Ok(response
+        .into_json::<ApiResponse<CustomRole>>()
+        .unwrap()
+        .data
+        .unwrap())
+}
+
+pub fn assign_role(
+    client: &Client,
+    workspace: Uuid,
+    member: Uuid,
+    assignment: RoleAssignment,
+) -> Status {
+    client
+        .put(route_workspaces_assign_role(workspace, member))
+        .header(csrf(client))
+        .header(ContentType::JSON)
+        .body(serde_json::to_string(&assignment).unwrap())
+        .dispatch()
+        .status()
+}
@@ -4,7 +4,7 @@ use crate::{
     forms::workspace::NewWorkspaceForm,
     models::workspaces::WorkspaceUpdate,
     tests::{
-        response_ok, test_client,
+        csrf, response_ok, test_client,
         users::{login, ADMIN_LOGIN},
         workspaces::{route_workspaces_new, route_workspaces_update},
     },
@@ -24,6 +24,7 @@ fn new_workspace_by_form() {
     response_ok(
         client
             .post(route_workspaces_new())
+            .header(csrf(&client))
             .header(ContentType::Form)
             .body(new_workspace.body()),
     );
@@ -47,6 +48,7 @@ fn update_workspace_information() {
     response_ok(
         client
             .put(route_workspaces_update())
+            .header(csrf(&client))
             .header(ContentType::JSON)
             .body(payload),
     );
@@ -0,0 +1,210 @@
+use rocket::{
+    http::{ContentType, Status},
+    local::blocking::Client,
+};
+use uuid::Uuid;
+
+use super::{
+    add_member, assign_role, create_role, create_workspace, route_workspaces_role,
+    route_workspaces_roles,
+};
+use crate::{
+    api::ApiResponse,
+    forms::{login::LoginForm, roles::CustomRoleForm},
+    models::{
+        roles::{RoleAssignment, WorkspaceRoles},
+        workspaces::{WorkspaceRole, WorkspaceUpdate, WorkspaceWithMembers},
+    },
+    routes::WORKSPACES,
+    tests::{
+        csrf, response_ok, test_client,
+        users::{inject_user, login, logout, remove_user, ADMIN_LOGIN, DEFAULT_PASSWORD},
+    },
+};
+
+#[test]
+fn custom_role_grants_its_permissions() {
+    let admin = test_client();
+    let (user_id, username) = inject_user(&admin, "custom_role");
+
+    login(&admin, ADMIN_LOGIN);
+    let workspace = create_workspace(&admin);
+    add_member(&admin, workspace, user_id, WorkspaceRole::Viewer);
+
+    let member = test_client();
+    login(
+        &member,
+        LoginForm {
+            username: &username,
+            password: DEFAULT_PASSWORD,
+        },
+    );
+    assert_eq!(update_workspace(&member, workspace), Status::Unauthorized);
+
+    // A viewer who may update the workspace
+    let role = create_role(&admin, workspace, "Editor", &["workspace.update"]).unwrap();
+    assert_eq!(
+        assign_role(&admin, workspace, user_id, RoleAssignment::Custom(role.id)),
+        Status::Ok
+    );
+    assert_eq!(update_workspace(&member, workspace), Status::Ok);
+
+    // The roles of the workspace, with the presets
+    let roles = member
+        .get(route_workspaces_roles(workspace))
+        .dispatch()
+        .into_json::<ApiResponse<WorkspaceRoles>>()
+        .unwrap()
+        .data
+        .unwrap();
+    assert_eq!(roles.presets.len(), WorkspaceRole::ALL.len());
+    assert_eq!(roles.custom.len(), 1);
+
+    // Without the role, the member is a viewer again
+    response_ok(
+        admin
+            .delete(route_workspaces_role(workspace, role.id))
+            .header(csrf(&admin)),
+    );
+    assert_eq!(update_workspace(&member, workspace), Status::Unauthorized);
+
+    // Unknown permissions are refused
+    assert_eq!(
+        create_role(&admin, workspace, "Unknown", &["workspace.rename"]).unwrap_err(),
+        Status::UnprocessableEntity
+    );
+
+    logout(&member);
+    delete_workspace(&admin, workspace);
+    logout(&admin);
+    remove_user(&admin, user_id);
+}
+
+#[test]
+fn permissions_cannot_be_granted_beyond_own() {
+    let admin = test_client();
+    let (manager_id, username) = inject_user(&admin, "role_manager");
+    let (other_id, _) = inject_user(&admin, "role_member");
+
+    login(&admin, ADMIN_LOGIN);
+    let workspace = create_workspace(&admin);
+    add_member(&admin, workspace, manager_id, WorkspaceRole::Manager);
+    add_member(&admin, workspace, other_id, WorkspaceRole::Viewer);
+
+    let manager = test_client();
+    login(
+        &manager,
+        LoginForm {
+            username: &username,
+            password: DEFAULT_PASSWORD,
+        },
+    );
+
+    // A manager can define roles with the permissions of a manager, but no others
+    let role = create_role(
+        &manager,
+        workspace,
+        "Inviter",
+        &["member.invite", "project.create"],
+    )
+    .unwrap();
+    assert_eq!(
+        create_role(&manager, workspace, "Remover", &["workspace.delete"]).unwrap_err(),
+        Status::Unauthorized
+    );
+    assert_eq!(
+        manager
+            .put(route_workspaces_role(workspace, role.id))
+            .header(csrf(&manager))
+            .header(ContentType::Form)
+            .body(
+                CustomRoleForm {
+                    name: "Inviter",
+                    permissions: vec!["workspace.delete"],
+                }
+                .body()
+            )
+            .dispatch()
+            .status(),
+        Status::Unauthorized
+    );
+
+    // And assign them, but not make anyone an owner, nor demote one
+    assert_eq!(
+        assign_role(
+            &manager,
+            workspace,
+            other_id,
+            RoleAssignment::Custom(role.id)
+        ),
+        Status::Ok
+    );
+    assert_eq!(
+        assign_role(
+            &manager,
+            workspace,
+            other_id,
+            RoleAssignment::Preset(WorkspaceRole::Owner)
+        ),
+        Status::Unauthorized
+    );
+
+    let owner_id = owner_of(&admin, workspace);
+    assert_eq!(
+        assign_role(
+            &manager,
+            workspace,
+            owner_id,
+            RoleAssignment::Preset(WorkspaceRole::Viewer)
+        ),
+        Status::Unauthorized
+    );
+
+    logout(&manager);
+    delete_workspace(&admin, workspace);
+    logout(&admin);
+    remove_user(&admin, manager_id);
+    remove_user(&admin, other_id);
+}
+
+fn update_workspace(client: &Client, workspace: Uuid) -> Status {
+    let workspace_update = WorkspaceUpdate {
+        name: None,
+        description: Some("Updated with a custom role".to_string()),
+        image_url: None,
+    };
+
+    client
+        .put(format!("{WORKSPACES}{workspace}/update"))
+        .header(csrf(client))
+        .header(ContentType::JSON)
+        .body(serde_json::to_string(&workspace_update).unwrap())
+        .dispatch()
+        .status()
+}
+
+fn owner_of(client: &Client, workspace: Uuid) -> Uuid {
+    let workspace_with_members = client
+        .get(format!("{WORKSPACES}{workspace}"))
+        .dispatch()
+        .into_json::<ApiResponse<WorkspaceWithMembers>>()
+        .unwrap()
+        .data
+        .unwrap();
+
+    workspace_with_members
+        .members
+        .iter()
+        .find(|member| member.role == WorkspaceRole::Owner)
+        .unwrap()
+        .user
+        .id
+}
+
+fn delete_workspace(client: &Client, workspace: Uuid) {
+    response_ok(
+        client
+            .delete(format!("{WORKSPACES}{workspace}/delete"))
+            .header(csrf(client)),
+    );
+}
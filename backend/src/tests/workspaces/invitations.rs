@@ -0,0 +1,333 @@
+use chrono::{Duration, Utc};
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
+use rocket::{
+    http::{ContentType, Status},
+    local::blocking::Client,
+    tokio::runtime,
+};
+use uuid::Uuid;
+
+use crate::{
+    api::ApiResponse,
+    cache,
+    database::{self, Db},
+    forms::{
+        invite::{InvitedMultipleUsersForm, InvitedUserForm},
+        password::Password,
+    },
+    models::{
+        invitations::{Invitation, InvitationStatus, PublicInvitation},
+        workspaces::{WorkspaceRole, WorkspaceWithMembers},
+    },
+    routes::{USERS, WORKSPACES},
+    schema::invitations,
+    tests::{
+        csrf, response_ok, test_client,
+        users::{login, logout, remove_user, ADMIN_LOGIN, DEFAULT_PASSWORD},
+        workspaces::{add_member, create_workspace},
+    },
+};
+
+#[test]
+fn pending_invitations_are_listed_revoked_and_renewed() {
+    let client = test_client();
+    login(&client, ADMIN_LOGIN);
+    let workspace = create_workspace(&client);
+
+    let token = invite(&client, workspace);
+    let pending = list_invitations(&client, workspace, Some("pending"));
+    assert_eq!(pending.len(), 1);
+    let invitation = &pending[0];
+    assert_eq!(invitation.role, WorkspaceRole::Contributor);
+    assert!(invitation.inviter.is_some());
+    assert!(is_member(&client, workspace, invitation.invitee.id));
+    assert_eq!(invite_link_status(&client, &token), Status::Ok);
+
+    // A revoked invitation takes the membership with it, and its link is gone
+    response_ok(
+        client
+            .delete(route_invitation(workspace, invitation.id))
+            .header(csrf(&client)),
+    );
+    assert!(!is_member(&client, workspace, invitation.invitee.id));
+    assert_eq!(invite_link_status(&client, &token), Status::Gone);
+    assert!(list_invitations(&client, workspace, Some("pending")).is_empty());
+    let revoked = list_invitations(&client, workspace, Some("revoked"));
+    assert_eq!(revoked.len(), 1);
+    assert!(revoked[0].revoked_at.is_some());
+
+    // Only a pending invitation can be revoked
+    let response = client
+        .delete(route_invitation(workspace, invitation.id))
+        .header(csrf(&client))
+        .dispatch();
+    assert_eq!(response.status(), Status::BadRequest);
+
+    // Inviting again renews the same invitation with a new link
+    let renewed_token = reinvite(&client, workspace, invitation.invitee.id);
+    let renewed = list_invitations(&client, workspace, None);
+    assert_eq!(renewed.len(), 1);
+    assert_eq!(renewed[0].id, invitation.id);
+    assert_eq!(renewed[0].status, InvitationStatus::Pending);
+    assert!(renewed[0].revoked_at.is_none());
+    assert!(is_member(&client, workspace, invitation.invitee.id));
+    assert_eq!(invite_link_status(&client, &token), Status::NotFound);
+    assert_eq!(invite_link_status(&client, &renewed_token), Status::Ok);
+
+    // A link that wasn't used in time has expired
+    expire(&client, invitation.id);
+    assert_eq!(
+        list_invitations(&client, workspace, None)[0].status,
+        InvitationStatus::Expired
+    );
+    assert_eq!(invite_link_status(&client, &renewed_token), Status::Gone);
+
+    // An unknown status is refused
+    let response = client
+        .get(format!("{WORKSPACES}{workspace}/invitations?status=lost"))
+        .dispatch();
+    assert_eq!(response.status(), Status::BadRequest);
+
+    response_ok(
+        client
+            .delete(format!("{WORKSPACES}{workspace}/delete"))
+            .header(csrf(&client)),
+    );
+    logout(&client);
+    remove_user(&client, invitation.invitee.id);
+}
+
+#[test]
+fn accepting_an_invitation_settles_the_others_of_the_user() {
+    let client = test_client();
+    login(&client, ADMIN_LOGIN);
+    let workspace = create_workspace(&client);
+    let other_workspace = create_workspace(&client);
+
+    let token = invite(&client, workspace);
+    let invitee = list_invitations(&client, workspace, None)[0].invitee.id;
+    add_member(&client, other_workspace, invitee, WorkspaceRole::Viewer);
+    let other_token = reinvite(&client, other_workspace, invitee);
+
+    response_ok(
+        client
+            .put(format!("{USERS}invite/set/{token}"))
+            .header(ContentType::Form)
+            .body(set_password().body()),
+    );
+
+    // The user is active now, so the other link is done with as well
+    let other = &list_invitations(&client, other_workspace, None)[0];
+    assert_eq!(other.status, InvitationStatus::Accepted);
+    assert!(other.accepted_at.is_some());
+    assert_eq!(invite_link_status(&client, &other_token), Status::Gone);
+
+    // A link that is used up can't set the password again
+    let response = client
+        .put(format!("{USERS}invite/set/{other_token}"))
+        .header(ContentType::Form)
+        .body(set_password().body())
+        .dispatch();
+    assert_eq!(response.status(), Status::Gone);
+
+    for workspace in [workspace, other_workspace] {
+        response_ok(
+            client
+                .delete(format!("{WORKSPACES}{workspace}/delete"))
+                .header(csrf(&client)),
+        );
+    }
+    logout(&client);
+    remove_user(&client, invitee);
+}
+
+#[test]
+fn an_invitation_accepted_in_the_meantime_is_not_revoked() {
+    let client = test_client();
+    login(&client, ADMIN_LOGIN);
+    let workspace = create_workspace(&client);
+
+    let token = invite(&client, workspace);
+    let id = list_invitations(&client, workspace, None)[0].id;
+    let pending = get_invitation(&client, workspace, id);
+
+    response_ok(
+        client
+            .put(format!("{USERS}invite/set/{token}"))
+            .header(ContentType::Form)
+            .body(set_password().body()),
+    );
+
+    // Revoking what was read before the user accepted leaves the membership alone
+    let invitee = pending.invitee;
+    let revoked = runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+        .block_on(async {
+            let db = Db::get_one(client.rocket()).await.unwrap();
+            database::invitations::revoke_invitation(&db, pending, Utc::now().naive_utc()).await
+        });
+    assert!(matches!(revoked, Err(e) if e.0 == Status::NotFound));
+    assert!(is_member(&client, workspace, invitee));
+    assert_eq!(
+        list_invitations(&client, workspace, None)[0].status,
+        InvitationStatus::Accepted
+    );
+
+    response_ok(
+        client
+            .delete(format!("{WORKSPACES}{workspace}/delete"))
+            .header(csrf(&client)),
+    );
+    logout(&client);
+    remove_user(&client, invitee);
+}
+
+#[test]
+fn the_ttl_of_invitations_is_configurable() {
+    let rocket = crate::rocket();
+    let figment = rocket
+        .figment()
+        .clone()
+        .merge(("invitation", serde_json::json!({ "ttl_hours": 72 })));
+    let client = Client::tracked(rocket.configure(figment)).expect("valid rocket instance");
+
+    login(&client, ADMIN_LOGIN);
+    let workspace = create_workspace(&client);
+    invite(&client, workspace);
+
+    let invitation = &list_invitations(&client, workspace, None)[0];
+    assert_eq!(
+        invitation.expires_at - invitation.sent_at,
+        Duration::hours(72)
+    );
+
+    response_ok(
+        client
+            .delete(format!("{WORKSPACES}{workspace}/delete"))
+            .header(csrf(&client)),
+    );
+    logout(&client);
+    remove_user(&client, invitation.invitee.id);
+}
+
+fn route_invitation(workspace: Uuid, invitation: Uuid) -> String {
+    format!("{WORKSPACES}{workspace}/invitations/{invitation}")
+}
+
+/// Invites a new user to the workspace as a contributor, returning the token of the link.
+fn invite(client: &Client, workspace: Uuid) -> String {
+    let email = format!(
+        "invitee_{}@example.com",
+        cache::create_random_token(8).to_lowercase()
+    );
+    let invitation = InvitedMultipleUsersForm {
+        users: vec![InvitedUserForm {
+            first_name: "Ivy",
+            last_name: "Invitee",
+            email: &email,
+            phone: None,
+            workspace_role: WorkspaceRole::Contributor,
+        }],
+    };
+
+    client
+        .post(format!("{WORKSPACES}{workspace}/invite"))
+        .header(csrf(client))
+        .header(ContentType::Form)
+        .body(invitation.body())
+        .dispatch()
+        .into_json::<ApiResponse<Vec<String>>>()
+        .unwrap()
+        .data
+        .unwrap()
+        .remove(0)
+}
+
+fn reinvite(client: &Client, workspace: Uuid, member: Uuid) -> String {
+    client
+        .post(format!("{WORKSPACES}{workspace}/re-invite/{member}"))
+        .header(csrf(client))
+        .dispatch()
+        .into_json::<ApiResponse<String>>()
+        .unwrap()
+        .data
+        .unwrap()
+}
+
+fn list_invitations(
+    client: &Client,
+    workspace: Uuid,
+    status: Option<&str>,
+) -> Vec<PublicInvitation> {
+    let query = status
+        .map(|status| format!("?status={status}"))
+        .unwrap_or_default();
+
+    client
+        .get(format!("{WORKSPACES}{workspace}/invitations{query}"))
+        .dispatch()
+        .into_json::<ApiResponse<Vec<PublicInvitation>>>()
+        .unwrap()
+        .data
+        .unwrap()
+}
+
+fn invite_link_status(client: &Client, token: &str) -> Status {
+    client
+        .get(format!("{USERS}invite/get/{token}"))
+        .dispatch()
+        .status()
+}
+
+fn is_member(client: &Client, workspace: Uuid, user: Uuid) -> bool {
+    client
+        .get(format!("{WORKSPACES}{workspace}"))
+        .dispatch()
+        .into_json::<ApiResponse<WorkspaceWithMembers>>()
+        .unwrap()
+        .data
+        .unwrap()
+        .members
+        .iter()
+        .any(|member| member.user.id == user)
+}
+
+fn set_password() -> Password<'static> {
+    Password {
+        first: DEFAULT_PASSWORD,
+        second: DEFAULT_PASSWORD,
+    }
+}
+
+fn get_invitation(client: &Client, workspace: Uuid, id: Uuid) -> Invitation {
+    runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+        .block_on(async {
+            let db = Db::get_one(client.rocket()).await.unwrap();
+            database::invitations::get_invitation(&db, workspace, id)
+                .await
+                .unwrap()
+        })
+}
+
+/// Moves the expiry of the invitation into the past.
+fn expire(client: &Client, invitation: Uuid) {
+    runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+        .block_on(async {
+            let db = Db::get_one(client.rocket()).await.unwrap();
+            db.run(move |conn| {
+                diesel::update(invitations::table.find(invitation))
+                    .set(invitations::expires_at.eq(Utc::now().naive_utc() - Duration::hours(1)))
+                    .execute(conn)
+            })
+            .await
+            .unwrap();
+        });
+}
@@ -0,0 +1,228 @@
+use rocket::{
+    http::{ContentType, Status},
+    local::blocking::Client,
+};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::{
+    api::ApiResponse,
+    database::pagination::{request::PaginationRequest, sort::ProjectField},
+    models::{
+        labels::{Label, LabelMatch, NewLabel},
+        workspaces::WorkspaceRole,
+    },
+    routes::{PROJECTS, WORKSPACES},
+    tests::{
+        csrf,
+        projects::create_project,
+        response_ok, test_client,
+        users::{client_of, inject_user, login, logout, remove_user, ADMIN_LOGIN},
+        workspaces::{add_member, create_workspace},
+    },
+};
+
+#[test]
+fn labels_have_a_name_and_a_hex_color() {
+    let label = |name: &str, color: &str| NewLabel {
+        name: name.to_string(),
+        color: color.to_string(),
+        description: None,
+    };
+
+    assert!(label("Frontend", "#1F883D").validate().is_ok());
+    assert!(label("  ", "#1f883d").validate().is_err());
+    assert!(label(&"x".repeat(41), "#1f883d").validate().is_err());
+    for color in ["1f883d", "#1f883", "#1f883dd", "#1g883d"] {
+        assert!(label("Frontend", color).validate().is_err());
+    }
+    assert_eq!(
+        label(" Frontend ", "#1F883D")
+            .into_insertable(Uuid::new_v4())
+            .color,
+        "#1f883d"
+    );
+
+    assert_eq!("ALL".parse::<LabelMatch>(), Ok(LabelMatch::All));
+    assert!("both".parse::<LabelMatch>().is_err());
+}
+
+#[test]
+fn managers_label_the_projects_of_their_workspace() {
+    let admin = test_client();
+    let (manager_id, manager_name) = inject_user(&admin, "label_manager");
+    let (contributor_id, contributor_name) = inject_user(&admin, "label_contributor");
+
+    login(&admin, ADMIN_LOGIN);
+    let workspace = create_workspace(&admin);
+    add_member(&admin, workspace, manager_id, WorkspaceRole::Manager);
+    add_member(
+        &admin,
+        workspace,
+        contributor_id,
+        WorkspaceRole::Contributor,
+    );
+    let tagged = create_project(&admin, workspace);
+    let other = create_project(&admin, workspace);
+
+    let manager = client_of(&manager_name);
+    let contributor = client_of(&contributor_name);
+    let labels = format!("{WORKSPACES}{workspace}/labels");
+
+    // Managers and above create the labels, the other members only see them
+    let (status, frontend) = create_label(&manager, &labels, "Frontend", "#1F883D");
+    assert_eq!(status, Status::Ok);
+    let frontend = frontend.unwrap();
+    assert_eq!(frontend.color, "#1f883d");
+    let (_, urgent) = create_label(&manager, &labels, "Urgent", "#d1242f");
+    let urgent = urgent.unwrap();
+    let (status, _) = create_label(&manager, &labels, "Urgent", "#000000");
+    assert_eq!(status, Status::BadRequest);
+    let (status, _) = create_label(&contributor, &labels, "Backend", "#0969da");
+    assert_eq!(status, Status::Unauthorized);
+    let response = contributor.get(&labels).dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(
+        response
+            .into_json::<ApiResponse<Vec<Label>>>()
+            .unwrap()
+            .data
+            .unwrap()
+            .len(),
+        2
+    );
+
+    // Projects are tagged with the labels of their workspace only
+    for (project, label) in [
+        (tagged, frontend.id),
+        (tagged, urgent.id),
+        (other, frontend.id),
+    ] {
+        response_ok(
+            admin
+                .put(format!("{PROJECTS}{project}/labels/{label}"))
+                .header(csrf(&admin)),
+        );
+    }
+    let response = admin
+        .put(format!("{PROJECTS}{tagged}/labels/{}", Uuid::new_v4()))
+        .header(csrf(&admin))
+        .dispatch();
+    assert_eq!(response.status(), Status::NotFound);
+
+    // A project has any or all of the labels it is filtered by
+    let both = format!("label={}&label={}", frontend.id, urgent.id);
+    assert_eq!(total_projects(&contributor, workspace, &both), 2);
+    assert_eq!(
+        total_projects(&contributor, workspace, &format!("{both}&label_match=all")),
+        1
+    );
+    assert_eq!(
+        total_projects(&contributor, workspace, &format!("label={}", urgent.id)),
+        1
+    );
+    let response = contributor
+        .get(format!(
+            "{PROJECTS}?workspace={workspace}&{both}&label_match=both"
+        ))
+        .header(ContentType::JSON)
+        .body(serde_json::to_string(&PaginationRequest::<ProjectField>::new()).unwrap())
+        .dispatch();
+    assert_eq!(response.status(), Status::BadRequest);
+
+    // Renaming a label shows on every project, deleting it removes it from every project
+    response_ok(
+        manager
+            .put(format!("{labels}/{}", urgent.id))
+            .header(csrf(&manager))
+            .header(ContentType::JSON)
+            .body(json!({ "name": "Blocker" }).to_string()),
+    );
+    assert_eq!(
+        project_labels(&contributor, tagged),
+        ["Blocker", "Frontend"]
+    );
+    response_ok(
+        manager
+            .delete(format!("{labels}/{}", frontend.id))
+            .header(csrf(&manager)),
+    );
+    assert_eq!(project_labels(&contributor, tagged), ["Blocker"]);
+    assert!(project_labels(&contributor, other).is_empty());
+    assert_eq!(
+        total_projects(&contributor, workspace, &format!("label={}", frontend.id)),
+        0
+    );
+
+    // Removing a label from a project leaves the label in the workspace
+    response_ok(
+        admin
+            .delete(format!("{PROJECTS}{tagged}/labels/{}", urgent.id))
+            .header(csrf(&admin)),
+    );
+    assert!(project_labels(&contributor, tagged).is_empty());
+    let response = admin
+        .delete(format!("{PROJECTS}{tagged}/labels/{}", urgent.id))
+        .header(csrf(&admin))
+        .dispatch();
+    assert_eq!(response.status(), Status::NotFound);
+
+    for client in [&manager, &contributor] {
+        logout(client);
+    }
+    response_ok(
+        admin
+            .delete(format!("{WORKSPACES}{workspace}/delete"))
+            .header(csrf(&admin)),
+    );
+    logout(&admin);
+    for user_id in [manager_id, contributor_id] {
+        remove_user(&admin, user_id);
+    }
+}
+
+fn create_label(client: &Client, route: &str, name: &str, color: &str) -> (Status, Option<Label>) {
+    let response = client
+        .post(route)
+        .header(csrf(client))
+        .header(ContentType::JSON)
+        .body(json!({ "name": name, "color": color }).to_string())
+        .dispatch();
+
+    let status = response.status();
+    (
+        status,
+        response
+            .into_json::<ApiResponse<Label>>()
+            .and_then(|response| response.data),
+    )
+}
+
+/// The names of the labels of the project, in order.
+fn project_labels(client: &Client, project: Uuid) -> Vec<String> {
+    client
+        .get(format!("{PROJECTS}{project}/labels"))
+        .dispatch()
+        .into_json::<ApiResponse<Vec<Label>>>()
+        .unwrap()
+        .data
+        .unwrap()
+        .into_iter()
+        .map(|label| label.name)
+        .collect()
+}
+
+/// The number of projects of the workspace found with the filters of the query.
+fn total_projects(client: &Client, workspace: Uuid, query: &str) -> i64 {
+    let params = PaginationRequest::<ProjectField>::new();
+
+    client
+        .get(format!("{PROJECTS}?workspace={workspace}&{query}"))
+        .header(ContentType::JSON)
+        .body(serde_json::to_string(&params).unwrap())
+        .dispatch()
+        .into_json::<Value>()
+        .unwrap()["data"]["total"]
+        .as_i64()
+        .unwrap()
+}
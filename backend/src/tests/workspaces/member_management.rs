@@ -6,7 +6,7 @@ use uuid::Uuid;
 use crate::{
     models::workspaces::{WorkspaceMember, WorkspaceRole},
     tests::{
-        response_ok, test_client,
+        csrf, response_ok, test_client,
         users::{login, ADMIN_LOGIN},
         workspaces::{
             route_workspaces_add_member, route_workspaces_remove_member, TARGETED_WORKSPACE,
@@ -25,7 +25,8 @@ fn add_member_to_workspace() {
     let new_member = WorkspaceMember {
         workspace: Uuid::from_str(TARGETED_WORKSPACE).unwrap(),
         member: Uuid::from_str(TARGETED_MEMBER).unwrap(),
-        role: i16::from(WorkspaceRole::Contributor),
+        role: WorkspaceRole::Contributor,
+        custom_role: None,
     };
 
     // Serialize the workspace update
@@ -34,6 +35,7 @@ fn add_member_to_workspace() {
     response_ok(
         client
             .post(route_workspaces_add_member())
+            .header(csrf(&client))
             .header(ContentType::JSON)
             .body(payload),
     );
@@ -43,5 +45,9 @@ fn add_member_to_workspace() {
 fn remove_member_from_workspace() {
     let client = test_client();
     login(&client, ADMIN_LOGIN);
-    response_ok(client.delete(route_workspaces_remove_member(TARGETED_MEMBER)));
+    response_ok(
+        client
+            .delete(route_workspaces_remove_member(TARGETED_MEMBER))
+            .header(csrf(&client)),
+    );
 }
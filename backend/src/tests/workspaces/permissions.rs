@@ -0,0 +1,182 @@
+use rocket::{
+    http::{ContentType, Status},
+    local::blocking::Client,
+};
+use uuid::Uuid;
+
+use crate::{
+    api::ApiResponse,
+    forms::login::LoginForm,
+    models::{
+        permissions::{Capability, EffectivePermissions},
+        projects::ProjectRole,
+        workspaces::{WorkspaceRole, WorkspaceUpdate},
+    },
+    routes::{PROJECTS, WORKSPACES},
+    tests::{
+        csrf,
+        projects::{add_project_member, create_project},
+        response_ok, test_client,
+        users::{inject_user, login, logout, remove_user, ADMIN_LOGIN, DEFAULT_PASSWORD},
+        workspaces::{add_member, create_workspace},
+    },
+};
+
+#[test]
+fn workspace_role_is_resolved_on_the_server() {
+    let admin = test_client();
+    let (user_id, username) = inject_user(&admin, "permissions");
+
+    login(&admin, ADMIN_LOGIN);
+    let workspace = create_workspace(&admin);
+    add_member(&admin, workspace, user_id, WorkspaceRole::Contributor);
+
+    // The member can update the workspace without fetching it first
+    let member = test_client();
+    login(
+        &member,
+        LoginForm {
+            username: &username,
+            password: DEFAULT_PASSWORD,
+        },
+    );
+
+    assert_eq!(update_workspace(&member, workspace), Status::Ok);
+
+    // Once removed, the same session loses the permission right away
+    response_ok(
+        admin
+            .delete(format!("{WORKSPACES}{workspace}/remove-member/{user_id}"))
+            .header(csrf(&admin)),
+    );
+    assert_eq!(update_workspace(&member, workspace), Status::Unauthorized);
+
+    logout(&member);
+    response_ok(
+        admin
+            .delete(format!("{WORKSPACES}{workspace}/delete"))
+            .header(csrf(&admin)),
+    );
+    logout(&admin);
+    remove_user(&admin, user_id);
+}
+
+#[test]
+fn effective_permissions_follow_the_policies() {
+    let admin = test_client();
+    let (user_id, username) = inject_user(&admin, "effective");
+
+    login(&admin, ADMIN_LOGIN);
+    let workspace = create_workspace(&admin);
+    add_member(&admin, workspace, user_id, WorkspaceRole::Contributor);
+    let project = create_project(&admin, workspace);
+
+    let member = test_client();
+    login(
+        &member,
+        LoginForm {
+            username: &username,
+            password: DEFAULT_PASSWORD,
+        },
+    );
+
+    // A contributor can update the workspace and comment on it, but not delete it
+    let permissions = get_permissions(&member, &format!("{WORKSPACES}{workspace}/permissions"));
+    assert_eq!(permissions.user, user_id);
+    assert_eq!(
+        permissions.permissions,
+        vec!["workspace.update", "comment.create"]
+    );
+    assert!(is_allowed(&permissions, "write_comments"));
+    assert!(is_allowed(&permissions, "view"));
+    assert!(is_allowed(&permissions, "update_info"));
+    assert!(!is_allowed(&permissions, "delete"));
+    assert!(permissions.capabilities.iter().all(|c| c.reason.is_none()));
+
+    // Explaining, or asking for someone else, is for admins
+    let response = member
+        .get(format!("{WORKSPACES}{workspace}/permissions?explain=true"))
+        .dispatch();
+    assert_eq!(response.status(), Status::Unauthorized);
+
+    let permissions = get_permissions(
+        &admin,
+        &format!("{WORKSPACES}{workspace}/permissions?user={user_id}&explain=true"),
+    );
+    assert_eq!(permissions.user, user_id);
+    assert_eq!(
+        reason(&permissions, "update_info"),
+        "Granted by the built-in role Contributor, which has workspace.update"
+    );
+    assert_eq!(
+        reason(&permissions, "delete"),
+        "Denied; the built-in role Contributor doesn't have workspace.delete"
+    );
+
+    // In the project, the project role adds to the workspace role
+    let route = format!("{PROJECTS}{project}/permissions?user={user_id}&explain=true");
+    assert!(!is_allowed(&get_permissions(&admin, &route), "update_info"));
+
+    assert_eq!(
+        add_project_member(&admin, project, user_id, ProjectRole::Contributor, false),
+        Status::Ok
+    );
+
+    let permissions = get_permissions(&admin, &route);
+    assert!(is_allowed(&permissions, "update_info"));
+    assert_eq!(
+        reason(&permissions, "update_info"),
+        "Granted by the project role Contributor, which has project.update"
+    );
+
+    logout(&member);
+    response_ok(
+        admin
+            .delete(format!("{WORKSPACES}{workspace}/delete"))
+            .header(csrf(&admin)),
+    );
+    logout(&admin);
+    remove_user(&admin, user_id);
+}
+
+fn get_permissions(client: &Client, route: &str) -> EffectivePermissions {
+    client
+        .get(route)
+        .dispatch()
+        .into_json::<ApiResponse<EffectivePermissions>>()
+        .unwrap()
+        .data
+        .unwrap()
+}
+
+fn capability<'a>(permissions: &'a EffectivePermissions, name: &str) -> &'a Capability {
+    permissions
+        .capabilities
+        .iter()
+        .find(|capability| capability.name == name)
+        .unwrap()
+}
+
+fn is_allowed(permissions: &EffectivePermissions, name: &str) -> bool {
+    capability(permissions, name).allowed
+}
+
+fn reason(permissions: &EffectivePermissions, name: &str) -> String {
+    capability(permissions, name).reason.clone().unwrap()
+}
+
+fn update_workspace(client: &Client, workspace: Uuid) -> Status {
+    let workspace_update = WorkspaceUpdate {
+        name: None,
+        description: Some("Updated by a contributor".to_string()),
+        image_url: None,
+    };
+
+    client
+        .put(format!("{WORKSPACES}{workspace}/update"))
+        .header(csrf(client))
+        .header(ContentType::JSON)
+        .body(serde_json::to_string(&workspace_update).unwrap())
+        .dispatch()
+        .status()
+}
@@ -5,7 +5,10 @@ const USER_ENDPOINT = "/user";
 export const User = {
     me: () => dispatcher.get(`${USER_ENDPOINT}/me`),
     login: (credentials) => dispatcher.post(`${USER_ENDPOINT}/login`, credentials, { form: true }),
+    loginSecondFactor: (challenge, code) => dispatcher.post(`${USER_ENDPOINT}/login/2fa`, { challenge, code }, { form: true }),
+    loginEnrollSecondFactor: (challenge) => dispatcher.post(`${USER_ENDPOINT}/login/2fa/enroll`, { challenge }, { form: true }),
     logout: () => dispatcher.post(`${USER_ENDPOINT}/logout`),
+    refresh: () => fetch(`${API_URL}${USER_ENDPOINT}/refresh`, { method: "POST", credentials: "include" }),
 };
 
 // Convenience helpers
@@ -16,7 +19,7 @@ const dispatcher = {
     del: (endpoint, options) => Dispatch(endpoint, { ...options, method: "DELETE" }),
 };
 
-async function Dispatch(endpoint, { method = "GET", body, headers = {}, form = false } = {}) {
+async function Dispatch(endpoint, { method = "GET", body, headers = {}, form = false, retry = true } = {}) {
     // Create the request configuration
     const config = {
         method,
@@ -40,6 +43,14 @@ async function Dispatch(endpoint, { method = "GET", body, headers = {}, form = f
     // Make the call and collect the response
     const res = await fetch(`${API_URL}${endpoint}`, config);
 
+    // The access token is short-lived; refresh it once and retry the request
+    if (res.status === 401 && retry) {
+        const refreshed = await User.refresh();
+        if (refreshed.ok) {
+            return Dispatch(endpoint, { method, body, headers, form, retry: false });
+        }
+    }
+
     // Extract the response message
     const response = await res.json();
 